@@ -2,14 +2,21 @@
 
 use anyhow::Result;
 use crossterm::event::{self, Event, KeyEvent, KeyEventKind};
-use std::time::Duration;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
 use tui_textarea::{CursorMove, TextArea};
 
-use crate::config::{archive_dir, config_path, folders_dir, index_path, prompts_dir, Config};
-use crate::fs::{ensure_directories, load_all_prompts, load_all_prompts_everywhere, save_prompt, delete_prompt, Index, IndexEntry};
-use crate::models::{Action, AppState, ConfirmDialog, EditorMode, FilePickerPopupState, FolderSelectorMode, FolderSelectorState, Mode, NotificationLevel, PendingAction, Prompt, SearchPopupState, SearchResult, TagSelectorState, VimOperator};
+use crate::config::{archive_dir, backup_dir, folders_dir, history_dir, index_path, piemme_dir, prompts_dir, session_path, themes_dirs, views_path, Config};
+use crate::engine::{check_pinned_references, resolve_prompt};
+use crate::fs::{edit_tags, ensure_directories, export_documents, load_all_prompts, load_prompt, save_prompt, delete_prompt, write_export, ExportFormat, ExportOptions, Body, Index, IndexEntry, PromptWatcher, is_own_write, read_bodies, Session, SyncedPrompts, list_backups, list_revisions, restore_backup, restore_revision};
+use crate::models::{Action, AppState, BackupPickerState, BlameState, CommandPaletteState, CommitPopupState, ConfirmDialog, ConflictState, EditorMode, ExportField, ExportPopupState, FilePickerPopupState, FolderEdit, FolderSelectorMode, FolderSelectorState, HistoryState, Mode, NotificationLevel, PaletteCommand, PendingAction, Prompt, PromptStatus, SearchPopupState, SearchResult, normalize_tag, TagEdit, TagFilter, TagFilterPopupState, TagSelectorEdit, TagSelectorState, VimOperator};
+use crate::search::{load_views, save_view, search, BackgroundSearch, Document, Query};
+use crate::git::{GitStatus, Repo};
 use crate::tui::{init_terminal, restore_terminal, Tui};
-use crate::ui::{handle_key_event, render};
+use crate::ui::{handle_key_event, handle_overlay_keys, render, KeyChord, Keymap, KeymapMode, Theme};
 
 /// Execute a vim motion on the editor (free function to avoid borrow issues)
 fn execute_vim_motion(editor: &mut TextArea, action: &Action) {
@@ -113,6 +120,15 @@ fn move_to_paragraph_boundary(editor: &mut TextArea, forward: bool) {
     }
 }
 
+/// How often the git marks of the listed prompts are refreshed
+const GIT_REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Most search results listed in the search popup
+const SEARCH_RESULT_LIMIT: usize = 100;
+
+/// How long to wait for input while the search matcher is running (about one frame)
+const SEARCH_FRAME: Duration = Duration::from_millis(16);
+
 /// The main application
 pub struct App<'a> {
     /// Terminal instance
@@ -121,6 +137,10 @@ pub struct App<'a> {
     state: AppState,
     /// Configuration
     config: Config,
+    /// Colors of the UI, fitted to the terminal
+    theme: Theme,
+    /// Key bindings: the defaults with the configured overrides
+    keymap: Keymap,
     /// Search index
     index: Index,
     /// Archived prompts count
@@ -129,6 +149,114 @@ pub struct App<'a> {
     editor: Option<TextArea<'a>>,
     /// All prompts (unfiltered) - used as source for tag filtering
     all_prompts: Vec<Prompt>,
+    /// Watcher for prompt files changed outside piemme (None if it could not start)
+    watcher: Option<PromptWatcher>,
+    /// Git repository containing the prompts (None outside a repository)
+    git: Option<Repo>,
+    /// When and for which directory the git marks were last refreshed
+    git_refreshed: Option<(Instant, PathBuf)>,
+    /// `git status` running in the background, with the directory it looks at
+    git_pending: Option<(PathBuf, Receiver<HashMap<PathBuf, GitStatus>>)>,
+    /// Matcher of the open search popup
+    search: Option<BackgroundSearch>,
+    /// Content whose pinned references `state.pinned_refs` holds
+    pinned_checked: Option<String>,
+    /// Pinned references being checked in the background, with the content they are from
+    pinned_pending: Option<(String, Receiver<HashMap<String, bool>>)>,
+    /// Content `state.resolved_preview` was resolved from
+    preview_source: Option<String>,
+    /// Bodies of the prompts listed at startup without them, being read in the background
+    bodies_pending: Option<Receiver<Vec<Body>>>,
+}
+
+/// What the first frame shows, loaded from the store at startup
+struct Startup {
+    state: AppState,
+    index: Index,
+    all_prompts: Vec<Prompt>,
+    archived_count: usize,
+    bodies_pending: Option<Receiver<Vec<Body>>>,
+}
+
+impl Startup {
+    /// Load the prompts of the store at `piemme` without their bodies, which are read
+    /// in the background (but the selected prompt's)
+    fn load(piemme: &Path) -> Result<Self> {
+        // Load the index, which caches every prompt file; a damaged one is rebuilt
+        let index_path = piemme.join(".index.json");
+        let mut index = Index::load(&index_path).unwrap_or_else(|_| Index::new());
+        let mut state = AppState::new();
+
+        // Read only the prompt files changed since the index was saved; the bodies of the
+        // others are read in the background
+        let synced = index.sync_metadata(piemme)?;
+        let bodies_pending = read_bodies_in_background(&synced.unread, None);
+        if synced.changed() {
+            index.save(&index_path)?;
+        }
+        if let Some(message) = synced.failure_message() {
+            state.notify(message, NotificationLevel::Warning);
+        }
+
+        // Restore the tag filter of the last session
+        match Session::load(&piemme.join(".session.yaml")) {
+            Ok(session) => state.tag_filter = session.tag_filter,
+            Err(e) => state.notify(format!("{:#}", e), NotificationLevel::Warning),
+        }
+
+        // Prompts for the initial view (main prompts directory) and every tag in use
+        let (prompts, all_tags) = startup_listing(&synced, &state.tag_filter);
+        state.prompts = prompts;
+        state.all_tags = all_tags;
+
+        // ALL prompts outside the archive (for reference resolution across folders)
+        let mut all_prompts = synced.active();
+
+        // The selected prompt is shown right away, so its body cannot wait
+        if let Some(first) = state.prompts.first_mut()
+            && let Some((_, path)) = synced.unread.iter().find(|(location, path)| {
+                location == "prompts" && path.file_stem().is_some_and(|stem| *stem == *first.name)
+            })
+            && let Ok(loaded) = load_prompt(path)
+            && index.set_body("prompts", &first.name, loaded.content.clone())
+        {
+            first.content = loaded.content;
+            if let Some(active) = all_prompts.iter_mut().find(|p| p.name == first.name) {
+                active.content = first.content.clone();
+            }
+        }
+
+        // Count archived prompts
+        let archived_count = synced.archived();
+
+        Ok(Self {
+            state,
+            index,
+            all_prompts,
+            archived_count,
+            bodies_pending,
+        })
+    }
+}
+
+/// Read the bodies of `unread` prompts on another thread, after those `previous` is still
+/// reading (None when there are none)
+fn read_bodies_in_background(
+    unread: &[(String, PathBuf)],
+    previous: Option<Receiver<Vec<Body>>>,
+) -> Option<Receiver<Vec<Body>>> {
+    if unread.is_empty() {
+        return previous;
+    }
+    let (sender, receiver) = channel();
+    let unread = unread.to_vec();
+    thread::spawn(move || {
+        let mut bodies = previous.and_then(|previous| previous.recv().ok()).unwrap_or_default();
+        bodies.extend(read_bodies(&unread));
+        // The receiver is gone if the app quit first
+        let _ = sender.send(bodies);
+    });
+    Some(receiver)
 }
 
 impl<'a> App<'a> {
@@ -140,64 +268,109 @@ impl<'a> App<'a> {
         // Ensure directories exist
         ensure_directories()?;
 
-        // Load configuration
-        let config = Config::load_or_default(&config_path()?)?;
+        // Load configuration (defaults -> user -> project -> environment)
+        let config = Config::load_layered()?;
 
-        // Load index
-        let index = Index::load_or_new(&index_path()?)?;
-
-        // Create initial state
-        let mut state = AppState::new();
+        // Prompts and tags for the first frame; most bodies follow in the background
+        let Startup {
+            mut state,
+            index,
+            all_prompts,
+            archived_count,
+            bodies_pending,
+        } = Startup::load(&piemme_dir()?)?;
         state.safe_mode = config.safe_mode;
 
-        // Load prompts for initial view (main prompts directory)
-        let prompts = load_all_prompts(&prompts_dir()?)?;
-        state.prompts = prompts;
-        
-        // Load ALL prompts from all locations (for reference resolution across folders)
-        let all_prompts = load_all_prompts_everywhere()?;
+        // Watch for prompt files changed outside piemme
+        let watcher = match PromptWatcher::new(&[prompts_dir()?, archive_dir()?, folders_dir()?]) {
+            Ok(watcher) => Some(watcher),
+            Err(e) => {
+                state.notify(format!("Live reload disabled: {:#}", e), NotificationLevel::Warning);
+                None
+            }
+        };
 
-        // Count archived prompts
-        let archived_count = load_all_prompts(&archive_dir()?)?.len();
+        // Surface the first config problem; the rest are reported by `piemme config show`
+        let config_errors = config.validate();
+        if let Some(error) = config_errors.first() {
+            state.notify(format!("Config: {}", error), NotificationLevel::Warning);
+        }
 
-        // Collect all tags
-        let mut all_tags: Vec<String> = state
-            .prompts
-            .iter()
-            .flat_map(|p| p.tags.clone())
-            .collect();
-        all_tags.sort();
-        all_tags.dedup();
-        state.all_tags = all_tags;
+        // Colors of the configured theme, falling back to the dark one
+        let theme = Theme::load(&config.theme, &themes_dirs())
+            .unwrap_or_else(|e| {
+                if config_errors.is_empty() {
+                    state.notify(format!("Theme: {:#}", e), NotificationLevel::Warning);
+                }
+                Theme::dark()
+            })
+            .for_terminal();
+
+        // Invalid bindings were reported above and are left out
+        let (keymap, _) = Keymap::from_config(&config.keybindings);
 
         Ok(Self {
             terminal,
             state,
             config,
+            theme,
+            keymap,
             index,
             archived_count,
             editor: None,
             all_prompts,
+            watcher,
+            git: Repo::discover(&piemme_dir()?),
+            git_refreshed: None,
+            git_pending: None,
+            search: None,
+            pinned_checked: None,
+            pinned_pending: None,
+            preview_source: None,
+            bodies_pending,
         })
     }
 
     /// Run the main application loop
     pub fn run(&mut self) -> Result<()> {
         loop {
+            self.check_pinned_references();
+            self.refresh_preview();
+
             // Draw UI
             self.terminal.draw(|frame| {
                 render(
                     frame,
                     &self.state,
                     &self.config,
+                    &self.keymap,
+                    &self.theme,
                     self.archived_count,
                     self.editor.as_ref(),
                     &self.all_prompts,
                 );
             })?;
 
+            // Take in the bodies read since startup, polling faster until they come in
+            self.poll_bodies();
+            let mut poll_timeout = if self.bodies_pending.is_some() {
+                SEARCH_FRAME
+            } else {
+                Duration::from_millis(100)
+            };
+
+            // Take in background search results, polling faster while they come in
+            if let Some(search) = self.search.as_mut() {
+                if search.tick(0) {
+                    self.refresh_search_results();
+                }
+                if self.search.as_ref().is_some_and(BackgroundSearch::is_running) {
+                    poll_timeout = SEARCH_FRAME;
+                }
+            }
+
             // Handle events
-            if event::poll(Duration::from_millis(100))? {
+            if event::poll(poll_timeout)? {
                 let evt = event::read()?;
                 
                 // Handle mouse events for text selection in Insert mode
@@ -214,9 +387,22 @@ impl<'a> App<'a> {
                 if let Event::Key(key) = evt {
                     // Only handle key press events (not release)
                     if key.kind == KeyEventKind::Press {
+                        // Moving through the list needs only the selected prompt's body; any
+                        // other key waits for them all, so nothing reads or saves a missing one
+                        if self.bodies_pending.is_some() && !self.moves_in_list(key) {
+                            self.wait_for_bodies();
+                        }
+
+                        // Handle the conflict prompt for a prompt changed on disk
+                        if self.state.conflict_popup.is_some() {
+                            let action = handle_key_event(key, &self.state, &mut self.keymap);
+                            self.handle_action(action)?;
+                            continue;
+                        }
+
                         // Handle rename popup input
                         if self.state.rename_popup.is_some() {
-                            let action = handle_key_event(key, &self.state);
+                            let action = handle_key_event(key, &self.state, &mut self.keymap);
                             match action {
                                 Action::ConfirmRename => {
                                     self.handle_action(action)?;
@@ -235,7 +421,7 @@ impl<'a> App<'a> {
 
                         // Handle reference popup input
                         if self.state.reference_popup.is_some() {
-                            let action = handle_key_event(key, &self.state);
+                            let action = handle_key_event(key, &self.state, &mut self.keymap);
                             match action {
                                 Action::ConfirmReference => {
                                     self.handle_action(action)?;
@@ -262,9 +448,35 @@ impl<'a> App<'a> {
                             continue;
                         }
 
+                        // Handle command palette input
+                        if self.state.command_palette.is_some() {
+                            let action = handle_key_event(key, &self.state, &mut self.keymap);
+                            match action {
+                                Action::ConfirmCommandPalette | Action::CancelCommandPalette => {
+                                    self.handle_action(action)?;
+                                }
+                                Action::CommandPaletteUp => {
+                                    if let Some(ref mut palette) = self.state.command_palette {
+                                        palette.select_previous();
+                                    }
+                                }
+                                Action::CommandPaletteDown => {
+                                    if let Some(ref mut palette) = self.state.command_palette {
+                                        palette.select_next();
+                                    }
+                                }
+                                Action::None => {
+                                    // Handle text input for filter
+                                    self.handle_command_palette_input(key);
+                                }
+                                _ => {}
+                            }
+                            continue;
+                        }
+
                         // Handle file picker popup input
                         if self.state.file_picker.is_some() {
-                            let action = handle_key_event(key, &self.state);
+                            let action = handle_key_event(key, &self.state, &mut self.keymap);
                             match action {
                                 Action::ConfirmFilePicker => {
                                     self.handle_action(action)?;
@@ -295,7 +507,7 @@ impl<'a> App<'a> {
 
                         // Handle tag selector input
                         if self.state.tag_selector.is_some() {
-                            let action = handle_key_event(key, &self.state);
+                            let action = handle_key_event(key, &self.state, &mut self.keymap);
                             match action {
                                 Action::ConfirmTagToggle => {
                                     self.handle_action(action)?;
@@ -312,6 +524,15 @@ impl<'a> App<'a> {
                                 Action::ConfirmNewTag => {
                                     self.handle_action(action)?;
                                 }
+                                Action::RenameTag
+                                | Action::ConfirmRenameTag
+                                | Action::DeleteTag
+                                | Action::ConfirmDeleteTag
+                                | Action::MergeTag
+                                | Action::ConfirmMergeTag
+                                | Action::CancelTagEdit => {
+                                    self.handle_action(action)?;
+                                }
                                 Action::None => {
                                     // Handle text input for filter or new tag
                                     self.handle_tag_selector_input(key);
@@ -321,9 +542,31 @@ impl<'a> App<'a> {
                             continue;
                         }
 
+                        // Handle tag filter popup input
+                        if self.state.tag_filter_popup.is_some() {
+                            let action = handle_key_event(key, &self.state, &mut self.keymap);
+                            match action {
+                                Action::ConfirmTagFilter
+                                | Action::CancelTagFilter
+                                | Action::TagFilterUp
+                                | Action::TagFilterDown
+                                | Action::CycleTagFilterTag
+                                | Action::ToggleTagFilterMatch
+                                | Action::ClearTagFilter => {
+                                    self.handle_action(action)?;
+                                }
+                                Action::None => {
+                                    // Handle text input for filter
+                                    self.handle_tag_filter_input(key);
+                                }
+                                _ => {}
+                            }
+                            continue;
+                        }
+
                         // Handle folder selector input
                         if self.state.folder_selector.is_some() {
-                            let action = handle_key_event(key, &self.state);
+                            let action = handle_key_event(key, &self.state, &mut self.keymap);
                             match action {
                                 Action::ConfirmFolderSelection => {
                                     self.handle_action(action)?;
@@ -334,12 +577,25 @@ impl<'a> App<'a> {
                                 Action::FolderSelectorUp | Action::FolderSelectorDown => {
                                     self.handle_action(action)?;
                                 }
+                                Action::FolderSelectorExpand | Action::FolderSelectorCollapse => {
+                                    self.handle_action(action)?;
+                                }
                                 Action::CreateNewFolder => {
                                     self.handle_action(action)?;
                                 }
                                 Action::ConfirmNewFolder => {
                                     self.handle_action(action)?;
                                 }
+                                Action::RenameFolder
+                                | Action::ConfirmRenameFolder
+                                | Action::DeleteFolder
+                                | Action::DeleteFolderToRoot
+                                | Action::DeleteFolderToArchive
+                                | Action::MergeFolder
+                                | Action::ConfirmMergeFolder
+                                | Action::CancelFolderEdit => {
+                                    self.handle_action(action)?;
+                                }
                                 Action::None => {
                                     // Handle text input for filter or new folder
                                     self.handle_folder_selector_input(key);
@@ -349,17 +605,72 @@ impl<'a> App<'a> {
                             continue;
                         }
 
+                        // Handle commit popup input
+                        if self.state.commit_popup.is_some() {
+                            let action = handle_key_event(key, &self.state, &mut self.keymap);
+                            match action {
+                                Action::None => {
+                                    // Handle text input for the commit message
+                                    self.handle_commit_popup_input(key);
+                                }
+                                _ => {
+                                    self.handle_action(action)?;
+                                }
+                            }
+                            continue;
+                        }
+
+                        // Handle blame view input
+                        if self.state.blame.is_some() {
+                            let action = handle_key_event(key, &self.state, &mut self.keymap);
+                            self.handle_action(action)?;
+                            continue;
+                        }
+
+                        // Handle version history input
+                        if self.state.history.is_some() {
+                            let action = handle_key_event(key, &self.state, &mut self.keymap);
+                            self.handle_action(action)?;
+                            continue;
+                        }
+
+                        // Handle backup picker input
+                        if self.state.backup_picker.is_some() {
+                            let action = handle_key_event(key, &self.state, &mut self.keymap);
+                            self.handle_action(action)?;
+                            continue;
+                        }
+
+                        // Handle export popup input
+                        if self.state.export_popup.is_some() {
+                            let action = handle_key_event(key, &self.state, &mut self.keymap);
+                            match action {
+                                Action::None => {
+                                    // Handle text input for the destination
+                                    self.handle_export_popup_input(key);
+                                }
+                                _ => {
+                                    self.handle_action(action)?;
+                                }
+                            }
+                            continue;
+                        }
+
                         // Handle search popup input
                         if self.state.search_popup.is_some() {
-                            let action = handle_key_event(key, &self.state);
+                            let action = handle_key_event(key, &self.state, &mut self.keymap);
                             match action {
                                 Action::ConfirmSearch => {
                                     self.handle_action(action)?;
                                 }
                                 Action::CloseSearch => {
-                                    self.state.search_popup = None;
+                                    self.handle_action(action)?;
                                 }
-                                Action::SearchUp | Action::SearchDown => {
+                                Action::SearchUp
+                                | Action::SearchDown
+                                | Action::SaveSearchAsView
+                                | Action::ConfirmSaveView
+                                | Action::CancelSaveView => {
                                     self.handle_action(action)?;
                                 }
                                 Action::None => {
@@ -374,17 +685,21 @@ impl<'a> App<'a> {
                         // In Insert mode, handle vim-style sub-modes
                         if self.state.mode == Mode::Insert {
                             if let Some(ref mut editor) = self.editor {
-                                let action = handle_key_event(key, &self.state);
+                                let action = handle_key_event(key, &self.state, &mut self.keymap);
                                 self.handle_vim_editor_action(action, key)?;
                             }
                         } else {
-                            let action = handle_key_event(key, &self.state);
+                            let action = handle_key_event(key, &self.state, &mut self.keymap);
                             self.handle_action(action)?;
                         }
                     }
                 }
             }
 
+            // Pick up prompt files changed outside piemme
+            self.apply_external_changes()?;
+            self.refresh_git_status()?;
+
             // Check if we should quit
             if self.state.should_quit {
                 break;
@@ -439,13 +754,20 @@ impl<'a> App<'a> {
                         // Save on exit from insert mode
                         self.exit_insert_mode()?;
                     }
-                    Mode::Archive | Mode::Folder => {
-                        // Reload main prompts when exiting archive/folder
+                    Mode::Archive => {
+                        // Reload main prompts when exiting archive
                         self.reload_prompts()?;
                         self.state.mode = Mode::Normal;
                     }
+                    Mode::Folder => {
+                        // Go up one level: to the parent folder, or to the root
+                        let parent = self.state.current_folder.as_deref()
+                            .and_then(crate::fs::parent_folder)
+                            .map(str::to_string);
+                        self.open_folder(parent)?;
+                    }
                     Mode::Preview => {
-                        self.state.mode = Mode::Normal;
+                        self.state.mode = self.list_mode();
                     }
                     Mode::Normal => {}
                 }
@@ -453,14 +775,15 @@ impl<'a> App<'a> {
             }
             Action::TogglePreview => {
                 if self.state.mode == Mode::Preview {
-                    self.state.mode = Mode::Normal;
+                    self.state.mode = self.list_mode();
                 } else {
                     self.state.mode = Mode::Preview;
                 }
             }
             Action::OpenArchive => {
                 self.state.mode = Mode::Archive;
-                self.state.prompts = load_all_prompts(&archive_dir()?)?;
+                self.state.current_view = None;
+                self.state.prompts = self.filter_by_tags(load_all_prompts(&archive_dir()?)?);
                 self.state.selected_index = 0;
             }
 
@@ -477,6 +800,134 @@ impl<'a> App<'a> {
             Action::UnarchivePrompt => {
                 self.unarchive_current_prompt()?;
             }
+            Action::AdoptPrompt => {
+                self.adopt_selected_prompt()?;
+            }
+
+            // Backups
+            Action::OpenBackups => {
+                self.open_backup_picker()?;
+            }
+            Action::ConfirmBackupRestore => {
+                self.restore_selected_backup()?;
+            }
+            Action::CancelBackupPicker => {
+                self.state.backup_picker = None;
+            }
+            Action::BackupPickerUp => {
+                if let Some(picker) = &mut self.state.backup_picker {
+                    picker.select_previous();
+                }
+            }
+            Action::BackupPickerDown => {
+                if let Some(picker) = &mut self.state.backup_picker {
+                    picker.select_next();
+                }
+            }
+
+            // Version history
+            Action::OpenHistory => {
+                self.open_history()?;
+            }
+            Action::RestoreRevision => {
+                self.restore_selected_revision()?;
+            }
+            Action::CloseHistory => {
+                self.state.history = None;
+            }
+            Action::HistoryUp => {
+                if let Some(history) = &mut self.state.history {
+                    history.select_previous();
+                }
+            }
+            Action::HistoryDown => {
+                if let Some(history) = &mut self.state.history {
+                    history.select_next();
+                }
+            }
+            Action::HistoryToggleCompare => {
+                if let Some(history) = &mut self.state.history {
+                    history.toggle_compare();
+                }
+            }
+            Action::HistoryScrollDown => {
+                if let Some(history) = &mut self.state.history {
+                    history.diff_scroll = history.diff_scroll.saturating_add(5);
+                }
+            }
+            Action::HistoryScrollUp => {
+                if let Some(history) = &mut self.state.history {
+                    history.diff_scroll = history.diff_scroll.saturating_sub(5);
+                }
+            }
+
+            // Git
+            Action::OpenCommitPopup => {
+                self.open_commit_popup()?;
+            }
+            Action::ConfirmCommit => {
+                self.confirm_commit()?;
+            }
+            Action::CancelCommit => {
+                self.state.commit_popup = None;
+            }
+            Action::ToggleCommitScope => {
+                if let Some(popup) = &mut self.state.commit_popup
+                    && !popup.changed.is_empty()
+                {
+                    popup.all_changed = !popup.all_changed;
+                }
+            }
+            Action::OpenBlame => {
+                self.open_blame()?;
+            }
+            Action::CloseBlame => {
+                self.state.blame = None;
+            }
+            Action::BlameScrollDown => {
+                if let Some(blame) = &mut self.state.blame {
+                    let max = blame.lines.len().saturating_sub(1) as u16;
+                    blame.scroll = blame.scroll.saturating_add(1).min(max);
+                }
+            }
+            Action::BlameScrollUp => {
+                if let Some(blame) = &mut self.state.blame {
+                    blame.scroll = blame.scroll.saturating_sub(1);
+                }
+            }
+
+            // External changes
+            Action::ConflictKeepMine => {
+                self.state.conflict_popup = None;
+                self.state.notify(
+                    "Keeping your version: saving will overwrite the file on disk",
+                    NotificationLevel::Warning,
+                );
+            }
+            Action::ConflictTakeTheirs => {
+                if let Some(conflict) = self.state.conflict_popup.take() {
+                    let name = conflict.theirs.name.clone();
+                    self.take_external_version(conflict.theirs);
+                    self.state
+                        .notify(format!("Reloaded '{}' from disk", name), NotificationLevel::Info);
+                }
+            }
+            Action::ConflictToggleDiff => {
+                if let Some(conflict) = &mut self.state.conflict_popup {
+                    conflict.show_diff = !conflict.show_diff;
+                    conflict.scroll = 0;
+                }
+            }
+            Action::ConflictScrollDown => {
+                if let Some(conflict) = &mut self.state.conflict_popup {
+                    conflict.scroll = conflict.scroll.saturating_add(1);
+                }
+            }
+            Action::ConflictScrollUp => {
+                if let Some(conflict) = &mut self.state.conflict_popup {
+                    conflict.scroll = conflict.scroll.saturating_sub(1);
+                }
+            }
 
             // Clipboard
             Action::CopyRendered => {
@@ -488,10 +939,50 @@ impl<'a> App<'a> {
 
             // Tags
             Action::NextTagFilter => {
-                self.cycle_tag_filter(true);
+                self.cycle_tag_filter(true)?;
             }
             Action::PreviousTagFilter => {
-                self.cycle_tag_filter(false);
+                self.cycle_tag_filter(false)?;
+            }
+            Action::OpenTagFilter => {
+                let popup = TagFilterPopupState::new(
+                    self.state.all_tags.clone(),
+                    self.state.tag_filter.clone(),
+                );
+                self.state.tag_filter_popup = Some(popup);
+            }
+            Action::ConfirmTagFilter => {
+                if let Some(popup) = self.state.tag_filter_popup.take() {
+                    self.set_tag_filter(popup.tag_filter)?;
+                }
+            }
+            Action::CancelTagFilter => {
+                self.state.tag_filter_popup = None;
+            }
+            Action::TagFilterUp => {
+                if let Some(ref mut popup) = self.state.tag_filter_popup {
+                    popup.select_previous();
+                }
+            }
+            Action::TagFilterDown => {
+                if let Some(ref mut popup) = self.state.tag_filter_popup {
+                    popup.select_next();
+                }
+            }
+            Action::CycleTagFilterTag => {
+                if let Some(ref mut popup) = self.state.tag_filter_popup {
+                    popup.cycle_selected_tag();
+                }
+            }
+            Action::ToggleTagFilterMatch => {
+                if let Some(ref mut popup) = self.state.tag_filter_popup {
+                    popup.toggle_match_any();
+                }
+            }
+            Action::ClearTagFilter => {
+                if let Some(ref mut popup) = self.state.tag_filter_popup {
+                    popup.clear();
+                }
             }
 
             // Settings
@@ -619,6 +1110,20 @@ impl<'a> App<'a> {
                 // Handled in run loop
             }
 
+            // Command palette actions
+            Action::OpenCommandPalette => {
+                self.open_command_palette();
+            }
+            Action::ConfirmCommandPalette => {
+                self.confirm_command_palette()?;
+            }
+            Action::CancelCommandPalette => {
+                self.state.command_palette = None;
+            }
+            Action::CommandPaletteUp | Action::CommandPaletteDown => {
+                // Handled in run loop
+            }
+
             // Duplicate prompt
             Action::DuplicatePrompt => {
                 self.duplicate_current_prompt()?;
@@ -635,29 +1140,74 @@ impl<'a> App<'a> {
             }
             Action::CloseSearch => {
                 self.state.search_popup = None;
+                self.search = None;
             }
             Action::SearchUp => {
                 if let Some(ref mut search) = self.state.search_popup {
                     search.select_previous();
                     search.ensure_visible(10);
                 }
+                self.refresh_search_preview();
             }
             Action::SearchDown => {
                 if let Some(ref mut search) = self.state.search_popup {
                     search.select_next();
                     search.ensure_visible(10);
                 }
+                self.refresh_search_preview();
             }
             Action::ConfirmSearch => {
-                self.confirm_search_selection();
+                self.confirm_search_selection()?;
+            }
+            Action::SaveSearchAsView => {
+                if let Some(ref mut popup) = self.state.search_popup {
+                    if popup.query.trim().is_empty() {
+                        self.state.notify("Type a search to save first", NotificationLevel::Warning);
+                    } else {
+                        popup.view_name = Some(String::new());
+                    }
+                }
+            }
+            Action::ConfirmSaveView => {
+                self.save_search_as_view()?;
+            }
+            Action::CancelSaveView => {
+                if let Some(ref mut popup) = self.state.search_popup {
+                    popup.view_name = None;
+                }
             }
 
             // TODO: Implement these
-            Action::QuickInsertReference
-            | Action::Export => {
+            Action::QuickInsertReference => {
                 self.state.notify("Feature not yet implemented", NotificationLevel::Warning);
             }
 
+            // Export
+            Action::Export => {
+                self.open_export_popup();
+            }
+            Action::ConfirmExport => {
+                self.confirm_export()?;
+            }
+            Action::CancelExport => {
+                self.state.export_popup = None;
+            }
+            Action::ExportNextField => {
+                if let Some(ref mut popup) = self.state.export_popup {
+                    popup.focus_next();
+                }
+            }
+            Action::ExportPreviousField => {
+                if let Some(ref mut popup) = self.state.export_popup {
+                    popup.focus_previous();
+                }
+            }
+            Action::ExportOptionNext | Action::ExportOptionPrevious => {
+                if let Some(ref mut popup) = self.state.export_popup {
+                    popup.change_option(action == Action::ExportOptionNext);
+                }
+            }
+
             // Tag selector actions
             Action::OpenTagSelector => {
                 self.open_tag_selector();
@@ -686,6 +1236,32 @@ impl<'a> App<'a> {
             Action::ConfirmNewTag => {
                 self.confirm_new_tag()?;
             }
+            Action::RenameTag | Action::DeleteTag | Action::MergeTag => {
+                if let Some(ref mut selector) = self.state.tag_selector
+                    && selector.edit.is_none()
+                {
+                    let started = match action {
+                        Action::RenameTag => {
+                            selector.start_edit(|tag| TagSelectorEdit::Rename { tag })
+                        }
+                        Action::DeleteTag => {
+                            selector.start_edit(|tag| TagSelectorEdit::Delete { tag })
+                        }
+                        _ => selector.start_edit(|tag| TagSelectorEdit::Merge { tag }),
+                    };
+                    if !started {
+                        self.state.notify("Select a tag first", NotificationLevel::Info);
+                    }
+                }
+            }
+            Action::ConfirmRenameTag | Action::ConfirmDeleteTag | Action::ConfirmMergeTag => {
+                self.confirm_tag_edit()?;
+            }
+            Action::CancelTagEdit => {
+                if let Some(ref mut selector) = self.state.tag_selector {
+                    selector.cancel_edit();
+                }
+            }
 
             // Folder selector actions
             Action::OpenFolder => {
@@ -712,6 +1288,51 @@ impl<'a> App<'a> {
                     selector.select_next();
                 }
             }
+            Action::FolderSelectorExpand => {
+                if let Some(ref mut selector) = self.state.folder_selector {
+                    selector.expand_selected();
+                }
+            }
+            Action::FolderSelectorCollapse => {
+                if let Some(ref mut selector) = self.state.folder_selector {
+                    selector.collapse_selected();
+                }
+            }
+            Action::RenameFolder | Action::DeleteFolder | Action::MergeFolder => {
+                if let Some(ref mut selector) = self.state.folder_selector
+                    && selector.edit.is_none()
+                {
+                    let started = match action {
+                        Action::RenameFolder => {
+                            selector.start_edit(|folder| FolderEdit::Rename { folder })
+                        }
+                        Action::DeleteFolder => {
+                            selector.start_edit(|folder| FolderEdit::Delete { folder })
+                        }
+                        _ => selector.start_edit(|folder| FolderEdit::Merge { folder }),
+                    };
+                    if !started {
+                        self.state.notify("Select a folder first", NotificationLevel::Info);
+                    }
+                }
+            }
+            Action::ConfirmRenameFolder => {
+                self.confirm_rename_folder()?;
+            }
+            Action::DeleteFolderToRoot => {
+                self.confirm_delete_folder(false)?;
+            }
+            Action::DeleteFolderToArchive => {
+                self.confirm_delete_folder(true)?;
+            }
+            Action::ConfirmMergeFolder => {
+                self.confirm_merge_folder()?;
+            }
+            Action::CancelFolderEdit => {
+                if let Some(ref mut selector) = self.state.folder_selector {
+                    selector.cancel_edit();
+                }
+            }
             Action::CreateNewFolder => {
                 if let Some(ref mut selector) = self.state.folder_selector {
                     selector.start_creating_new();
@@ -806,7 +1427,7 @@ impl<'a> App<'a> {
             self.save_current_prompt()?;
         }
         
-        self.state.mode = Mode::Normal;
+        self.state.mode = self.list_mode();
         self.state.editor_mode = EditorMode::VimNormal; // Reset editor mode
         self.state.editor_focused = false;
         self.state.visual_anchor = None;
@@ -850,6 +1471,10 @@ impl<'a> App<'a> {
                 self.open_file_picker();
                 return Ok(());
             }
+            Action::OpenCommandPalette => {
+                self.open_command_palette();
+                return Ok(());
+            }
             Action::QuickInsertReference => {
                 self.state.notify("Quick insert not yet implemented", NotificationLevel::Warning);
                 return Ok(());
@@ -1289,11 +1914,12 @@ impl<'a> App<'a> {
         prompt.name = crate::models::prompt::make_unique_name("new_prompt", &existing_names);
         prompt.content = String::new();
 
-        // Save to disk
-        save_prompt(&prompt, &prompts_dir()?)?;
+        // Save to disk, in the folder being viewed
+        let (dir, location) = self.selected_prompt_dir()?;
+        save_prompt(&prompt, &dir, self.config.save_options())?;
 
         // Update index
-        let entry = IndexEntry::from_prompt(&prompt, "prompts");
+        let entry = IndexEntry::from_prompt(&prompt, &location);
         self.index.upsert(entry);
         self.index.save(&index_path()?)?;
 
@@ -1331,17 +1957,435 @@ impl<'a> App<'a> {
             }
         }
 
+        let (dir, location) = self.selected_prompt_dir()?;
         if let Some(prompt) = self.state.selected_prompt() {
-            save_prompt(prompt, &prompts_dir()?)?;
-
-            // Update index
-            let entry = IndexEntry::from_prompt(prompt, "prompts");
-            self.index.upsert(entry);
-            self.index.save(&index_path()?)?;
-
+            save_prompt(prompt, &dir, self.config.save_options())?;
+
+            // A broken prompt was saved verbatim; reload it to see whether it is repaired
+            if prompt.broken_error().is_some() {
+                let reloaded = load_prompt(&dir.join(format!("{}.md", prompt.name)))?;
+                let old_id = prompt.id;
+                if let Some(all_prompt) = self.all_prompts.iter_mut().find(|p| p.id == old_id) {
+                    *all_prompt = reloaded.clone();
+                }
+                let message = match reloaded.broken_error() {
+                    Some(error) => Some(format!("Frontmatter still invalid: {}", error)),
+                    None => {
+                        self.index.upsert(IndexEntry::from_prompt(&reloaded, &location));
+                        self.index.save(&index_path()?)?;
+                        None
+                    }
+                };
+                if let Some(prompt) = self.state.selected_prompt_mut() {
+                    *prompt = reloaded;
+                }
+                match message {
+                    Some(message) => self.state.notify(message, NotificationLevel::Error),
+                    None => self.state.notify("Repaired", NotificationLevel::Success),
+                }
+                return Ok(());
+            }
+
+            // Update index
+            let entry = IndexEntry::from_prompt(prompt, &location);
+            self.index.upsert(entry);
+            self.index.save(&index_path()?)?;
+
             self.state.notify("Saved", NotificationLevel::Success);
         }
 
+        // Saving writes frontmatter, so an unmanaged prompt is now managed
+        if let Some(prompt) = self.state.selected_prompt_mut() {
+            prompt.status = PromptStatus::Managed;
+            let id = prompt.id;
+            if let Some(all_prompt) = self.all_prompts.iter_mut().find(|p| p.id == id) {
+                all_prompt.status = PromptStatus::Managed;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Mode to return to from editing or preview: the folder view while a folder is open
+    fn list_mode(&self) -> Mode {
+        if self.state.current_folder.is_some() || self.state.current_view.is_some() {
+            Mode::Folder
+        } else {
+            Mode::Normal
+        }
+    }
+
+    /// Directory and index location of the prompts currently listed (in a view, of the
+    /// selected prompt)
+    fn selected_prompt_dir(&self) -> Result<(PathBuf, String)> {
+        if self.state.current_view.is_some() {
+            let location = self.state.selected_prompt()
+                .and_then(|prompt| self.index.find(&prompt.name))
+                .map_or_else(|| "prompts".to_string(), |entry| entry.location.clone());
+            return Ok((piemme_dir()?.join(&location), location));
+        }
+        Ok(match (&self.state.mode, &self.state.current_folder) {
+            (Mode::Archive, _) => (archive_dir()?, "archive".to_string()),
+            (_, Some(folder)) => (folders_dir()?.join(folder), format!("folders/{}", folder)),
+            (_, None) => (prompts_dir()?, "prompts".to_string()),
+        })
+    }
+
+    /// Open the backup picker for the selected prompt
+    fn open_backup_picker(&mut self) -> Result<()> {
+        let Some(prompt) = self.state.selected_prompt() else {
+            return Ok(());
+        };
+
+        let backups = list_backups(&backup_dir()?, prompt.id)?;
+        if backups.is_empty() {
+            let message = format!("No backups of '{}'", prompt.name);
+            self.state.notify(message, NotificationLevel::Info);
+            return Ok(());
+        }
+
+        let backups = backups
+            .into_iter()
+            .map(|backup| {
+                let preview = backup
+                    .load()
+                    .map(|p| p.first_line().to_string())
+                    .unwrap_or_else(|e| format!("(unreadable: {})", e));
+                (backup, preview)
+            })
+            .collect();
+        self.state.backup_picker = Some(BackupPickerState::new(prompt.name.clone(), backups));
+        Ok(())
+    }
+
+    /// Restore the backup selected in the picker over the selected prompt
+    fn restore_selected_backup(&mut self) -> Result<()> {
+        let Some(picker) = self.state.backup_picker.take() else {
+            return Ok(());
+        };
+        let (Some(backup), Some(prompt)) = (picker.selected(), self.state.selected_prompt()) else {
+            return Ok(());
+        };
+
+        let (dir, location) = self.selected_prompt_dir()?;
+        let restored = restore_backup(backup, prompt, &dir, self.config.save_options())?;
+        let taken = backup.taken.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S");
+
+        self.index.upsert(IndexEntry::from_prompt(&restored, &location));
+        self.index.save(&index_path()?)?;
+        replace_or_insert(&mut self.all_prompts, restored.clone());
+        let name = restored.name.clone();
+        self.replace_listed_prompt(&name, Some(restored));
+
+        self.state.notify(format!("Restored '{}' from {}", name, taken), NotificationLevel::Success);
+        Ok(())
+    }
+
+    /// Open the version history of the selected prompt
+    fn open_history(&mut self) -> Result<()> {
+        let Some(prompt) = self.state.selected_prompt() else {
+            return Ok(());
+        };
+
+        let mut revisions = list_revisions(&history_dir()?, prompt.id)?;
+        if revisions.is_empty() {
+            let message = format!("No history for '{}'", prompt.name);
+            self.state.notify(message, NotificationLevel::Info);
+            return Ok(());
+        }
+
+        revisions.reverse();
+        self.state.history = Some(HistoryState::new(prompt.name.clone(), revisions));
+        Ok(())
+    }
+
+    /// Restore the revision selected in the history, saving it as a new revision
+    fn restore_selected_revision(&mut self) -> Result<()> {
+        let Some(history) = self.state.history.take() else {
+            return Ok(());
+        };
+        let (Some(revision), Some(prompt)) = (history.selected(), self.state.selected_prompt())
+        else {
+            return Ok(());
+        };
+
+        let (dir, location) = self.selected_prompt_dir()?;
+        let restored = restore_revision(revision, prompt, &dir, self.config.save_options())?;
+
+        self.index.upsert(IndexEntry::from_prompt(&restored, &location));
+        self.index.save(&index_path()?)?;
+        replace_or_insert(&mut self.all_prompts, restored.clone());
+        let name = restored.name.clone();
+        self.replace_listed_prompt(&name, Some(restored));
+
+        let message = format!("Restored '{}' to revision #{}", name, revision.number);
+        self.state.notify(message, NotificationLevel::Success);
+        Ok(())
+    }
+
+    /// Refresh the git marks of the listed prompts, at most every couple of seconds.
+    ///
+    /// `git status` runs on a separate thread; its result is applied on a later call.
+    fn refresh_git_status(&mut self) -> Result<()> {
+        if self.git.is_none() {
+            return Ok(());
+        }
+        let (dir, _) = self.selected_prompt_dir()?;
+        let dir = dir.canonicalize().unwrap_or(dir);
+
+        // Take in a finished `git status`, unless the listed folder changed meanwhile
+        if let Some((pending_dir, receiver)) = &self.git_pending {
+            match receiver.try_recv() {
+                Ok(statuses) => {
+                    if *pending_dir == dir {
+                        self.apply_git_statuses(dir.clone(), statuses);
+                    }
+                    self.git_pending = None;
+                }
+                Err(TryRecvError::Empty) => return Ok(()),
+                Err(TryRecvError::Disconnected) => self.git_pending = None,
+            }
+        }
+
+        if let Some((refreshed, refreshed_dir)) = &self.git_refreshed
+            && *refreshed_dir == dir
+            && refreshed.elapsed() < GIT_REFRESH_INTERVAL
+        {
+            return Ok(());
+        }
+
+        let Some(repo) = self.git.clone() else {
+            return Ok(());
+        };
+        let (sender, receiver) = channel();
+        let status_dir = dir.clone();
+        thread::spawn(move || {
+            // A failing `git status` just leaves the prompts unmarked
+            let _ = sender.send(repo.statuses(&status_dir).unwrap_or_default());
+        });
+        self.git_pending = Some((dir, receiver));
+        Ok(())
+    }
+
+    /// Mark the prompts of `dir` (the listed folder) with their git status
+    fn apply_git_statuses(&mut self, dir: PathBuf, statuses: HashMap<PathBuf, GitStatus>) {
+        self.state.git_status = statuses
+            .into_iter()
+            .filter(|(path, _)| {
+                path.parent() == Some(dir.as_path()) && path.extension().is_some_and(|e| e == "md")
+            })
+            .filter_map(|(path, status)| {
+                let name = path.file_stem()?.to_str()?.to_string();
+                Some((name, status))
+            })
+            .collect();
+        self.git_refreshed = Some((Instant::now(), dir));
+    }
+
+    /// Check the pinned references of the selected prompt once per change of its content, so
+    /// drawing only looks the results up instead of running git.
+    ///
+    /// The check runs on a separate thread; its result is applied on a later call.
+    fn check_pinned_references(&mut self) {
+        let content = self.state.selected_prompt().map_or_else(String::new, |p| p.content.clone());
+
+        // Take in a finished check, unless the content changed meanwhile
+        if let Some((pending, receiver)) = &self.pinned_pending {
+            match receiver.try_recv() {
+                Ok(pinned_refs) => {
+                    if *pending == content {
+                        self.state.pinned_refs = pinned_refs;
+                        self.pinned_checked = Some(content.clone());
+                    }
+                    self.pinned_pending = None;
+                }
+                Err(TryRecvError::Empty) if *pending == content => return,
+                Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => {
+                    self.pinned_pending = None;
+                }
+            }
+        }
+
+        if self.pinned_checked.as_ref() == Some(&content) {
+            return;
+        }
+        // Without an `@` there is no revision to ask git about
+        if !content.contains('@') {
+            self.state.pinned_refs.clear();
+            self.pinned_checked = Some(content);
+            return;
+        }
+
+        let checked = content.clone();
+        let base_dir = std::env::current_dir().unwrap_or_default();
+        let (sender, receiver) = channel();
+        thread::spawn(move || {
+            let _ = sender.send(check_pinned_references(&base_dir, &checked));
+        });
+        self.pinned_pending = Some((content, receiver));
+    }
+
+    /// Resolve the selected prompt for Preview mode once per change of its content rather
+    /// than on every frame (pinned references read git)
+    fn refresh_preview(&mut self) {
+        let content = match self.state.selected_prompt() {
+            Some(prompt) if self.state.mode == Mode::Preview => prompt.content.as_str(),
+            _ => {
+                self.state.resolved_preview = None;
+                self.preview_source = None;
+                return;
+            }
+        };
+        if self.preview_source.as_deref() == Some(content) {
+            return;
+        }
+
+        // References resolve across every folder; commands are not run in preview for safety
+        let all_prompts = &self.all_prompts;
+        let get_content = |name: &str| -> Option<String> {
+            all_prompts.iter().find(|p| p.name == name).map(|p| p.content.clone())
+        };
+        let result = resolve_prompt(content, get_content, false);
+        self.preview_source = Some(content.to_string());
+        self.state.resolved_preview = Some(result.content);
+    }
+
+    /// Open the commit popup for the selected prompt
+    fn open_commit_popup(&mut self) -> Result<()> {
+        if self.git.is_none() {
+            self.state.notify("Not in a git repository", NotificationLevel::Warning);
+            return Ok(());
+        }
+        let Some(prompt) = self.state.selected_prompt() else {
+            return Ok(());
+        };
+        let selected_name = prompt.name.clone();
+
+        // The popup needs the current changes, so ask git right away
+        let (dir, _) = self.selected_prompt_dir()?;
+        let dir = dir.canonicalize().unwrap_or(dir);
+        let statuses = match &self.git {
+            Some(repo) => repo.statuses(&dir).unwrap_or_default(),
+            None => HashMap::new(),
+        };
+        self.git_pending = None;
+        self.apply_git_statuses(dir, statuses);
+        let mut changed: Vec<String> = self.state.git_status.keys().cloned().collect();
+        changed.sort();
+        if changed.is_empty() {
+            self.state.notify("Nothing to commit", NotificationLevel::Info);
+            return Ok(());
+        }
+
+        self.state.commit_popup = Some(CommitPopupState::new(selected_name, changed));
+        Ok(())
+    }
+
+    /// Handle text input in the commit popup
+    fn handle_commit_popup_input(&mut self, key: crossterm::event::KeyEvent) {
+        use crossterm::event::KeyCode;
+
+        if let Some(ref mut popup) = self.state.commit_popup {
+            match key.code {
+                KeyCode::Char(c) => popup.message.push(c),
+                KeyCode::Backspace => {
+                    popup.message.pop();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Stage and commit the prompts chosen in the commit popup
+    fn confirm_commit(&mut self) -> Result<()> {
+        let (Some(repo), Some(popup)) = (&self.git, &self.state.commit_popup) else {
+            return Ok(());
+        };
+        let (dir, _) = self.selected_prompt_dir()?;
+        let paths: Vec<PathBuf> = popup
+            .names()
+            .iter()
+            .map(|name| dir.join(format!("{}.md", name)))
+            .collect();
+
+        // Keep the popup open on failure so the message is not lost
+        match repo.commit(&paths, &popup.message) {
+            Ok(summary) => {
+                self.state.commit_popup = None;
+                self.state.notify(format!("Committed {}", summary), NotificationLevel::Success);
+            }
+            Err(e) => self.state.notify(format!("{:#}", e), NotificationLevel::Error),
+        }
+        self.git_refreshed = None;
+        self.git_pending = None;
+        Ok(())
+    }
+
+    /// Show who last changed each line of the selected prompt
+    fn open_blame(&mut self) -> Result<()> {
+        let Some(repo) = &self.git else {
+            self.state.notify("Not in a git repository", NotificationLevel::Warning);
+            return Ok(());
+        };
+        let Some(prompt) = self.state.selected_prompt() else {
+            return Ok(());
+        };
+        if self.state.git_status.get(&prompt.name) == Some(&GitStatus::Untracked) {
+            let message = format!("'{}' is not tracked by git", prompt.name);
+            self.state.notify(message, NotificationLevel::Info);
+            return Ok(());
+        }
+
+        let (dir, _) = self.selected_prompt_dir()?;
+        let path = dir.join(format!("{}.md", prompt.name));
+        match repo.blame(&path) {
+            Ok(lines) => {
+                self.state.blame = Some(BlameState {
+                    prompt_name: prompt.name.clone(),
+                    lines,
+                    scroll: 0,
+                });
+            }
+            Err(e) => self.state.notify(format!("{:#}", e), NotificationLevel::Error),
+        }
+        Ok(())
+    }
+
+    /// Adopt the selected unmanaged prompt by writing frontmatter to its file
+    fn adopt_selected_prompt(&mut self) -> Result<()> {
+        let Some(prompt) = self.state.selected_prompt() else {
+            return Ok(());
+        };
+
+        if prompt.broken_error().is_some() {
+            self.state.notify(
+                "Frontmatter is invalid: press Enter to repair it",
+                NotificationLevel::Warning,
+            );
+            return Ok(());
+        }
+        if !prompt.is_unmanaged() {
+            self.state.notify("Already managed", NotificationLevel::Info);
+            return Ok(());
+        }
+
+        let mut adopted = prompt.clone();
+        adopted.status = PromptStatus::Managed;
+        let (dir, location) = self.selected_prompt_dir()?;
+        save_prompt(&adopted, &dir, self.config.save_options())?;
+
+        self.index.upsert(IndexEntry::from_prompt(&adopted, &location));
+        self.index.save(&index_path()?)?;
+
+        if let Some(all_prompt) = self.all_prompts.iter_mut().find(|p| p.id == adopted.id) {
+            all_prompt.status = PromptStatus::Managed;
+        }
+        let name = adopted.name.clone();
+        if let Some(prompt) = self.state.selected_prompt_mut() {
+            *prompt = adopted;
+        }
+
+        self.state.notify(format!("Adopted '{}'", name), NotificationLevel::Success);
         Ok(())
     }
 
@@ -1381,18 +2425,18 @@ impl<'a> App<'a> {
             let new_name = crate::models::prompt::make_unique_name(&base_name, &existing_names);
 
             // Rename file on disk
-            let dir = prompts_dir()?;
+            let (dir, location) = self.selected_prompt_dir()?;
             crate::fs::rename_prompt(&old_name, &new_name, &dir)?;
 
             // Update index
-            self.index.remove(&old_name);
+            self.index.remove(&location, &old_name);
             
             // Update the prompt in state
             if let Some(prompt) = self.state.selected_prompt_mut() {
                 prompt.name = new_name.clone();
                 
                 // Re-add to index with new name
-                let entry = IndexEntry::from_prompt(prompt, "prompts");
+                let entry = IndexEntry::from_prompt(prompt, &location);
                 self.index.upsert(entry);
             }
             
@@ -1434,11 +2478,12 @@ impl<'a> App<'a> {
             let base_name = crate::models::prompt::generate_name_from_content(&content);
             new_prompt.name = crate::models::prompt::make_unique_name(&base_name, &existing_names);
 
-            // Save to disk
-            save_prompt(&new_prompt, &prompts_dir()?)?;
+            // Save to disk, next to the original
+            let (dir, location) = self.selected_prompt_dir()?;
+            save_prompt(&new_prompt, &dir, self.config.save_options())?;
 
             // Update index
-            let entry = IndexEntry::from_prompt(&new_prompt, "prompts");
+            let entry = IndexEntry::from_prompt(&new_prompt, &location);
             self.index.upsert(entry);
             self.index.save(&index_path()?)?;
 
@@ -1487,6 +2532,9 @@ impl<'a> App<'a> {
                     PendingAction::DeletePrompt { name } | PendingAction::PermanentDelete { name } => {
                         self.execute_delete(&name)?;
                     }
+                    PendingAction::ExportPrompts { names, options, dest } => {
+                        self.export_prompts(&names, &options, &dest)?;
+                    }
                     PendingAction::ExecuteCommandsAndCopy { content_with_refs, .. } => {
                         // User confirmed: execute commands and copy to clipboard
                         let final_content = crate::engine::resolve_commands_in_content(&content_with_refs);
@@ -1502,17 +2550,14 @@ impl<'a> App<'a> {
 
     /// Execute the actual deletion
     fn execute_delete(&mut self, name: &str) -> Result<()> {
-        // Determine directory based on mode
-        let dir = match self.state.mode {
-            Mode::Archive => archive_dir()?,
-            _ => prompts_dir()?,
-        };
+        // Determine directory based on mode and folder
+        let (dir, location) = self.selected_prompt_dir()?;
 
         // Delete from disk
         delete_prompt(name, &dir)?;
 
         // Remove from index
-        self.index.remove(name);
+        self.index.remove(&location, name);
         self.index.save(&index_path()?)?;
 
         // Remove from the filtered list
@@ -1544,17 +2589,14 @@ impl<'a> App<'a> {
         if let Some(prompt) = self.state.selected_prompt() {
             let name = prompt.name.clone();
 
-            // Determine directory based on mode
-            let dir = match self.state.mode {
-                Mode::Archive => archive_dir()?,
-                _ => prompts_dir()?,
-            };
+            // Determine directory based on mode and folder
+            let (dir, location) = self.selected_prompt_dir()?;
 
             // Delete from disk
             delete_prompt(&name, &dir)?;
 
             // Remove from index
-            self.index.remove(&name);
+            self.index.remove(&location, &name);
             self.index.save(&index_path()?)?;
 
             // Remove from list
@@ -1588,9 +2630,7 @@ impl<'a> App<'a> {
             crate::fs::move_prompt(&name, &prompts_dir()?, &archive_dir()?)?;
 
             // Update index
-            if let Some(entry) = self.index.entries.get_mut(&name) {
-                entry.location = "archive".to_string();
-            }
+            self.index.move_entry(&name, "prompts", "archive");
             self.index.save(&index_path()?)?;
 
             // Remove from current list
@@ -1628,9 +2668,7 @@ impl<'a> App<'a> {
             crate::fs::move_prompt(&name, &archive_dir()?, &prompts_dir()?)?;
 
             // Update index
-            if let Some(entry) = self.index.entries.get_mut(&name) {
-                entry.location = "prompts".to_string();
-            }
+            self.index.move_entry(&name, "archive", "prompts");
             self.index.save(&index_path()?)?;
 
             // Add to all_prompts (will be added when returning to Normal mode via reload)
@@ -1709,6 +2747,128 @@ impl<'a> App<'a> {
         Ok(())
     }
 
+    /// Open the export popup with the configured default format
+    fn open_export_popup(&mut self) {
+        if !self.state.has_prompts() {
+            self.state.notify("No prompts to export", NotificationLevel::Warning);
+            return;
+        }
+        let format = ExportFormat::from_name(&self.config.default_export_format)
+            .unwrap_or(ExportFormat::Rendered);
+        self.state.export_popup = Some(ExportPopupState::new(format));
+    }
+
+    /// Handle text input for the export destination
+    fn handle_export_popup_input(&mut self, key: crossterm::event::KeyEvent) {
+        use crossterm::event::KeyCode;
+
+        if let Some(ref mut popup) = self.state.export_popup {
+            if popup.focused != ExportField::Destination {
+                return;
+            }
+            match key.code {
+                KeyCode::Char(c) => popup.destination.push(c),
+                KeyCode::Backspace => {
+                    popup.destination.pop();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Confirm the export popup, asking first if commands would run in safe mode
+    fn confirm_export(&mut self) -> Result<()> {
+        let Some(popup) = self.state.export_popup.take() else {
+            return Ok(());
+        };
+
+        let names: Vec<String> = if popup.all_filtered {
+            self.state.prompts.iter().map(|p| p.name.clone()).collect()
+        } else {
+            self.state.selected_prompt().map(|p| p.name.clone()).into_iter().collect()
+        };
+        let options = popup.options();
+        let dest = popup.destination.trim().to_string();
+
+        if dest.is_empty() {
+            self.state.notify("Export destination is empty", NotificationLevel::Warning);
+            self.state.export_popup = Some(popup);
+            return Ok(());
+        }
+
+        if options.include_command_output && self.state.safe_mode {
+            let commands: Vec<String> = self
+                .state
+                .prompts
+                .iter()
+                .filter(|p| names.contains(&p.name))
+                .flat_map(|p| {
+                    let get_content = |name: &str| -> Option<String> {
+                        self.all_prompts.iter().find(|p| p.name == name).map(|p| p.content.clone())
+                    };
+                    crate::engine::resolve_prompt(&p.content, get_content, false).commands
+                })
+                .collect();
+
+            if !commands.is_empty() {
+                let cmd_list = commands
+                    .iter()
+                    .enumerate()
+                    .map(|(i, c)| format!("{}. {}", i + 1, c))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                let message = format!(
+                    "The following commands will be executed:\n\n{}\n\nProceed?",
+                    cmd_list
+                );
+                self.state.confirm_dialog = Some(ConfirmDialog::new(
+                    "Execute Commands?",
+                    message,
+                    PendingAction::ExportPrompts { names, options, dest },
+                ));
+                return Ok(());
+            }
+        }
+
+        self.export_prompts(&names, &options, &dest)
+    }
+
+    /// Export the named prompts from the current view to `dest`
+    fn export_prompts(&mut self, names: &[String], options: &ExportOptions, dest: &str) -> Result<()> {
+        let prompts: Vec<Prompt> = self
+            .state
+            .prompts
+            .iter()
+            .filter(|p| names.contains(&p.name))
+            .cloned()
+            .collect();
+
+        let get_content = |name: &str| -> Option<String> {
+            self.all_prompts.iter().find(|p| p.name == name).map(|p| p.content.clone())
+        };
+
+        let result = export_documents(&prompts, get_content, options)
+            .and_then(|documents| write_export(&documents, std::path::Path::new(dest)));
+
+        match result {
+            Ok(written) => {
+                let target = match written.as_slice() {
+                    [path] => path.display().to_string(),
+                    _ => dest.to_string(),
+                };
+                self.state.notify(
+                    format!("Exported {} prompt(s) to {}", prompts.len(), target),
+                    NotificationLevel::Success,
+                );
+            }
+            Err(e) => {
+                self.state.notify(format!("Export failed: {}", e), NotificationLevel::Error);
+            }
+        }
+
+        Ok(())
+    }
+
     /// Actually copy text to the system clipboard
     fn copy_text_to_clipboard(&mut self, content: &str) -> Result<()> {
         match arboard::Clipboard::new() {
@@ -1729,93 +2889,333 @@ impl<'a> App<'a> {
         Ok(())
     }
 
-    /// Cycle through tag filters
-    fn cycle_tag_filter(&mut self, forward: bool) {
+    /// Cycle through single-tag filters
+    fn cycle_tag_filter(&mut self, forward: bool) -> Result<()> {
         if self.state.all_tags.is_empty() {
+            return Ok(());
+        }
+
+        // Only a filter on exactly one tag continues from that tag; any other starts over
+        let tags = &self.state.all_tags;
+        let current = match (&self.state.tag_filter.include[..], &self.state.tag_filter.exclude[..]) {
+            ([tag], []) => tags.iter().position(|t| t == tag),
+            _ => None,
+        };
+        let next = match (current, forward) {
+            (None, true) => Some(0),
+            (None, false) => Some(tags.len() - 1),
+            (Some(pos), true) => Some(pos + 1).filter(|&next| next < tags.len()),
+            // Wrap to "all"
+            (Some(pos), false) => pos.checked_sub(1),
+        };
+        let filter = next.map_or_else(TagFilter::default, |pos| TagFilter::single(&tags[pos]));
+        self.set_tag_filter(filter)
+    }
+
+    /// Make `filter` the active tag filter, list the prompts it lets through and remember it
+    /// for the next session
+    fn set_tag_filter(&mut self, filter: TagFilter) -> Result<()> {
+        self.state.tag_filter = filter;
+        self.apply_tag_filter()?;
+
+        if self.state.tag_filter.is_empty() {
+            self.state.notify("Showing all prompts", NotificationLevel::Info);
+        } else {
+            let message = format!("Filtering by: {}", self.state.tag_filter.describe());
+            self.state.notify(message, NotificationLevel::Info);
+        }
+
+        let session = Session { tag_filter: self.state.tag_filter.clone() };
+        session.save(&session_path()?)
+    }
+
+    /// Prompts among `prompts` that pass the tag filter
+    fn filter_by_tags(&self, mut prompts: Vec<Prompt>) -> Vec<Prompt> {
+        prompts.retain(|p| self.state.tag_filter.matches(&p.tags));
+        prompts
+    }
+
+    /// List the prompts of the open folder, view or archive that pass the tag filter,
+    /// keeping the selected prompt selected if it still passes
+    fn apply_tag_filter(&mut self) -> Result<()> {
+        if self.state.current_view.is_some() {
+            return self.refresh_view();
+        }
+
+        let (_, location) = self.selected_prompt_dir()?;
+        let synced = self.sync_index_metadata()?;
+
+        let selected = self.state.selected_prompt().map(|p| p.name.clone());
+        self.state.prompts = self.filter_by_tags(synced.in_location(&location));
+        self.state.selected_index = selected
+            .and_then(|name| self.state.prompts.iter().position(|p| p.name == name))
+            .unwrap_or(self.state.selected_index)
+            .min(self.state.prompts.len().saturating_sub(1));
+        Ok(())
+    }
+
+    /// Apply prompt files created, changed or removed outside piemme since the last call
+    fn apply_external_changes(&mut self) -> Result<()> {
+        let Some(watcher) = &self.watcher else {
+            return Ok(());
+        };
+        let changed = watcher.changed_paths();
+        if changed.is_empty() {
+            return Ok(());
+        }
+        // Referenced prompts may have changed, so the preview is resolved again
+        self.preview_source = None;
+
+        let piemme = piemme_dir()?.canonicalize().unwrap_or(piemme_dir()?);
+        let (listed_dir, _) = self.selected_prompt_dir()?;
+        let listed_dir = listed_dir.canonicalize().unwrap_or(listed_dir);
+
+        for path in changed {
+            let (Some(dir), Some(name)) = (path.parent(), path.file_stem().and_then(|s| s.to_str()))
+            else {
+                continue;
+            };
+            if is_own_write(&path) {
+                // Saved by piemme, which already updated the list
+                continue;
+            }
+            let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+            let Ok(location) = dir.strip_prefix(&piemme) else {
+                continue;
+            };
+            let location = location.to_string_lossy().replace('\\', "/");
+            let in_archive = location == "archive";
+            let listed = dir == listed_dir;
+
+            // Files that vanished mid-write or cannot be read are treated as removed
+            let loaded = if path.exists() { load_prompt(&path).ok() } else { None };
+
+            let editing = self.state.mode == Mode::Insert
+                && listed
+                && self.state.selected_prompt().is_some_and(|p| p.name == name);
+            if editing {
+                self.handle_external_edit(name, loaded);
+                continue;
+            }
+
+            match loaded {
+                Some(prompt) => {
+                    self.index.upsert(IndexEntry::from_prompt(&prompt, &location));
+                    if !in_archive {
+                        replace_or_insert(&mut self.all_prompts, prompt.clone());
+                    }
+                    if listed {
+                        self.replace_listed_prompt(name, Some(prompt));
+                    }
+                }
+                None => {
+                    self.index.remove(&location, name);
+                    if !in_archive {
+                        self.all_prompts.retain(|p| p.name != name);
+                    }
+                    if listed {
+                        self.replace_listed_prompt(name, None);
+                    }
+                }
+            }
+        }
+
+        self.index.save(&index_path()?)?;
+        self.state.all_tags = collect_tags(&self.all_prompts);
+
+        // Views list whatever matches now (but not while one of their prompts is edited)
+        if self.state.mode != Mode::Insert {
+            self.refresh_view()?;
+        }
+        Ok(())
+    }
+
+    /// React to the prompt open in the editor changing on disk
+    fn handle_external_edit(&mut self, name: &str, loaded: Option<Prompt>) {
+        let Some(theirs) = loaded else {
+            self.state.notify(
+                format!("'{}' was deleted on disk: saving will recreate it", name),
+                NotificationLevel::Warning,
+            );
+            return;
+        };
+        let Some(mine) = self.state.selected_prompt() else {
+            return;
+        };
+
+        let editor_text = self.editor.as_ref().map(|e| e.lines().join("\n"));
+        if editor_text.as_deref().is_none_or(|text| text == mine.content) {
+            // No unsaved edits to lose
+            self.take_external_version(theirs);
+            self.state.notify(format!("Reloaded '{}' from disk", name), NotificationLevel::Info);
+        } else if let Some(text) = editor_text {
+            self.state.conflict_popup = Some(ConflictState::new(text, theirs));
+        }
+    }
+
+    /// Replace the selected prompt (and the editor's content) with the version from disk
+    fn take_external_version(&mut self, theirs: Prompt) {
+        if self.state.mode == Mode::Insert {
+            let lines: Vec<String> = theirs.content.lines().map(String::from).collect();
+            let mut textarea = TextArea::new(if lines.is_empty() {
+                vec![String::new()]
+            } else {
+                lines
+            });
+            textarea.move_cursor(CursorMove::Top);
+            self.editor = Some(textarea);
+        }
+
+        replace_or_insert(&mut self.all_prompts, theirs.clone());
+        let name = theirs.name.clone();
+        self.replace_listed_prompt(&name, Some(theirs));
+    }
+
+    /// Update, add or remove a prompt in the listed prompts, keeping the selection on the same name
+    fn replace_listed_prompt(&mut self, name: &str, prompt: Option<Prompt>) {
+        let selected_name = self.state.selected_prompt().map(|p| p.name.clone());
+        let position = self.state.prompts.iter().position(|p| p.name == name);
+
+        match (position, prompt) {
+            (Some(pos), Some(prompt)) => self.state.prompts[pos] = prompt,
+            (Some(pos), None) => {
+                self.state.prompts.remove(pos);
+            }
+            (None, Some(prompt)) => {
+                if self.state.tag_filter.matches(&prompt.tags) {
+                    replace_or_insert(&mut self.state.prompts, prompt);
+                }
+            }
+            (None, None) => {}
+        }
+
+        if let Some(pos) = selected_name
+            .and_then(|selected| self.state.prompts.iter().position(|p| p.name == selected))
+        {
+            self.state.selected_index = pos;
+        }
+        if self.state.selected_index >= self.state.prompts.len() {
+            self.state.selected_index = self.state.prompts.len().saturating_sub(1);
+        }
+    }
+
+    /// Take in the prompt bodies read in the background once they are all read
+    fn poll_bodies(&mut self) {
+        let Some(receiver) = &self.bodies_pending else {
+            return;
+        };
+        match receiver.try_recv() {
+            Ok(bodies) => {
+                self.bodies_pending = None;
+                self.fill_bodies(bodies);
+            }
+            Err(TryRecvError::Empty) => self.load_selected_body(),
+            // A failed reader leaves the bodies to the next sync, which reads missing ones
+            Err(TryRecvError::Disconnected) => self.bodies_pending = None,
+        }
+    }
+
+    /// Wait for the prompt bodies being read in the background and fill them in
+    fn wait_for_bodies(&mut self) {
+        if let Some(receiver) = self.bodies_pending.take() {
+            self.fill_bodies(receiver.recv().unwrap_or_default());
+        }
+    }
+
+    /// Whether `key` only moves the selection in the prompt list
+    fn moves_in_list(&self, key: KeyEvent) -> bool {
+        let Some(mode) = KeymapMode::of(&self.state) else {
+            return false;
+        };
+        matches!(mode, KeymapMode::Normal | KeymapMode::Folder | KeymapMode::Archive)
+            && !self.keymap.is_pending()
+            && handle_overlay_keys(key, &self.state, &self.keymap).is_none()
+            && matches!(
+                self.keymap.action_for(mode, &[KeyChord::from_event(key)]),
+                Some(Action::MoveUp | Action::MoveDown | Action::GoToFirst | Action::GoToLast)
+            )
+    }
+
+    /// Read the selected prompt's body now if the background reader has not yet
+    fn load_selected_body(&mut self) {
+        let Some(name) = self.state.selected_prompt().map(|p| p.name.clone()) else {
+            return;
+        };
+        let Ok((dir, location)) = self.selected_prompt_dir() else {
+            return;
+        };
+        if self.index.get(&location, &name).is_none_or(|entry| entry.content.is_some()) {
             return;
         }
+        if let Ok(loaded) = load_prompt(&dir.join(format!("{}.md", name))) {
+            let content = loaded.content;
+            self.fill_bodies(vec![Body { location, name, content }]);
+        }
+    }
 
-        match &self.state.tag_filter {
-            None => {
-                // Start filtering by first/last tag
-                let tag = if forward {
-                    self.state.all_tags.first()
-                } else {
-                    self.state.all_tags.last()
-                };
-                self.state.tag_filter = tag.cloned();
-            }
-            Some(current) => {
-                if let Some(pos) = self.state.all_tags.iter().position(|t| t == current) {
-                    if forward {
-                        if pos + 1 < self.state.all_tags.len() {
-                            self.state.tag_filter = Some(self.state.all_tags[pos + 1].clone());
-                        } else {
-                            self.state.tag_filter = None; // Wrap to "all"
-                        }
-                    } else {
-                        if pos > 0 {
-                            self.state.tag_filter = Some(self.state.all_tags[pos - 1].clone());
-                        } else {
-                            self.state.tag_filter = None; // Wrap to "all"
-                        }
-                    }
-                }
+    /// Fill in bodies the index and the listed prompts are still missing
+    fn fill_bodies(&mut self, bodies: Vec<Body>) {
+        let listed_location = self.selected_prompt_dir().ok().map(|(_, location)| location);
+        for body in bodies {
+            if !self.index.set_body(&body.location, &body.name, body.content.clone()) {
+                continue;
+            }
+            let listed_here = listed_location.as_deref() == Some(body.location.as_str());
+            let listed = self
+                .state
+                .prompts
+                .iter_mut()
+                .filter(|p| listed_here && p.name == body.name);
+            let active = self
+                .all_prompts
+                .iter_mut()
+                .filter(|p| body.location != "archive" && p.name == body.name && p.content.is_empty());
+            for prompt in listed.chain(active) {
+                prompt.content = body.content.clone();
             }
         }
+    }
 
-        // Apply the filter to the prompt list
-        self.apply_tag_filter();
-
-        // Show notification
-        if let Some(tag) = &self.state.tag_filter {
-            self.state.notify(format!("Filtering by: {}", tag), NotificationLevel::Info);
-        } else {
-            self.state.notify("Showing all prompts", NotificationLevel::Info);
+    /// Bring the index up to date with the prompt files, saving it when it changed and
+    /// warning about files that could not be read
+    fn sync_index(&mut self) -> Result<SyncedPrompts> {
+        let synced = self.index.sync(&piemme_dir()?)?;
+        if synced.changed() {
+            self.index.save(&index_path()?)?;
         }
+        if let Some(message) = synced.failure_message() {
+            self.state.notify(message, NotificationLevel::Warning);
+        }
+        Ok(synced)
     }
 
-    /// Apply the current tag filter to the prompt list
-    fn apply_tag_filter(&mut self) {
-        match &self.state.tag_filter {
-            None => {
-                // Show all prompts
-                self.state.prompts = self.all_prompts.clone();
-            }
-            Some(tag) => {
-                // Filter prompts that have this tag
-                self.state.prompts = self.all_prompts
-                    .iter()
-                    .filter(|p| p.tags.contains(tag))
-                    .cloned()
-                    .collect();
-            }
+    /// Bring the index's prompt list up to date, reading changed bodies in the background
+    fn sync_index_metadata(&mut self) -> Result<SyncedPrompts> {
+        let synced = self.index.sync_metadata(&piemme_dir()?)?;
+        if synced.changed() {
+            self.index.save(&index_path()?)?;
         }
-        
-        // Reset selection if it's out of bounds
-        if self.state.selected_index >= self.state.prompts.len() {
-            self.state.selected_index = self.state.prompts.len().saturating_sub(1);
+        if let Some(message) = synced.failure_message() {
+            self.state.notify(message, NotificationLevel::Warning);
         }
+        let previous = self.bodies_pending.take();
+        self.bodies_pending = read_bodies_in_background(&synced.unread, previous);
+        Ok(synced)
     }
 
     /// Reload prompts from disk
     fn reload_prompts(&mut self) -> Result<()> {
-        let prompts = load_all_prompts(&prompts_dir()?)?;
-        self.state.prompts = prompts;
+        let synced = self.sync_index()?;
+        self.state.prompts = self.filter_by_tags(synced.in_location("prompts"));
         self.state.current_folder = None;
-        self.state.tag_filter = None;  // Reset filter when reloading
+        self.state.current_view = None;
         
         // Reload ALL prompts from all locations (for reference resolution)
-        self.all_prompts = load_all_prompts_everywhere()?;
+        self.all_prompts = synced.active();
+        self.archived_count = synced.archived();
         
         // Re-collect all tags
-        let mut all_tags: Vec<String> = self.all_prompts
-            .iter()
-            .flat_map(|p| p.tags.clone())
-            .collect();
-        all_tags.sort();
-        all_tags.dedup();
-        self.state.all_tags = all_tags;
+        self.state.all_tags = collect_tags(&self.all_prompts);
         
         if self.state.selected_index >= self.state.prompts.len() {
             self.state.selected_index = self.state.prompts.len().saturating_sub(1);
@@ -1914,18 +3314,18 @@ impl<'a> App<'a> {
         }
 
         // Rename file on disk
-        let dir = prompts_dir()?;
+        let (dir, location) = self.selected_prompt_dir()?;
         crate::fs::rename_prompt(&old_name, &new_name, &dir)?;
 
         // Update index
-        self.index.remove(&old_name);
+        self.index.remove(&location, &old_name);
 
         // Update the prompt in state
         if let Some(prompt) = self.state.selected_prompt_mut() {
             prompt.name = new_name.clone();
 
             // Re-add to index with new name
-            let entry = IndexEntry::from_prompt(prompt, "prompts");
+            let entry = IndexEntry::from_prompt(prompt, &location);
             self.index.upsert(entry);
         }
 
@@ -2007,6 +3407,60 @@ impl<'a> App<'a> {
         Ok(())
     }
 
+    /// Open the command palette with the actions of the current mode
+    fn open_command_palette(&mut self) {
+        let Some(mode) = KeymapMode::of(&self.state) else {
+            return;
+        };
+        let commands = self
+            .keymap
+            .commands(mode)
+            .into_iter()
+            .map(|action| PaletteCommand {
+                keys: self.keymap.keys_for(mode, &action).join(" / "),
+                action,
+            })
+            .collect();
+        self.state.command_palette = Some(CommandPaletteState::new(commands));
+    }
+
+    /// Handle text input in the command palette
+    fn handle_command_palette_input(&mut self, key: KeyEvent) {
+        use crossterm::event::KeyCode;
+
+        if let Some(ref mut palette) = self.state.command_palette {
+            match key.code {
+                KeyCode::Char(c) => {
+                    palette.filter.push(c);
+                    palette.update_filter();
+                }
+                KeyCode::Backspace => {
+                    palette.filter.pop();
+                    palette.update_filter();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Run the action picked in the command palette as if its key was pressed
+    fn confirm_command_palette(&mut self) -> Result<()> {
+        let action = match self.state.command_palette.take() {
+            Some(palette) => match palette.selected() {
+                Some(command) => command.action.clone(),
+                None => return Ok(()),
+            },
+            None => return Ok(()),
+        };
+        if self.state.mode == Mode::Insert {
+            // In the editor, actions go where its keys go
+            let key = KeyEvent::from(crossterm::event::KeyCode::Null);
+            self.handle_vim_editor_action(action, key)
+        } else {
+            self.handle_action(action)
+        }
+    }
+
     /// Open the file picker popup (for Ctrl+f in insert mode)
     fn open_file_picker(&mut self) {
         // Only allow file picker popup in Insert mode (editor is active)
@@ -2084,7 +3538,9 @@ impl<'a> App<'a> {
         use crossterm::event::KeyCode;
 
         if let Some(ref mut selector) = self.state.tag_selector {
-            if selector.creating_new {
+            if matches!(selector.edit, Some(TagSelectorEdit::Delete { .. })) {
+                // Only the confirmation keys apply while confirming a delete
+            } else if selector.is_typing_name() {
                 match key.code {
                     KeyCode::Char(c) => {
                         selector.new_tag_input.push(c);
@@ -2110,6 +3566,25 @@ impl<'a> App<'a> {
         }
     }
 
+    /// Handle text input in tag filter popup
+    fn handle_tag_filter_input(&mut self, key: crossterm::event::KeyEvent) {
+        use crossterm::event::KeyCode;
+
+        if let Some(ref mut popup) = self.state.tag_filter_popup {
+            match key.code {
+                KeyCode::Char(c) => {
+                    popup.filter.push(c);
+                    popup.update_filter();
+                }
+                KeyCode::Backspace => {
+                    popup.filter.pop();
+                    popup.update_filter();
+                }
+                _ => {}
+            }
+        }
+    }
+
     /// Toggle the selected tag on the current prompt
     fn toggle_tag(&mut self) -> Result<()> {
         let (tag, added) = {
@@ -2210,10 +3685,79 @@ impl<'a> App<'a> {
         Ok(())
     }
 
+    /// Rename, delete or merge the tag being edited in the tag selector on every prompt
+    fn confirm_tag_edit(&mut self) -> Result<()> {
+        let Some(selector) = &self.state.tag_selector else {
+            return Ok(());
+        };
+        let edit = match selector.edit.clone() {
+            Some(TagSelectorEdit::Rename { tag }) => {
+                TagEdit::Rename { from: tag, to: normalize_tag(&selector.new_tag_input) }
+            }
+            Some(TagSelectorEdit::Delete { tag }) => TagEdit::Delete(tag),
+            Some(TagSelectorEdit::Merge { tag }) => {
+                let Some(into) = selector.selected_tag().map(str::to_string) else {
+                    self.state.notify("Select the tag to merge into", NotificationLevel::Info);
+                    return Ok(());
+                };
+                TagEdit::Merge { from: tag, into }
+            }
+            None => return Ok(()),
+        };
+
+        // Check against every tag in use, archived prompts included
+        self.sync_index()?;
+        // Keep the input open on errors so the name can be fixed
+        if let Err(e) = edit.validate(&self.index.all_tags()) {
+            self.state.notify(format!("{:#}", e), NotificationLevel::Error);
+            return Ok(());
+        }
+
+        let changed = edit_tags(&piemme_dir()?, &edit, self.config.save_options())?;
+        self.tags_changed(&edit)?;
+        let message = match &edit {
+            TagEdit::Rename { from, to } => format!("Renamed tag '{}' to '{}'", from, to),
+            TagEdit::Merge { from, into } => format!("Merged tag '{}' into '{}'", from, into),
+            TagEdit::Delete(tag) => format!("Deleted tag '{}'", tag),
+        };
+        let message = format!("{} on {} prompt(s)", message, changed.len());
+        self.state.notify(message, NotificationLevel::Success);
+        Ok(())
+    }
+
+    /// Follow a tag renamed, merged or deleted on every prompt file: in the tag colors, the tag
+    /// filter, the tag selector and the listed prompts
+    fn tags_changed(&mut self, edit: &TagEdit) -> Result<()> {
+        let (_, env_vars) = self.config.edit_tag_colors_in_layers(edit)?;
+        if let Some(var) = env_vars.first() {
+            let message = format!("Tag color set by {} is not renamed; update the variable", var);
+            self.state.notify(message, NotificationLevel::Warning);
+        }
+
+        if self.state.tag_filter.apply_edit(edit) {
+            let session = Session { tag_filter: self.state.tag_filter.clone() };
+            session.save(&session_path()?)?;
+        }
+        if let Some(ref mut selector) = self.state.tag_selector {
+            selector.apply_edit(edit);
+        }
+
+        let synced = self.sync_index()?;
+        self.all_prompts = synced.active();
+        self.state.all_tags = collect_tags(&self.all_prompts);
+        self.apply_tag_filter()
+    }
+
     /// Open the folder selector popup
     fn open_folder_selector(&mut self, mode: FolderSelectorMode) -> Result<()> {
         let folders = crate::fs::list_folders()?;
-        let selector = FolderSelectorState::new(folders, mode);
+        let mut selector = FolderSelectorState::new(folders, mode);
+        if mode == FolderSelectorMode::Open {
+            selector.set_views(load_views(&self.config, &views_path()?)?.into_iter().collect());
+        }
+        if let Some(folder) = &self.state.current_folder {
+            selector.reveal(folder);
+        }
         self.state.folder_selector = Some(selector);
         Ok(())
     }
@@ -2223,7 +3767,9 @@ impl<'a> App<'a> {
         use crossterm::event::KeyCode;
 
         if let Some(ref mut selector) = self.state.folder_selector {
-            if selector.creating_new {
+            if matches!(selector.edit, Some(FolderEdit::Delete { .. })) {
+                // Only the choice keys apply while confirming a delete
+            } else if selector.is_typing_name() {
                 match key.code {
                     KeyCode::Char(c) => {
                         selector.new_folder_input.push(c);
@@ -2249,6 +3795,35 @@ impl<'a> App<'a> {
         }
     }
 
+    /// List the prompts of `folder` (a nested path like `work/backend`), or the root for None
+    fn open_folder(&mut self, folder: Option<String>) -> Result<()> {
+        self.state.current_view = None;
+        match folder {
+            None => {
+                // Go to root - reload prompts from main prompts directory
+                self.state.current_folder = None;
+                self.state.mode = Mode::Normal;
+                self.reload_prompts()?;
+                self.state.notify("Viewing all prompts", NotificationLevel::Info);
+            }
+            Some(folder) => {
+                // Load prompts from selected folder
+                let folder_path = folders_dir()?.join(&folder);
+                if folder_path.exists() {
+                    let prompts = load_all_prompts(&folder_path)?;
+                    self.state.prompts = self.filter_by_tags(prompts);
+                    self.state.current_folder = Some(folder.clone());
+                    self.state.mode = Mode::Folder;
+                    self.state.selected_index = 0;
+                    self.state.notify(format!("Opened folder: {}", folder), NotificationLevel::Info);
+                } else {
+                    self.state.notify(format!("Folder not found: {}", folder), NotificationLevel::Error);
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Confirm folder selection (open folder or move prompt)
     fn confirm_folder_selection(&mut self) -> Result<()> {
         let selector = match self.state.folder_selector.take() {
@@ -2259,40 +3834,15 @@ impl<'a> App<'a> {
         let selected = selector.selected_folder().map(|s| s.to_string());
 
         match selector.mode {
-            FolderSelectorMode::Open => {
-                // Open the folder (or go to root)
-                match selected {
-                    None => {
-                        // Go to root - reload prompts from main prompts directory
-                        self.state.current_folder = None;
-                        self.state.mode = Mode::Normal;
-                        self.reload_prompts()?;
-                        self.state.notify("Viewing all prompts", NotificationLevel::Info);
-                    }
-                    Some(folder) => {
-                        // Load prompts from selected folder
-                        let folder_path = folders_dir()?.join(&folder);
-                        if folder_path.exists() {
-                            let prompts = load_all_prompts(&folder_path)?;
-                            self.state.prompts = prompts;
-                            self.state.current_folder = Some(folder.clone());
-                            self.state.mode = Mode::Folder;
-                            self.state.selected_index = 0;
-                            self.state.notify(format!("Opened folder: {}", folder), NotificationLevel::Info);
-                        } else {
-                            self.state.notify(format!("Folder not found: {}", folder), NotificationLevel::Error);
-                        }
-                    }
-                }
-            }
+            FolderSelectorMode::Open => match selector.selected_view() {
+                Some(view) => self.open_view(view.to_string())?,
+                None => self.open_folder(selected)?,
+            },
             FolderSelectorMode::Move => {
                 // Move the current prompt to the selected folder
                 if let Some(prompt) = self.state.selected_prompt() {
                     let name = prompt.name.clone();
-                    let source_dir = match &self.state.current_folder {
-                        None => prompts_dir()?,
-                        Some(folder) => folders_dir()?.join(folder),
-                    };
+                    let (source_dir, source) = self.selected_prompt_dir()?;
 
                     let dest_dir = match selected {
                         None => prompts_dir()?,
@@ -2307,9 +3857,7 @@ impl<'a> App<'a> {
                             None => "prompts".to_string(),
                             Some(folder) => format!("folders/{}", folder),
                         };
-                        if let Some(entry) = self.index.entries.get_mut(&name) {
-                            entry.location = location;
-                        }
+                        self.index.move_entry(&name, &source, &location);
                         self.index.save(&index_path()?)?;
 
                         // Remove from current list
@@ -2336,6 +3884,112 @@ impl<'a> App<'a> {
         Ok(())
     }
 
+    /// Rename the folder being edited in the folder selector to the typed path
+    fn confirm_rename_folder(&mut self) -> Result<()> {
+        let Some(selector) = &self.state.folder_selector else {
+            return Ok(());
+        };
+        let Some(FolderEdit::Rename { folder }) = selector.edit.clone() else {
+            return Ok(());
+        };
+        let new_folder = crate::fs::normalize_folder_path(&selector.new_folder_input);
+
+        // Keep the input open on errors so the name can be fixed
+        if let Err(e) = crate::fs::rename_folder(&folders_dir()?, &folder, &new_folder) {
+            self.state.notify(format!("{:#}", e), NotificationLevel::Error);
+            return Ok(());
+        }
+        self.index.rename_location(&format!("folders/{}", folder), &format!("folders/{}", new_folder));
+        self.index.save(&index_path()?)?;
+
+        self.folders_changed(&folder, Some(&new_folder))?;
+        self.state.notify(format!("Renamed folder '{}' to '{}'", folder, new_folder), NotificationLevel::Success);
+        Ok(())
+    }
+
+    /// Delete the folder being edited, moving its prompts to the main list or the archive
+    fn confirm_delete_folder(&mut self, to_archive: bool) -> Result<()> {
+        let Some(FolderEdit::Delete { folder }) =
+            self.state.folder_selector.as_ref().and_then(|s| s.edit.clone())
+        else {
+            return Ok(());
+        };
+        let (dest, location) = if to_archive {
+            (archive_dir()?, "archive")
+        } else {
+            (prompts_dir()?, "prompts")
+        };
+
+        let moved = match crate::fs::delete_folder(&folders_dir()?, &folder, &dest) {
+            Ok(moved) => moved,
+            Err(e) => {
+                if let Some(ref mut selector) = self.state.folder_selector {
+                    selector.cancel_edit();
+                }
+                self.state.notify(format!("{:#}", e), NotificationLevel::Error);
+                return Ok(());
+            }
+        };
+        self.index.flatten_location(&format!("folders/{}", folder), location);
+        self.index.save(&index_path()?)?;
+        if to_archive {
+            self.archived_count += moved.len();
+        }
+
+        self.folders_changed(&folder, None)?;
+        let message = format!("Deleted folder '{}', moved {} prompt(s) to {}", folder, moved.len(), location);
+        self.state.notify(message, NotificationLevel::Success);
+        Ok(())
+    }
+
+    /// Merge the folder being edited into the selected folder
+    fn confirm_merge_folder(&mut self) -> Result<()> {
+        let Some(selector) = &self.state.folder_selector else {
+            return Ok(());
+        };
+        let Some(FolderEdit::Merge { folder }) = selector.edit.clone() else {
+            return Ok(());
+        };
+        let Some(into) = selector.selected_folder().map(str::to_string) else {
+            self.state.notify("Select the folder to merge into", NotificationLevel::Info);
+            return Ok(());
+        };
+
+        if let Err(e) = crate::fs::merge_folder(&folders_dir()?, &folder, &into) {
+            self.state.notify(format!("{:#}", e), NotificationLevel::Error);
+            return Ok(());
+        }
+        self.index.rename_location(&format!("folders/{}", folder), &format!("folders/{}", into));
+        self.index.save(&index_path()?)?;
+
+        self.folders_changed(&folder, Some(&into))?;
+        self.state.notify(format!("Merged folder '{}' into '{}'", folder, into), NotificationLevel::Success);
+        Ok(())
+    }
+
+    /// Refresh the folder selector and the open folder after `folder` was renamed or merged
+    /// into `replacement`, or deleted (None)
+    fn folders_changed(&mut self, folder: &str, replacement: Option<&str>) -> Result<()> {
+        if let Some(ref mut selector) = self.state.folder_selector {
+            selector.set_folders(crate::fs::list_folders()?, replacement);
+        }
+
+        // Follow the open folder (or one of its subfolders) to its new place
+        match self.state.current_folder.clone() {
+            Some(open) => {
+                if let Some(rest) = open.strip_prefix(folder)
+                    && (rest.is_empty() || rest.starts_with('/'))
+                {
+                    self.open_folder(replacement.map(|new| format!("{}{}", new, rest)))?;
+                }
+            }
+            // A deleted folder's prompts may have moved into the main list
+            None if replacement.is_none() => self.reload_prompts()?,
+            None => {}
+        }
+        Ok(())
+    }
+
     /// Confirm new folder creation
     fn confirm_new_folder(&mut self) -> Result<()> {
         let (new_folder, mode) = {
@@ -2355,19 +4009,14 @@ impl<'a> App<'a> {
             if mode == FolderSelectorMode::Move {
                 if let Some(prompt) = self.state.selected_prompt() {
                     let name = prompt.name.clone();
-                    let source_dir = match &self.state.current_folder {
-                        None => prompts_dir()?,
-                        Some(f) => folders_dir()?.join(f),
-                    };
+                    let (source_dir, source) = self.selected_prompt_dir()?;
                     let dest_dir = folders_dir()?.join(&folder);
 
                     crate::fs::move_prompt(&name, &source_dir, &dest_dir)?;
 
                     // Update index
                     let location = format!("folders/{}", folder);
-                    if let Some(entry) = self.index.entries.get_mut(&name) {
-                        entry.location = location;
-                    }
+                    self.index.move_entry(&name, &source, &location);
                     self.index.save(&index_path()?)?;
 
                     // Remove from current list
@@ -2390,12 +4039,14 @@ impl<'a> App<'a> {
         Ok(())
     }
 
-    /// Open the fuzzy search popup
+    /// Open the search popup, matching every indexed prompt in the background
     fn open_search_popup(&mut self) {
-        let mut popup = SearchPopupState::new();
-        // Start with all prompts shown
-        popup.results = self.build_search_results("");
-        self.state.search_popup = Some(popup);
+        let entries = self.index.all_entries().cloned().collect();
+        let mut search = BackgroundSearch::new(entries);
+        search.tick(10);
+        self.search = Some(search);
+        self.state.search_popup = Some(SearchPopupState::new());
+        self.refresh_search_results();
     }
 
     /// Handle text input in search popup
@@ -2403,6 +4054,16 @@ impl<'a> App<'a> {
         use crossterm::event::KeyCode;
 
         let query = if let Some(ref mut popup) = self.state.search_popup {
+            if let Some(ref mut name) = popup.view_name {
+                match key.code {
+                    KeyCode::Char(c) => name.push(c),
+                    KeyCode::Backspace => {
+                        name.pop();
+                    }
+                    _ => {}
+                }
+                return;
+            }
             match key.code {
                 KeyCode::Char(c) => {
                     popup.query.push(c);
@@ -2412,115 +4073,319 @@ impl<'a> App<'a> {
                 }
                 _ => return,
             }
-            popup.query.clone()
+            match &self.search {
+                Some(search) => search.parse(&popup.query),
+                None => Query::parse(&popup.query),
+            }
         } else {
             return;
         };
 
-        // Update results after modifying query
-        let results = self.build_search_results(&query);
+        // Match the new query; results arrive as the matcher progresses
         if let Some(ref mut popup) = self.state.search_popup {
-            popup.results = results;
+            popup.error = query.errors.first().cloned();
             popup.selected_index = 0;
             popup.scroll_offset = 0;
         }
+        if let Some(search) = self.search.as_mut() {
+            search.set_query(query);
+            search.tick(10);
+        }
+        self.refresh_search_results();
     }
 
-    /// Build fuzzy search results using nucleo
-    fn build_search_results(&self, query: &str) -> Vec<SearchResult> {
-        use nucleo::{Matcher, Utf32Str, Config as NucleoConfig};
-        use nucleo::pattern::{Pattern, CaseMatching, Normalization, AtomKind};
-        
-        if query.is_empty() {
-            // Return all prompts sorted alphabetically when no query
-            return self.all_prompts
-                .iter()
-                .map(|p| SearchResult {
-                    name: p.name.clone(),
-                    preview: p.content.lines().next().unwrap_or("").to_string(),
-                    score: 0,
-                    name_match_indices: Vec::new(),
-                })
-                .collect();
+    /// Save the search popup's query under the typed name in `.piemme/views.yaml`
+    fn save_search_as_view(&mut self) -> Result<()> {
+        let Some(ref mut popup) = self.state.search_popup else {
+            return Ok(());
+        };
+        let Some(name) = popup.view_name.take() else {
+            return Ok(());
+        };
+        let name = name.trim();
+        if name.is_empty() {
+            self.state.notify("A view needs a name", NotificationLevel::Warning);
+            return Ok(());
         }
 
-        let mut matcher = Matcher::new(NucleoConfig::DEFAULT);
-        let pattern = Pattern::new(
-            query,
-            CaseMatching::Smart,
-            Normalization::Smart,
-            AtomKind::Fuzzy,
-        );
+        let query = popup.query.trim().to_string();
+        save_view(&views_path()?, name, &query)?;
+        self.state.notify(format!("Saved view '{}': {}", name, query), NotificationLevel::Success);
+        Ok(())
+    }
 
-        let mut results: Vec<SearchResult> = Vec::new();
+    /// List the prompts matching the saved search `name`, as a virtual folder
+    fn open_view(&mut self, name: String) -> Result<()> {
+        self.state.current_folder = None;
+        self.state.current_view = Some(name.clone());
+        self.state.mode = Mode::Folder;
+        self.state.selected_index = 0;
+        self.refresh_view()?;
+        self.state.notify(format!("Opened view: {}", name), NotificationLevel::Info);
+        Ok(())
+    }
 
-        for prompt in &self.all_prompts {
-            // Convert strings to Utf32Str using buffers
-            let mut name_buf = Vec::new();
-            let mut content_buf = Vec::new();
-            
-            let name_utf32 = Utf32Str::new(&prompt.name, &mut name_buf);
-            let content_utf32 = Utf32Str::new(&prompt.content, &mut content_buf);
+    /// Evaluate the open view again, keeping the selected prompt selected if it still matches
+    fn refresh_view(&mut self) -> Result<()> {
+        let Some(name) = self.state.current_view.clone() else {
+            return Ok(());
+        };
+        let views = load_views(&self.config, &views_path()?)?;
+        let Some(text) = views.get(&name) else {
+            self.state.notify(format!("View '{}' no longer exists", name), NotificationLevel::Warning);
+            return self.open_folder(None);
+        };
 
-            // Match against name
-            let name_score = pattern.score(name_utf32, &mut matcher);
-            
-            // Also match against content
-            let content_score = pattern.score(content_utf32, &mut matcher);
-
-            // Use the best score
-            let score = name_score.unwrap_or(0).max(content_score.unwrap_or(0));
-
-            // Get match indices for the name (for highlighting)
-            let mut match_indices = Vec::new();
-            if name_score.is_some() && name_score >= content_score {
-                // Use the indices method to get match positions
-                let mut indices_buf = Vec::new();
-                pattern.indices(name_utf32, &mut matcher, &mut indices_buf);
-                match_indices = indices_buf.iter().map(|&i| i as usize).collect();
-            }
-
-            if score > 0 {
-                results.push(SearchResult {
-                    name: prompt.name.clone(),
-                    preview: prompt.content.lines().next().unwrap_or("").to_string(),
-                    score,
-                    name_match_indices: match_indices,
-                });
-            }
-        }
+        let synced = self.sync_index()?;
+        let documents: Vec<Document> = synced
+            .prompts
+            .iter()
+            .map(|(location, prompt)| Document {
+                name: &prompt.name,
+                content: &prompt.content,
+                tags: &prompt.tags,
+                modified: prompt.modified,
+                location,
+            })
+            .collect();
+        let hits = search(&Query::parse(text), &documents);
+
+        let selected = self.state.selected_prompt().map(|p| p.name.clone());
+        let prompts = hits.iter().map(|hit| synced.prompts[hit.index].1.clone()).collect();
+        self.state.prompts = self.filter_by_tags(prompts);
+        self.state.selected_index = selected
+            .and_then(|name| self.state.prompts.iter().position(|p| p.name == name))
+            .unwrap_or(self.state.selected_index)
+            .min(self.state.prompts.len().saturating_sub(1));
+        Ok(())
+    }
 
-        // Sort by score (highest first)
-        results.sort_by(|a, b| b.score.cmp(&a.score));
+    /// Copy the best matches found so far into the search popup
+    fn refresh_search_results(&mut self) {
+        let (Some(search), Some(popup)) = (self.search.as_mut(), self.state.search_popup.as_mut())
+        else {
+            return;
+        };
+
+        popup.searching = search.is_running();
+        popup.total = search.count();
+        popup.results = search
+            .results(SEARCH_RESULT_LIMIT)
+            .into_iter()
+            .map(|found| {
+                let (line, preview_match_indices) = match found.snippet {
+                    Some(snippet) => (Some(snippet.line), snippet.indices),
+                    None => (None, Vec::new()),
+                };
+                let content = found.entry.body();
+                let preview = content.lines().nth(line.unwrap_or(0)).unwrap_or("");
+                SearchResult {
+                    name: found.entry.name.clone(),
+                    preview: preview.to_string(),
+                    line,
+                    preview_match_indices,
+                    name_match_indices: found.name_indices,
+                    location: found.entry.location.clone(),
+                }
+            })
+            .collect();
+        popup.selected_index = popup.selected_index.min(popup.results.len().saturating_sub(1));
+        self.refresh_search_preview();
+    }
 
-        results
+    /// Show the content of the selected search result in the preview pane
+    fn refresh_search_preview(&mut self) {
+        if let Some(ref mut popup) = self.state.search_popup {
+            popup.selected_content = popup.selected_result()
+                .and_then(|result| self.index.get(&result.location, &result.name))
+                .map(|entry| entry.body().to_string())
+                .unwrap_or_default();
+        }
     }
 
-    /// Confirm search selection and jump to the selected prompt
-    fn confirm_search_selection(&mut self) {
-        let selected_name = if let Some(ref popup) = self.state.search_popup {
-            popup.selected_result().map(|r| r.name.clone())
+    /// Confirm search selection and jump to the selected prompt, opening its folder or the
+    /// archive when it lives there, and the editor at the matching line of a content match
+    fn confirm_search_selection(&mut self) -> Result<()> {
+        self.search = None;
+        let selected = self.state.search_popup.take()
+            .and_then(|popup| popup.selected_result().cloned());
+        let Some(result) = selected else {
+            return Ok(());
+        };
+
+        let name = result.name;
+        let mut message = format!("Jumped to '{}'", name);
+
+        // The same name can live in several locations, so the listing must be the result's.
+        // A view lists prompts from several folders, but never archived ones.
+        let listed = if self.state.current_view.is_some() {
+            result.location != "archive" && self.state.prompts.iter().any(|p| p.name == name)
         } else {
-            None
+            self.selected_prompt_dir()?.1 == result.location
         };
+        if !listed {
+            if result.location == "archive" {
+                self.state.mode = Mode::Archive;
+                self.state.current_folder = None;
+                self.state.current_view = None;
+                self.state.prompts = self.filter_by_tags(load_all_prompts(&archive_dir()?)?);
+                message = format!("Jumped to '{}' in the archive", name);
+            } else {
+                let folder = result.location.strip_prefix("folders/").map(str::to_string);
+                if let Some(folder) = &folder {
+                    message = format!("Jumped to '{}' in {}", name, folder);
+                }
+                self.open_folder(folder)?;
+            }
+        }
 
-        self.state.search_popup = None;
+        // Search ignores the tag filter, so a result it hides is shown by clearing it
+        if !self.state.tag_filter.is_empty() && !self.state.prompts.iter().any(|p| p.name == name) {
+            self.set_tag_filter(TagFilter::default())?;
+            message = format!("{} (tag filter cleared)", message);
+        }
 
-        if let Some(name) = selected_name {
-            // Find the prompt in the current list
-            if let Some(pos) = self.state.prompts.iter().position(|p| p.name == name) {
-                self.state.selected_index = pos;
-                self.state.list_scroll_offset = pos.saturating_sub(5);
-                self.state.notify(format!("Jumped to '{}'", name), NotificationLevel::Info);
-            } else if let Some(pos) = self.all_prompts.iter().position(|p| p.name == name) {
-                // The prompt might be in another folder or filtered out
-                // For now, we'll switch to showing all prompts
-                self.state.tag_filter = None;
-                self.state.prompts = self.all_prompts.clone();
-                self.state.selected_index = pos;
-                self.state.list_scroll_offset = pos.saturating_sub(5);
-                self.state.notify(format!("Jumped to '{}' (cleared filter)", name), NotificationLevel::Info);
+        if let Some(pos) = self.state.prompts.iter().position(|p| p.name == name) {
+            self.state.selected_index = pos;
+            self.state.list_scroll_offset = pos.saturating_sub(5);
+            if let Some(line) = result.line {
+                if self.state.mode == Mode::Archive {
+                    // Archived prompts are read-only: scroll the content pane to the line
+                    self.state.editor_scroll_offset = line;
+                } else {
+                    self.enter_insert_mode();
+                    if let Some(editor) = self.editor.as_mut() {
+                        let row = u16::try_from(line).unwrap_or(u16::MAX);
+                        editor.move_cursor(CursorMove::Jump(row, 0));
+                    }
+                }
+                message = format!("{} (line {})", message, line + 1);
             }
+            self.state.notify(message, NotificationLevel::Info);
+        } else {
+            self.state.notify(format!("'{}' is no longer listed", name), NotificationLevel::Warning);
+        }
+        Ok(())
+    }
+}
+
+/// Replace the prompt with the same name, or insert it before the first prompt sorting after it
+fn replace_or_insert(prompts: &mut Vec<Prompt>, prompt: Prompt) {
+    if let Some(existing) = prompts.iter_mut().find(|p| p.name == prompt.name) {
+        *existing = prompt;
+    } else {
+        let pos = prompts.iter().position(|p| p.name > prompt.name).unwrap_or(prompts.len());
+        prompts.insert(pos, prompt);
+    }
+}
+
+/// Collect the sorted, distinct tags of the given prompts
+fn collect_tags(prompts: &[Prompt]) -> Vec<String> {
+    let mut tags: Vec<String> = prompts.iter().flat_map(|p| p.tags.clone()).collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+/// The main list as shown at startup, through the restored tag filter, and every tag in
+/// use outside the archive (those of hidden prompts too, so the filter can be undone)
+fn startup_listing(synced: &SyncedPrompts, filter: &TagFilter) -> (Vec<Prompt>, Vec<String>) {
+    let mut listed = synced.in_location("prompts");
+    listed.retain(|p| filter.matches(&p.tags));
+    (listed, collect_tags(&synced.active()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn prompt(name: &str, tags: &[&str]) -> (String, Prompt) {
+        let mut prompt = Prompt::with_content(name);
+        prompt.name = name.to_string();
+        prompt.tags = tags.iter().map(|t| t.to_string()).collect();
+        ("prompts".to_string(), prompt)
+    }
+
+    #[test]
+    fn test_restored_filter_keeps_hidden_tags() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(".session.yaml");
+        let mut session = Session::default();
+        session.tag_filter.cycle("draft");
+        session.tag_filter.cycle("draft");
+        session.save(&path).unwrap();
+
+        let synced = SyncedPrompts {
+            prompts: vec![prompt("notes", &["draft"]), prompt("review", &["work"])],
+            ..SyncedPrompts::default()
+        };
+        let (listed, tags) = startup_listing(&synced, &Session::load(&path).unwrap().tag_filter);
+
+        let names: Vec<&str> = listed.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["review"]);
+        assert_eq!(tags, vec!["draft", "work"]);
+    }
+
+    /// Synthetic benchmark: startup with 5k prompts and an up-to-date index, up to the
+    /// first frame. Run with `cargo test --release bench_startup -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn bench_startup() {
+        let dir = tempdir().unwrap();
+        let piemme = dir.path();
+        for i in 0..5_000 {
+            let content: String = (0..20)
+                .map(|line| format!("Line {} of prompt {} asks for a careful review.\n", line, i))
+                .collect();
+            let mut prompt = Prompt::with_content(content);
+            prompt.name = format!("prompt_{:05}", i);
+            prompt.tags = vec![format!("tag_{}", i % 10)];
+            let location = match i % 3 {
+                0 => "prompts".to_string(),
+                _ => format!("folders/folder_{}", i % 7),
+            };
+            crate::fs::save_prompt(&prompt, &piemme.join(location), Default::default()).unwrap();
         }
+        std::fs::create_dir_all(piemme.join("archive")).unwrap();
+        let mut index = Index::new();
+        index.sync(piemme).unwrap();
+        index.save(&piemme.join(".index.json")).unwrap();
+
+        // What `App::new` and the first pass of `App::run` do before the first frame
+        let started = std::time::Instant::now();
+        let startup = Startup::load(piemme).unwrap();
+        let loaded = started.elapsed();
+        let dirs = ["prompts", "archive", "folders"].map(|location| piemme.join(location));
+        let _watcher = PromptWatcher::new(&dirs).unwrap();
+        let _git = Repo::discover(piemme);
+        let config = Config::new();
+        let (keymap, _) = Keymap::from_config(&config.keybindings);
+        let theme = Theme::dark();
+        let mut terminal =
+            ratatui::Terminal::new(ratatui::backend::TestBackend::new(120, 40)).unwrap();
+        terminal
+            .draw(|frame| {
+                render(
+                    frame,
+                    &startup.state,
+                    &config,
+                    &keymap,
+                    &theme,
+                    startup.archived_count,
+                    None,
+                    &startup.all_prompts,
+                );
+            })
+            .unwrap();
+        println!(
+            "first frame of {} prompts after {:?} (store loaded in {:?})",
+            startup.state.prompts.len(),
+            started.elapsed(),
+            loaded
+        );
+
+        let bodies = startup.bodies_pending.unwrap().recv().unwrap();
+        println!("read {} bodies {:?} after startup", bodies.len(), started.elapsed());
     }
 }
@@ -0,0 +1,537 @@
+//! Command-line subcommands
+//!
+//! Running `piemme` without arguments starts the TUI. Any arguments are
+//! dispatched here instead, and the process exits once the command completes.
+
+use anyhow::{bail, Context, Result};
+use std::io::{BufRead, IsTerminal, Write};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+use crate::config::{index_path, piemme_dir, prompts_dir, session_path, Config};
+use crate::fs::{
+    apply_markdown_import, create_bundle, edit_tags, create_folder, detect_collisions, ensure_directories,
+    existing_prompts, export_documents, import_bundle,
+    load_all_prompts_everywhere, normalize_folder_path, plan_markdown_import, write_export, Bundle,
+    Collision, ConflictResolution, ExportFormat, ExportOptions, Index, IndexEntry, SaveOptions,
+    Session, SyncedPrompts,
+};
+use crate::models::{normalize_tag, tag_tree, Prompt, TagEdit};
+use crate::search::{Document, Query};
+
+/// Port used by `piemme serve` without --port
+const DEFAULT_SERVE_PORT: u16 = 7411;
+
+/// Usage text printed by `piemme help`
+const USAGE: &str = "\
+Usage: piemme [COMMAND]
+
+Without a command, starts the interactive TUI.
+
+Commands:
+  config show    Print the effective configuration and the layer each value came from
+  export         Export prompts to a file or directory
+                   piemme export [NAME...] [--all] [--tag TAG] [--format FORMAT]
+                                 [--with-commands] [--output PATH]
+                   FORMAT is raw, rendered, json or combined (default from config);
+                   without --output a single document is printed to stdout
+  bundle create  Bundle prompts with every prompt they reference into one file
+                   piemme bundle create NAME... [--with-files] [--output FILE]
+  bundle import  Import a bundle, resolving name/UUID collisions per prompt
+                   piemme bundle import FILE [--on-conflict rename|skip|overwrite]
+                   Without --on-conflict you are asked for each collision
+  import         Import an Obsidian vault or Markdown directory
+                   piemme import DIR [--folder NAME] [--dry-run]
+                   --dry-run only reports renames and unresolved links
+  search         Search prompts with the query language of the TUI search (/)
+                   piemme search QUERY...   e.g. piemme search tag:coding -tag:draft review
+                   Prints NAME<TAB>LOCATION:LINE<TAB>TEXT per match, best first, where
+                   LINE is the best matching line (or 1 for a name match)
+  tags           List every tag as a tree, with the number of prompts tagged with it
+  tags rename    Rename a tag (and the tags below it) on every prompt
+                   piemme tags rename OLD NEW   e.g. piemme tags rename lang language
+  tags merge     Replace a tag (and the tags below it) by an existing one everywhere
+                   piemme tags merge FROM INTO
+  tags delete    Remove a tag (and the tags below it) from every prompt
+                   piemme tags delete TAG
+                 Tag colors follow renames, merges and deletes in the config file
+                 (user or project) that sets them
+  reindex        Rebuild the prompt index (.piemme/.index.json) from every prompt file
+  mcp            Serve prompts to MCP clients over stdin/stdout
+                   {{arg:NAME}} placeholders become prompt arguments; prompts that
+                   run {{commands}} are refused unless mcp_allow_commands is set
+  serve          Serve a local HTTP/JSON API on 127.0.0.1
+                   piemme serve [--port PORT] [--token TOKEN]   (default port 7411)
+                   Requests need an Authorization: Bearer TOKEN header; without
+                   --token a random token is printed at startup. Other origins
+                   (e.g. browser extensions) must be listed in api_allowed_origins
+  help           Show this message
+";
+
+/// How commands that overwrite prompts save them (honouring the configured backup retention)
+fn save_options() -> SaveOptions {
+    Config::load_layered().map(|config| config.save_options()).unwrap_or_default()
+}
+
+/// Run the subcommand described by `args` (program name excluded)
+pub fn run(args: &[String]) -> Result<()> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    match args.as_slice() {
+        ["config", "show"] => config_show(),
+        ["export", rest @ ..] => export(rest),
+        ["bundle", "create", rest @ ..] => bundle_create(rest),
+        ["bundle", "import", rest @ ..] => bundle_import(rest),
+        ["import", rest @ ..] => import_markdown(rest),
+        ["search", rest @ ..] => search(rest),
+        ["tags"] => list_tags(),
+        ["tags", "rename", from, to] => edit_tag(TagEdit::Rename {
+            from: normalize_tag(from),
+            to: normalize_tag(to),
+        }),
+        ["tags", "merge", from, into] => edit_tag(TagEdit::Merge {
+            from: normalize_tag(from),
+            into: normalize_tag(into),
+        }),
+        ["tags", "delete", tag] => edit_tag(TagEdit::Delete(normalize_tag(tag))),
+        ["reindex"] => reindex(),
+        ["mcp"] => mcp(),
+        ["serve", rest @ ..] => serve(rest),
+        ["help" | "--help" | "-h"] => {
+            print!("{}", USAGE);
+            Ok(())
+        }
+        _ => bail!("Unknown command: {}\n\n{}", args.join(" "), USAGE),
+    }
+}
+
+/// Print the merged config, annotating each value with its source layer
+fn config_show() -> Result<()> {
+    let config = Config::load_layered()?;
+    print!("{}", config.to_annotated_yaml()?);
+
+    let errors = config.validate();
+    for error in &errors {
+        eprintln!("error: {}", error);
+    }
+    if !errors.is_empty() {
+        bail!("Configuration has {} error(s)", errors.len());
+    }
+
+    Ok(())
+}
+
+/// Export prompts selected by name, tag or `--all`
+fn export(args: &[&str]) -> Result<()> {
+    let config = Config::load_layered()?;
+
+    let mut names = Vec::new();
+    let mut all = false;
+    let mut tag = None;
+    let mut format_name = config.default_export_format.clone();
+    let mut include_command_output = false;
+    let mut output = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match *arg {
+            "--all" => all = true,
+            "--with-commands" => include_command_output = true,
+            "--tag" => tag = Some(*args.next().context("--tag requires a value")?),
+            "--format" => {
+                format_name = args.next().context("--format requires a value")?.to_string()
+            }
+            "--output" | "-o" => output = Some(*args.next().context("--output requires a value")?),
+            flag if flag.starts_with('-') => bail!("Unknown option for export: {}", flag),
+            name => names.push(name),
+        }
+    }
+
+    let format = ExportFormat::from_name(&format_name)
+        .with_context(|| format!("Unknown export format: {}", format_name))?;
+    if names.is_empty() && !all && tag.is_none() {
+        bail!("Nothing to export: give prompt names, --tag or --all");
+    }
+
+    let all_prompts = load_all_prompts_everywhere()?;
+    for name in &names {
+        if !all_prompts.iter().any(|p| p.name == *name) {
+            bail!("Prompt not found: {}", name);
+        }
+    }
+
+    let selected: Vec<_> = all_prompts
+        .iter()
+        .filter(|p| all || names.contains(&p.name.as_str()) || tag.is_some_and(|t| p.has_tag(t)))
+        .cloned()
+        .collect();
+    if selected.is_empty() {
+        bail!("No prompts matched");
+    }
+
+    let get_content = |name: &str| -> Option<String> {
+        all_prompts.iter().find(|p| p.name == name).map(|p| p.content.clone())
+    };
+    let options = ExportOptions {
+        format,
+        include_command_output,
+    };
+    let documents = export_documents(&selected, get_content, &options)?;
+
+    match output {
+        Some(dest) => {
+            for path in write_export(&documents, Path::new(dest))? {
+                eprintln!("Wrote {}", path.display());
+            }
+        }
+        None => match documents.as_slice() {
+            [document] => print!("{}", document.contents),
+            _ => bail!("Several documents to write: use --output DIR or --format combined/json"),
+        },
+    }
+
+    Ok(())
+}
+
+/// Write a bundle of the named prompts and their transitive references
+fn bundle_create(args: &[&str]) -> Result<()> {
+    let mut roots = Vec::new();
+    let mut with_files = false;
+    let mut output = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match *arg {
+            "--with-files" => with_files = true,
+            "--output" | "-o" => output = Some(*args.next().context("--output requires a value")?),
+            flag if flag.starts_with('-') => bail!("Unknown option for bundle create: {}", flag),
+            name => roots.push(name),
+        }
+    }
+    if roots.is_empty() {
+        bail!("Nothing to bundle: give one or more prompt names");
+    }
+
+    let all_prompts = load_all_prompts_everywhere()?;
+    let base_dir = std::env::current_dir()?;
+    let bundle = create_bundle(&roots, &all_prompts, with_files.then_some(base_dir.as_path()))?;
+
+    let default_output = format!("{}.bundle.json", roots[0]);
+    let output = Path::new(output.unwrap_or(&default_output));
+    bundle.save(output)?;
+
+    eprintln!(
+        "Wrote {} ({} prompt(s), {} file(s))",
+        output.display(),
+        bundle.prompts.len(),
+        bundle.files.len()
+    );
+    for missing in &bundle.manifest.missing {
+        eprintln!("warning: unresolved reference [[{}]]", missing);
+    }
+
+    Ok(())
+}
+
+/// Import a bundle into the prompts directory
+fn bundle_import(args: &[&str]) -> Result<()> {
+    let mut file = None;
+    let mut on_conflict = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match *arg {
+            "--on-conflict" => {
+                let value = args.next().context("--on-conflict requires a value")?;
+                on_conflict = Some(
+                    ConflictResolution::from_name(value)
+                        .with_context(|| format!("Unknown conflict resolution: {}", value))?,
+                );
+            }
+            flag if flag.starts_with('-') => bail!("Unknown option for bundle import: {}", flag),
+            path => file = Some(path),
+        }
+    }
+    let file = file.context("Missing bundle file")?;
+
+    ensure_directories()?;
+    let bundle = Bundle::load(Path::new(file))?;
+    let existing = existing_prompts()?;
+    let collisions = detect_collisions(&bundle, &existing);
+
+    let mut resolutions = Vec::with_capacity(collisions.len());
+    for (prompt, collision) in bundle.prompts.iter().zip(&collisions) {
+        let resolution = match (collision, on_conflict) {
+            (None, _) => ConflictResolution::Rename,
+            (Some(_), Some(resolution)) => resolution,
+            (Some(collision), None) => ask_resolution(&prompt.name, *collision)?,
+        };
+        resolutions.push(resolution);
+    }
+
+    let base_dir = std::env::current_dir()?;
+    let report = import_bundle(
+        &bundle,
+        &resolutions,
+        &existing,
+        &prompts_dir()?,
+        &base_dir,
+        save_options(),
+    )?;
+
+    let written = report.written.iter().map(|(prompt, dir)| (prompt, dir.as_path()));
+    update_index(written, &report.removed)?;
+
+    eprintln!("Imported {} prompt(s)", report.written.len());
+    for (old, new) in &report.renamed {
+        eprintln!("  renamed {} -> {}", old, new);
+    }
+    for name in &report.overwritten {
+        eprintln!("  overwrote {}", name);
+    }
+    for name in &report.skipped {
+        eprintln!("  skipped {}", name);
+    }
+    for path in &report.files_written {
+        eprintln!("  wrote file {}", path.display());
+    }
+    for path in &report.files_skipped {
+        eprintln!("  skipped file {} (exists with different content or unsafe path)", path);
+    }
+
+    Ok(())
+}
+
+/// Import an Obsidian vault or Markdown directory as prompts
+fn import_markdown(args: &[&str]) -> Result<()> {
+    let mut source = None;
+    let mut folder = None;
+    let mut dry_run = false;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match *arg {
+            "--dry-run" => dry_run = true,
+            "--folder" => folder = Some(*args.next().context("--folder requires a value")?),
+            flag if flag.starts_with('-') => bail!("Unknown option for import: {}", flag),
+            path => source = Some(path),
+        }
+    }
+    let source = Path::new(source.context("Missing directory to import")?);
+    if !source.is_dir() {
+        bail!("Not a directory: {}", source.display());
+    }
+
+    ensure_directories()?;
+    let existing = existing_prompts()?;
+    let names: Vec<&str> = existing.iter().map(|e| e.name.as_str()).collect();
+    let ids: HashSet<Uuid> = existing.iter().map(|e| e.id).collect();
+    let plan = plan_markdown_import(source, &names, &ids)?;
+
+    print!("{}", plan.report());
+    if dry_run {
+        return Ok(());
+    }
+
+    let dest = match folder.map(normalize_folder_path) {
+        Some(folder) if folder.is_empty() => bail!("Invalid folder name"),
+        Some(folder) => create_folder(&folder)?,
+        None => prompts_dir()?,
+    };
+    apply_markdown_import(&plan, &dest, save_options())?;
+    update_index(plan.notes.iter().map(|note| (&note.prompt, dest.as_path())), &[])?;
+
+    println!("Imported {} prompt(s) into {}", plan.notes.len(), dest.display());
+    Ok(())
+}
+
+/// Serve the prompt library over the Model Context Protocol on stdio
+fn mcp() -> Result<()> {
+    let config = Config::load_layered()?;
+    let stdin = std::io::stdin();
+    crate::mcp::serve(
+        stdin.lock(),
+        std::io::stdout(),
+        config.mcp_allow_commands,
+        load_all_prompts_everywhere,
+    )
+}
+
+/// Serve the HTTP/JSON API until interrupted
+fn serve(args: &[&str]) -> Result<()> {
+    let mut port = DEFAULT_SERVE_PORT;
+    let mut token = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match *arg {
+            "--port" | "-p" => {
+                let value = args.next().context("--port requires a value")?;
+                port = value.parse().with_context(|| format!("Invalid port: {}", value))?;
+            }
+            "--token" => {
+                token = Some(args.next().context("--token requires a value")?.to_string())
+            }
+            other => bail!("Unknown option for serve: {}", other),
+        }
+    }
+
+    ensure_directories()?;
+    let config = Config::load_layered()?;
+    crate::server::serve(&piemme_dir()?, port, token, &config)
+}
+
+/// Print the prompts matching a search query, best matches first
+fn search(args: &[&str]) -> Result<()> {
+    if args.is_empty() {
+        bail!("search requires a query, e.g. piemme search tag:coding review");
+    }
+    // The shell strips quotes from phrases: put them back around arguments with spaces
+    let text = args
+        .iter()
+        .map(|arg| {
+            if arg.contains(char::is_whitespace) && !arg.contains('"') {
+                format!("\"{}\"", arg)
+            } else {
+                arg.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    let query = Query::parse(&text);
+    if !query.errors.is_empty() {
+        bail!("{}", query.errors.join("\n"));
+    }
+
+    let index = synced_index()?;
+    let documents: Vec<Document> = index.all_entries().map(Document::from).collect();
+    for hit in crate::search::search(&query, &documents) {
+        let document = &documents[hit.index];
+        let line = hit.snippet.map_or(0, |snippet| snippet.line);
+        let text = document.content.lines().nth(line).unwrap_or("");
+        println!("{}\t{}:{}\t{}", document.name, document.location, line + 1, text);
+    }
+    Ok(())
+}
+
+/// Print every tag in use as a tree, with the number of prompts having it or a tag below it
+fn list_tags() -> Result<()> {
+    let index = synced_index()?;
+    for node in tag_tree(&index.all_tags()) {
+        let count = index.filter_by_tag(&node.tag).len();
+        println!("{}{}\t{}", "  ".repeat(node.depth), node.label(), count);
+    }
+    Ok(())
+}
+
+/// Rename, merge or delete a tag on every prompt file, in the project's tag colors and in
+/// the saved tag filter
+fn edit_tag(edit: TagEdit) -> Result<()> {
+    let index = synced_index()?;
+    edit.validate(&index.all_tags())?;
+
+    let mut config = Config::load_layered()?;
+    let changed = edit_tags(&piemme_dir()?, &edit, config.save_options())?;
+    let (written, env_vars) = config.edit_tag_colors_in_layers(&edit)?;
+    for path in written {
+        eprintln!("Updated tag colors in {}", path.display());
+    }
+    for var in env_vars {
+        eprintln!("Warning: Tag color set by {} is not renamed; update the variable", var);
+    }
+    let mut session = Session::load(&session_path()?)?;
+    if session.tag_filter.apply_edit(&edit) {
+        session.save(&session_path()?)?;
+    }
+    synced_index()?;
+
+    for name in &changed {
+        println!("{}", name);
+    }
+    eprintln!("Updated {} prompt(s)", changed.len());
+    Ok(())
+}
+
+/// Load the index and bring it up to date with the prompt files
+fn synced_index() -> Result<Index> {
+    ensure_directories()?;
+    let mut index = Index::load(&index_path()?).unwrap_or_else(|_| Index::new());
+    let synced = index.sync(&piemme_dir()?)?;
+    warn_failed(&synced);
+    if synced.changed() {
+        index.save(&index_path()?)?;
+    }
+    Ok(index)
+}
+
+/// Print the prompt files a sync could not read
+fn warn_failed(synced: &SyncedPrompts) {
+    for failure in &synced.failed {
+        eprintln!("Warning: Failed to load prompt {}", failure);
+    }
+}
+
+/// Rebuild the index from scratch, reading every prompt file
+fn reindex() -> Result<()> {
+    ensure_directories()?;
+
+    let started = std::time::Instant::now();
+    let mut index = Index::new();
+    let synced = index.sync(&piemme_dir()?)?;
+    warn_failed(&synced);
+    index.save(&index_path()?)?;
+
+    println!(
+        "Indexed {} prompt(s) in {} ms",
+        synced.prompts.len(),
+        started.elapsed().as_millis()
+    );
+    Ok(())
+}
+
+/// Record written prompts in the search index, located by the directory they live in, and
+/// drop the entries of prompts deleted along the way
+fn update_index<'a>(
+    written: impl Iterator<Item = (&'a Prompt, &'a Path)>,
+    removed: &[(String, PathBuf)],
+) -> Result<()> {
+    let piemme = piemme_dir()?;
+    let location = |dir: &Path| {
+        dir.strip_prefix(&piemme)
+            .map(|p| p.to_string_lossy().replace('\\', "/"))
+            .unwrap_or_else(|_| "prompts".to_string())
+    };
+    let mut index = Index::load_or_new(&index_path()?)?;
+    for (name, dir) in removed {
+        index.remove(&location(dir), name);
+    }
+    for (prompt, dir) in written {
+        index.upsert(IndexEntry::from_prompt(prompt, &location(dir)));
+    }
+    index.save(&index_path()?)
+}
+
+/// Ask on the terminal how to resolve a collision
+fn ask_resolution(name: &str, collision: Collision) -> Result<ConflictResolution> {
+    if !std::io::stdin().is_terminal() {
+        bail!("'{}': {}. Use --on-conflict to choose without prompting", name, collision);
+    }
+
+    let stdin = std::io::stdin();
+    loop {
+        eprint!("'{}': {}. [r]ename, [s]kip or [o]verwrite? ", name, collision);
+        std::io::stderr().flush()?;
+
+        let mut answer = String::new();
+        if stdin.lock().read_line(&mut answer)? == 0 {
+            bail!("Import cancelled");
+        }
+        match answer.trim() {
+            "r" | "rename" => return Ok(ConflictResolution::Rename),
+            "s" | "skip" => return Ok(ConflictResolution::Skip),
+            "o" | "overwrite" => return Ok(ConflictResolution::Overwrite),
+            _ => {}
+        }
+    }
+}
@@ -1,10 +1,17 @@
 //! Configuration management
 
 use anyhow::{Context, Result};
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use ratatui::style::Color;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_yaml::{Mapping, Value};
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 
+use crate::fs::{SaveOptions, DEFAULT_BACKUP_RETENTION, DEFAULT_HISTORY_RETENTION};
+use crate::models::TagEdit;
+use crate::search::Query;
+use crate::ui::{theme_exists, Keymap, BUILTIN_THEMES, THEME_ROLES};
+
 /// Application configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -12,41 +19,228 @@ pub struct Config {
     #[serde(default = "default_safe_mode")]
     pub safe_mode: bool,
 
-    /// Tag colors mapping (tag name -> color name)
-    #[serde(default)]
+    /// Tag colors mapping (tag name -> color name, `#rrggbb` or 256-color index)
+    #[serde(default, deserialize_with = "deserialize_colors")]
     pub tag_colors: HashMap<String, String>,
 
-    /// Default export format ("rendered" or "raw")
+    /// Default export format ("rendered", "raw", "json" or "combined")
     #[serde(default = "default_export_format")]
     pub default_export_format: String,
+
+    /// Backups kept per prompt in `.piemme/.backup/` (0 disables backups)
+    #[serde(default = "default_backup_retention")]
+    pub backup_retention: usize,
+
+    /// Revisions kept in each prompt's history in `.piemme/.history/` (0 disables history)
+    #[serde(default = "default_history_retention")]
+    pub history_retention: usize,
+
+    /// Whether `piemme mcp` may serve prompts that run {{commands}}
+    #[serde(default)]
+    pub mcp_allow_commands: bool,
+
+    /// Origins besides its own that may call `piemme serve` (e.g. `chrome-extension://<id>`)
+    #[serde(default)]
+    pub api_allowed_origins: Vec<String>,
+
+    /// Saved searches (view name -> search query), listed as virtual folders
+    #[serde(default)]
+    pub views: BTreeMap<String, String>,
+
+    /// Color theme: a theme name, or a name with per-role color overrides
+    #[serde(default, deserialize_with = "deserialize_theme")]
+    pub theme: ThemeConfig,
+
+    /// Key bindings per mode (mode -> key or key sequence -> action name, `none` unbinds)
+    #[serde(default)]
+    pub keybindings: BTreeMap<String, BTreeMap<String, String>>,
+
+    /// Layer each effective value came from, keyed by dotted path (e.g. "tag_colors.coding")
+    #[serde(skip)]
+    pub sources: BTreeMap<String, ConfigLayer>,
+
+    /// Environment overrides that were ignored because their value has the wrong type
+    #[serde(skip)]
+    pub env_errors: Vec<ConfigValidationError>,
+}
+
+/// Theme selection: a built-in or file theme name plus colors overriding single roles
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThemeConfig {
+    /// Built-in theme (`dark`, `light`, `high-contrast`) or theme file name without `.yaml`
+    #[serde(default = "default_theme_name")]
+    pub name: String,
+    /// Role name -> color name, `#rrggbb` or 256-color index
+    #[serde(default, deserialize_with = "deserialize_colors")]
+    pub colors: BTreeMap<String, String>,
+}
+
+impl Default for ThemeConfig {
+    fn default() -> Self {
+        Self { name: default_theme_name(), colors: BTreeMap::new() }
+    }
+}
+
+fn default_theme_name() -> String {
+    "dark".to_string()
+}
+
+/// Read `theme: light` as well as a full `theme:` mapping
+fn deserialize_theme<'de, D>(deserializer: D) -> Result<ThemeConfig, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Setting {
+        Name(String),
+        Full(ThemeConfig),
+    }
+
+    Ok(match Setting::deserialize(deserializer)? {
+        Setting::Name(name) => ThemeConfig { name, ..ThemeConfig::default() },
+        Setting::Full(theme) => theme,
+    })
+}
+
+/// Prefix for environment variable overrides (e.g. `PIEMME_SAFE_MODE=false`)
+const ENV_PREFIX: &str = "PIEMME_";
+
+/// Top-level keys holding strings or (nested) maps of strings. Environment overrides of
+/// these are taken verbatim rather than read as YAML, where `#ff0000` is a comment and
+/// `on` a boolean.
+const STRING_KEYS: &[&str] =
+    &["default_export_format", "tag_colors", "views", "theme", "keybindings"];
+
+/// A layer of the configuration merge chain, in increasing order of precedence
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigLayer {
+    /// Built-in default value
+    Default,
+    /// User-level config file (e.g. `~/.config/piemme/config.yaml`)
+    User(PathBuf),
+    /// Project config file (`.piemme/config.yaml`)
+    Project(PathBuf),
+    /// Environment variable override
+    Env(String),
+}
+
+impl ConfigLayer {
+    /// Get the file backing this layer, if any
+    pub fn file(&self) -> Option<&Path> {
+        match self {
+            ConfigLayer::User(path) | ConfigLayer::Project(path) => Some(path),
+            ConfigLayer::Default | ConfigLayer::Env(_) => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ConfigLayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigLayer::Default => write!(f, "default"),
+            ConfigLayer::User(path) => write!(f, "user ({})", path.display()),
+            ConfigLayer::Project(path) => write!(f, "project ({})", path.display()),
+            ConfigLayer::Env(var) => write!(f, "env ({})", var),
+        }
+    }
 }
 
 fn default_safe_mode() -> bool {
     true
 }
 
+fn default_backup_retention() -> usize {
+    DEFAULT_BACKUP_RETENTION
+}
+
+fn default_history_retention() -> usize {
+    DEFAULT_HISTORY_RETENTION
+}
+
 fn default_export_format() -> String {
     "rendered".to_string()
 }
 
 /// Valid export format values
-const VALID_EXPORT_FORMATS: &[&str] = &["rendered", "raw"];
+const VALID_EXPORT_FORMATS: &[&str] = &["rendered", "raw", "json", "combined"];
 
 /// Valid color names for tags
 const VALID_COLORS: &[&str] = &[
     "blue", "green", "yellow", "magenta", "purple", "cyan", "red", "white", "gray", "grey",
 ];
 
+/// Parse a tag color: a name from [`VALID_COLORS`], `#rrggbb`, or a 256-color index (0-255)
+pub fn parse_color(value: &str) -> Option<Color> {
+    let value = value.trim().to_lowercase();
+    if let Some(hex) = value.strip_prefix('#') {
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        return Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?));
+    }
+    if let Ok(index) = value.parse::<u8>() {
+        return Some(Color::Indexed(index));
+    }
+    match value.as_str() {
+        "blue" => Some(Color::Blue),
+        "green" => Some(Color::Green),
+        "yellow" => Some(Color::Yellow),
+        "magenta" | "purple" => Some(Color::Magenta),
+        "cyan" => Some(Color::Cyan),
+        "red" => Some(Color::Red),
+        "white" => Some(Color::White),
+        "gray" | "grey" => Some(Color::Gray),
+        _ => None,
+    }
+}
+
+/// Read colors (of tags or theme roles) given as strings or as bare 256-color indices
+/// (`coding: 208`)
+fn deserialize_colors<'de, D, C>(deserializer: D) -> Result<C, D::Error>
+where
+    D: Deserializer<'de>,
+    C: FromIterator<(String, String)>,
+{
+    let colors: HashMap<String, Value> = HashMap::deserialize(deserializer)?;
+    Ok(colors
+        .into_iter()
+        .map(|(tag, color)| {
+            let color = match color {
+                Value::String(color) => color,
+                // An unquoted `#rrggbb` is a YAML comment, leaving no value
+                Value::Null => String::new(),
+                other => value_key(&other),
+            };
+            (tag, color)
+        })
+        .collect())
+}
+
 /// Configuration validation errors
 #[derive(Debug, Clone)]
 pub struct ConfigValidationError {
     pub field: String,
     pub message: String,
+    /// Layer the offending value came from
+    pub layer: ConfigLayer,
+    /// Line of the offending value in the layer's file (1-based)
+    pub line: Option<usize>,
 }
 
 impl std::fmt::Display for ConfigValidationError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}: {}", self.field, self.message)
+        match (&self.layer, self.line) {
+            (ConfigLayer::User(path) | ConfigLayer::Project(path), Some(line)) => {
+                write!(f, "{}:{}: {}: {}", path.display(), line, self.field, self.message)
+            }
+            (ConfigLayer::User(path) | ConfigLayer::Project(path), None) => {
+                write!(f, "{}: {}: {}", path.display(), self.field, self.message)
+            }
+            (ConfigLayer::Env(var), _) => write!(f, "{}: {}: {}", var, self.field, self.message),
+            (ConfigLayer::Default, _) => write!(f, "{}: {}", self.field, self.message),
+        }
     }
 }
 
@@ -57,52 +251,175 @@ impl Config {
             safe_mode: true,
             tag_colors: HashMap::new(),
             default_export_format: "rendered".to_string(),
+            backup_retention: DEFAULT_BACKUP_RETENTION,
+            history_retention: DEFAULT_HISTORY_RETENTION,
+            mcp_allow_commands: false,
+            api_allowed_origins: Vec::new(),
+            views: BTreeMap::new(),
+            theme: ThemeConfig::default(),
+            keybindings: BTreeMap::new(),
+            sources: BTreeMap::new(),
+            env_errors: Vec::new(),
         }
     }
 
-    /// Load config from a file path
-    pub fn load(path: &Path) -> Result<Self> {
-        let contents = std::fs::read_to_string(path)
-            .with_context(|| format!("Failed to read config file: {}", path.display()))?;
-        
-        let config: Config = serde_yaml::from_str(&contents)
-            .with_context(|| "Failed to parse config file")?;
-        
+    /// Load the effective config by merging every layer: built-in defaults, the
+    /// user-level config, the project config, then `PIEMME_*` environment variables
+    pub fn load_layered() -> Result<Self> {
+        Self::load_layers(user_config_path().as_deref(), &config_path()?, std::env::vars())
+    }
+
+    /// Merge the given layers (missing files are skipped) into an effective config
+    pub fn load_layers(
+        user_path: Option<&Path>,
+        project_path: &Path,
+        env: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Self> {
+        let mut merged = Mapping::new();
+        let mut sources = BTreeMap::new();
+
+        let file_layers = [
+            user_path.map(|p| ConfigLayer::User(p.to_path_buf())),
+            Some(ConfigLayer::Project(project_path.to_path_buf())),
+        ];
+
+        for layer in file_layers.into_iter().flatten() {
+            let Some(path) = layer.file().filter(|p| p.exists()) else {
+                continue;
+            };
+            let contents = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read config file: {}", path.display()))?;
+            let value: Value = serde_yaml::from_str(&contents)
+                .with_context(|| format!("Failed to parse config file: {}", path.display()))?;
+            // Checked on its own, so a value of the wrong type is reported with its file and line
+            if let Err(e) = serde_yaml::from_str::<Config>(&contents) {
+                anyhow::bail!("Invalid config file {}: {}", path.display(), e);
+            }
+            match value {
+                Value::Mapping(mapping) => {
+                    merge_mapping(&mut merged, mapping, "", &layer, &mut sources);
+                }
+                Value::Null => {}
+                _ => anyhow::bail!("Config file must be a YAML mapping: {}", path.display()),
+            }
+        }
+
+        // Only accept environment overrides for keys the config actually has
+        let known_keys = match serde_yaml::to_value(Self::new())? {
+            Value::Mapping(mapping) => mapping,
+            _ => Mapping::new(),
+        };
+
+        let mut env: Vec<(String, String)> = env
+            .into_iter()
+            .filter(|(key, _)| key.starts_with(ENV_PREFIX))
+            .collect();
+        env.sort();
+
+        let mut env_errors = Vec::new();
+        for (var, raw) in env {
+            let path: Vec<String> = var[ENV_PREFIX.len()..]
+                .split("__")
+                .map(|segment| segment.to_lowercase())
+                .collect();
+            if !known_keys.contains_key(path[0].as_str()) || path.iter().any(|s| s.is_empty()) {
+                continue;
+            }
+
+            let mut value = if STRING_KEYS.contains(&path[0].as_str()) {
+                Value::String(raw)
+            } else {
+                serde_yaml::from_str(&raw).unwrap_or(Value::String(raw))
+            };
+            for segment in path.iter().skip(1).rev() {
+                let mut nested = Mapping::new();
+                nested.insert(Value::String(segment.clone()), value);
+                value = Value::Mapping(nested);
+            }
+            let mut layer_mapping = Mapping::new();
+            layer_mapping.insert(Value::String(path[0].clone()), value);
+
+            // A value of the wrong type would fail the whole config, so it is left out
+            let checked = serde_yaml::from_value::<Config>(Value::Mapping(layer_mapping.clone()));
+            if let Err(e) = checked {
+                env_errors.push(ConfigValidationError {
+                    field: path.join("."),
+                    message: format!("Ignored: {}", e),
+                    layer: ConfigLayer::Env(var),
+                    line: None,
+                });
+                continue;
+            }
+            merge_mapping(&mut merged, layer_mapping, "", &ConfigLayer::Env(var), &mut sources);
+        }
+
+        let mut config: Config = serde_yaml::from_value(Value::Mapping(merged))
+            .with_context(|| "Invalid configuration")?;
+        config.sources = sources;
+        config.env_errors = env_errors;
+
         Ok(config)
     }
 
-    /// Load config from the default location, or create a default config
-    pub fn load_or_default(path: &Path) -> Result<Self> {
-        if path.exists() {
-            Self::load(path)
-        } else {
-            Ok(Self::new())
+    /// Get the layer a value came from, by dotted path (e.g. "tag_colors.coding")
+    pub fn source_of(&self, field: &str) -> &ConfigLayer {
+        self.sources.get(field).unwrap_or(&ConfigLayer::Default)
+    }
+
+    /// Render the effective config as YAML, annotating each value with its layer
+    pub fn to_annotated_yaml(&self) -> Result<String> {
+        let value = serde_yaml::to_value(self).with_context(|| "Failed to serialize config")?;
+        let mut out = String::new();
+        if let Value::Mapping(mapping) = value {
+            self.write_annotated(&mut out, &mapping, "", 0);
         }
+        Ok(out)
     }
 
-    /// Save config to a file path
-    pub fn save(&self, path: &Path) -> Result<()> {
-        let contents = serde_yaml::to_string(self)
-            .with_context(|| "Failed to serialize config")?;
-        
-        // Ensure parent directory exists
-        if let Some(parent) = path.parent() {
-            std::fs::create_dir_all(parent)
-                .with_context(|| format!("Failed to create config directory: {}", parent.display()))?;
+    fn write_annotated(&self, out: &mut String, mapping: &Mapping, prefix: &str, indent: usize) {
+        let pad = "  ".repeat(indent);
+        for (key, value) in mapping {
+            let key = value_key(key);
+            let path = format!("{}{}", prefix, key);
+            match value {
+                Value::Mapping(nested) if !nested.is_empty() => {
+                    out.push_str(&format!("{}{}:\n", pad, key));
+                    self.write_annotated(out, nested, &format!("{}.", path), indent + 1);
+                }
+                _ => {
+                    let rendered = serde_yaml::to_string(value).unwrap_or_default();
+                    out.push_str(&format!(
+                        "{}{}: {}  # {}\n",
+                        pad,
+                        key,
+                        rendered.trim_end(),
+                        self.source_of(&path)
+                    ));
+                }
+            }
         }
-        
-        std::fs::write(path, contents)
-            .with_context(|| format!("Failed to write config file: {}", path.display()))?;
-        
-        Ok(())
     }
 
-    /// Get the color for a tag, or a default color if not set
+    /// How prompts are saved under this config
+    pub fn save_options(&self) -> SaveOptions {
+        SaveOptions {
+            backup_retention: self.backup_retention,
+            history_retention: self.history_retention,
+        }
+    }
+
+    /// Get the color for a tag (or its closest parent with one), or a default color if not set
     pub fn get_tag_color(&self, tag: &str) -> &str {
-        self.tag_colors
-            .get(tag)
-            .map(|s| s.as_str())
-            .unwrap_or_else(|| Self::default_color_for_tag(tag))
+        let mut path = tag;
+        loop {
+            if let Some(color) = self.tag_colors.get(path) {
+                return color;
+            }
+            match path.rsplit_once('/') {
+                Some((parent, _)) => path = parent,
+                None => return Self::default_color_for_tag(tag),
+            }
+        }
     }
 
     /// Set the color for a tag
@@ -110,40 +427,159 @@ impl Config {
         self.tag_colors.insert(tag.into(), color.into());
     }
 
+    /// Rename, merge or delete the colors of the tags `edit` changes; when merging, the
+    /// color of the tag merged into wins
+    pub fn edit_tag_colors(&mut self, edit: &TagEdit) {
+        let colors = std::mem::take(&mut self.tag_colors).into_iter().collect();
+        self.tag_colors = edit_color_entries(colors, edit).into_iter().collect();
+
+        // The layers of the colors follow them to their new tags
+        let (color_sources, rest): (BTreeMap<_, _>, BTreeMap<_, _>) =
+            std::mem::take(&mut self.sources)
+                .into_iter()
+                .partition(|(path, _)| path.starts_with("tag_colors."));
+        let color_sources = color_sources
+            .into_iter()
+            .map(|(path, layer)| (path["tag_colors.".len()..].to_string(), layer))
+            .collect();
+        self.sources = rest;
+        self.sources.extend(
+            edit_color_entries(color_sources, edit)
+                .into_iter()
+                .map(|(tag, layer)| (format!("tag_colors.{}", tag), layer)),
+        );
+    }
+
+    /// Apply `edit` to the tag colors, in memory and in the config file each affected color
+    /// comes from (see [`edit_tag_colors_file`]). Returns the files rewritten and the
+    /// environment variables setting affected colors, which cannot be rewritten.
+    pub fn edit_tag_colors_in_layers(
+        &mut self,
+        edit: &TagEdit,
+    ) -> Result<(Vec<PathBuf>, Vec<String>)> {
+        let mut files: Vec<PathBuf> = Vec::new();
+        let mut env_vars = Vec::new();
+        for tag in self.tag_colors.keys().filter(|tag| edit.apply_to(tag).is_some()) {
+            match self.source_of(&format!("tag_colors.{}", tag)) {
+                ConfigLayer::Env(var) => env_vars.push(var.clone()),
+                layer => {
+                    if let Some(path) = layer.file()
+                        && !files.iter().any(|f| f == path)
+                    {
+                        files.push(path.to_path_buf());
+                    }
+                }
+            }
+        }
+        env_vars.sort();
+
+        self.edit_tag_colors(edit);
+        let mut written = Vec::new();
+        for path in files {
+            if edit_tag_colors_file(&path, edit)? {
+                written.push(path);
+            }
+        }
+        Ok((written, env_vars))
+    }
+
     /// Validate the configuration and return any errors
     pub fn validate(&self) -> Vec<ConfigValidationError> {
-        let mut errors = Vec::new();
+        let mut errors = self.env_errors.clone();
 
         // Validate export format
         if !VALID_EXPORT_FORMATS.contains(&self.default_export_format.as_str()) {
-            errors.push(ConfigValidationError {
-                field: "default_export_format".to_string(),
-                message: format!(
+            errors.push(self.validation_error(
+                "default_export_format".to_string(),
+                format!(
                     "Invalid value '{}'. Must be one of: {}",
                     self.default_export_format,
                     VALID_EXPORT_FORMATS.join(", ")
                 ),
-            });
+            ));
         }
 
         // Validate tag colors
-        for (tag, color) in &self.tag_colors {
-            let color_lower = color.to_lowercase();
-            if !VALID_COLORS.contains(&color_lower.as_str()) {
-                errors.push(ConfigValidationError {
-                    field: format!("tag_colors.{}", tag),
-                    message: format!(
-                        "Invalid color '{}'. Must be one of: {}",
+        let mut tag_colors: Vec<_> = self.tag_colors.iter().collect();
+        tag_colors.sort();
+        for (tag, color) in tag_colors {
+            if parse_color(color).is_none() {
+                let hint = if color.is_empty() { " (quote hex colors: \"#rrggbb\")" } else { "" };
+                errors.push(self.validation_error(
+                    format!("tag_colors.{}", tag),
+                    format!(
+                        "Invalid color '{}'{}. Must be #rrggbb, a 256-color index (0-255) or \
+                         one of: {}",
                         color,
+                        hint,
                         VALID_COLORS.join(", ")
                     ),
-                });
+                ));
+            }
+        }
+
+        // Validate saved search queries
+        for (name, query) in &self.views {
+            if let Some(error) = Query::parse(query).errors.first() {
+                errors.push(self.validation_error(format!("views.{}", name), error.clone()));
             }
         }
 
+        // Validate the theme
+        if !theme_exists(&self.theme.name, &themes_dirs()) {
+            errors.push(self.validation_error(
+                "theme.name".to_string(),
+                format!(
+                    "Unknown theme '{}'. Must be one of: {}, or a <name>.yaml theme file",
+                    self.theme.name,
+                    BUILTIN_THEMES.join(", ")
+                ),
+            ));
+        }
+        for (role, color) in &self.theme.colors {
+            let message = if !THEME_ROLES.contains(&role.as_str()) {
+                format!("Unknown theme color. Must be one of: {}", THEME_ROLES.join(", "))
+            } else if parse_color(color).is_none() {
+                format!("Invalid color '{}'. Must be #rrggbb, a 256-color index or a name", color)
+            } else {
+                continue;
+            };
+            errors.push(self.validation_error(format!("theme.colors.{}", role), message));
+        }
+
+        // Validate key bindings, including keys that clash with each other
+        let (_, keymap_errors) = Keymap::from_config(&self.keybindings);
+        for error in keymap_errors {
+            errors.push(self.validation_error(error.field, error.message));
+        }
+
         errors
     }
 
+    /// Build a validation error located at the layer (and line) the field came from
+    fn validation_error(&self, field: String, message: String) -> ConfigValidationError {
+        // A shorthand value (like `theme: nope`) is recorded under its parent key
+        let mut key = field.as_str();
+        while !self.sources.contains_key(key) {
+            match key.rsplit_once('.') {
+                Some((parent, _)) => key = parent,
+                None => break,
+            }
+        }
+        let layer = self.source_of(key).clone();
+        let line = layer
+            .file()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|contents| find_key_line(&contents, key));
+
+        ConfigValidationError {
+            field,
+            message,
+            layer,
+            line,
+        }
+    }
+
     /// Check if the configuration is valid
     pub fn is_valid(&self) -> bool {
         self.validate().is_empty()
@@ -163,6 +599,208 @@ impl Default for Config {
     }
 }
 
+/// Render a YAML mapping key as a plain string
+fn value_key(key: &Value) -> String {
+    match key {
+        Value::String(s) => s.clone(),
+        other => serde_yaml::to_string(other)
+            .map(|s| s.trim_end().to_string())
+            .unwrap_or_default(),
+    }
+}
+
+/// Deep-merge `layer` into `target`, recording the layer of every leaf value
+fn merge_mapping(
+    target: &mut Mapping,
+    layer: Mapping,
+    prefix: &str,
+    origin: &ConfigLayer,
+    sources: &mut BTreeMap<String, ConfigLayer>,
+) {
+    for (key, value) in layer {
+        let path = format!("{}{}", prefix, value_key(&key));
+        match value {
+            Value::Mapping(nested) => {
+                let entry = target
+                    .entry(key)
+                    .or_insert_with(|| Value::Mapping(Mapping::new()));
+                if !entry.is_mapping() {
+                    *entry = Value::Mapping(Mapping::new());
+                }
+                if let Value::Mapping(entry) = entry {
+                    merge_mapping(entry, nested, &format!("{}.", path), origin, sources);
+                }
+                sources.entry(path).or_insert_with(|| origin.clone());
+            }
+            value => {
+                target.insert(key, value);
+                sources.insert(path, origin.clone());
+            }
+        }
+    }
+}
+
+/// Find the 1-based line defining a dotted key path in a YAML document
+fn find_key_line(contents: &str, path: &str) -> Option<usize> {
+    let mut segments = path.split('.').peekable();
+    let mut segment = segments.next()?;
+    let mut parent_indent: Option<usize> = None;
+
+    for (number, line) in contents.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let indent = line.len() - trimmed.len();
+        if parent_indent.is_some_and(|parent| indent <= parent) {
+            // Left the parent mapping without finding the key
+            return None;
+        }
+
+        let key = trimmed.split(':').next().unwrap_or("").trim().trim_matches(['"', '\'']);
+        if key == segment && trimmed.contains(':') {
+            if segments.peek().is_none() {
+                return Some(number + 1);
+            }
+            segment = segments.next()?;
+            parent_indent = Some(indent);
+        }
+    }
+
+    None
+}
+
+/// Apply `edit` to `(tag, color)` pairs, keeping an existing color over a renamed one
+fn edit_color_entries<V>(entries: Vec<(String, V)>, edit: &TagEdit) -> Vec<(String, V)> {
+    let (touched, mut kept): (Vec<_>, Vec<_>) =
+        entries.into_iter().partition(|(tag, _)| edit.apply_to(tag).is_some());
+    for (tag, color) in touched {
+        if let Some(Some(new_tag)) = edit.apply_to(&tag)
+            && !kept.iter().any(|(kept_tag, _)| *kept_tag == new_tag)
+        {
+            kept.push((new_tag, color));
+        }
+    }
+    kept
+}
+
+/// Apply `edit` to the `tag_colors` of the config file at `path`, rewriting only the lines of
+/// the affected colors so comments and the rest of the file survive. Returns whether the file
+/// changed.
+fn edit_tag_colors_file(path: &Path, edit: &TagEdit) -> Result<bool> {
+    if !path.exists() {
+        return Ok(false);
+    }
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read config file: {}", path.display()))?;
+    let value: Value = serde_yaml::from_str(&contents)
+        .with_context(|| format!("Failed to parse config file: {}", path.display()))?;
+    let Some(Value::Mapping(colors)) = value.get("tag_colors") else {
+        return Ok(false);
+    };
+    let entries: Vec<(String, Value)> = colors
+        .iter()
+        .map(|(tag, color)| (value_key(tag), color.clone()))
+        .collect();
+    if !entries.iter().any(|(tag, _)| edit.apply_to(tag).is_some()) {
+        return Ok(false);
+    }
+    let Some(key_line) = find_key_line(&contents, "tag_colors") else {
+        return Ok(false);
+    };
+
+    let mut lines: Vec<String> = contents.lines().map(str::to_string).collect();
+    let header = &lines[key_line - 1];
+    let indent = header.len() - header.trim_start().len();
+    let inline = header.split_once(':').map_or("", |(_, rest)| rest).trim();
+    if !inline.is_empty() && !inline.starts_with('#') {
+        // Flow mapping (`tag_colors: {a: red}`): rewrite that one line
+        let pairs: Vec<String> = edit_color_entries(entries, edit)
+            .iter()
+            .map(|(tag, color)| {
+                format!("{}: {}", yaml_scalar(&Value::from(tag.as_str())), yaml_scalar(color))
+            })
+            .collect();
+        lines[key_line - 1] = format!("{}tag_colors: {{{}}}", " ".repeat(indent), pairs.join(", "));
+    } else {
+        edit_color_lines(&mut lines, key_line, indent, &entries, edit);
+    }
+
+    let mut contents = lines.join("\n");
+    contents.push('\n');
+    crate::fs::write_atomic(path, contents)
+        .with_context(|| format!("Failed to write config file: {}", path.display()))?;
+    Ok(true)
+}
+
+/// Apply `edit` to the block mapping whose key sits on line `key_line` (1-based), the same way
+/// `edit_color_entries` does: renamed keys are rewritten in place, and deleted keys (or renamed
+/// ones whose new name already has a color) lose their lines
+fn edit_color_lines(
+    lines: &mut Vec<String>,
+    key_line: usize,
+    indent: usize,
+    entries: &[(String, Value)],
+    edit: &TagEdit,
+) {
+    let mut present: Vec<String> = entries
+        .iter()
+        .filter(|(tag, _)| edit.apply_to(tag).is_none())
+        .map(|(tag, _)| tag.clone())
+        .collect();
+    let mut entry_indent = None;
+    let mut dropping = false;
+    let mut i = key_line;
+    while i < lines.len() {
+        let line = &lines[i];
+        let trimmed = line.trim_start();
+        let line_indent = line.len() - trimmed.len();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            i += 1;
+            continue;
+        }
+        if line_indent <= indent {
+            break;
+        }
+        let entry_indent = *entry_indent.get_or_insert(line_indent);
+        if line_indent > entry_indent {
+            // Continuation of the previous entry's value
+            if dropping {
+                lines.remove(i);
+            } else {
+                i += 1;
+            }
+            continue;
+        }
+
+        let raw_key = trimmed.split(':').next().unwrap_or("");
+        let tag = raw_key.trim().trim_matches(['"', '\'']);
+        dropping = match edit.apply_to(tag) {
+            None => false,
+            Some(Some(new_tag)) if !present.contains(&new_tag) => {
+                let rest = &trimmed[raw_key.len()..];
+                let key = yaml_scalar(&Value::from(new_tag.as_str()));
+                lines[i] = format!("{}{}{}", " ".repeat(line_indent), key, rest);
+                present.push(new_tag);
+                false
+            }
+            Some(_) => true,
+        };
+        if dropping {
+            lines.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+}
+
+/// A YAML scalar as it would appear in a file, quoted when needed
+fn yaml_scalar(value: &Value) -> String {
+    serde_yaml::to_string(value)
+        .map(|s| s.trim_end().to_string())
+        .unwrap_or_default()
+}
+
 /// Get the piemme configuration directory path
 pub fn piemme_dir() -> Result<PathBuf> {
     // Use current directory's .piemme folder
@@ -177,6 +815,20 @@ pub fn config_path() -> Result<PathBuf> {
     Ok(piemme_dir()?.join("config.yaml"))
 }
 
+/// Get the path to the user-level config file (e.g. `~/.config/piemme/config.yaml`)
+pub fn user_config_path() -> Option<PathBuf> {
+    directories::ProjectDirs::from("", "", "piemme")
+        .map(|dirs| dirs.config_dir().join("config.yaml"))
+}
+
+/// Get the directories searched for `<name>.yaml` theme files: `.piemme/themes/`, then the
+/// `themes/` directory next to the user-level config file
+pub fn themes_dirs() -> Vec<PathBuf> {
+    let project = piemme_dir().ok().map(|dir| dir.join("themes"));
+    let user = user_config_path().and_then(|path| Some(path.parent()?.join("themes")));
+    project.into_iter().chain(user).collect()
+}
+
 /// Get the path to the prompts directory
 pub fn prompts_dir() -> Result<PathBuf> {
     Ok(piemme_dir()?.join("prompts"))
@@ -192,6 +844,26 @@ pub fn folders_dir() -> Result<PathBuf> {
     Ok(piemme_dir()?.join("folders"))
 }
 
+/// Get the path to the prompt backups directory
+pub fn backup_dir() -> Result<PathBuf> {
+    Ok(piemme_dir()?.join(".backup"))
+}
+
+/// Get the path to the prompt history directory
+pub fn history_dir() -> Result<PathBuf> {
+    Ok(piemme_dir()?.join(".history"))
+}
+
+/// Get the path to the saved searches file
+pub fn views_path() -> Result<PathBuf> {
+    Ok(piemme_dir()?.join("views.yaml"))
+}
+
+/// Get the path to the file of settings kept between sessions
+pub fn session_path() -> Result<PathBuf> {
+    Ok(piemme_dir()?.join(".session.yaml"))
+}
+
 /// Get the path to the index file
 pub fn index_path() -> Result<PathBuf> {
     Ok(piemme_dir()?.join(".index.json"))
@@ -269,6 +941,84 @@ mod tests {
         assert!(errors[0].field.contains("tag_colors.test"));
     }
 
+    #[test]
+    fn test_tag_color_formats() {
+        assert_eq!(parse_color("Blue"), Some(Color::Blue));
+        assert_eq!(parse_color("#FF8800"), Some(Color::Rgb(255, 136, 0)));
+        assert_eq!(parse_color("208"), Some(Color::Indexed(208)));
+        assert_eq!(parse_color("256"), None);
+        assert_eq!(parse_color("#ff88"), None);
+        assert_eq!(parse_color("#gg8800"), None);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.yaml");
+        std::fs::write(&path, "tag_colors:\n  coding: 208\n  work: \"#1e90ff\"\n  bad: #1e90ff\n")
+            .unwrap();
+        let config = Config::load_layers(None, &path, Vec::new()).unwrap();
+        assert_eq!(config.tag_colors["coding"], "208");
+        assert_eq!(config.tag_colors["work"], "#1e90ff");
+        let errors = config.validate();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "tag_colors.bad");
+        assert!(errors[0].message.contains("quote hex colors"));
+    }
+
+    #[test]
+    fn test_theme_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.yaml");
+
+        std::fs::write(&path, "theme: light\n").unwrap();
+        let config = Config::load_layers(None, &path, Vec::new()).unwrap();
+        assert_eq!(config.theme, ThemeConfig { name: "light".into(), colors: BTreeMap::new() });
+
+        std::fs::write(
+            &path,
+            "theme:\n  name: high-contrast\n  colors:\n    accent: 33\n    muted: \"#808080\"\n",
+        )
+        .unwrap();
+        let env = vec![("PIEMME_THEME__COLORS__ERROR".to_string(), "magenta".to_string())];
+        let config = Config::load_layers(None, &path, env).unwrap();
+        assert_eq!(config.theme.name, "high-contrast");
+        assert_eq!(config.theme.colors["accent"], "33");
+        assert_eq!(config.theme.colors["error"], "magenta");
+        assert!(config.validate().is_empty());
+
+        std::fs::write(&path, "theme:\n  name: nope\n  colors:\n    acent: red\n    text: bad\n")
+            .unwrap();
+        let config = Config::load_layers(None, &path, Vec::new()).unwrap();
+        let fields: Vec<String> = config.validate().into_iter().map(|e| e.field).collect();
+        assert_eq!(fields, ["theme.name", "theme.colors.acent", "theme.colors.text"]);
+        assert_eq!(Config::new().theme.name, "dark");
+    }
+
+    #[test]
+    fn test_keybindings_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.yaml");
+
+        std::fs::write(
+            &path,
+            "keybindings:\n  normal:\n    ctrl+h: none\n    alt+h: decrease_left_column_width\n",
+        )
+        .unwrap();
+        let config = Config::load_layers(None, &path, Vec::new()).unwrap();
+        assert_eq!(config.keybindings["normal"]["alt+h"], "decrease_left_column_width");
+        assert!(config.validate().is_empty());
+
+        std::fs::write(
+            &path,
+            "keybindings:\n  editor:\n    g g: vim_go_to_top\n    x: frobnicate\n",
+        )
+        .unwrap();
+        let config = Config::load_layers(None, &path, Vec::new()).unwrap();
+        let errors = config.validate();
+        let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, ["keybindings.editor.x", "keybindings.editor.g g"]);
+        assert_eq!(errors[0].line, Some(4));
+        assert_eq!(errors[1].line, Some(3));
+    }
+
     #[test]
     fn test_config_validation_multiple_errors() {
         let mut config = Config::new();
@@ -279,4 +1029,257 @@ mod tests {
         let errors = config.validate();
         assert_eq!(errors.len(), 3);
     }
+
+    #[test]
+    fn test_config_validation_invalid_view() {
+        let mut config = Config::new();
+        config.views.insert("stale".to_string(), "tag:review modified:<30d".to_string());
+        assert!(config.validate().is_empty());
+
+        config.views.insert("broken".to_string(), "has:nothing".to_string());
+        let errors = config.validate();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "views.broken");
+    }
+
+    #[test]
+    fn test_edit_tag_colors() {
+        let mut config = Config::new();
+        config.set_tag_color("lang", "blue");
+        config.set_tag_color("lang/rust", "red");
+        config.set_tag_color("code", "green");
+        config.set_tag_color("coding", "yellow");
+        assert_eq!(config.get_tag_color("lang/go"), "blue");
+
+        config.edit_tag_colors(&TagEdit::Rename { from: "lang".into(), to: "language".into() });
+        config.edit_tag_colors(&TagEdit::Merge { from: "code".into(), into: "coding".into() });
+        assert_eq!(config.tag_colors.len(), 3);
+        assert_eq!(config.tag_colors["language"], "blue");
+        assert_eq!(config.tag_colors["language/rust"], "red");
+        assert_eq!(config.tag_colors["coding"], "yellow");
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.yaml");
+        std::fs::write(&path, "safe_mode: false\ntag_colors:\n  lang/rust: red\n  work: blue\n")
+            .unwrap();
+        assert!(!edit_tag_colors_file(&path, &TagEdit::Delete("draft".into())).unwrap());
+        assert!(edit_tag_colors_file(&path, &TagEdit::Delete("lang".into())).unwrap());
+        let config = Config::load_layers(None, &path, Vec::new()).unwrap();
+        assert!(!config.safe_mode);
+        assert_eq!(config.tag_colors.len(), 1);
+        assert_eq!(config.tag_colors["work"], "blue");
+    }
+
+    #[test]
+    fn test_edit_tag_colors_in_layers() {
+        let dir = tempfile::tempdir().unwrap();
+        let user = dir.path().join("user.yaml");
+        let project = dir.path().join("project.yaml");
+        std::fs::write(&user, "tag_colors:\n  lang: blue  # languages\n  work: red\n").unwrap();
+        std::fs::write(&project, "tag_colors:\n  draft: gray\n").unwrap();
+        let env = vec![("PIEMME_TAG_COLORS__DRAFT".to_string(), "cyan".to_string())];
+        let mut config = Config::load_layers(Some(&user), &project, env).unwrap();
+
+        let rename = TagEdit::Rename { from: "lang".into(), to: "language".into() };
+        let (written, env_vars) = config.edit_tag_colors_in_layers(&rename).unwrap();
+        assert_eq!(written, vec![user.clone()]);
+        assert!(env_vars.is_empty());
+        assert_eq!(config.source_of("tag_colors.language"), &ConfigLayer::User(user.clone()));
+        let renamed = "tag_colors:\n  language: blue  # languages\n  work: red\n";
+        assert_eq!(std::fs::read_to_string(&user).unwrap(), renamed);
+
+        // A color from the environment cannot be rewritten
+        let delete = TagEdit::Delete("draft".into());
+        let (written, env_vars) = config.edit_tag_colors_in_layers(&delete).unwrap();
+        assert!(written.is_empty());
+        assert_eq!(env_vars, vec!["PIEMME_TAG_COLORS__DRAFT".to_string()]);
+        assert_eq!(std::fs::read_to_string(&project).unwrap(), "tag_colors:\n  draft: gray\n");
+
+        // Renamed again in the same session, the color is still found in its file
+        let rename = TagEdit::Rename { from: "language".into(), to: "lang".into() };
+        let (written, _) = config.edit_tag_colors_in_layers(&rename).unwrap();
+        assert_eq!(written, vec![user.clone()]);
+        let config = Config::load_layers(Some(&user), &project, Vec::new()).unwrap();
+        assert_eq!(config.tag_colors["lang"], "blue");
+    }
+
+    #[test]
+    fn test_edit_tag_colors_file_keeps_comments() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.yaml");
+        let original = concat!(
+            "# piemme settings\n",
+            "safe_mode: false # keep commands off\n",
+            "tag_colors:\n",
+            "  # languages\n",
+            "  lang: blue # the default\n",
+            "  lang/rust: red\n",
+            "  code: green\n",
+            "  coding: yellow\n",
+            "editor: vim\n",
+        );
+        std::fs::write(&path, original).unwrap();
+
+        let rename = TagEdit::Rename { from: "lang".into(), to: "language".into() };
+        assert!(edit_tag_colors_file(&path, &rename).unwrap());
+        let merge = TagEdit::Merge { from: "code".into(), into: "coding".into() };
+        assert!(edit_tag_colors_file(&path, &merge).unwrap());
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            concat!(
+                "# piemme settings\n",
+                "safe_mode: false # keep commands off\n",
+                "tag_colors:\n",
+                "  # languages\n",
+                "  language: blue # the default\n",
+                "  language/rust: red\n",
+                "  coding: yellow\n",
+                "editor: vim\n",
+            )
+        );
+
+        std::fs::write(&path, "tag_colors: {draft: red, work: '#00ff00'} # inline\n").unwrap();
+        assert!(edit_tag_colors_file(&path, &TagEdit::Delete("draft".into())).unwrap());
+        let config = Config::load_layers(None, &path, Vec::new()).unwrap();
+        assert_eq!(config.tag_colors.len(), 1);
+        assert_eq!(config.tag_colors["work"], "#00ff00");
+    }
+
+    #[test]
+    fn test_layered_precedence() {
+        let dir = tempfile::tempdir().unwrap();
+        let user = dir.path().join("user.yaml");
+        let project = dir.path().join("project.yaml");
+        std::fs::write(&user, "safe_mode: false\ntag_colors:\n  coding: red\n  writing: green\n")
+            .unwrap();
+        std::fs::write(&project, "tag_colors:\n  coding: blue\n").unwrap();
+
+        let env = vec![
+            ("PIEMME_DEFAULT_EXPORT_FORMAT".to_string(), "raw".to_string()),
+            ("PIEMME_UNKNOWN".to_string(), "ignored".to_string()),
+            ("OTHER".to_string(), "ignored".to_string()),
+        ];
+        let config = Config::load_layers(Some(&user), &project, env).unwrap();
+
+        assert!(!config.safe_mode);
+        assert_eq!(config.get_tag_color("coding"), "blue");
+        assert_eq!(config.get_tag_color("writing"), "green");
+        assert_eq!(config.default_export_format, "raw");
+
+        assert_eq!(config.source_of("safe_mode"), &ConfigLayer::User(user.clone()));
+        assert_eq!(config.source_of("tag_colors.coding"), &ConfigLayer::Project(project));
+        assert_eq!(config.source_of("tag_colors.writing"), &ConfigLayer::User(user));
+        assert_eq!(
+            config.source_of("default_export_format"),
+            &ConfigLayer::Env("PIEMME_DEFAULT_EXPORT_FORMAT".to_string())
+        );
+    }
+
+    #[test]
+    fn test_layered_bad_value_names_its_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let user = dir.path().join("user.yaml");
+        let project = dir.path().join("project.yaml");
+        std::fs::write(&user, "safe_mode: false\n").unwrap();
+        std::fs::write(&project, "tag_colors:\n  coding: blue\nbackup_retention: lots\n").unwrap();
+
+        let error = Config::load_layers(Some(&user), &project, Vec::new()).unwrap_err();
+        let message = format!("{:#}", error);
+        assert!(message.contains(&project.display().to_string()), "{}", message);
+        assert!(message.contains("backup_retention"), "{}", message);
+        assert!(message.contains("line 3"), "{}", message);
+    }
+
+    #[test]
+    fn test_layered_missing_files_use_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let config =
+            Config::load_layers(None, &dir.path().join("missing.yaml"), Vec::new()).unwrap();
+
+        assert!(config.safe_mode);
+        assert_eq!(config.source_of("safe_mode"), &ConfigLayer::Default);
+    }
+
+    #[test]
+    fn test_layered_nested_env_override() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = vec![("PIEMME_TAG_COLORS__CODING".to_string(), "cyan".to_string())];
+        let config = Config::load_layers(None, &dir.path().join("none.yaml"), env).unwrap();
+
+        assert_eq!(config.get_tag_color("coding"), "cyan");
+    }
+
+    #[test]
+    fn test_env_values_keep_their_type() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = vec![
+            ("PIEMME_TAG_COLORS__CODING".to_string(), "#ff0000".to_string()),
+            ("PIEMME_VIEWS__OLD".to_string(), "on".to_string()),
+            ("PIEMME_THEME__NAME".to_string(), "1e3".to_string()),
+            ("PIEMME_BACKUP_RETENTION".to_string(), "3".to_string()),
+        ];
+        let config = Config::load_layers(None, &dir.path().join("none.yaml"), env).unwrap();
+
+        assert_eq!(config.get_tag_color("coding"), "#ff0000");
+        assert_eq!(config.views["old"], "on");
+        assert_eq!(config.theme.name, "1e3");
+        assert_eq!(config.backup_retention, 3);
+    }
+
+    #[test]
+    fn test_bad_env_value_is_a_validation_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = vec![
+            ("PIEMME_BACKUP_RETENTION".to_string(), "lots".to_string()),
+            ("PIEMME_SAFE_MODE".to_string(), "false".to_string()),
+        ];
+        let config = Config::load_layers(None, &dir.path().join("none.yaml"), env).unwrap();
+
+        assert_eq!(config.backup_retention, DEFAULT_BACKUP_RETENTION);
+        assert!(!config.safe_mode);
+        let errors = config.validate();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].to_string().starts_with("PIEMME_BACKUP_RETENTION: backup_retention: "));
+    }
+
+    #[test]
+    fn test_validation_error_names_file_and_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("config.yaml");
+        std::fs::write(
+            &project,
+            "safe_mode: true\ntag_colors:\n  coding: blue\n  test: nope\n",
+        )
+        .unwrap();
+
+        let config = Config::load_layers(None, &project, Vec::new()).unwrap();
+        let errors = config.validate();
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].line, Some(4));
+        let message = errors[0].to_string();
+        assert!(message.starts_with(&format!("{}:4:", project.display())));
+    }
+
+    #[test]
+    fn test_find_key_line() {
+        let yaml = "# comment\nsafe_mode: true\ntag_colors:\n  coding: blue\nother:\n  coding: x\n";
+        assert_eq!(find_key_line(yaml, "safe_mode"), Some(2));
+        assert_eq!(find_key_line(yaml, "tag_colors.coding"), Some(4));
+        assert_eq!(find_key_line(yaml, "tag_colors.missing"), None);
+    }
+
+    #[test]
+    fn test_annotated_yaml() {
+        let mut config = Config::new();
+        config.set_tag_color("coding", "blue");
+        config.sources.insert(
+            "tag_colors.coding".to_string(),
+            ConfigLayer::Env("PIEMME_TAG_COLORS__CODING".to_string()),
+        );
+
+        let rendered = config.to_annotated_yaml().unwrap();
+        assert!(rendered.contains("safe_mode: true  # default"));
+        assert!(rendered.contains("  coding: blue  # env (PIEMME_TAG_COLORS__CODING)"));
+    }
 }
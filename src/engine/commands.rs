@@ -5,6 +5,8 @@ use regex::Regex;
 use std::process::Command;
 use std::sync::LazyLock;
 
+use super::placeholders::is_placeholder;
+
 /// A command found in prompt content
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ShellCommand {
@@ -23,10 +25,11 @@ static COMMAND_REGEX: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"\{\{([^}]+)\}\}").expect("Invalid command regex")
 });
 
-/// Find all commands in content
+/// Find all commands in content ({{arg:...}} placeholders are not commands)
 pub fn find_commands(content: &str) -> Vec<ShellCommand> {
     COMMAND_REGEX
         .captures_iter(content)
+        .filter(|cap| !is_placeholder(&cap[1]))
         .map(|cap| {
             let full_match = cap.get(0).unwrap();
             let cmd_match = cap.get(1).unwrap();
@@ -43,7 +46,9 @@ pub fn find_commands(content: &str) -> Vec<ShellCommand> {
 
 /// Check if content contains any commands
 pub fn has_commands(content: &str) -> bool {
-    COMMAND_REGEX.is_match(content)
+    COMMAND_REGEX
+        .captures_iter(content)
+        .any(|cap| !is_placeholder(&cap[1]))
 }
 
 /// Execute a shell command and return its output
@@ -100,6 +105,7 @@ mod tests {
     fn test_has_commands() {
         assert!(has_commands("Contains {{command}}"));
         assert!(!has_commands("No commands here"));
+        assert!(!has_commands("Only a {{arg:topic}} placeholder"));
     }
 
     #[test]
@@ -1,9 +1,13 @@
 //! Prompt engine - reference resolution and command execution
 
 mod commands;
+mod pinned;
+mod placeholders;
 mod references;
 mod resolver;
 
-pub use commands::execute_command;
-pub use references::{find_references, find_file_references, validate_reference, validate_file_reference, Reference, FileReference, has_file_references};
-pub use resolver::{resolve_commands_in_content, resolve_prompt, resolve_prompt_with_base, ResolveOptions};
+pub use commands::has_commands;
+pub use references::{find_references, find_file_references, has_file_references};
+pub use resolver::{resolve_commands_in_content, resolve_prompt, resolve_prompt_with_base};
+pub use pinned::{check_pinned_references, settle_file_revision, split_revision};
+pub use placeholders::{fill_placeholders, find_placeholders};
@@ -0,0 +1,284 @@
+//! References pinned to a git revision: [[name@rev]] and [[file:path@rev]]
+//!
+//! Pinned content is read from the repository's object store, so edits to the
+//! working tree do not change what a pinned reference resolves to.
+
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use super::references::{find_file_references, find_references, FileReference};
+use crate::fs::parse_frontmatter;
+use crate::git::Repo;
+
+/// How long the commit a revision names is trusted (revisions like HEAD~1 move)
+const TREE_CACHE_TTL: Duration = Duration::from_secs(5);
+
+/// Repository, commit and files at a revision; None if either does not exist
+type Tree = Option<(Repo, String, Vec<String>)>;
+
+/// Trees by (base directory, revision), with when they were read
+type TreeCache = HashMap<(PathBuf, String), (Instant, Tree)>;
+
+// Shared by `check_pinned_references` and the resolver
+static TREE_CACHE: LazyLock<Mutex<TreeCache>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// File contents by (repository root, commit, path); a commit never changes, so neither do they
+type BlobCache = HashMap<(PathBuf, String, String), String>;
+
+static BLOB_CACHE: LazyLock<Mutex<BlobCache>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Split `target@rev` into the target and its revision.
+///
+/// An `@` starting a path component (as in `node_modules/@types`) belongs to the path.
+pub fn split_revision(target: &str) -> (&str, Option<&str>) {
+    match target.rfind('@') {
+        Some(at) if at > 0 && at + 1 < target.len() && !target[..at].ends_with('/') => {
+            (&target[..at], Some(&target[at + 1..]))
+        }
+        _ => (target, None),
+    }
+}
+
+/// Put the `@suffix` of a file reference back into its path when it is part of the file
+/// name (as in `docs/user@example.com.md`): when the whole path is a file in the working
+/// tree, or the suffix is not a revision of the repository.
+pub fn settle_file_revision(base_dir: &Path, reference: &mut FileReference) {
+    let Some(rev) = reference.revision.take() else {
+        return;
+    };
+    let whole = format!("{}@{}", reference.path, rev);
+    if base_dir.join(&whole).is_file() || tree_at(base_dir, &rev).is_none() {
+        reference.path = whole;
+    } else {
+        reference.revision = Some(rev);
+    }
+}
+
+/// The repository containing `base_dir`, the commit `rev` names and its files (cached)
+fn tree_at(base_dir: &Path, rev: &str) -> Tree {
+    let key = (base_dir.to_path_buf(), rev.to_string());
+    let mut cache = TREE_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((fetched, tree)) = cache.get(&key)
+        && fetched.elapsed() < TREE_CACHE_TTL
+    {
+        return tree.clone();
+    }
+
+    let tree = Repo::discover(base_dir).and_then(|repo| {
+        let commit = repo.resolve(rev).ok()?;
+        let files = repo.files_at(&commit).ok()?;
+        Some((repo, commit, files))
+    });
+    cache.insert(key, (Instant::now(), tree.clone()));
+    tree
+}
+
+/// Content of `path` at `commit`, read from git once
+fn show_cached(repo: &Repo, commit: &str, path: &str) -> Result<String> {
+    let key = (repo.root().to_path_buf(), commit.to_string(), path.to_string());
+    if let Some(text) = BLOB_CACHE.lock().unwrap_or_else(|e| e.into_inner()).get(&key) {
+        return Ok(text.clone());
+    }
+    let text = repo.show(commit, path)?;
+    BLOB_CACHE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(key, text.clone());
+    Ok(text)
+}
+
+/// Path relative to the repository root of `relative` (a path from `base_dir`); None
+/// when it leads out of the repository
+fn repo_path(repo: &Repo, base_dir: &Path, relative: &str) -> Option<String> {
+    let root = repo.root().canonicalize().ok()?;
+    let base = base_dir.canonicalize().ok()?;
+    let mut parts: Vec<String> = Vec::new();
+    for component in base.strip_prefix(&root).ok()?.join(relative).components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy().into_owned()),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                parts.pop()?;
+            }
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(parts.join("/"))
+}
+
+/// Find the file of prompt `name` at a revision: the main list first, then folders
+fn find_prompt_path(repo: &Repo, files: &[String], base_dir: &Path, name: &str) -> Option<String> {
+    let piemme = repo_path(repo, base_dir, ".piemme")?;
+    let main = format!("{}/prompts/{}.md", piemme, name);
+    let folders = format!("{}/folders/", piemme);
+    let file_name = format!("/{}.md", name);
+
+    files.iter().find(|f| **f == main).cloned().or_else(|| {
+        files
+            .iter()
+            .find(|f| f.starts_with(&folders) && f.ends_with(&file_name))
+            .cloned()
+    })
+}
+
+/// Find `path` (relative to `base_dir`) at a revision
+fn find_file_path(repo: &Repo, files: &[String], base_dir: &Path, path: &str) -> Option<String> {
+    let path = repo_path(repo, base_dir, path)?;
+    files.contains(&path).then_some(path)
+}
+
+/// Whether `target` (a prompt name or `file:path`) exists at revision `rev`
+fn pinned_target_exists(base_dir: &Path, target: &str, rev: &str) -> bool {
+    let Some((repo, _, files)) = tree_at(base_dir, rev) else {
+        return false;
+    };
+    match target.strip_prefix("file:") {
+        Some(path) => find_file_path(&repo, &files, base_dir, path).is_some(),
+        None => find_prompt_path(&repo, &files, base_dir, target).is_some(),
+    }
+}
+
+/// Check every pinned reference in `content` against its revision, keyed by the text inside
+/// the brackets (`name@rev` or `file:path@rev`).
+///
+/// This runs git, so call it when the content changes rather than while drawing.
+pub fn check_pinned_references(base_dir: &Path, content: &str) -> HashMap<String, bool> {
+    let prompts = find_references(content)
+        .into_iter()
+        .filter_map(|r| r.revision.map(|rev| (r.name, rev)));
+    let files = find_file_references(content).into_iter().filter_map(|mut r| {
+        settle_file_revision(base_dir, &mut r);
+        r.revision.map(|rev| (format!("file:{}", r.path), rev))
+    });
+
+    prompts
+        .chain(files)
+        .map(|(target, rev)| {
+            let exists = pinned_target_exists(base_dir, &target, &rev);
+            (format!("{}@{}", target, rev), exists)
+        })
+        .collect()
+}
+
+/// Content (without frontmatter) of prompt `name` as of revision `rev`
+pub fn read_pinned_prompt(base_dir: &Path, name: &str, rev: &str) -> Result<String> {
+    let (repo, commit, files) = tree_at(base_dir, rev)
+        .ok_or_else(|| anyhow!("Revision not found: {}", rev))?;
+    let path = find_prompt_path(&repo, &files, base_dir, name)
+        .ok_or_else(|| anyhow!("Prompt '{}' not found at {}", name, rev))?;
+
+    let text = show_cached(&repo, &commit, &path)?;
+    Ok(parse_frontmatter(&text).map(|(_, body)| body).unwrap_or(text))
+}
+
+/// Content of the file at `path` (relative to `base_dir`) as of revision `rev`
+pub fn read_pinned_file(base_dir: &Path, path: &str, rev: &str) -> Result<String> {
+    let (repo, commit, files) = tree_at(base_dir, rev)
+        .ok_or_else(|| anyhow!("Revision not found: {}", rev))?;
+    let repo_path = find_file_path(&repo, &files, base_dir, path)
+        .ok_or_else(|| anyhow!("File '{}' not found at {}", path, rev))?;
+    show_cached(&repo, &commit, &repo_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::resolve_prompt_with_base;
+    use crate::fs::{save_prompt, SaveOptions};
+    use crate::models::Prompt;
+    use std::process::Command;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_pinned_references_read_from_git() {
+        let dir = tempdir().unwrap();
+        let git = |args: &[&str]| {
+            let status = Command::new("git").arg("-C").arg(dir.path()).args(args).status();
+            status.is_ok_and(|s| s.success())
+        };
+        // Without git installed there is nothing to test
+        if !git(&["init", "-q"]) {
+            return;
+        }
+        git(&["config", "user.name", "Ada"]);
+        git(&["config", "user.email", "ada@example.com"]);
+
+        let prompts = dir.path().join(".piemme").join("prompts");
+        let save = |name: &str, content: &str| {
+            let mut prompt = Prompt::with_content(content);
+            prompt.name = name.to_string();
+            save_prompt(&prompt, &prompts, SaveOptions::default()).unwrap();
+        };
+        save("style", "v1 [[tone]]");
+        save("tone", "calm");
+        std::fs::write(dir.path().join("notes.txt"), "old notes").unwrap();
+        git(&["add", "."]);
+        git(&["commit", "-q", "-m", "v1"]);
+        git(&["tag", "v1"]);
+
+        save("style", "v2");
+        save("tone", "loud");
+        std::fs::write(dir.path().join("notes.txt"), "new notes").unwrap();
+        // `@` in a file name is not a revision, even in a repository
+        std::fs::write(dir.path().join("user@example.com.md"), "mail").unwrap();
+
+        assert!(pinned_target_exists(dir.path(), "style", "v1"));
+        assert!(pinned_target_exists(dir.path(), "file:notes.txt", "v1"));
+        assert!(!pinned_target_exists(dir.path(), "style", "v9"));
+        assert!(!pinned_target_exists(dir.path(), "missing", "v1"));
+        // Paths are taken relative to the repository root, whatever their `.` and `..`
+        let piemme = dir.path().join(".piemme");
+        assert!(pinned_target_exists(&piemme, "file:../notes.txt", "v1"));
+        assert!(pinned_target_exists(dir.path(), "file:./.piemme/../notes.txt", "v1"));
+        assert!(!pinned_target_exists(dir.path(), "file:../notes.txt", "v1"));
+
+        let checked = check_pinned_references(
+            dir.path(),
+            "[[style@v1]] [[style]] [[file:notes.txt@v1]] [[style@v9]] [[file:user@example.com.md]]",
+        );
+        assert_eq!(checked.len(), 3);
+        assert!(checked["style@v1"]);
+        assert!(checked["file:notes.txt@v1"]);
+        assert!(!checked["style@v9"]);
+
+        let working = |name: &str| (name == "style").then(|| "v2".to_string());
+        let content = "[[style@v1]] / [[style]] / [[file:notes.txt@v1]] / [[file:user@example.com.md]]";
+        let result = resolve_prompt_with_base(content, working, false, dir.path());
+        // References inside pinned content are read at the same revision
+        assert_eq!(result.content, "v1 calm / v2 / old notes / mail");
+    }
+
+    #[test]
+    fn test_at_in_file_names() {
+        let dir = tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("docs")).unwrap();
+        std::fs::write(dir.path().join("docs").join("user@example.com.md"), "mail").unwrap();
+
+        let content = "[[file:docs/user@example.com.md]] [[file:docs/other@example.com.md]]";
+        let mut refs = find_file_references(content);
+        for reference in &mut refs {
+            settle_file_revision(dir.path(), reference);
+        }
+        assert_eq!(refs[0].path, "docs/user@example.com.md");
+        assert_eq!(refs[0].revision, None);
+        // Missing files whose suffix is no revision stay plain paths too
+        assert_eq!(refs[1].path, "docs/other@example.com.md");
+        assert_eq!(refs[1].revision, None);
+
+        assert!(check_pinned_references(dir.path(), content).is_empty());
+        let result = resolve_prompt_with_base(content, |_| None, false, dir.path());
+        assert_eq!(result.content, "mail <!-- [FILE NOT FOUND: docs/other@example.com.md] -->");
+    }
+
+    #[test]
+    fn test_split_revision() {
+        assert_eq!(split_revision("review@v1.2"), ("review", Some("v1.2")));
+        assert_eq!(split_revision("src/lib.rs@HEAD~3"), ("src/lib.rs", Some("HEAD~3")));
+        assert_eq!(split_revision("review"), ("review", None));
+        assert_eq!(split_revision("review@"), ("review@", None));
+        assert_eq!(split_revision("node_modules/@types/x"), ("node_modules/@types/x", None));
+    }
+}
@@ -0,0 +1,63 @@
+//! Template placeholders filled from caller-supplied arguments
+
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// Prefix distinguishing placeholders from commands: {{arg:name}}
+pub const PLACEHOLDER_PREFIX: &str = "arg:";
+
+// Regex for matching {{arg:name}} patterns
+static PLACEHOLDER_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\{\{\s*arg:\s*([A-Za-z0-9_-]+)\s*\}\}").expect("Invalid placeholder regex")
+});
+
+/// Check whether the inside of a {{...}} block is a placeholder rather than a command
+pub fn is_placeholder(inner: &str) -> bool {
+    inner.trim_start().starts_with(PLACEHOLDER_PREFIX)
+}
+
+/// Find the distinct placeholder names in content, in order of first appearance
+pub fn find_placeholders(content: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for cap in PLACEHOLDER_REGEX.captures_iter(content) {
+        let name = &cap[1];
+        if !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+    }
+    names
+}
+
+/// Replace placeholders with argument values; placeholders without a value are left as-is
+pub fn fill_placeholders<F>(content: &str, get_arg: F) -> String
+where
+    F: Fn(&str) -> Option<String>,
+{
+    PLACEHOLDER_REGEX
+        .replace_all(content, |cap: &regex::Captures| {
+            get_arg(&cap[1]).unwrap_or_else(|| cap[0].to_string())
+        })
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_and_fill_placeholders() {
+        let content = "Review {{arg:file}} for {{ arg:focus }}, then {{arg:file}} again";
+        assert_eq!(find_placeholders(content), vec!["file", "focus"]);
+
+        let filled = fill_placeholders(content, |name| {
+            (name == "file").then(|| "main.rs".to_string())
+        });
+        assert_eq!(filled, "Review main.rs for {{ arg:focus }}, then main.rs again");
+    }
+
+    #[test]
+    fn test_argument_values_are_not_expanded() {
+        let filled = fill_placeholders("{{arg:a}}", |_| Some("{{arg:a}} $(rm)".to_string()));
+        assert_eq!(filled, "{{arg:a}} $(rm)");
+    }
+}
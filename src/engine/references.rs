@@ -3,6 +3,8 @@
 use regex::Regex;
 use std::sync::LazyLock;
 
+use super::pinned::split_revision;
+
 /// A reference to another prompt
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Reference {
@@ -10,6 +12,8 @@ pub struct Reference {
     pub full_match: String,
     /// The prompt name being referenced
     pub name: String,
+    /// Git revision the reference is pinned to: [[name@rev]]
+    pub revision: Option<String>,
     /// Start position in the content
     pub start: usize,
     /// End position in the content
@@ -25,6 +29,8 @@ pub struct FileReference {
     pub full_match: String,
     /// The file path being referenced
     pub path: String,
+    /// Git revision the reference is pinned to: [[file:path@rev]]
+    pub revision: Option<String>,
     /// Start position in the content
     pub start: usize,
     /// End position in the content
@@ -33,9 +39,9 @@ pub struct FileReference {
     pub is_valid: bool,
 }
 
-// Regex for matching [[prompt_name]] references
+// Regex for matching [[prompt_name]] and [[prompt_name@rev]] references
 static REFERENCE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
-    Regex::new(r"\[\[([a-z0-9_]+)\]\]").expect("Invalid reference regex")
+    Regex::new(r"\[\[([a-z0-9_]+)(?:@([^\]\s]+))?\]\]").expect("Invalid reference regex")
 });
 
 // Regex for matching [[file:path/to/file]] references
@@ -54,6 +60,7 @@ pub fn find_references(content: &str) -> Vec<Reference> {
             Reference {
                 full_match: full_match.as_str().to_string(),
                 name: name_match.as_str().to_string(),
+                revision: cap.get(2).map(|m| m.as_str().to_string()),
                 start: full_match.start(),
                 end: full_match.end(),
                 is_valid: false, // Will be set by validate_reference
@@ -68,11 +75,12 @@ pub fn find_file_references(content: &str) -> Vec<FileReference> {
         .captures_iter(content)
         .map(|cap| {
             let full_match = cap.get(0).unwrap();
-            let path_match = cap.get(1).unwrap();
+            let (path, revision) = split_revision(cap.get(1).unwrap().as_str());
             
             FileReference {
                 full_match: full_match.as_str().to_string(),
-                path: path_match.as_str().to_string(),
+                path: path.to_string(),
+                revision: revision.map(String::from),
                 start: full_match.start(),
                 end: full_match.end(),
                 is_valid: false, // Will be set by validate_file_reference
@@ -186,6 +194,21 @@ mod tests {
         assert!(!has_file_references("Contains [[reference]] but not file"));
     }
 
+    #[test]
+    fn test_pinned_references() {
+        let content = "[[code_review@v1.2]] [[plain]] [[file:src/lib.rs@HEAD~3]] [[bad@]]";
+        let refs = find_references(content);
+        assert_eq!(refs.len(), 2);
+        assert_eq!(refs[0].name, "code_review");
+        assert_eq!(refs[0].revision.as_deref(), Some("v1.2"));
+        assert_eq!(refs[0].full_match, "[[code_review@v1.2]]");
+        assert_eq!(refs[1].revision, None);
+
+        let file_refs = find_file_references(content);
+        assert_eq!(file_refs[0].path, "src/lib.rs");
+        assert_eq!(file_refs[0].revision.as_deref(), Some("HEAD~3"));
+    }
+
     #[test]
     fn test_mixed_references() {
         let content = "Prompt: [[my_prompt]], File: [[file:src/lib.rs]]";
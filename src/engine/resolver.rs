@@ -4,6 +4,7 @@ use std::collections::HashSet;
 use std::path::Path;
 
 use super::commands::{execute_command_safe, find_commands, has_commands};
+use super::pinned::{read_pinned_file, read_pinned_prompt, settle_file_revision};
 use super::references::{find_references, find_file_references, has_references, has_file_references};
 
 /// Options for resolving prompt content
@@ -55,30 +56,26 @@ pub fn resolve_prompt_with_base<F>(content: &str, get_content: F, execute_cmds:
 where
     F: Fn(&str) -> Option<String>,
 {
-    let mut visited = HashSet::new();
-    let mut result = ResolveResult {
-        content: content.to_string(),
-        commands: Vec::new(),
-        references: Vec::new(),
-        file_references: Vec::new(),
-        had_circular_refs: false,
-        max_depth_exceeded: false,
+    let mut context = ResolveContext {
+        get_content: &get_content,
+        base_dir,
+        max_depth: 10,
+        visited: HashSet::new(),
+        result: ResolveResult {
+            content: String::new(),
+            commands: Vec::new(),
+            references: Vec::new(),
+            file_references: Vec::new(),
+            had_circular_refs: false,
+            max_depth_exceeded: false,
+        },
     };
 
     // Resolve both prompt references and file references recursively
     // File references are resolved at each level of recursion
-    result.content = resolve_references_recursive(
-        &result.content,
-        &get_content,
-        &mut visited,
-        0,
-        10,
-        &mut result.references,
-        &mut result.file_references,
-        base_dir,
-        &mut result.had_circular_refs,
-        &mut result.max_depth_exceeded,
-    );
+    let content = context.resolve(content, 0, None);
+    let mut result = context.result;
+    result.content = content;
 
     // Find commands
     let commands = find_commands(&result.content);
@@ -92,8 +89,15 @@ where
     result
 }
 
-/// Resolve file references in content by reading file contents
-fn resolve_file_references(content: &str, base_dir: &Path, resolved_files: &mut Vec<String>) -> String {
+/// Resolve file references in content by reading file contents.
+///
+/// Inside content pinned to a git revision, unpinned references are read at that revision too.
+fn resolve_file_references(
+    content: &str,
+    base_dir: &Path,
+    pinned: Option<&str>,
+    resolved_files: &mut Vec<String>,
+) -> String {
     if !has_file_references(content) {
         return content.to_string();
     }
@@ -102,7 +106,20 @@ fn resolve_file_references(content: &str, base_dir: &Path, resolved_files: &mut
     let mut result = content.to_string();
 
     // Process file references in reverse order to maintain correct positions
-    for file_ref in file_refs.into_iter().rev() {
+    for mut file_ref in file_refs.into_iter().rev() {
+        settle_file_revision(base_dir, &mut file_ref);
+        if let Some(rev) = file_ref.revision.as_deref().or(pinned) {
+            let replacement = match read_pinned_file(base_dir, &file_ref.path, rev) {
+                Ok(file_content) => {
+                    resolved_files.push(format!("{}@{}", file_ref.path, rev));
+                    file_content
+                }
+                Err(e) => format!("<!-- [FILE NOT FOUND: {}@{} - {}] -->", file_ref.path, rev, e),
+            };
+            result = result.replace(&file_ref.full_match, &replacement);
+            continue;
+        }
+
         let file_path = base_dir.join(&file_ref.path);
         
         if file_path.exists() && file_path.is_file() {
@@ -127,78 +144,84 @@ fn resolve_file_references(content: &str, base_dir: &Path, resolved_files: &mut
     result
 }
 
-/// Recursively resolve references in content
-fn resolve_references_recursive<F>(
-    content: &str,
-    get_content: &F,
-    visited: &mut HashSet<String>,
-    depth: usize,
+/// State shared across the levels of a recursive resolution
+struct ResolveContext<'a, F> {
+    get_content: &'a F,
+    /// Directory file references are relative to
+    base_dir: &'a Path,
     max_depth: usize,
-    resolved_refs: &mut Vec<String>,
-    resolved_files: &mut Vec<String>,
-    base_dir: &Path,
-    had_circular: &mut bool,
-    max_exceeded: &mut bool,
-) -> String
+    /// References being resolved on the current path, to detect cycles
+    visited: HashSet<String>,
+    /// Collects what was resolved (its `content` is filled in by the caller)
+    result: ResolveResult,
+}
+
+impl<F> ResolveContext<'_, F>
 where
     F: Fn(&str) -> Option<String>,
 {
-    if depth >= max_depth {
-        *max_exceeded = true;
-        return content.to_string();
-    }
+    /// Recursively resolve references in content, reading them at `pinned` when the
+    /// content itself was read at a git revision
+    fn resolve(&mut self, content: &str, depth: usize, pinned: Option<&str>) -> String {
+        if depth >= self.max_depth {
+            self.result.max_depth_exceeded = true;
+            return content.to_string();
+        }
 
-    if !has_references(content) && !has_file_references(content) {
-        return content.to_string();
-    }
+        if !has_references(content) && !has_file_references(content) {
+            return content.to_string();
+        }
 
-    let mut result = content.to_string();
+        // Resolve file references at this level
+        let mut result = resolve_file_references(
+            content,
+            self.base_dir,
+            pinned,
+            &mut self.result.file_references,
+        );
+
+        // Now resolve prompt references
+        if !has_references(&result) {
+            return result;
+        }
 
-    // Resolve file references at this level
-    result = resolve_file_references(&result, base_dir, resolved_files);
+        let refs = find_references(&result);
+
+        // Process references in reverse order to maintain correct positions
+        for reference in refs.into_iter().rev() {
+            let revision = reference.revision.as_deref().or(pinned);
+            let key = match revision {
+                Some(rev) => format!("{}@{}", reference.name, rev),
+                None => reference.name.clone(),
+            };
+
+            if self.visited.contains(&key) {
+                // Circular reference detected
+                self.result.had_circular_refs = true;
+                let warning = format!("<!-- [CIRCULAR REFERENCE DETECTED: {}] -->", key);
+                result = result.replace(&reference.full_match, &warning);
+                continue;
+            }
 
-    // Now resolve prompt references
-    if !has_references(&result) {
-        return result;
-    }
+            let ref_content = match revision {
+                Some(rev) => read_pinned_prompt(self.base_dir, &reference.name, rev).ok(),
+                None => (self.get_content)(&reference.name),
+            };
+            if let Some(ref_content) = ref_content {
+                self.visited.insert(key.clone());
+                self.result.references.push(key.clone());
 
-    let refs = find_references(&result);
+                // Recursively resolve the referenced content (including its file references)
+                let resolved_content = self.resolve(&ref_content, depth + 1, revision);
 
-    // Process references in reverse order to maintain correct positions
-    for reference in refs.into_iter().rev() {
-        if visited.contains(&reference.name) {
-            // Circular reference detected
-            *had_circular = true;
-            let warning = format!("<!-- [CIRCULAR REFERENCE DETECTED: {}] -->", reference.name);
-            result = result.replace(&reference.full_match, &warning);
-            continue;
+                result = result.replace(&reference.full_match, &resolved_content);
+                self.visited.remove(&key);
+            }
+            // If reference not found, leave it as-is (will show as invalid in highlighting)
         }
 
-        if let Some(ref_content) = get_content(&reference.name) {
-            visited.insert(reference.name.clone());
-            resolved_refs.push(reference.name.clone());
-
-            // Recursively resolve the referenced content (including any file references it may have)
-            let resolved_content = resolve_references_recursive(
-                &ref_content,
-                get_content,
-                visited,
-                depth + 1,
-                max_depth,
-                resolved_refs,
-                resolved_files,
-                base_dir,
-                had_circular,
-                max_exceeded,
-            );
-
-            result = result.replace(&reference.full_match, &resolved_content);
-            visited.remove(&reference.name);
-        }
-        // If reference not found, leave it as-is (will show as invalid in highlighting)
+        result
     }
-
-    result
 }
 
 /// Replace command placeholders with their output (public for use in command confirmation flow)
@@ -0,0 +1,68 @@
+//! Crash-safe file writes
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Write `contents` to `path` without ever leaving a truncated file behind.
+///
+/// The data goes to a temporary file in the same directory, is flushed to disk,
+/// then renamed over `path`; a failure at any point leaves the old file intact.
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> std::io::Result<()> {
+    let temp = temp_path(path);
+
+    let result = (|| {
+        let mut file = std::fs::File::create(&temp)?;
+        file.write_all(contents.as_ref())?;
+        file.sync_all()?;
+        std::fs::rename(&temp, path)
+    })();
+
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    result
+}
+
+/// Hidden sibling of `path` used while writing (e.g. `.name.md.tmp-1234`)
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{}.tmp-{}", name, std::process::id()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_write_atomic_replaces_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("prompt.md");
+
+        write_atomic(&path, "first").unwrap();
+        write_atomic(&path, "second").unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "second");
+        // No temporary files are left behind
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_write_atomic_failure_keeps_old_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("prompt.md");
+        write_atomic(&path, "original").unwrap();
+
+        // Renaming a file over a directory fails
+        let target = dir.path().join("sub");
+        std::fs::create_dir(&target).unwrap();
+        std::fs::write(target.join("x"), "").unwrap();
+        assert!(write_atomic(&target, "data").is_err());
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "original");
+        assert!(!temp_path(&target).exists());
+    }
+}
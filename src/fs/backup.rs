@@ -0,0 +1,169 @@
+//! Rolling backups of prompt files taken before they are overwritten
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+use super::prompt_io::{load_prompt, save_prompt, SaveOptions};
+use crate::models::Prompt;
+
+/// Backups kept per prompt unless configured otherwise
+pub const DEFAULT_BACKUP_RETENTION: usize = 10;
+
+/// Format of backup file stems (UTC, sortable)
+const TIMESTAMP_FORMAT: &str = "%Y%m%dT%H%M%S%.6fZ";
+
+/// A saved copy of a prompt file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backup {
+    /// Path of the backup file
+    pub path: PathBuf,
+    /// When the backup was taken
+    pub taken: DateTime<Utc>,
+}
+
+impl Backup {
+    /// Load the prompt as it was when the backup was taken
+    pub fn load(&self) -> Result<Prompt> {
+        load_prompt(&self.path)
+    }
+}
+
+/// The `.piemme` directory containing `dir`, if any
+pub(super) fn store_root(dir: &Path) -> Option<&Path> {
+    dir.ancestors().find(|a| a.file_name().is_some_and(|n| n == ".piemme"))
+}
+
+/// Backup directory of the store containing `dir`, if `dir` is inside a `.piemme` directory
+pub fn store_backup_dir(dir: &Path) -> Option<PathBuf> {
+    store_root(dir).map(|root| root.join(".backup"))
+}
+
+/// Copy the file at `path` into the backups of prompt `id`, keeping the newest `retention`
+pub fn backup_file(
+    path: &Path,
+    backup_dir: &Path,
+    id: Uuid,
+    retention: usize,
+) -> Result<Option<PathBuf>> {
+    if retention == 0 || !path.exists() {
+        return Ok(None);
+    }
+
+    let dir = backup_dir.join(id.to_string());
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create backup directory: {}", dir.display()))?;
+
+    let backup = dir.join(format!("{}.md", Utc::now().format(TIMESTAMP_FORMAT)));
+    std::fs::copy(path, &backup)
+        .with_context(|| format!("Failed to back up {}", path.display()))?;
+
+    // Drop the oldest backups beyond the retention count
+    let backups = list_backups(backup_dir, id)?;
+    for old in backups.iter().skip(retention) {
+        std::fs::remove_file(&old.path)
+            .with_context(|| format!("Failed to remove old backup: {}", old.path.display()))?;
+    }
+
+    Ok(Some(backup))
+}
+
+/// List the backups of prompt `id`, newest first
+pub fn list_backups(backup_dir: &Path, id: Uuid) -> Result<Vec<Backup>> {
+    let dir = backup_dir.join(id.to_string());
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups = Vec::new();
+    for entry in std::fs::read_dir(&dir)
+        .with_context(|| format!("Failed to read backups: {}", dir.display()))?
+    {
+        let path = entry?.path();
+        let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        if let Ok(taken) = NaiveDateTime::parse_from_str(stem, TIMESTAMP_FORMAT) {
+            backups.push(Backup {
+                path,
+                taken: taken.and_utc(),
+            });
+        }
+    }
+
+    backups.sort_by_key(|b| std::cmp::Reverse(b.taken));
+    Ok(backups)
+}
+
+/// Restore `prompt` (stored in `dir`) to the content and tags of `backup`.
+///
+/// The current file is itself backed up first, so a restore can be undone.
+pub fn restore_backup(
+    backup: &Backup,
+    prompt: &Prompt,
+    dir: &Path,
+    options: SaveOptions,
+) -> Result<Prompt> {
+    let mut restored = backup.load()?;
+    restored.name = prompt.name.clone();
+    restored.id = prompt.id;
+    restored.modified = Utc::now();
+
+    save_prompt(&restored, dir, options)?;
+    Ok(restored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_backups_roll_and_restore() {
+        let dir = tempdir().unwrap();
+        let prompts = dir.path().join(".piemme").join("prompts");
+        let backup_dir = store_backup_dir(&prompts).unwrap();
+        assert_eq!(backup_dir, dir.path().join(".piemme").join(".backup"));
+
+        let mut prompt = Prompt::with_content("v0");
+        prompt.name = "p".to_string();
+        let path = prompts.join("p.md");
+        save_prompt(&prompt, &prompts, SaveOptions::default()).unwrap();
+
+        // Each overwrite backs up the previous version
+        for version in 1..=4 {
+            prompt.content = format!("v{}", version);
+            save_prompt(&prompt, &prompts, SaveOptions::default()).unwrap();
+        }
+        backup_file(&path, &backup_dir, prompt.id, 3).unwrap();
+
+        let backups = list_backups(&backup_dir, prompt.id).unwrap();
+        assert_eq!(backups.len(), 3);
+        assert_eq!(backups[0].load().unwrap().content, "v4");
+        assert_eq!(backups[1].load().unwrap().content, "v3");
+
+        let restored =
+            restore_backup(&backups[1], &prompt, &prompts, SaveOptions::default()).unwrap();
+        assert_eq!(restored.content, "v3");
+        assert_eq!(load_prompt(&path).unwrap().content, "v3");
+
+        // A retention of 0 keeps no backups
+        let count = list_backups(&backup_dir, prompt.id).unwrap().len();
+        prompt.content = "v5".to_string();
+        let options = SaveOptions {
+            backup_retention: 0,
+            ..SaveOptions::default()
+        };
+        save_prompt(&prompt, &prompts, options).unwrap();
+        assert_eq!(list_backups(&backup_dir, prompt.id).unwrap().len(), count);
+    }
+
+    #[test]
+    fn test_no_backups_outside_store() {
+        let dir = tempdir().unwrap();
+        assert!(store_backup_dir(dir.path()).is_none());
+        let missing = dir.path().join("missing.md");
+        assert_eq!(backup_file(&missing, dir.path(), Uuid::new_v4(), 5).unwrap(), None);
+    }
+}
@@ -0,0 +1,742 @@
+//! Prompt bundles: a prompt plus everything it references, in one file
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::path::{Component, Path, PathBuf};
+use uuid::Uuid;
+
+use super::atomic::write_atomic;
+use super::directory::list_folders;
+use super::prompt_io::{delete_prompt, load_all_prompts, save_prompt, SaveOptions};
+use crate::config::{archive_dir, folders_dir, prompts_dir};
+use crate::engine::{find_file_references, find_references, settle_file_revision};
+use crate::models::prompt::{is_valid_name, make_unique_name, normalize_name, PromptStatus};
+use crate::models::Prompt;
+
+/// Current bundle format version
+const BUNDLE_VERSION: u32 = 1;
+
+/// A self-contained set of prompts and referenced files
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bundle {
+    pub manifest: BundleManifest,
+    pub prompts: Vec<BundledPrompt>,
+    #[serde(default)]
+    pub files: Vec<BundledFile>,
+}
+
+/// Describes what a bundle contains
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleManifest {
+    /// Bundle format version
+    pub version: u32,
+    /// When the bundle was created
+    pub created: DateTime<Utc>,
+    /// Prompts the bundle was created from
+    pub roots: Vec<String>,
+    /// Every prompt in the bundle (roots first, then dependencies)
+    pub prompts: Vec<String>,
+    /// Referenced files included in the bundle
+    #[serde(default)]
+    pub files: Vec<String>,
+    /// References that could not be resolved when bundling
+    #[serde(default)]
+    pub missing: Vec<String>,
+}
+
+/// A prompt stored in a bundle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundledPrompt {
+    pub id: Uuid,
+    pub name: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub created: DateTime<Utc>,
+    pub modified: DateTime<Utc>,
+    pub content: String,
+}
+
+impl BundledPrompt {
+    fn from_prompt(prompt: &Prompt) -> Self {
+        Self {
+            id: prompt.id,
+            name: prompt.name.clone(),
+            tags: prompt.tags.clone(),
+            created: prompt.created,
+            modified: prompt.modified,
+            content: prompt.content.clone(),
+        }
+    }
+
+    fn to_prompt(&self) -> Prompt {
+        Prompt {
+            id: self.id,
+            name: self.name.clone(),
+            content: self.content.clone(),
+            tags: self.tags.clone(),
+            created: self.created,
+            modified: self.modified,
+            status: PromptStatus::Managed,
+        }
+    }
+}
+
+/// A referenced file stored in a bundle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundledFile {
+    /// Path as written in the `[[file:...]]` reference
+    pub path: String,
+    pub content: String,
+}
+
+impl Bundle {
+    /// Load a bundle from a file
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read bundle: {}", path.display()))?;
+        let bundle: Bundle = serde_json::from_str(&contents)
+            .with_context(|| format!("Failed to parse bundle: {}", path.display()))?;
+        if bundle.manifest.version > BUNDLE_VERSION {
+            anyhow::bail!(
+                "Bundle version {} is newer than supported version {}",
+                bundle.manifest.version,
+                BUNDLE_VERSION
+            );
+        }
+        Ok(bundle)
+    }
+
+    /// A copy whose prompts all have valid names, as prompt creation gives them, so none
+    /// can point outside the store. Returns the renames (bundle name, new name), with
+    /// references to the renamed prompts rewritten.
+    fn with_valid_names(&self) -> (Self, Vec<(String, String)>) {
+        let mut bundle = self.clone();
+        let mut taken: Vec<String> = bundle
+            .prompts
+            .iter()
+            .filter(|p| is_valid_name(&p.name))
+            .map(|p| p.name.clone())
+            .collect();
+
+        let mut renamed = Vec::new();
+        for prompt in bundle.prompts.iter_mut().filter(|p| !is_valid_name(&p.name)) {
+            let mut base = normalize_name(&prompt.name);
+            if base.is_empty() {
+                base = "imported".to_string();
+            }
+            let names: Vec<&str> = taken.iter().map(String::as_str).collect();
+            let name = make_unique_name(&base, &names);
+            taken.push(name.clone());
+            renamed.push((std::mem::replace(&mut prompt.name, name.clone()), name));
+        }
+
+        for prompt in &mut bundle.prompts {
+            for (old, new) in &renamed {
+                prompt.content = rename_references(&prompt.content, old, new);
+            }
+        }
+        for name in &mut bundle.manifest.prompts {
+            if let Some((_, new)) = renamed.iter().find(|(old, _)| old == name) {
+                *name = new.clone();
+            }
+        }
+        (bundle, renamed)
+    }
+
+    /// Save the bundle to a file
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let contents =
+            serde_json::to_string_pretty(self).with_context(|| "Failed to serialize bundle")?;
+        write_atomic(path, contents)
+            .with_context(|| format!("Failed to write bundle: {}", path.display()))?;
+        Ok(())
+    }
+}
+
+/// Point `[[old]]` and `[[old@rev]]` references in `content` at `new`
+fn rename_references(content: &str, old: &str, new: &str) -> String {
+    content
+        .replace(&format!("[[{}]]", old), &format!("[[{}]]", new))
+        .replace(&format!("[[{}@", old), &format!("[[{}@", new))
+}
+
+/// Collect `roots` and every prompt transitively reachable from them via `[[...]]`.
+///
+/// Returns the reachable names (roots first, breadth-first) and the names that
+/// were referenced but not found.
+pub fn collect_dependencies<F>(roots: &[&str], get_content: F) -> (Vec<String>, Vec<String>)
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut found = Vec::new();
+    let mut missing = Vec::new();
+    let mut seen = HashSet::new();
+    let mut queue: VecDeque<String> = roots.iter().map(|r| r.to_string()).collect();
+
+    while let Some(name) = queue.pop_front() {
+        if !seen.insert(name.clone()) {
+            continue;
+        }
+        match get_content(&name) {
+            Some(content) => {
+                queue.extend(find_references(&content).into_iter().map(|r| r.name));
+                found.push(name);
+            }
+            None => missing.push(name),
+        }
+    }
+
+    (found, missing)
+}
+
+/// Build a bundle from `roots` and their dependencies.
+///
+/// When `files_base` is given, `[[file:...]]` references are read relative to it
+/// and included; files that cannot be read are listed as missing.
+pub fn create_bundle(
+    roots: &[&str],
+    all_prompts: &[Prompt],
+    files_base: Option<&Path>,
+) -> Result<Bundle> {
+    let find = |name: &str| all_prompts.iter().find(|p| p.name == name);
+    for root in roots {
+        if find(root).is_none() {
+            anyhow::bail!("Prompt not found: {}", root);
+        }
+    }
+
+    let (names, mut missing) = collect_dependencies(roots, |n| find(n).map(|p| p.content.clone()));
+    let prompts: Vec<BundledPrompt> = names
+        .iter()
+        .filter_map(|name| find(name))
+        .map(BundledPrompt::from_prompt)
+        .collect();
+
+    let mut files: Vec<BundledFile> = Vec::new();
+    if let Some(base) = files_base {
+        for prompt in &prompts {
+            for mut reference in find_file_references(&prompt.content) {
+                settle_file_revision(base, &mut reference);
+                if files.iter().any(|f| f.path == reference.path) {
+                    continue;
+                }
+                match std::fs::read_to_string(base.join(&reference.path)) {
+                    Ok(content) => files.push(BundledFile {
+                        path: reference.path,
+                        content,
+                    }),
+                    Err(_) => missing.push(format!("file:{}", reference.path)),
+                }
+            }
+        }
+    }
+
+    Ok(Bundle {
+        manifest: BundleManifest {
+            version: BUNDLE_VERSION,
+            created: Utc::now(),
+            roots: roots.iter().map(|r| r.to_string()).collect(),
+            prompts: names,
+            files: files.iter().map(|f| f.path.clone()).collect(),
+            missing,
+        },
+        prompts,
+        files,
+    })
+}
+
+/// A prompt already in the store, with the directory it lives in
+#[derive(Debug, Clone)]
+pub struct ExistingPrompt {
+    pub name: String,
+    pub id: Uuid,
+    pub dir: PathBuf,
+}
+
+/// Load every prompt in the store (main, archive and folders) with its directory
+pub fn existing_prompts() -> Result<Vec<ExistingPrompt>> {
+    let mut dirs = vec![prompts_dir()?, archive_dir()?];
+    for folder in list_folders()? {
+        dirs.push(folders_dir()?.join(folder));
+    }
+
+    let mut existing = Vec::new();
+    for dir in dirs {
+        for prompt in load_all_prompts(&dir)? {
+            existing.push(ExistingPrompt {
+                name: prompt.name,
+                id: prompt.id,
+                dir: dir.clone(),
+            });
+        }
+    }
+
+    Ok(existing)
+}
+
+/// How a bundled prompt collides with the store
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Collision {
+    /// A different prompt has the same name
+    Name,
+    /// A prompt with another name has the same UUID
+    Id,
+    /// Same name and same UUID (the prompt was imported before)
+    NameAndId,
+}
+
+impl std::fmt::Display for Collision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Collision::Name => write!(f, "name already exists"),
+            Collision::Id => write!(f, "UUID already used by another prompt"),
+            Collision::NameAndId => write!(f, "already exists"),
+        }
+    }
+}
+
+/// What to do with a colliding prompt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictResolution {
+    /// Import under a new unique name (and a new UUID if needed)
+    Rename,
+    /// Keep the existing prompt
+    Skip,
+    /// Replace the existing prompt
+    Overwrite,
+}
+
+impl ConflictResolution {
+    /// Parse a resolution name ("rename", "skip" or "overwrite")
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "rename" => Some(ConflictResolution::Rename),
+            "skip" => Some(ConflictResolution::Skip),
+            "overwrite" => Some(ConflictResolution::Overwrite),
+            _ => None,
+        }
+    }
+}
+
+/// Find how each bundled prompt collides with existing prompts
+pub fn detect_collisions(bundle: &Bundle, existing: &[ExistingPrompt]) -> Vec<Option<Collision>> {
+    bundle
+        .with_valid_names()
+        .0
+        .prompts
+        .iter()
+        .map(|prompt| {
+            let name = existing.iter().any(|e| e.name == prompt.name);
+            let id = existing.iter().any(|e| e.id == prompt.id);
+            match (name, id) {
+                (true, true) => Some(Collision::NameAndId),
+                (true, false) => Some(Collision::Name),
+                (false, true) => Some(Collision::Id),
+                (false, false) => None,
+            }
+        })
+        .collect()
+}
+
+/// Outcome of importing a bundle
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    /// Prompts written, with the directory they were written to
+    pub written: Vec<(Prompt, PathBuf)>,
+    /// Prompts imported under a new name: (bundle name, new name)
+    pub renamed: Vec<(String, String)>,
+    /// Prompts that replaced an existing one
+    pub overwritten: Vec<String>,
+    /// Existing prompts deleted by an overwrite that matched by id under another name,
+    /// with the directory they lived in
+    pub removed: Vec<(String, PathBuf)>,
+    /// Prompts left out because of a collision
+    pub skipped: Vec<String>,
+    /// Referenced files written
+    pub files_written: Vec<PathBuf>,
+    /// Referenced files not written (already present with other content, or unsafe path)
+    pub files_skipped: Vec<String>,
+}
+
+/// Import a bundle.
+///
+/// `resolutions` holds one entry per bundled prompt and is only consulted for
+/// prompts that collide. New prompts go to `target_dir`; overwritten prompts stay
+/// in the directory of the prompt they replace. Files are written under `files_base`
+/// only when absent. Prompts with invalid names (such as `../x`) are renamed first.
+pub fn import_bundle(
+    bundle: &Bundle,
+    resolutions: &[ConflictResolution],
+    existing: &[ExistingPrompt],
+    target_dir: &Path,
+    files_base: &Path,
+    options: SaveOptions,
+) -> Result<ImportReport> {
+    let (bundle, renamed) = bundle.with_valid_names();
+    let bundle = &bundle;
+    let collisions = detect_collisions(bundle, existing);
+    let mut report = ImportReport {
+        renamed,
+        ..ImportReport::default()
+    };
+
+    let mut taken_names: Vec<String> = existing.iter().map(|e| e.name.clone()).collect();
+    taken_names.extend(bundle.prompts.iter().map(|p| p.name.clone()));
+    let mut taken_ids: HashSet<Uuid> = existing.iter().map(|e| e.id).collect();
+
+    // Decide the fate of each prompt before writing, so references can be rewritten
+    let mut planned: Vec<(Prompt, PathBuf, Option<&ExistingPrompt>)> = Vec::new();
+    for (i, bundled) in bundle.prompts.iter().enumerate() {
+        let mut prompt = bundled.to_prompt();
+        let Some(collision) = collisions[i] else {
+            taken_ids.insert(prompt.id);
+            planned.push((prompt, target_dir.to_path_buf(), None));
+            continue;
+        };
+
+        match resolutions.get(i).copied().unwrap_or(ConflictResolution::Skip) {
+            ConflictResolution::Skip => report.skipped.push(prompt.name),
+            ConflictResolution::Rename => {
+                if collision != Collision::Id {
+                    let names: Vec<&str> = taken_names.iter().map(String::as_str).collect();
+                    prompt.name = make_unique_name(&prompt.name, &names);
+                    taken_names.push(prompt.name.clone());
+                    report.renamed.push((bundled.name.clone(), prompt.name.clone()));
+                }
+                if taken_ids.contains(&prompt.id) {
+                    prompt.id = Uuid::new_v4();
+                }
+                taken_ids.insert(prompt.id);
+                planned.push((prompt, target_dir.to_path_buf(), None));
+            }
+            ConflictResolution::Overwrite => {
+                let replaced = existing
+                    .iter()
+                    .find(|e| e.name == prompt.name)
+                    .or_else(|| existing.iter().find(|e| e.id == prompt.id));
+                if let Some(replaced) = replaced {
+                    // Keep UUIDs unique if another prompt still owns this one
+                    if existing.iter().any(|e| e.id == prompt.id && e.name != replaced.name) {
+                        prompt.id = Uuid::new_v4();
+                    }
+                    report.overwritten.push(prompt.name.clone());
+                    planned.push((prompt, replaced.dir.clone(), Some(replaced)));
+                }
+            }
+        }
+    }
+
+    for (mut prompt, dir, replaced) in planned {
+        for (old, new) in &report.renamed {
+            prompt.content = rename_references(&prompt.content, old, new);
+        }
+        // Written atomically over a replaced file of the same name, so a failed import
+        // never loses it; one matched by id under another name goes once this is written
+        save_prompt(&prompt, &dir, options)?;
+        if let Some(replaced) = replaced
+            && replaced.name != prompt.name
+        {
+            delete_prompt(&replaced.name, &replaced.dir)?;
+            report.removed.push((replaced.name.clone(), replaced.dir.clone()));
+        }
+        report.written.push((prompt, dir));
+    }
+
+    for file in &bundle.files {
+        let relative = Path::new(&file.path);
+        let safe = relative
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+        let dest = files_base.join(relative);
+
+        if !safe {
+            report.files_skipped.push(file.path.clone());
+        } else if dest.exists() {
+            let same = std::fs::read_to_string(&dest).is_ok_and(|c| c == file.content);
+            if !same {
+                report.files_skipped.push(file.path.clone());
+            }
+        } else {
+            if let Some(parent) = dest.parent() {
+                std::fs::create_dir_all(parent)
+                    .with_context(|| format!("Failed to create {}", parent.display()))?;
+            }
+            write_atomic(&dest, &file.content)
+                .with_context(|| format!("Failed to write {}", dest.display()))?;
+            report.files_written.push(dest);
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn prompt(name: &str, content: &str) -> Prompt {
+        let mut prompt = Prompt::with_content(content);
+        prompt.name = name.to_string();
+        prompt
+    }
+
+    #[test]
+    fn test_collect_dependencies_transitive_and_circular() {
+        let prompts = [
+            prompt("a", "[[b]] and [[c]]"),
+            prompt("b", "[[c]] [[a]]"),
+            prompt("c", "[[gone]]"),
+            prompt("unrelated", "x"),
+        ];
+        let get = |n: &str| prompts.iter().find(|p| p.name == n).map(|p| p.content.clone());
+
+        let (found, missing) = collect_dependencies(&["a"], get);
+        assert_eq!(found, vec!["a", "b", "c"]);
+        assert_eq!(missing, vec!["gone"]);
+    }
+
+    #[test]
+    fn test_create_bundle_with_files() {
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "notes").unwrap();
+        let prompts = [
+            prompt("root", "[[dep]] [[file:notes.txt]] [[file:missing.txt]]"),
+            prompt("dep", "dependency"),
+        ];
+
+        let bundle = create_bundle(&["root"], &prompts, Some(dir.path())).unwrap();
+        assert_eq!(bundle.manifest.prompts, vec!["root", "dep"]);
+        assert_eq!(bundle.manifest.files, vec!["notes.txt"]);
+        assert_eq!(bundle.manifest.missing, vec!["file:missing.txt"]);
+        assert_eq!(bundle.files[0].content, "notes");
+
+        let without_files = create_bundle(&["root"], &prompts, None).unwrap();
+        assert!(without_files.files.is_empty());
+
+        assert!(create_bundle(&["nope"], &prompts, None).is_err());
+    }
+
+    #[test]
+    fn test_bundle_roundtrip() {
+        let dir = tempdir().unwrap();
+        let prompts = [prompt("root", "hello")];
+        let bundle = create_bundle(&["root"], &prompts, None).unwrap();
+
+        let path = dir.path().join("root.bundle.json");
+        bundle.save(&path).unwrap();
+        let loaded = Bundle::load(&path).unwrap();
+
+        assert_eq!(loaded.prompts[0].name, "root");
+        assert_eq!(loaded.prompts[0].id, prompts[0].id);
+    }
+
+    #[test]
+    fn test_import_resolutions() {
+        let store = tempdir().unwrap();
+        let files = tempdir().unwrap();
+        let existing_dir = store.path().join("prompts");
+
+        let mut existing_root = prompt("root", "local root");
+        let existing_dep = prompt("dep", "local dep");
+        save_prompt(&existing_root, &existing_dir, SaveOptions::default()).unwrap();
+        save_prompt(&existing_dep, &existing_dir, SaveOptions::default()).unwrap();
+
+        let bundle_root = prompt("root", "bundled [[dep]] [[extra]]");
+        let bundle_dep = prompt("dep", "bundled dep");
+        let bundle_extra = prompt("extra", "extra");
+        let bundle = create_bundle(
+            &["root"],
+            &[bundle_root, bundle_dep, bundle_extra],
+            None,
+        )
+        .unwrap();
+
+        let existing = vec![
+            ExistingPrompt {
+                name: "root".to_string(),
+                id: existing_root.id,
+                dir: existing_dir.clone(),
+            },
+            ExistingPrompt {
+                name: "dep".to_string(),
+                id: existing_dep.id,
+                dir: existing_dir.clone(),
+            },
+        ];
+
+        let collisions = detect_collisions(&bundle, &existing);
+        assert_eq!(collisions, vec![Some(Collision::Name), Some(Collision::Name), None]);
+
+        let target = store.path().join("target");
+        let report = import_bundle(
+            &bundle,
+            &[ConflictResolution::Skip, ConflictResolution::Rename, ConflictResolution::Skip],
+            &existing,
+            &target,
+            files.path(),
+            SaveOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(report.skipped, vec!["root"]);
+        assert_eq!(report.renamed, vec![("dep".to_string(), "dep_1".to_string())]);
+        assert!(target.join("dep_1.md").exists());
+        assert!(target.join("extra.md").exists());
+        assert!(!target.join("root.md").exists());
+
+        // Overwrite replaces the existing prompt in place, rewriting renamed references
+        let report = import_bundle(
+            &bundle,
+            &[ConflictResolution::Overwrite, ConflictResolution::Rename, ConflictResolution::Skip],
+            &existing,
+            &target,
+            files.path(),
+            SaveOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(report.overwritten, vec!["root"]);
+        existing_root = crate::fs::load_prompt(&existing_dir.join("root.md")).unwrap();
+        assert!(existing_root.content.contains("[[dep_1]]"));
+    }
+
+    #[test]
+    fn test_import_id_collision_gets_new_uuid() {
+        let store = tempdir().unwrap();
+        let files = tempdir().unwrap();
+        let bundled = prompt("new_name", "content");
+        let bundle = create_bundle(&["new_name"], std::slice::from_ref(&bundled), None).unwrap();
+        let existing = vec![ExistingPrompt {
+            name: "old_name".to_string(),
+            id: bundled.id,
+            dir: store.path().to_path_buf(),
+        }];
+
+        let report = import_bundle(
+            &bundle,
+            &[ConflictResolution::Rename],
+            &existing,
+            store.path(),
+            files.path(),
+            SaveOptions::default(),
+        )
+        .unwrap();
+
+        let (written, _) = &report.written[0];
+        assert_eq!(written.name, "new_name");
+        assert_ne!(written.id, bundled.id);
+    }
+
+    #[test]
+    fn test_import_overwrite_by_id_reports_removed_name() {
+        let store = tempdir().unwrap();
+        let files = tempdir().unwrap();
+        let bundled = prompt("new_name", "content");
+        let mut old = bundled.clone();
+        old.name = "old_name".to_string();
+        crate::fs::save_prompt(&old, store.path(), SaveOptions::default()).unwrap();
+        let bundle = create_bundle(&["new_name"], std::slice::from_ref(&bundled), None).unwrap();
+        let existing = vec![ExistingPrompt {
+            name: "old_name".to_string(),
+            id: bundled.id,
+            dir: store.path().to_path_buf(),
+        }];
+
+        let report = import_bundle(
+            &bundle,
+            &[ConflictResolution::Overwrite],
+            &existing,
+            store.path(),
+            files.path(),
+            SaveOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(report.overwritten, vec!["new_name"]);
+        assert_eq!(report.removed, vec![("old_name".to_string(), store.path().to_path_buf())]);
+        assert!(!store.path().join("old_name.md").exists());
+        assert!(store.path().join("new_name.md").exists());
+    }
+
+    #[test]
+    fn test_import_overwrite_keeps_old_file_when_write_fails() {
+        let store = tempdir().unwrap();
+        let files = tempdir().unwrap();
+        let bundled = prompt("new_name", "content");
+        let mut old = bundled.clone();
+        old.name = "old_name".to_string();
+        crate::fs::save_prompt(&old, store.path(), SaveOptions::default()).unwrap();
+        // Renaming the written file over a directory fails
+        std::fs::create_dir(store.path().join("new_name.md")).unwrap();
+        let bundle = create_bundle(&["new_name"], std::slice::from_ref(&bundled), None).unwrap();
+        let existing = vec![ExistingPrompt {
+            name: "old_name".to_string(),
+            id: bundled.id,
+            dir: store.path().to_path_buf(),
+        }];
+
+        let options = SaveOptions::default();
+        let resolutions = [ConflictResolution::Overwrite];
+        let result =
+            import_bundle(&bundle, &resolutions, &existing, store.path(), files.path(), options);
+
+        assert!(result.is_err());
+        assert!(store.path().join("old_name.md").exists());
+    }
+
+    #[test]
+    fn test_import_renames_invalid_names() {
+        let store = tempdir().unwrap();
+        let files = tempdir().unwrap();
+        let target = store.path().join("prompts");
+        let prompts = [
+            prompt("../../escape", "hostile"),
+            prompt("uses", "[[../../escape]] and [[../../escape@v1]]"),
+        ];
+        // A hand-made bundle: bundling never produces such names
+        let mut bundle = create_bundle(&["uses"], &prompts, None).unwrap();
+        bundle.prompts = prompts.iter().map(BundledPrompt::from_prompt).collect();
+
+        let options = SaveOptions::default();
+        let report = import_bundle(&bundle, &[], &[], &target, files.path(), options).unwrap();
+
+        assert_eq!(report.renamed, vec![("../../escape".to_string(), "escape".to_string())]);
+        assert!(target.join("escape.md").exists());
+        assert!(!store.path().parent().unwrap().join("escape.md").exists());
+        let uses = report.written.iter().find(|(p, _)| p.name == "uses").unwrap();
+        assert_eq!(uses.0.content, "[[escape]] and [[escape@v1]]");
+    }
+
+    #[test]
+    fn test_import_files_only_when_absent_and_safe() {
+        let store = tempdir().unwrap();
+        let files = tempdir().unwrap();
+        std::fs::write(files.path().join("keep.txt"), "local").unwrap();
+
+        let mut bundle = create_bundle(&["p"], &[prompt("p", "x")], None).unwrap();
+        bundle.files = vec![
+            BundledFile {
+                path: "keep.txt".to_string(),
+                content: "bundled".to_string(),
+            },
+            BundledFile {
+                path: "docs/new.txt".to_string(),
+                content: "new".to_string(),
+            },
+            BundledFile {
+                path: "../escape.txt".to_string(),
+                content: "bad".to_string(),
+            },
+        ];
+
+        let options = SaveOptions::default();
+        let report =
+            import_bundle(&bundle, &[], &[], store.path(), files.path(), options).unwrap();
+
+        assert_eq!(report.files_written, vec![files.path().join("docs/new.txt")]);
+        assert_eq!(report.files_skipped, vec!["keep.txt", "../escape.txt"]);
+        assert_eq!(std::fs::read_to_string(files.path().join("keep.txt")).unwrap(), "local");
+    }
+}
@@ -1,13 +1,14 @@
 //! Directory management
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use std::io::ErrorKind;
 use std::path::{Path, PathBuf};
 
 use crate::config::{archive_dir, folders_dir, piemme_dir, prompts_dir};
+use crate::models::prompt::normalize_name;
 
 /// Provide user-friendly error messages for I/O errors
-fn format_io_error(err: &std::io::Error, path: &Path, operation: &str) -> String {
+pub(super) fn format_io_error(err: &std::io::Error, path: &Path, operation: &str) -> String {
     match err.kind() {
         ErrorKind::PermissionDenied => {
             format!(
@@ -50,34 +51,57 @@ pub fn ensure_directories() -> Result<()> {
     Ok(())
 }
 
-/// List all user-created folders
+/// List all user-created folders, nested ones as paths like `work/backend`
 pub fn list_folders() -> Result<Vec<String>> {
-    let folders_path = folders_dir()?;
-    
-    if !folders_path.exists() {
-        return Ok(Vec::new());
-    }
+    list_subfolders(&folders_dir()?)
+}
 
+/// List every directory below `root` (at any depth) as a `/`-separated relative path
+pub fn list_subfolders(root: &Path) -> Result<Vec<String>> {
     let mut folders = Vec::new();
+    collect_subfolders(root, "", &mut folders)?;
+    // Compare component by component so every folder is directly followed by its subfolders
+    folders.sort_by(|a, b| a.split('/').cmp(b.split('/')));
+    Ok(folders)
+}
 
-    for entry in std::fs::read_dir(&folders_path)
-        .map_err(|e| anyhow::anyhow!(format_io_error(&e, &folders_path, "read")))?
+fn collect_subfolders(dir: &Path, prefix: &str, folders: &mut Vec<String>) -> Result<()> {
+    if !dir.exists() {
+        return Ok(());
+    }
+
+    for entry in std::fs::read_dir(dir)
+        .map_err(|e| anyhow::anyhow!(format_io_error(&e, dir, "read")))?
     {
-        let entry = entry?;
-        let path = entry.path();
-        
-        if path.is_dir() {
-            if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                folders.push(name.to_string());
-            }
+        let path = entry?.path();
+        if !path.is_dir() {
+            continue;
+        }
+        if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
+            let folder = format!("{}{}", prefix, name);
+            collect_subfolders(&path, &format!("{}/", folder), folders)?;
+            folders.push(folder);
         }
     }
 
-    folders.sort();
-    Ok(folders)
+    Ok(())
 }
 
-/// Create a new folder
+/// Normalize a folder path: each `/`-separated component becomes a valid name
+pub fn normalize_folder_path(path: &str) -> String {
+    path.split('/')
+        .map(normalize_name)
+        .filter(|component| !component.is_empty())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Parent of a nested folder path (None for top-level folders)
+pub fn parent_folder(folder: &str) -> Option<&str> {
+    folder.rsplit_once('/').map(|(parent, _)| parent)
+}
+
+/// Create a new folder (and any missing parents)
 pub fn create_folder(name: &str) -> Result<PathBuf> {
     let folder_path = folders_dir()?.join(name);
     
@@ -87,6 +111,137 @@ pub fn create_folder(name: &str) -> Result<PathBuf> {
     Ok(folder_path)
 }
 
+/// Rename (or move) folder `from` to `to`, both paths below the `folders` directory
+pub fn rename_folder(folders: &Path, from: &str, to: &str) -> Result<PathBuf> {
+    let source = existing_folder(folders, from)?;
+    let dest = folders.join(to);
+    if to.is_empty() || to == from {
+        bail!("Choose a different name for folder '{}'", from);
+    }
+    if is_inside(to, from) {
+        bail!("Cannot move folder '{}' inside itself", from);
+    }
+    if dest.exists() {
+        bail!("Folder '{}' already exists (merge it instead)", to);
+    }
+
+    if let Some(parent) = dest.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| anyhow::anyhow!(format_io_error(&e, parent, "create")))?;
+    }
+    std::fs::rename(&source, &dest)
+        .map_err(|e| anyhow::anyhow!(format_io_error(&e, &source, "rename")))?;
+    Ok(dest)
+}
+
+/// Merge folder `from` into folder `into`: files and subfolders move over, then `from` is removed.
+///
+/// Nothing moves if a file exists in both folders.
+pub fn merge_folder(folders: &Path, from: &str, into: &str) -> Result<PathBuf> {
+    let source = existing_folder(folders, from)?;
+    let dest = existing_folder(folders, into)?;
+    if from == into || is_inside(into, from) || is_inside(from, into) {
+        bail!("Cannot merge '{}' into '{}': one contains the other", from, into);
+    }
+
+    let files = list_files_below(&source)?;
+    let clashes: Vec<String> = files
+        .iter()
+        .filter(|file| dest.join(file).exists())
+        .map(|file| file.display().to_string())
+        .collect();
+    if !clashes.is_empty() {
+        bail!("Both folders contain: {}", clashes.join(", "));
+    }
+
+    for file in &files {
+        let target = dest.join(file);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| anyhow::anyhow!(format_io_error(&e, parent, "create")))?;
+        }
+        let path = source.join(file);
+        std::fs::rename(&path, &target)
+            .map_err(|e| anyhow::anyhow!(format_io_error(&e, &path, "move")))?;
+    }
+    std::fs::remove_dir_all(&source)
+        .map_err(|e| anyhow::anyhow!(format_io_error(&e, &source, "remove")))?;
+    Ok(dest)
+}
+
+/// Delete folder `folder`, first moving the prompts in it and its subfolders into `dest`.
+///
+/// Returns the names of the moved prompts. Nothing happens if a prompt name would clash
+/// in `dest` or the folder holds files that are not prompts.
+pub fn delete_folder(folders: &Path, folder: &str, dest: &Path) -> Result<Vec<String>> {
+    let source = existing_folder(folders, folder)?;
+    let files = list_files_below(&source)?;
+
+    if let Some(other) = files.iter().find(|f| f.extension().is_none_or(|ext| ext != "md")) {
+        bail!("Folder '{}' contains a file that is not a prompt: {}", folder, other.display());
+    }
+    let mut names = Vec::new();
+    for file in &files {
+        let Some(name) = file.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        if names.iter().any(|n: &String| n == name) || dest.join(name).exists() {
+            bail!("A prompt named '{}' already exists", name.trim_end_matches(".md"));
+        }
+        names.push(name.to_string());
+    }
+
+    std::fs::create_dir_all(dest)
+        .map_err(|e| anyhow::anyhow!(format_io_error(&e, dest, "create")))?;
+    for (file, name) in files.iter().zip(&names) {
+        let path = source.join(file);
+        std::fs::rename(&path, dest.join(name))
+            .map_err(|e| anyhow::anyhow!(format_io_error(&e, &path, "move")))?;
+    }
+    std::fs::remove_dir_all(&source)
+        .map_err(|e| anyhow::anyhow!(format_io_error(&e, &source, "remove")))?;
+
+    Ok(names.iter().map(|n| n.trim_end_matches(".md").to_string()).collect())
+}
+
+/// Path of an existing folder below `folders`
+fn existing_folder(folders: &Path, folder: &str) -> Result<PathBuf> {
+    let path = folders.join(folder);
+    if folder.is_empty() || !path.is_dir() {
+        bail!("Folder not found: {}", folder);
+    }
+    Ok(path)
+}
+
+/// Whether folder path `folder` is strictly inside folder path `ancestor`
+fn is_inside(folder: &str, ancestor: &str) -> bool {
+    folder
+        .strip_prefix(ancestor)
+        .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// Every file below `dir` (at any depth), relative to `dir`
+fn list_files_below(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![PathBuf::new()];
+    while let Some(relative) = pending.pop() {
+        let path = dir.join(&relative);
+        for entry in std::fs::read_dir(&path)
+            .map_err(|e| anyhow::anyhow!(format_io_error(&e, &path, "read")))?
+        {
+            let entry = entry?;
+            let child = relative.join(entry.file_name());
+            if entry.path().is_dir() {
+                pending.push(child);
+            } else {
+                files.push(child);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
 /// Check if a directory is empty
 pub fn is_directory_empty(path: &Path) -> Result<bool> {
     if !path.exists() {
@@ -146,6 +301,61 @@ mod tests {
         assert_eq!(files.len(), 2);
     }
 
+    #[test]
+    fn test_nested_folders() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("work").join("backend").join("db")).unwrap();
+        fs::create_dir_all(dir.path().join("personal")).unwrap();
+        fs::write(dir.path().join("work").join("note.md"), "content").unwrap();
+
+        let folders = list_subfolders(dir.path()).unwrap();
+        assert_eq!(folders, vec!["personal", "work", "work/backend", "work/backend/db"]);
+
+        assert_eq!(normalize_folder_path(" Work / Back End//db "), "work/back_end/db");
+        assert_eq!(normalize_folder_path("../x"), "x");
+        assert_eq!(parent_folder("work/backend"), Some("work"));
+        assert_eq!(parent_folder("work"), None);
+    }
+
+    #[test]
+    fn test_rename_merge_and_delete_folders() {
+        let dir = tempdir().unwrap();
+        let folders = dir.path().join("folders");
+        fs::create_dir_all(folders.join("work").join("backend")).unwrap();
+        fs::create_dir_all(folders.join("job")).unwrap();
+        fs::write(folders.join("work").join("a.md"), "a").unwrap();
+        fs::write(folders.join("work").join("backend").join("b.md"), "b").unwrap();
+        fs::write(folders.join("job").join("c.md"), "c").unwrap();
+
+        assert!(rename_folder(&folders, "work", "work/inner").is_err());
+        assert!(rename_folder(&folders, "work", "job").is_err());
+        rename_folder(&folders, "work", "old/work").unwrap();
+        assert!(folders.join("old").join("work").join("backend").join("b.md").exists());
+
+        // A clash leaves both folders untouched
+        fs::write(folders.join("job").join("a.md"), "other a").unwrap();
+        assert!(merge_folder(&folders, "old/work", "job").is_err());
+        assert!(folders.join("old").join("work").join("a.md").exists());
+        fs::remove_file(folders.join("job").join("a.md")).unwrap();
+
+        merge_folder(&folders, "old/work", "job").unwrap();
+        assert!(!folders.join("old").join("work").exists());
+        assert_eq!(fs::read_to_string(folders.join("job").join("a.md")).unwrap(), "a");
+        assert!(folders.join("job").join("backend").join("b.md").exists());
+
+        let prompts = dir.path().join("prompts");
+        let mut moved = delete_folder(&folders, "job", &prompts).unwrap();
+        moved.sort();
+        assert_eq!(moved, vec!["a", "b", "c"]);
+        assert!(prompts.join("b.md").exists());
+        assert!(!folders.join("job").exists());
+
+        fs::create_dir_all(folders.join("notes")).unwrap();
+        fs::write(folders.join("notes").join("image.png"), "").unwrap();
+        assert!(delete_folder(&folders, "notes", &prompts).is_err());
+        assert!(delete_folder(&folders, "missing", &prompts).is_err());
+    }
+
     #[test]
     fn test_is_directory_empty() {
         let dir = tempdir().unwrap();
@@ -0,0 +1,311 @@
+//! Exporting prompts to files
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+use super::atomic::write_atomic;
+use super::directory::format_io_error;
+use crate::models::Prompt;
+
+/// Output format for exported prompts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Prompt content as written, one Markdown file per prompt
+    Raw,
+    /// References resolved, one text file per prompt
+    Rendered,
+    /// A single JSON array with metadata, raw and rendered content
+    Json,
+    /// A single Markdown document with one section per prompt
+    Combined,
+}
+
+impl ExportFormat {
+    /// All formats, in the order they are offered in the export popup
+    pub const ALL: [ExportFormat; 4] = [
+        ExportFormat::Raw,
+        ExportFormat::Rendered,
+        ExportFormat::Json,
+        ExportFormat::Combined,
+    ];
+
+    /// Parse a format name as used in config and on the command line
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "raw" => Some(ExportFormat::Raw),
+            "rendered" => Some(ExportFormat::Rendered),
+            "json" => Some(ExportFormat::Json),
+            "combined" => Some(ExportFormat::Combined),
+            _ => None,
+        }
+    }
+
+    /// Get the format name as used in config and on the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExportFormat::Raw => "raw",
+            ExportFormat::Rendered => "rendered",
+            ExportFormat::Json => "json",
+            ExportFormat::Combined => "combined",
+        }
+    }
+
+    /// Whether this format resolves references (and so may run commands)
+    pub fn is_rendered(&self) -> bool {
+        !matches!(self, ExportFormat::Raw)
+    }
+
+    /// File extension for documents in this format
+    fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Raw | ExportFormat::Combined => "md",
+            ExportFormat::Rendered => "txt",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+/// Options controlling an export
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportOptions {
+    /// Output format
+    pub format: ExportFormat,
+    /// Whether to execute {{commands}} and include their output
+    pub include_command_output: bool,
+}
+
+/// A single output document produced by an export
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportDocument {
+    /// File name (without directory)
+    pub file_name: String,
+    /// Document contents
+    pub contents: String,
+}
+
+/// A prompt as serialized in JSON exports
+#[derive(Serialize)]
+struct ExportedPrompt<'a> {
+    id: Uuid,
+    name: &'a str,
+    tags: &'a [String],
+    created: DateTime<Utc>,
+    modified: DateTime<Utc>,
+    content: &'a str,
+    rendered: String,
+}
+
+/// Build export documents for the given prompts.
+///
+/// `get_content` looks up prompts by name for reference resolution.
+pub fn export_documents<F>(
+    prompts: &[Prompt],
+    get_content: F,
+    options: &ExportOptions,
+) -> Result<Vec<ExportDocument>>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let render = |prompt: &Prompt| -> String {
+        crate::engine::resolve_prompt(&prompt.content, &get_content, options.include_command_output)
+            .content
+    };
+
+    let documents = match options.format {
+        ExportFormat::Raw => prompts
+            .iter()
+            .map(|p| ExportDocument {
+                file_name: format!("{}.md", p.name),
+                contents: p.content.clone(),
+            })
+            .collect(),
+        ExportFormat::Rendered => prompts
+            .iter()
+            .map(|p| ExportDocument {
+                file_name: format!("{}.txt", p.name),
+                contents: render(p),
+            })
+            .collect(),
+        ExportFormat::Json => {
+            let exported: Vec<ExportedPrompt> = prompts
+                .iter()
+                .map(|p| ExportedPrompt {
+                    id: p.id,
+                    name: &p.name,
+                    tags: &p.tags,
+                    created: p.created,
+                    modified: p.modified,
+                    content: &p.content,
+                    rendered: render(p),
+                })
+                .collect();
+            let contents = serde_json::to_string_pretty(&exported)
+                .with_context(|| "Failed to serialize export")?;
+            vec![ExportDocument {
+                file_name: combined_file_name(prompts, options.format),
+                contents,
+            }]
+        }
+        ExportFormat::Combined => {
+            let sections: Vec<String> = prompts
+                .iter()
+                .map(|p| format!("# {}\n\n{}\n", p.name, render(p).trim_end()))
+                .collect();
+            vec![ExportDocument {
+                file_name: combined_file_name(prompts, options.format),
+                contents: sections.join("\n---\n\n"),
+            }]
+        }
+    };
+
+    Ok(documents)
+}
+
+/// File name for single-document formats: the prompt's name, or "prompts" for several
+fn combined_file_name(prompts: &[Prompt], format: ExportFormat) -> String {
+    match prompts {
+        [prompt] => format!("{}.{}", prompt.name, format.extension()),
+        _ => format!("prompts.{}", format.extension()),
+    }
+}
+
+/// Write export documents to `dest`.
+///
+/// A single document is written to `dest` itself unless it is an existing
+/// directory; several documents are written into `dest` as a directory.
+pub fn write_export(documents: &[ExportDocument], dest: &Path) -> Result<Vec<PathBuf>> {
+    let into_dir = documents.len() > 1 || dest.is_dir();
+
+    let mut written = Vec::new();
+    for document in documents {
+        let path = if into_dir {
+            dest.join(&document.file_name)
+        } else {
+            dest.to_path_buf()
+        };
+
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| anyhow::anyhow!(format_io_error(&e, parent, "create directory")))?;
+        }
+        write_atomic(&path, &document.contents)
+            .map_err(|e| anyhow::anyhow!(format_io_error(&e, &path, "write")))?;
+
+        written.push(path);
+    }
+
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn prompts() -> Vec<Prompt> {
+        let mut greeting = Prompt::with_content("Hello [[name]]!");
+        greeting.name = "greeting".to_string();
+        greeting.tags = vec!["demo".to_string()];
+        let mut name = Prompt::with_content("World");
+        name.name = "name".to_string();
+        vec![greeting, name]
+    }
+
+    fn lookup(prompts: &[Prompt]) -> impl Fn(&str) -> Option<String> + '_ {
+        |n: &str| prompts.iter().find(|p| p.name == n).map(|p| p.content.clone())
+    }
+
+    #[test]
+    fn test_export_raw_and_rendered() {
+        let prompts = prompts();
+        let options = |format| ExportOptions {
+            format,
+            include_command_output: false,
+        };
+
+        let raw = export_documents(&prompts[..1], lookup(&prompts), &options(ExportFormat::Raw))
+            .unwrap();
+        assert_eq!(raw[0].file_name, "greeting.md");
+        assert_eq!(raw[0].contents, "Hello [[name]]!");
+
+        let rendered =
+            export_documents(&prompts, lookup(&prompts), &options(ExportFormat::Rendered))
+                .unwrap();
+        assert_eq!(rendered.len(), 2);
+        assert_eq!(rendered[0].file_name, "greeting.txt");
+        assert_eq!(rendered[0].contents, "Hello World!");
+    }
+
+    #[test]
+    fn test_export_json_and_combined() {
+        let prompts = prompts();
+        let options = |format| ExportOptions {
+            format,
+            include_command_output: false,
+        };
+
+        let json =
+            export_documents(&prompts, lookup(&prompts), &options(ExportFormat::Json)).unwrap();
+        assert_eq!(json.len(), 1);
+        assert_eq!(json[0].file_name, "prompts.json");
+        let value: serde_json::Value = serde_json::from_str(&json[0].contents).unwrap();
+        assert_eq!(value[0]["name"], "greeting");
+        assert_eq!(value[0]["tags"][0], "demo");
+        assert_eq!(value[0]["rendered"], "Hello World!");
+
+        let combined =
+            export_documents(&prompts, lookup(&prompts), &options(ExportFormat::Combined))
+                .unwrap();
+        assert_eq!(combined[0].file_name, "prompts.md");
+        assert!(combined[0].contents.starts_with("# greeting\n\nHello World!\n"));
+        assert!(combined[0].contents.contains("\n---\n\n# name\n\nWorld\n"));
+    }
+
+    #[test]
+    fn test_export_command_output_optional() {
+        let mut prompt = Prompt::with_content("Say {{echo hi}}");
+        prompt.name = "cmd".to_string();
+        let prompts = vec![prompt];
+        let mut options = ExportOptions {
+            format: ExportFormat::Rendered,
+            include_command_output: false,
+        };
+
+        let docs = export_documents(&prompts, lookup(&prompts), &options).unwrap();
+        assert_eq!(docs[0].contents, "Say {{echo hi}}");
+
+        options.include_command_output = true;
+        let docs = export_documents(&prompts, lookup(&prompts), &options).unwrap();
+        assert_eq!(docs[0].contents, "Say hi");
+    }
+
+    #[test]
+    fn test_write_export() {
+        let dir = tempdir().unwrap();
+        let docs = vec![
+            ExportDocument {
+                file_name: "a.md".to_string(),
+                contents: "A".to_string(),
+            },
+            ExportDocument {
+                file_name: "b.md".to_string(),
+                contents: "B".to_string(),
+            },
+        ];
+
+        let out = dir.path().join("out");
+        let written = write_export(&docs, &out).unwrap();
+        assert_eq!(written, vec![out.join("a.md"), out.join("b.md")]);
+
+        let single = dir.path().join("single.md");
+        write_export(&docs[..1], &single).unwrap();
+        assert_eq!(std::fs::read_to_string(&single).unwrap(), "A");
+
+        // An existing directory receives the file under its own name
+        write_export(&docs[1..], &out).unwrap();
+        assert_eq!(std::fs::read_to_string(out.join("b.md")).unwrap(), "B");
+    }
+}
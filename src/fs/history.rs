@@ -0,0 +1,444 @@
+//! Per-prompt version history
+//!
+//! Every save of a prompt inside a store appends a revision to
+//! `.piemme/.history/<id>.jsonl`. Each line holds the line edits turning the
+//! previous revision into the saved file (frontmatter included), so history
+//! stays small for long prompts that are saved often. Every
+//! `SNAPSHOT_INTERVAL`th line holds the whole file instead, so finding the
+//! latest revision replays only the lines after the last snapshot.
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use similar::{capture_diff_slices, Algorithm, DiffOp};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+use super::atomic::write_atomic;
+use super::backup::store_root;
+use super::prompt_io::{parse_frontmatter, save_prompt, SaveOptions};
+use crate::models::{Prompt, PromptStatus};
+
+/// Revisions kept per prompt unless configured otherwise
+pub const DEFAULT_HISTORY_RETENTION: usize = 200;
+
+/// Revisions between full snapshots
+const SNAPSHOT_INTERVAL: usize = 20;
+
+/// One step from the previous revision's lines to the next revision's
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Edit {
+    /// Keep this many lines
+    Keep(usize),
+    /// Drop this many lines
+    Delete(usize),
+    /// Insert these lines
+    Insert(Vec<String>),
+}
+
+/// A revision as stored on disk
+#[derive(Debug, Serialize, Deserialize)]
+struct Record {
+    saved: DateTime<Utc>,
+    /// Edits from the previous revision (none in snapshots)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    edits: Vec<Edit>,
+    /// The whole revision, in snapshots
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+}
+
+impl Record {
+    /// Store `revision`, a snapshot every `SNAPSHOT_INTERVAL` revisions and otherwise as
+    /// edits from `previous`
+    fn new(revision: &Revision, previous: &str) -> Self {
+        let snapshot = (revision.number - 1).is_multiple_of(SNAPSHOT_INTERVAL);
+        Self {
+            saved: revision.saved,
+            edits: if snapshot { Vec::new() } else { diff_edits(previous, &revision.text) },
+            text: snapshot.then(|| revision.text.clone()),
+        }
+    }
+
+    /// Rebuild the revision this record stores from the previous one
+    fn apply(&self, previous: &str) -> Result<String> {
+        match &self.text {
+            Some(text) => Ok(text.clone()),
+            None => apply_edits(previous, &self.edits),
+        }
+    }
+}
+
+/// A saved revision of a prompt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Revision {
+    /// Revision number, starting at 1
+    pub number: usize,
+    /// When the revision was saved
+    pub saved: DateTime<Utc>,
+    /// The prompt file as saved (frontmatter and content)
+    pub text: String,
+}
+
+impl Revision {
+    /// Prompt content of the revision, without frontmatter
+    pub fn content(&self) -> String {
+        parse_frontmatter(&self.text)
+            .map(|(_, body)| body)
+            .unwrap_or_else(|_| self.text.clone())
+    }
+}
+
+/// History directory of the store containing `dir`, if `dir` is inside a `.piemme` directory
+pub fn store_history_dir(dir: &Path) -> Option<PathBuf> {
+    store_root(dir).map(|root| root.join(".history"))
+}
+
+fn history_file(history_dir: &Path, id: Uuid) -> PathBuf {
+    history_dir.join(format!("{}.jsonl", id))
+}
+
+/// Split text into lines, keeping line endings so it can be rebuilt exactly
+fn split_lines(text: &str) -> Vec<&str> {
+    text.split_inclusive('\n').collect()
+}
+
+/// Compute the edits turning `old` into `new`
+fn diff_edits(old: &str, new: &str) -> Vec<Edit> {
+    let old_lines = split_lines(old);
+    let new_lines = split_lines(new);
+    let inserted = |index: usize, len: usize| {
+        Edit::Insert(new_lines[index..index + len].iter().map(|l| l.to_string()).collect())
+    };
+
+    let mut edits = Vec::new();
+    for op in capture_diff_slices(Algorithm::Myers, &old_lines, &new_lines) {
+        match op {
+            DiffOp::Equal { len, .. } => edits.push(Edit::Keep(len)),
+            DiffOp::Delete { old_len, .. } => edits.push(Edit::Delete(old_len)),
+            DiffOp::Insert { new_index, new_len, .. } => edits.push(inserted(new_index, new_len)),
+            DiffOp::Replace { old_len, new_index, new_len, .. } => {
+                edits.push(Edit::Delete(old_len));
+                edits.push(inserted(new_index, new_len));
+            }
+        }
+    }
+    edits
+}
+
+/// Apply edits to `old`, rebuilding the next revision
+fn apply_edits(old: &str, edits: &[Edit]) -> Result<String> {
+    let lines = split_lines(old);
+    let mut position = 0;
+    let mut text = String::new();
+
+    for edit in edits {
+        match edit {
+            Edit::Keep(count) => {
+                let kept = lines
+                    .get(position..position + count)
+                    .context("History edit keeps lines past the end")?;
+                kept.iter().for_each(|line| text.push_str(line));
+                position += count;
+            }
+            Edit::Delete(count) => position += count,
+            Edit::Insert(inserted) => inserted.iter().for_each(|line| text.push_str(line)),
+        }
+    }
+
+    if position != lines.len() {
+        bail!("History edits do not cover the previous revision");
+    }
+    Ok(text)
+}
+
+/// Read the records of a history file, with the length of the part holding them.
+///
+/// Reading stops at the first unreadable or unterminated line, which can only be a
+/// record cut short by a crash while it was appended.
+fn read_records(path: &Path) -> Result<(Vec<Record>, u64)> {
+    if !path.exists() {
+        return Ok((Vec::new(), 0));
+    }
+    let data = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read history: {}", path.display()))?;
+
+    let mut records = Vec::new();
+    let mut end = 0;
+    for line in data.split_inclusive('\n') {
+        if !line.ends_with('\n') {
+            break;
+        }
+        if !line.trim().is_empty() {
+            let Ok(record) = serde_json::from_str(line) else {
+                break;
+            };
+            records.push(record);
+        }
+        end += line.len();
+    }
+    Ok((records, end as u64))
+}
+
+/// Encode `revisions`, the first of which follows `previous`, as history lines
+fn encode<'a>(revisions: &'a [Revision], mut previous: &'a str) -> Result<String> {
+    let mut lines = String::new();
+    for revision in revisions {
+        lines.push_str(&serde_json::to_string(&Record::new(revision, previous))?);
+        lines.push('\n');
+        previous = &revision.text;
+    }
+    Ok(lines)
+}
+
+/// List the revisions of prompt `id`, oldest first
+pub fn list_revisions(history_dir: &Path, id: Uuid) -> Result<Vec<Revision>> {
+    let path = history_file(history_dir, id);
+    let mut revisions: Vec<Revision> = Vec::new();
+    for record in read_records(&path)?.0 {
+        let previous = revisions.last().map(|r| r.text.as_str()).unwrap_or_default();
+        let text = record
+            .apply(previous)
+            .with_context(|| format!("Corrupt history: {}", path.display()))?;
+        revisions.push(Revision {
+            number: revisions.len() + 1,
+            saved: record.saved,
+            text,
+        });
+    }
+    Ok(revisions)
+}
+
+/// The newest revision's text, replayed from the last snapshot
+fn latest_text(records: &[Record]) -> Result<Option<String>> {
+    let start = records.iter().rposition(|r| r.text.is_some()).unwrap_or(0);
+    let mut text = String::new();
+    for record in &records[start..] {
+        text = record.apply(&text)?;
+    }
+    Ok((!records.is_empty()).then_some(text))
+}
+
+/// Record `text` as the newest revision of prompt `id` unless it matches the latest one,
+/// keeping the newest `retention` revisions (0 disables history).
+///
+/// `previous` is the file being replaced and when it was written; it seeds the
+/// history of prompts saved for the first time since history was enabled. Older
+/// revisions are dropped in batches, so up to `SNAPSHOT_INTERVAL` more may be listed.
+pub fn record_revision(
+    history_dir: &Path,
+    id: Uuid,
+    previous: Option<(&str, DateTime<Utc>)>,
+    text: &str,
+    retention: usize,
+) -> Result<bool> {
+    if retention == 0 {
+        return Ok(false);
+    }
+    let path = history_file(history_dir, id);
+    let (records, end) = read_records(&path)?;
+    let latest = latest_text(&records)
+        .with_context(|| format!("Corrupt history: {}", path.display()))?;
+
+    let mut added = Vec::new();
+    if latest.is_none()
+        && let Some((old, saved)) = previous
+        && old != text
+    {
+        added.push((saved, old));
+    }
+    if !added.is_empty() || latest.as_deref().unwrap_or_default() != text {
+        added.push((Utc::now(), text));
+    }
+    if added.is_empty() {
+        return Ok(false);
+    }
+    let added: Vec<Revision> = added
+        .into_iter()
+        .enumerate()
+        .map(|(index, (saved, text))| Revision {
+            number: records.len() + index + 1,
+            saved,
+            text: text.to_string(),
+        })
+        .collect();
+
+    std::fs::create_dir_all(history_dir).with_context(|| {
+        format!("Failed to create history directory: {}", history_dir.display())
+    })?;
+
+    // Past the limit, rewrite the file with the newest revisions, renumbered
+    if records.len() + added.len() > retention + SNAPSHOT_INTERVAL {
+        let mut revisions = list_revisions(history_dir, id)?;
+        revisions.extend(added);
+        let mut kept = revisions.split_off(revisions.len() - retention);
+        for (index, revision) in kept.iter_mut().enumerate() {
+            revision.number = index + 1;
+        }
+        write_atomic(&path, &encode(&kept, "")?)
+            .with_context(|| format!("Failed to write history: {}", path.display()))?;
+        return Ok(true);
+    }
+
+    let lines = encode(&added, latest.as_deref().unwrap_or_default())?;
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open history: {}", path.display()))?;
+    // Drop a record cut short by a crash, which would hide every one after it
+    if file.metadata()?.len() > end {
+        file.set_len(end)
+            .with_context(|| format!("Failed to repair history: {}", path.display()))?;
+    }
+    file.write_all(lines.as_bytes())
+        .with_context(|| format!("Failed to write history: {}", path.display()))?;
+    Ok(true)
+}
+
+/// Restore `prompt` (stored in `dir`) to `revision`, saved as a new revision
+pub fn restore_revision(
+    revision: &Revision,
+    prompt: &Prompt,
+    dir: &Path,
+    options: SaveOptions,
+) -> Result<Prompt> {
+    let mut restored = prompt.clone();
+    match parse_frontmatter(&revision.text) {
+        Ok((frontmatter, body)) => {
+            restored.content = body;
+            restored.tags = frontmatter.tags;
+            restored.status = PromptStatus::Managed;
+        }
+        Err(e) => {
+            // The revision was saved while the frontmatter was broken
+            restored.content = revision.text.clone();
+            restored.status = PromptStatus::Broken {
+                error: format!("{:#}", e),
+            };
+        }
+    }
+    restored.modified = Utc::now();
+
+    save_prompt(&restored, dir, options)?;
+    Ok(restored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_revisions_are_stored_as_edits() {
+        let dir = tempdir().unwrap();
+        let id = Uuid::new_v4();
+        let long_line = "unchanged ".repeat(50);
+        let v1 = format!("{}\nfirst\n", long_line);
+        let v2 = format!("{}\nsecond\nthird", long_line);
+
+        let record = |text: &str| {
+            record_revision(dir.path(), id, None, text, DEFAULT_HISTORY_RETENTION).unwrap()
+        };
+        assert!(record(&v1));
+        assert!(record(&v2));
+        assert!(!record(&v2));
+        assert!(record(""));
+
+        let revisions = list_revisions(dir.path(), id).unwrap();
+        let texts: Vec<&str> = revisions.iter().map(|r| r.text.as_str()).collect();
+        assert_eq!(texts, vec![v1.as_str(), v2.as_str(), ""]);
+        assert_eq!(revisions[2].number, 3);
+
+        // Unchanged lines are not repeated
+        let stored = std::fs::read_to_string(history_file(dir.path(), id)).unwrap();
+        assert_eq!(stored.matches(&long_line).count(), 1);
+    }
+
+    #[test]
+    fn test_record_after_cut_short_line() {
+        let dir = tempdir().unwrap();
+        let id = Uuid::new_v4();
+        let path = history_file(dir.path(), id);
+        record_revision(dir.path(), id, None, "one\n", DEFAULT_HISTORY_RETENTION).unwrap();
+        record_revision(dir.path(), id, None, "two\n", DEFAULT_HISTORY_RETENTION).unwrap();
+
+        // A crash while appending left half a record
+        let mut file = std::fs::OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(br#"{"saved":"2026-01-01T00:00:00Z","ed"#).unwrap();
+        drop(file);
+        assert_eq!(list_revisions(dir.path(), id).unwrap().len(), 2);
+
+        record_revision(dir.path(), id, None, "three\n", DEFAULT_HISTORY_RETENTION).unwrap();
+        let texts: Vec<String> =
+            list_revisions(dir.path(), id).unwrap().into_iter().map(|r| r.text).collect();
+        assert_eq!(texts, vec!["one\n", "two\n", "three\n"]);
+    }
+
+    #[test]
+    fn test_snapshots_and_retention() {
+        let dir = tempdir().unwrap();
+        let id = Uuid::new_v4();
+        let path = history_file(dir.path(), id);
+        for version in 0..45 {
+            record_revision(dir.path(), id, None, &format!("v{}\n", version), 30).unwrap();
+        }
+
+        // Snapshots start revisions 1, 21 and 41; the latest is rebuilt from the last one
+        let (records, _) = read_records(&path).unwrap();
+        let snapshots: Vec<usize> =
+            (0..records.len()).filter(|&i| records[i].text.is_some()).collect();
+        assert_eq!(snapshots, vec![0, 20, 40]);
+        assert_eq!(latest_text(&records).unwrap().as_deref(), Some("v44\n"));
+
+        // Past the limit, only the newest revisions are kept
+        for version in 45..60 {
+            record_revision(dir.path(), id, None, &format!("v{}\n", version), 30).unwrap();
+        }
+        let revisions = list_revisions(dir.path(), id).unwrap();
+        assert!((30..=30 + SNAPSHOT_INTERVAL).contains(&revisions.len()));
+        assert_eq!(revisions[0].number, 1);
+        assert_eq!(revisions.last().unwrap().text, "v59\n");
+        assert_eq!(revisions[0].text, format!("v{}\n", 60 - revisions.len()));
+
+        // A retention of 0 records nothing
+        assert!(!record_revision(dir.path(), Uuid::new_v4(), None, "text", 0).unwrap());
+    }
+
+    #[test]
+    fn test_saves_record_history_and_restore() {
+        let dir = tempdir().unwrap();
+        let prompts = dir.path().join(".piemme").join("prompts");
+        let history_dir = store_history_dir(&prompts).unwrap();
+
+        // A prompt written before history existed seeds the history with that version
+        let mut prompt = Prompt::with_content("first draft");
+        prompt.name = "p".to_string();
+        std::fs::create_dir_all(&prompts).unwrap();
+        let original = format!(
+            "---\n{}---\nfirst draft",
+            serde_yaml::to_string(&prompt.frontmatter()).unwrap()
+        );
+        std::fs::write(prompts.join("p.md"), &original).unwrap();
+
+        prompt.content = "careless edit".to_string();
+        prompt.tags = vec!["work".to_string()];
+        save_prompt(&prompt, &prompts, SaveOptions::default()).unwrap();
+
+        let revisions = list_revisions(&history_dir, prompt.id).unwrap();
+        assert_eq!(revisions.len(), 2);
+        assert_eq!(revisions[0].text, original);
+        assert_eq!(revisions[1].content(), "careless edit");
+
+        let restored =
+            restore_revision(&revisions[0], &prompt, &prompts, SaveOptions::default()).unwrap();
+        assert_eq!(restored.content, "first draft");
+        assert!(restored.tags.is_empty());
+
+        let revisions = list_revisions(&history_dir, prompt.id).unwrap();
+        assert_eq!(revisions.len(), 3);
+        assert_eq!(revisions[2].content(), "first draft");
+    }
+}
@@ -1,12 +1,29 @@
 //! Search index management
+//!
+//! The index doubles as a cache of every prompt file: entries remember the
+//! modification time and size of the file they were read from, so startup only
+//! reads files that changed since the index was last saved.
+//!
+//! `.index.json` keeps the metadata only (name, tags, preview and file stamp), so it
+//! stays small and quick to parse. Bodies are read lazily: [`Index::sync_metadata`]
+//! lists every prompt without reading unchanged files, leaving their bodies to be read
+//! in the background with [`read_bodies`], and [`Index::sync`] reads any body still
+//! missing when it is needed.
 
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::fs::Metadata;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
 use uuid::Uuid;
 
+use super::atomic::write_atomic;
+use super::directory::{format_io_error, list_subfolders};
+use super::prompt_io::load_prompt;
+use crate::models::{is_tag_or_child, Prompt, PromptStatus};
+
 /// The search index structure
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Index {
@@ -14,7 +31,8 @@ pub struct Index {
     pub version: u32,
     /// When the index was last updated
     pub updated: Option<DateTime<Utc>>,
-    /// Index entries by prompt name
+    /// Index entries by location and prompt name (see [`entry_key`]), since a prompt in
+    /// the archive may share its name with an active one
     pub entries: HashMap<String, IndexEntry>,
 }
 
@@ -27,14 +45,151 @@ pub struct IndexEntry {
     pub name: String,
     /// First line of content (for preview)
     pub preview: String,
-    /// Full content (for searching)
-    pub content: String,
+    /// Full content (for searching); not saved, so None until the file's body is read
+    #[serde(skip)]
+    pub content: Option<String>,
     /// Tags
     pub tags: Vec<String>,
     /// Location: "prompts", "archive", or folder path
     pub location: String,
     /// Last modified timestamp
     pub modified: DateTime<Utc>,
+    /// The file the entry was read from; entries without it are re-read on the next sync
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<FileStamp>,
+}
+
+/// State of a prompt file when its entry was read, with what only the file records
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileStamp {
+    /// Modification time, in nanoseconds since the Unix epoch
+    pub mtime: u64,
+    /// Size in bytes
+    pub size: u64,
+    /// When the prompt was created
+    pub created: DateTime<Utc>,
+    /// Whether the file had valid frontmatter
+    pub status: PromptStatus,
+}
+
+/// Prompts found by [`Index::sync`]
+#[derive(Debug, Default)]
+pub struct SyncedPrompts {
+    /// Every prompt with its location, each location sorted by name
+    pub prompts: Vec<(String, Prompt)>,
+    /// Files read because they were new or changed since the last sync
+    pub read: usize,
+    /// Entries dropped because their file is gone
+    pub removed: usize,
+    /// Files that could not be read, each with why
+    pub failed: Vec<String>,
+    /// Files whose prompts were listed with an empty body, by location (see
+    /// [`Index::sync_metadata`])
+    pub unread: Vec<(String, PathBuf)>,
+}
+
+impl SyncedPrompts {
+    /// Whether the index changed and should be saved
+    pub fn changed(&self) -> bool {
+        self.read > 0 || self.removed > 0
+    }
+
+    /// Prompts in `location`
+    pub fn in_location(&self, location: &str) -> Vec<Prompt> {
+        self.prompts
+            .iter()
+            .filter(|(l, _)| l == location)
+            .map(|(_, prompt)| prompt.clone())
+            .collect()
+    }
+
+    /// Prompts outside the archive, the ones references resolve to
+    pub fn active(&self) -> Vec<Prompt> {
+        self.prompts
+            .iter()
+            .filter(|(l, _)| l != "archive")
+            .map(|(_, prompt)| prompt.clone())
+            .collect()
+    }
+
+    /// Number of archived prompts
+    pub fn archived(&self) -> usize {
+        self.prompts.iter().filter(|(l, _)| l == "archive").count()
+    }
+
+    /// A warning naming the files that could not be read, if any
+    pub fn failure_message(&self) -> Option<String> {
+        let first = self.failed.first()?;
+        Some(match self.failed.len() {
+            1 => format!("Could not read {}", first),
+            count => format!("Could not read {} prompt files, first {}", count, first),
+        })
+    }
+}
+
+/// A prompt body read by [`read_bodies`]
+#[derive(Debug)]
+pub struct Body {
+    pub location: String,
+    pub name: String,
+    pub content: String,
+}
+
+/// Read the bodies of the files [`Index::sync_metadata`] left unread, skipping files that
+/// can no longer be read (the next sync reports them)
+pub fn read_bodies(unread: &[(String, PathBuf)]) -> Vec<Body> {
+    unread
+        .iter()
+        .filter_map(|(location, path)| {
+            let prompt = load_prompt(path).ok()?;
+            Some(Body {
+                location: location.clone(),
+                name: prompt.name,
+                content: prompt.content,
+            })
+        })
+        .collect()
+}
+
+/// Key of the entry for prompt `name` in `location`
+fn entry_key(location: &str, name: &str) -> String {
+    format!("{}/{}", location, name)
+}
+
+/// Markdown files directly in `dir` with their metadata, reading each file's metadata once
+fn markdown_files_with_metadata(dir: &Path) -> Result<Vec<(PathBuf, Metadata)>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)
+        .map_err(|e| anyhow::anyhow!(format_io_error(&e, dir, "read")))?
+    {
+        let entry = entry?;
+        let path = entry.path();
+        if path.extension().is_none_or(|ext| ext != "md") {
+            continue;
+        }
+        // Follows symlinks like the other loaders; files that vanish are skipped
+        if let Ok(metadata) = std::fs::metadata(&path)
+            && metadata.is_file()
+        {
+            files.push((path, metadata));
+        }
+    }
+    Ok(files)
+}
+
+/// Modification time (nanoseconds since the epoch) and size of a file
+fn file_times(metadata: &Metadata) -> (u64, u64) {
+    let mtime = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|since| since.as_nanos() as u64)
+        .unwrap_or_default();
+    (mtime, metadata.len())
 }
 
 impl Index {
@@ -52,9 +207,11 @@ impl Index {
         let contents = std::fs::read_to_string(path)
             .with_context(|| format!("Failed to read index file: {}", path.display()))?;
         
-        let index: Index = serde_json::from_str(&contents)
+        let mut index: Index = serde_json::from_str(&contents)
             .with_context(|| "Failed to parse index file")?;
-        
+        // Indexes written before entries were keyed by location too
+        index.rekey();
+
         Ok(index)
     }
 
@@ -69,7 +226,8 @@ impl Index {
 
     /// Save index to file
     pub fn save(&self, path: &Path) -> Result<()> {
-        let contents = serde_json::to_string_pretty(self)
+        // Compact: the index is a cache read at every startup
+        let contents = serde_json::to_string(self)
             .with_context(|| "Failed to serialize index")?;
         
         // Ensure parent directory exists
@@ -77,7 +235,7 @@ impl Index {
             std::fs::create_dir_all(parent)?;
         }
         
-        std::fs::write(path, contents)
+        write_atomic(path, contents)
             .with_context(|| format!("Failed to write index file: {}", path.display()))?;
         
         Ok(())
@@ -85,22 +243,39 @@ impl Index {
 
     /// Add or update an entry
     pub fn upsert(&mut self, entry: IndexEntry) {
-        self.entries.insert(entry.name.clone(), entry);
+        self.entries.insert(entry_key(&entry.location, &entry.name), entry);
         self.updated = Some(Utc::now());
     }
 
-    /// Remove an entry by name
-    pub fn remove(&mut self, name: &str) -> Option<IndexEntry> {
-        let entry = self.entries.remove(name);
+    /// Remove the entry of prompt `name` in `location`
+    pub fn remove(&mut self, location: &str, name: &str) -> Option<IndexEntry> {
+        let entry = self.entries.remove(&entry_key(location, name));
         if entry.is_some() {
             self.updated = Some(Utc::now());
         }
         entry
     }
 
-    /// Get an entry by name
-    pub fn get(&self, name: &str) -> Option<&IndexEntry> {
-        self.entries.get(name)
+    /// Get the entry of prompt `name` in `location`
+    pub fn get(&self, location: &str, name: &str) -> Option<&IndexEntry> {
+        self.entries.get(&entry_key(location, name))
+    }
+
+    /// Get an entry named `name` wherever it lives, preferring an active prompt over an
+    /// archived one
+    pub fn find(&self, name: &str) -> Option<&IndexEntry> {
+        self.entries
+            .values()
+            .filter(|entry| entry.name == name)
+            .min_by_key(|entry| entry.location == "archive")
+    }
+
+    /// Move the entry of prompt `name` from `from` to `to`
+    pub fn move_entry(&mut self, name: &str, from: &str, to: &str) {
+        if let Some(mut entry) = self.entries.remove(&entry_key(from, name)) {
+            entry.location = to.to_string();
+            self.upsert(entry);
+        }
     }
 
     /// Get all entries
@@ -110,7 +285,7 @@ impl Index {
 
     /// Get all prompt names
     pub fn all_names(&self) -> impl Iterator<Item = &str> {
-        self.entries.keys().map(|s| s.as_str())
+        self.entries.values().map(|entry| entry.name.as_str())
     }
 
     /// Get all unique tags
@@ -133,19 +308,118 @@ impl Index {
             .values()
             .filter(|entry| {
                 entry.name.to_lowercase().contains(&query_lower)
-                    || entry.content.to_lowercase().contains(&query_lower)
+                    || entry
+                        .content
+                        .as_ref()
+                        .is_some_and(|content| content.to_lowercase().contains(&query_lower))
             })
             .collect()
     }
 
-    /// Filter entries by tag
+    /// Filter entries by tag, including the tags below it
     pub fn filter_by_tag(&self, tag: &str) -> Vec<&IndexEntry> {
         self.entries
             .values()
-            .filter(|entry| entry.tags.contains(&tag.to_string()))
+            .filter(|entry| entry.tags.iter().any(|t| is_tag_or_child(t, tag)))
             .collect()
     }
 
+    /// Bring the index up to date with the prompt files under `piemme` (a `.piemme` directory).
+    ///
+    /// Only files whose modification time or size differ from their entry are read, along
+    /// with unchanged files whose body has not been read yet; every other prompt is rebuilt
+    /// from the index.
+    pub fn sync(&mut self, piemme: &Path) -> Result<SyncedPrompts> {
+        self.sync_files(piemme, true)
+    }
+
+    /// Like [`Index::sync`], but prompts of unchanged files whose body has not been read yet
+    /// are listed with an empty body instead of reading it; their files are in
+    /// [`SyncedPrompts::unread`]. Fill them in with [`read_bodies`] and [`Index::set_body`].
+    pub fn sync_metadata(&mut self, piemme: &Path) -> Result<SyncedPrompts> {
+        self.sync_files(piemme, false)
+    }
+
+    fn sync_files(&mut self, piemme: &Path, read_bodies: bool) -> Result<SyncedPrompts> {
+        let mut locations = vec!["prompts".to_string(), "archive".to_string()];
+        let folders = list_subfolders(&piemme.join("folders"))?;
+        locations.extend(folders.into_iter().map(|folder| format!("folders/{}", folder)));
+
+        let mut synced = SyncedPrompts::default();
+        let mut seen = HashSet::new();
+        for location in locations {
+            let mut prompts = Vec::new();
+            for (path, metadata) in markdown_files_with_metadata(&piemme.join(&location))? {
+                let Some(name) = path.file_stem().and_then(|n| n.to_str()) else {
+                    continue;
+                };
+                let (mtime, size) = file_times(&metadata);
+
+                let cached = self
+                    .get(&location, name)
+                    .and_then(|entry| entry.cached_prompt(mtime, size));
+                let stamped = cached.is_some();
+                let prompt = match cached {
+                    Some((prompt, true)) => prompt,
+                    Some((prompt, false)) if !read_bodies => {
+                        synced.unread.push((location.clone(), path.clone()));
+                        prompt
+                    }
+                    _ => match load_prompt(&path) {
+                        // Only the body was missing, which the saved index leaves out anyway
+                        Ok(prompt) if stamped => {
+                            self.set_body(&location, &prompt.name, prompt.content.clone());
+                            prompt
+                        }
+                        Ok(prompt) => {
+                            let mut entry = IndexEntry::from_prompt(&prompt, &location);
+                            entry.file = Some(FileStamp {
+                                mtime,
+                                size,
+                                created: prompt.created,
+                                status: prompt.status.clone(),
+                            });
+                            self.upsert(entry);
+                            synced.read += 1;
+                            prompt
+                        }
+                        Err(e) => {
+                            // Report the file but keep loading the other prompts
+                            synced.failed.push(format!("{}: {:#}", path.display(), e));
+                            continue;
+                        }
+                    },
+                };
+                seen.insert(entry_key(&location, &prompt.name));
+                prompts.push(prompt);
+            }
+
+            prompts.sort_by(|a, b| a.name.cmp(&b.name));
+            synced.prompts.extend(prompts.into_iter().map(|p| (location.clone(), p)));
+        }
+
+        let before = self.entries.len();
+        self.entries.retain(|key, _| seen.contains(key));
+        synced.removed = before - self.entries.len();
+        if synced.removed > 0 {
+            self.updated = Some(Utc::now());
+        }
+        Ok(synced)
+    }
+
+    /// Record the body of prompt `name` in `location` read after [`Index::sync_metadata`].
+    /// Returns false, leaving the entry alone, when it is gone or has its body already
+    /// (its file was read again meanwhile).
+    pub fn set_body(&mut self, location: &str, name: &str, content: String) -> bool {
+        match self.entries.get_mut(&entry_key(location, name)) {
+            Some(entry) if entry.content.is_none() => {
+                entry.content = Some(content);
+                true
+            }
+            _ => false,
+        }
+    }
+
     /// Filter entries by location
     pub fn filter_by_location(&self, location: &str) -> Vec<&IndexEntry> {
         self.entries
@@ -153,6 +427,45 @@ impl Index {
             .filter(|entry| entry.location == location)
             .collect()
     }
+
+    /// Move entries in `from` or below it to the same place under `to` (a folder rename or
+    /// merge), returning how many moved
+    pub fn rename_location(&mut self, from: &str, to: &str) -> usize {
+        self.relocate(from, |rest| format!("{}{}", to, rest))
+    }
+
+    /// Move entries in `from` or below it directly into `to` (a deleted folder's prompts),
+    /// returning how many moved
+    pub fn flatten_location(&mut self, from: &str, to: &str) -> usize {
+        self.relocate(from, |_| to.to_string())
+    }
+
+    /// Set the location of entries in `from` or below it from the rest of their location
+    fn relocate(&mut self, from: &str, new_location: impl Fn(&str) -> String) -> usize {
+        let mut moved = 0;
+        for entry in self.entries.values_mut() {
+            let Some(rest) = entry.location.strip_prefix(from) else {
+                continue;
+            };
+            if rest.is_empty() || rest.starts_with('/') {
+                entry.location = new_location(rest);
+                moved += 1;
+            }
+        }
+        if moved > 0 {
+            self.rekey();
+            self.updated = Some(Utc::now());
+        }
+        moved
+    }
+
+    /// Key every entry by its current location and name
+    fn rekey(&mut self) {
+        self.entries = std::mem::take(&mut self.entries)
+            .into_values()
+            .map(|entry| (entry_key(&entry.location, &entry.name), entry))
+            .collect();
+    }
 }
 
 impl IndexEntry {
@@ -162,12 +475,34 @@ impl IndexEntry {
             id: prompt.id,
             name: prompt.name.clone(),
             preview: prompt.first_line().to_string(),
-            content: prompt.content.clone(),
+            content: Some(prompt.content.clone()),
             tags: prompt.tags.clone(),
             location: location.to_string(),
             modified: prompt.modified,
+            file: None,
         }
     }
+
+    /// The prompt's content, empty while its body has not been read
+    pub fn body(&self) -> &str {
+        self.content.as_deref().unwrap_or_default()
+    }
+
+    /// The prompt this entry caches, if it was read from a file with this mtime and size,
+    /// and whether its body is cached too (the body is empty otherwise)
+    fn cached_prompt(&self, mtime: u64, size: u64) -> Option<(Prompt, bool)> {
+        let file = self.file.as_ref().filter(|f| f.mtime == mtime && f.size == size)?;
+        let prompt = Prompt {
+            id: self.id,
+            name: self.name.clone(),
+            content: self.content.clone().unwrap_or_default(),
+            tags: self.tags.clone(),
+            created: file.created,
+            modified: self.modified,
+            status: file.status.clone(),
+        };
+        Some((prompt, self.content.is_some()))
+    }
 }
 
 #[cfg(test)]
@@ -183,19 +518,20 @@ mod tests {
             id: Uuid::new_v4(),
             name: "test_prompt".to_string(),
             preview: "This is a test".to_string(),
-            content: "This is a test prompt content".to_string(),
+            content: Some("This is a test prompt content".to_string()),
             tags: vec!["coding".to_string(), "test".to_string()],
             location: "prompts".to_string(),
             modified: Utc::now(),
+            file: None,
         };
         
         index.upsert(entry.clone());
         assert_eq!(index.entries.len(), 1);
         
-        let retrieved = index.get("test_prompt").unwrap();
+        let retrieved = index.get("prompts", "test_prompt").unwrap();
         assert_eq!(retrieved.name, "test_prompt");
         
-        let removed = index.remove("test_prompt");
+        let removed = index.remove("prompts", "test_prompt");
         assert!(removed.is_some());
         assert!(index.entries.is_empty());
     }
@@ -208,20 +544,22 @@ mod tests {
             id: Uuid::new_v4(),
             name: "coding_tips".to_string(),
             preview: "Tips for coding".to_string(),
-            content: "Tips for coding in Python".to_string(),
+            content: Some("Tips for coding in Python".to_string()),
             tags: vec!["coding".to_string()],
             location: "prompts".to_string(),
             modified: Utc::now(),
+            file: None,
         });
         
         index.upsert(IndexEntry {
             id: Uuid::new_v4(),
             name: "writing_guide".to_string(),
             preview: "Writing guide".to_string(),
-            content: "A guide for technical writing".to_string(),
+            content: Some("A guide for technical writing".to_string()),
             tags: vec!["writing".to_string()],
             location: "prompts".to_string(),
             modified: Utc::now(),
+            file: None,
         });
         
         let results = index.search("coding");
@@ -233,6 +571,123 @@ mod tests {
         assert_eq!(results[0].name, "writing_guide");
     }
 
+    #[test]
+    fn test_index_relocate() {
+        let mut index = Index::new();
+        for (name, location) in [
+            ("a", "folders/work"),
+            ("b", "folders/work/backend"),
+            ("c", "folders/workshop"),
+            ("d", "prompts"),
+        ] {
+            index.upsert(IndexEntry {
+                id: Uuid::new_v4(),
+                name: name.to_string(),
+                preview: String::new(),
+                content: None,
+                tags: vec![],
+                location: location.to_string(),
+                modified: Utc::now(),
+                file: None,
+            });
+        }
+        let location = |index: &Index, name: &str| index.find(name).unwrap().location.clone();
+
+        assert_eq!(index.rename_location("folders/work", "folders/job"), 2);
+        assert_eq!(location(&index, "a"), "folders/job");
+        assert_eq!(location(&index, "b"), "folders/job/backend");
+        assert_eq!(location(&index, "c"), "folders/workshop");
+
+        assert_eq!(index.flatten_location("folders/job", "archive"), 2);
+        assert_eq!(location(&index, "b"), "archive");
+        assert_eq!(location(&index, "d"), "prompts");
+    }
+
+    #[test]
+    fn test_index_keeps_same_names_apart() {
+        let dir = tempdir().unwrap();
+        let piemme = dir.path();
+        for (content, location) in [("active", "prompts"), ("archived", "archive")] {
+            let mut prompt = Prompt::with_content(content);
+            prompt.name = "review".to_string();
+            crate::fs::save_prompt(&prompt, &piemme.join(location), Default::default()).unwrap();
+        }
+        std::fs::write(piemme.join("prompts").join("bad.md"), [0xff, 0xfe, 0xfd]).unwrap();
+
+        let mut index = Index::new();
+        let synced = index.sync(piemme).unwrap();
+        assert_eq!(index.entries.len(), 2);
+        assert_eq!(index.get("prompts", "review").unwrap().content.as_deref(), Some("active"));
+        assert_eq!(index.get("archive", "review").unwrap().content.as_deref(), Some("archived"));
+        assert_eq!(index.find("review").unwrap().location, "prompts");
+        // Unreadable files are reported instead of printed
+        assert_eq!(synced.failed.len(), 1);
+        assert!(synced.failed[0].contains("bad.md"));
+
+        index.move_entry("review", "archive", "folders/old");
+        let moved = index.get("folders/old", "review").unwrap();
+        assert_eq!(moved.content.as_deref(), Some("archived"));
+        assert!(index.remove("archive", "review").is_none());
+        assert!(index.remove("prompts", "review").is_some());
+        assert_eq!(index.entries.len(), 1);
+    }
+
+    #[test]
+    fn test_index_sync_reads_only_changed_files() {
+        let dir = tempdir().unwrap();
+        let piemme = dir.path();
+        let save = |name: &str, content: &str, location: &str| {
+            let mut prompt = Prompt::with_content(content);
+            prompt.name = name.to_string();
+            prompt.tags = vec!["t".to_string()];
+            crate::fs::save_prompt(&prompt, &piemme.join(location), Default::default()).unwrap();
+        };
+        save("main", "in main", "prompts");
+        save("old", "archived", "archive");
+        save("deep", "nested", "folders/work/backend");
+        std::fs::write(piemme.join("prompts").join("plain.md"), "no frontmatter").unwrap();
+
+        let mut index = Index::new();
+        let synced = index.sync(piemme).unwrap();
+        assert_eq!(synced.read, 4);
+        assert_eq!(synced.archived(), 1);
+        let names: Vec<String> = synced.active().into_iter().map(|p| p.name).collect();
+        assert_eq!(names, vec!["main", "plain", "deep"]);
+        assert_eq!(index.find("deep").unwrap().location, "folders/work/backend");
+
+        // A saved and reloaded index lists the same prompts without reading any file, and
+        // their bodies are read separately
+        let path = piemme.join(".index.json");
+        index.save(&path).unwrap();
+        assert!(!std::fs::read_to_string(&path).unwrap().contains("\"content\""));
+        let mut index = Index::load(&path).unwrap();
+        let listed = index.sync_metadata(piemme).unwrap();
+        assert!(!listed.changed());
+        assert_eq!(listed.unread.len(), 4);
+        assert_eq!(listed.in_location("prompts")[0].content, "");
+        assert_eq!(listed.in_location("prompts")[0].tags, vec!["t"]);
+        for body in read_bodies(&listed.unread) {
+            assert!(index.set_body(&body.location, &body.name, body.content));
+        }
+        let cached = index.sync(piemme).unwrap();
+        assert!(!cached.changed());
+        assert_eq!(cached.prompts, synced.prompts);
+
+        // Bodies still missing are read by a full sync
+        let mut index = Index::load(&path).unwrap();
+        let cached = index.sync(piemme).unwrap();
+        assert!(!cached.changed());
+        assert!(cached.unread.is_empty());
+        assert_eq!(cached.prompts, synced.prompts);
+
+        save("main", "edited in main", "prompts");
+        std::fs::remove_file(piemme.join("archive").join("old.md")).unwrap();
+        let synced = index.sync(piemme).unwrap();
+        assert_eq!((synced.read, synced.removed), (1, 1));
+        assert_eq!(synced.in_location("prompts")[0].content, "edited in main");
+        assert!(index.find("old").is_none());
+    }
+
     #[test]
     fn test_index_save_load() {
         let dir = tempdir().unwrap();
@@ -243,16 +698,17 @@ mod tests {
             id: Uuid::new_v4(),
             name: "test".to_string(),
             preview: "Test".to_string(),
-            content: "Test content".to_string(),
+            content: Some("Test content".to_string()),
             tags: vec![],
             location: "prompts".to_string(),
             modified: Utc::now(),
+            file: None,
         });
         
         index.save(&path).unwrap();
         
         let loaded = Index::load(&path).unwrap();
         assert_eq!(loaded.entries.len(), 1);
-        assert!(loaded.get("test").is_some());
+        assert!(loaded.get("prompts", "test").is_some());
     }
 }
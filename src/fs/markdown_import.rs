@@ -0,0 +1,455 @@
+//! Importing an Obsidian vault or plain Markdown directory
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde_yaml::Value;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use uuid::Uuid;
+
+use super::prompt_io::{save_prompt, SaveOptions};
+use crate::models::prompt::{make_unique_name, normalize_name, PromptStatus};
+use crate::models::{normalize_tag, Prompt};
+
+/// Directories never descended into (Obsidian settings, trash, VCS)
+const SKIP_DIRS: &[&str] = &[".obsidian", ".trash", ".git", ".piemme", "node_modules"];
+
+// Obsidian wiki links: [[Target]], [[Target#Heading]], [[Target|Alias]], ![[Embed]]
+static WIKI_LINK_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"!?\[\[([^\]|#]*)(#[^\]|]*)?(\|[^\]]*)?\]\]").expect("Invalid wiki link regex")
+});
+
+// Inline #tags (a heading's "# " is not a tag, and tags can't be purely numeric)
+static INLINE_TAG_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:^|[\s(,])#([A-Za-z_][\w/-]*)").expect("Invalid inline tag regex")
+});
+
+/// A Markdown file that will become a prompt
+#[derive(Debug, Clone)]
+pub struct ImportedNote {
+    /// Source file, relative to the imported directory
+    pub source: PathBuf,
+    /// File name without extension, as found on disk
+    pub original_name: String,
+    /// The prompt to write (name normalised, links rewritten)
+    pub prompt: Prompt,
+    /// Whether the source file had frontmatter
+    pub had_frontmatter: bool,
+}
+
+impl ImportedNote {
+    /// Whether the prompt name differs from the original file name
+    pub fn is_renamed(&self) -> bool {
+        self.original_name != self.prompt.name
+    }
+}
+
+/// A link that does not point to any imported note
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnresolvedLink {
+    /// Source file, relative to the imported directory
+    pub source: PathBuf,
+    /// The link as written
+    pub link: String,
+}
+
+/// Everything an import would do, computed without touching the store
+#[derive(Debug, Clone, Default)]
+pub struct MarkdownImportPlan {
+    pub notes: Vec<ImportedNote>,
+    pub unresolved: Vec<UnresolvedLink>,
+}
+
+impl MarkdownImportPlan {
+    /// Human-readable dry-run report
+    pub fn report(&self) -> String {
+        let mut out = format!("{} note(s) to import\n", self.notes.len());
+
+        let renamed: Vec<&ImportedNote> = self.notes.iter().filter(|n| n.is_renamed()).collect();
+        if !renamed.is_empty() {
+            out.push_str(&format!("\nRenames ({}):\n", renamed.len()));
+            for note in renamed {
+                out.push_str(&format!("  {} -> {}\n", note.source.display(), note.prompt.name));
+            }
+        }
+
+        let created = self.notes.iter().filter(|n| !n.had_frontmatter).count();
+        if created > 0 {
+            out.push_str(&format!("\nFrontmatter created for {} note(s)\n", created));
+        }
+
+        if !self.unresolved.is_empty() {
+            out.push_str(&format!("\nUnresolved links ({}):\n", self.unresolved.len()));
+            for link in &self.unresolved {
+                out.push_str(&format!("  {}: {}\n", link.source.display(), link.link));
+            }
+        }
+
+        out
+    }
+}
+
+/// Scan `root` for Markdown files and plan their import.
+///
+/// Names are normalised and made unique against `existing_names`; links between
+/// imported notes are rewritten to the new names, and links to existing prompts
+/// resolve to them. Frontmatter ids already in `existing_ids`, or used by an earlier
+/// note, are replaced with new ones.
+pub fn plan_markdown_import(
+    root: &Path,
+    existing_names: &[&str],
+    existing_ids: &HashSet<Uuid>,
+) -> Result<MarkdownImportPlan> {
+    let mut files = Vec::new();
+    collect_markdown_files(root, root, &mut files)?;
+    files.sort();
+
+    // First pass: parse files and assign names
+    let mut taken: Vec<String> = existing_names.iter().map(|n| n.to_string()).collect();
+    let mut taken_ids = existing_ids.clone();
+    let mut notes = Vec::new();
+    for relative in files {
+        let path = root.join(&relative);
+        let raw = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let mtime = std::fs::metadata(&path)
+            .and_then(|m| m.modified())
+            .map(DateTime::<Utc>::from)
+            .unwrap_or_else(|_| Utc::now());
+
+        let original_name = relative
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("note")
+            .to_string();
+        let mut base = normalize_name(&original_name);
+        if base.is_empty() {
+            base = "note".to_string();
+        }
+        let taken_refs: Vec<&str> = taken.iter().map(String::as_str).collect();
+        let name = make_unique_name(&base, &taken_refs);
+        taken.push(name.clone());
+
+        let (frontmatter, body) = split_frontmatter(&raw);
+        let mut prompt = Prompt {
+            id: Uuid::new_v4(),
+            name,
+            content: body.to_string(),
+            tags: Vec::new(),
+            created: mtime,
+            modified: mtime,
+            status: PromptStatus::Managed,
+        };
+        if let Some(frontmatter) = &frontmatter {
+            apply_frontmatter(&mut prompt, frontmatter);
+        }
+        // Prompts sharing an id would share history and backups
+        if !taken_ids.insert(prompt.id) {
+            prompt.id = Uuid::new_v4();
+            taken_ids.insert(prompt.id);
+        }
+        for tag in inline_tags(body) {
+            if !prompt.tags.contains(&tag) {
+                prompt.tags.push(tag);
+            }
+        }
+
+        notes.push(ImportedNote {
+            source: relative,
+            original_name,
+            prompt,
+            had_frontmatter: frontmatter.is_some(),
+        });
+    }
+
+    // Links may use the file name or the vault-relative path, in any case
+    let mut targets: HashMap<String, String> = HashMap::new();
+    for note in &notes {
+        let path_key = note.source.with_extension("").to_string_lossy().replace('\\', "/");
+        targets.insert(path_key.to_lowercase(), note.prompt.name.clone());
+        targets
+            .entry(note.original_name.to_lowercase())
+            .or_insert_with(|| note.prompt.name.clone());
+    }
+
+    // Second pass: rewrite links
+    let mut unresolved = Vec::new();
+    for note in &mut notes {
+        note.prompt.content = WIKI_LINK_REGEX
+            .replace_all(&note.prompt.content, |caps: &regex::Captures| {
+                let full = caps[0].to_string();
+                let target = caps[1].trim();
+                if target.starts_with("file:") {
+                    return full;
+                }
+                let key = target.strip_suffix(".md").unwrap_or(target).to_lowercase();
+                // Imported notes win; otherwise the link may name a prompt already in the store
+                let existing = || {
+                    let name = normalize_name(&key);
+                    existing_names.iter().find(|n| **n == name).map(|n| n.to_string())
+                };
+                match targets.get(&key).cloned().or_else(existing) {
+                    Some(name) => format!("[[{}]]", name),
+                    None => {
+                        unresolved.push(UnresolvedLink {
+                            source: note.source.clone(),
+                            link: full.clone(),
+                        });
+                        full
+                    }
+                }
+            })
+            .into_owned();
+    }
+
+    Ok(MarkdownImportPlan { notes, unresolved })
+}
+
+/// Write the planned prompts into `dest`
+pub fn apply_markdown_import(
+    plan: &MarkdownImportPlan,
+    dest: &Path,
+    options: SaveOptions,
+) -> Result<Vec<PathBuf>> {
+    plan.notes
+        .iter()
+        .map(|note| save_prompt(&note.prompt, dest, options))
+        .collect()
+}
+
+/// Recursively collect `.md` files under `dir`, relative to `root`
+fn collect_markdown_files(root: &Path, dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    let entries =
+        std::fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))?;
+
+    for entry in entries {
+        let path = entry?.path();
+        let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+
+        if path.is_dir() {
+            if !SKIP_DIRS.contains(&file_name) && !file_name.starts_with('.') {
+                collect_markdown_files(root, &path, files)?;
+            }
+        } else if path.extension().is_some_and(|ext| ext == "md")
+            && let Ok(relative) = path.strip_prefix(root)
+        {
+            files.push(relative.to_path_buf());
+        }
+    }
+
+    Ok(())
+}
+
+/// Split YAML frontmatter from the body; files without valid frontmatter are all body
+fn split_frontmatter(raw: &str) -> (Option<Value>, &str) {
+    let Some(rest) = raw.strip_prefix("---\n").or_else(|| raw.strip_prefix("---\r\n")) else {
+        return (None, raw);
+    };
+    let Some(end) = rest.find("\n---") else {
+        return (None, raw);
+    };
+
+    match serde_yaml::from_str::<Value>(&rest[..end]) {
+        Ok(value) if value.is_mapping() || value.is_null() => {
+            let body = rest[end + 4..].trim_start_matches(['\r', '\n']);
+            (Some(value), body)
+        }
+        _ => (None, raw),
+    }
+}
+
+/// Copy the id, timestamps and tags from Obsidian/piemme frontmatter onto `prompt`
+fn apply_frontmatter(prompt: &mut Prompt, frontmatter: &Value) {
+    let get = |key: &str| frontmatter.get(key);
+
+    if let Some(id) = get("id").and_then(Value::as_str).and_then(|s| Uuid::parse_str(s).ok()) {
+        prompt.id = id;
+    }
+    if let Some(created) = get("created").and_then(parse_date) {
+        prompt.created = created;
+    }
+    if let Some(modified) = get("modified").or(get("updated")).and_then(parse_date) {
+        prompt.modified = modified;
+    }
+
+    for key in ["tags", "tag"] {
+        let values: Vec<String> = match get(key) {
+            Some(Value::Sequence(items)) => items
+                .iter()
+                .filter_map(|v| v.as_str().map(str::to_string))
+                .collect(),
+            Some(Value::String(s)) => s
+                .split([',', ' '])
+                .map(str::to_string)
+                .collect(),
+            _ => Vec::new(),
+        };
+        for value in values {
+            // Obsidian allows a leading `#` on frontmatter tags
+            let tag = normalize_tag(value.trim().trim_start_matches('#'));
+            if !tag.is_empty() && !prompt.tags.contains(&tag) {
+                prompt.tags.push(tag);
+            }
+        }
+    }
+}
+
+/// Parse an RFC 3339 timestamp or a plain `YYYY-MM-DD` date
+fn parse_date(value: &Value) -> Option<DateTime<Utc>> {
+    let text = value.as_str()?;
+    DateTime::parse_from_rfc3339(text)
+        .map(|d| d.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            chrono::NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                .ok()
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+                .map(|d| d.and_utc())
+        })
+}
+
+/// Find inline `#tags` outside fenced code blocks
+fn inline_tags(body: &str) -> Vec<String> {
+    let mut tags = Vec::new();
+    let mut in_fence = false;
+
+    for line in body.lines() {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        for cap in INLINE_TAG_REGEX.captures_iter(line) {
+            let tag = normalize_tag(&cap[1]);
+            if !tag.is_empty() && !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+    }
+
+    tags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn write(root: &Path, relative: &str, contents: &str) {
+        let path = root.join(relative);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn test_plan_renames_and_rewrites_links() {
+        let dir = tempdir().unwrap();
+        write(
+            dir.path(),
+            "Code Review.md",
+            "See [[Style Guide|the guide]] and [[notes/Checklist#Top]] and [[Nowhere]].",
+        );
+        write(dir.path(), "Style Guide.md", "Be nice. ![[Code Review]]");
+        write(dir.path(), "notes/Checklist.md", "- [ ] tests");
+        write(dir.path(), ".obsidian/workspace.md", "ignored");
+
+        let plan = plan_markdown_import(dir.path(), &["style_guide"], &HashSet::new()).unwrap();
+        let names: Vec<&str> = plan.notes.iter().map(|n| n.prompt.name.as_str()).collect();
+        assert_eq!(names, vec!["code_review", "style_guide_1", "checklist"]);
+
+        let review = &plan.notes[0].prompt;
+        assert_eq!(
+            review.content,
+            "See [[style_guide_1]] and [[checklist]] and [[Nowhere]]."
+        );
+        assert_eq!(plan.notes[1].prompt.content, "Be nice. [[code_review]]");
+
+        assert_eq!(
+            plan.unresolved,
+            vec![UnresolvedLink {
+                source: PathBuf::from("Code Review.md"),
+                link: "[[Nowhere]]".to_string(),
+            }]
+        );
+
+        let report = plan.report();
+        assert!(report.contains("Code Review.md -> code_review"));
+        assert!(report.contains("Code Review.md: [[Nowhere]]"));
+    }
+
+    #[test]
+    fn test_plan_resolves_links_to_existing_prompts() {
+        let dir = tempdir().unwrap();
+        write(dir.path(), "Draft.md", "Use [[Team Style]], [[team_style#Tone]] and [[Gone]].");
+
+        let plan = plan_markdown_import(dir.path(), &["team_style"], &HashSet::new()).unwrap();
+        assert_eq!(
+            plan.notes[0].prompt.content,
+            "Use [[team_style]], [[team_style]] and [[Gone]]."
+        );
+        let links: Vec<&str> = plan.unresolved.iter().map(|l| l.link.as_str()).collect();
+        assert_eq!(links, vec!["[[Gone]]"]);
+    }
+
+    #[test]
+    fn test_plan_maps_tags_and_frontmatter() {
+        let dir = tempdir().unwrap();
+        let id = Uuid::new_v4();
+        write(
+            dir.path(),
+            "tagged.md",
+            &format!(
+                "---\nid: {}\ntags: [Work, \"#Project//Alpha/\"]\ncreated: 2024-03-01\n---\n# Heading\nBody #idea and #work\n```\n#not_a_tag\n```\nIssue #42\n",
+                id
+            ),
+        );
+        write(dir.path(), "plain.md", "No frontmatter #solo");
+
+        let plan = plan_markdown_import(dir.path(), &[], &HashSet::new()).unwrap();
+        let plain = &plan.notes[0];
+        let tagged = &plan.notes[1];
+
+        assert!(!plain.had_frontmatter);
+        assert_eq!(plain.prompt.tags, vec!["solo"]);
+
+        assert!(tagged.had_frontmatter);
+        assert_eq!(tagged.prompt.id, id);
+        assert_eq!(tagged.prompt.tags, vec!["work", "project/alpha", "idea"]);
+        assert_eq!(tagged.prompt.created.format("%Y-%m-%d").to_string(), "2024-03-01");
+        assert!(tagged.prompt.content.starts_with("# Heading"));
+    }
+
+    #[test]
+    fn test_plan_replaces_taken_ids() {
+        let dir = tempdir().unwrap();
+        let stored = Uuid::new_v4();
+        let shared = Uuid::new_v4();
+        write(dir.path(), "a.md", &format!("---\nid: {}\n---\nA", stored));
+        write(dir.path(), "b.md", &format!("---\nid: {}\n---\nB", shared));
+        write(dir.path(), "c.md", &format!("---\nid: {}\n---\nC", shared));
+
+        let plan = plan_markdown_import(dir.path(), &[], &HashSet::from([stored])).unwrap();
+        let ids: Vec<Uuid> = plan.notes.iter().map(|note| note.prompt.id).collect();
+        assert_ne!(ids[0], stored);
+        assert_eq!(ids[1], shared);
+        assert_ne!(ids[2], shared);
+        assert_ne!(ids[0], ids[2]);
+    }
+
+    #[test]
+    fn test_apply_writes_prompts_with_frontmatter() {
+        let source = tempdir().unwrap();
+        let dest = tempdir().unwrap();
+        write(source.path(), "My Note.md", "Hello");
+
+        let plan = plan_markdown_import(source.path(), &[], &HashSet::new()).unwrap();
+        apply_markdown_import(&plan, dest.path(), SaveOptions::default()).unwrap();
+
+        let prompt = crate::fs::load_prompt(&dest.path().join("my_note.md")).unwrap();
+        assert_eq!(prompt.content, "Hello");
+    }
+}
@@ -1,9 +1,34 @@
 //! File system operations
 
+mod atomic;
+mod backup;
+mod bundle;
 mod directory;
+mod export;
+mod history;
+mod markdown_import;
 mod prompt_io;
 mod index;
+mod session;
+mod tags;
+mod watcher;
 
-pub use directory::{ensure_directories, list_folders, create_folder};
-pub use prompt_io::{load_prompt, save_prompt, load_all_prompts, load_all_prompts_everywhere, delete_prompt, move_prompt, rename_prompt, get_all_prompt_names, create_new_prompt};
-pub use index::{Index, IndexEntry};
+pub use atomic::write_atomic;
+pub use backup::{list_backups, restore_backup, Backup, DEFAULT_BACKUP_RETENTION};
+pub use bundle::{
+    create_bundle, detect_collisions, existing_prompts, import_bundle, Bundle, Collision,
+    ConflictResolution,
+};
+pub use directory::{
+    create_folder, delete_folder, ensure_directories, list_folders, list_subfolders, merge_folder,
+    normalize_folder_path, parent_folder, rename_folder,
+};
+pub(crate) use prompt_io::parse_frontmatter;
+pub use prompt_io::{load_prompt, save_prompt, SaveOptions, load_all_prompts, load_all_prompts_everywhere, delete_prompt, move_prompt, rename_prompt, create_new_prompt};
+pub use history::{list_revisions, restore_revision, Revision, DEFAULT_HISTORY_RETENTION};
+pub use export::{export_documents, write_export, ExportFormat, ExportOptions};
+pub use index::{read_bodies, Body, Index, IndexEntry, SyncedPrompts};
+pub use session::Session;
+pub use tags::edit_tags;
+pub use markdown_import::{apply_markdown_import, plan_markdown_import};
+pub use watcher::{is_own_write, PromptWatcher};
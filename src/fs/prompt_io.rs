@@ -1,11 +1,20 @@
 //! Prompt file I/O operations
 
 use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
 use std::io::ErrorKind;
 use std::path::{Path, PathBuf};
-
-use crate::config::{archive_dir, folders_dir, prompts_dir};
-use crate::models::prompt::{generate_name_from_content, make_unique_name, Prompt, PromptFrontmatter};
+use uuid::Uuid;
+
+use super::atomic::write_atomic;
+use super::directory::list_folders;
+use super::backup::{backup_file, store_backup_dir, DEFAULT_BACKUP_RETENTION};
+use super::watcher::record_own_write;
+use super::history::{record_revision, store_history_dir, DEFAULT_HISTORY_RETENTION};
+use crate::config::{folders_dir, prompts_dir};
+use crate::models::prompt::{
+    generate_name_from_content, make_unique_name, Prompt, PromptFrontmatter, PromptStatus,
+};
 
 /// Provide user-friendly error messages for I/O errors
 fn format_io_error(err: &std::io::Error, path: &Path, operation: &str) -> String {
@@ -41,44 +50,143 @@ fn format_io_error(err: &std::io::Error, path: &Path, operation: &str) -> String
 }
 
 /// Load a prompt from a markdown file
+///
+/// Files without frontmatter load as unmanaged prompts and files whose frontmatter is
+/// not a valid prompt header load as broken ones, both with metadata generated from the
+/// file. Only a closed `---` block holding a YAML mapping counts as frontmatter, so a
+/// note opening with a horizontal rule stays unmanaged.
 pub fn load_prompt(path: &Path) -> Result<Prompt> {
     let content = std::fs::read_to_string(path)
         .map_err(|e| anyhow::anyhow!(format_io_error(&e, path, "read")))?;
 
-    let (frontmatter, body) = parse_frontmatter(&content)?;
-
     let name = path
         .file_stem()
         .and_then(|n| n.to_str())
         .unwrap_or("unknown")
         .to_string();
 
-    Ok(Prompt {
-        id: frontmatter.id,
+    if !has_frontmatter(&content) {
+        return Ok(prompt_from_plain_file(path, name, content, PromptStatus::Unmanaged));
+    }
+
+    match parse_frontmatter(&content) {
+        Ok((frontmatter, body)) => Ok(Prompt {
+            id: frontmatter.id,
+            name,
+            content: body,
+            tags: frontmatter.tags,
+            created: frontmatter.created,
+            modified: frontmatter.modified,
+            status: PromptStatus::Managed,
+        }),
+        Err(e) => {
+            let status = PromptStatus::Broken {
+                error: format!("{:#}", e),
+            };
+            Ok(prompt_from_plain_file(path, name, content, status))
+        }
+    }
+}
+
+/// Whether `content` opens with a closed `---` block holding a YAML mapping
+fn has_frontmatter(content: &str) -> bool {
+    let Some(rest) = content.trim().strip_prefix("---") else {
+        return false;
+    };
+    if !rest.starts_with(['\n', '\r']) {
+        return false;
+    }
+    let Some(end) = rest.find("\n---") else {
+        return false;
+    };
+    matches!(
+        serde_yaml::from_str::<serde_yaml::Value>(&rest[..end]),
+        Ok(serde_yaml::Value::Mapping(_))
+    )
+}
+
+/// Build a prompt for a file without usable frontmatter.
+///
+/// The id is derived from the path so it stays stable across reloads, and the
+/// timestamps come from the file's modification time.
+fn prompt_from_plain_file(path: &Path, name: String, content: String, status: PromptStatus) -> Prompt {
+    let modified = std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .map(DateTime::<Utc>::from)
+        .unwrap_or_else(|_| Utc::now());
+
+    Prompt {
+        id: Uuid::new_v5(&Uuid::NAMESPACE_URL, path.to_string_lossy().as_bytes()),
         name,
-        content: body,
-        tags: frontmatter.tags,
-        created: frontmatter.created,
-        modified: frontmatter.modified,
-    })
+        content,
+        tags: Vec::new(),
+        created: modified,
+        modified,
+        status,
+    }
+}
+
+/// How prompts are saved, as configured
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SaveOptions {
+    /// Backups kept per prompt (0 disables backups)
+    pub backup_retention: usize,
+    /// Revisions kept in each prompt's history (0 disables history)
+    pub history_retention: usize,
+}
+
+impl Default for SaveOptions {
+    fn default() -> Self {
+        Self {
+            backup_retention: DEFAULT_BACKUP_RETENTION,
+            history_retention: DEFAULT_HISTORY_RETENTION,
+        }
+    }
 }
 
 /// Save a prompt to a markdown file
-pub fn save_prompt(prompt: &Prompt, dir: &Path) -> Result<PathBuf> {
+pub fn save_prompt(prompt: &Prompt, dir: &Path, options: SaveOptions) -> Result<PathBuf> {
     let path = dir.join(format!("{}.md", prompt.name));
-    
-    let frontmatter = prompt.frontmatter();
-    let frontmatter_str = serde_yaml::to_string(&frontmatter)
-        .with_context(|| "Failed to serialize frontmatter")?;
 
-    let file_content = format!("---\n{}---\n{}", frontmatter_str, prompt.content);
+    // A broken prompt's content is the raw file being repaired
+    let file_content = if prompt.broken_error().is_some() {
+        prompt.content.clone()
+    } else {
+        let frontmatter = prompt.frontmatter();
+        let frontmatter_str = serde_yaml::to_string(&frontmatter)
+            .with_context(|| "Failed to serialize frontmatter")?;
+        format!("---\n{}---\n{}", frontmatter_str, prompt.content)
+    };
 
     // Ensure directory exists
     std::fs::create_dir_all(dir)
         .map_err(|e| anyhow::anyhow!(format_io_error(&e, dir, "create directory")))?;
 
-    std::fs::write(&path, file_content)
+    // The file being replaced, and when it was written
+    let previous = std::fs::read_to_string(&path).ok().map(|old| {
+        let written = std::fs::metadata(&path)
+            .and_then(|m| m.modified())
+            .map(DateTime::<Utc>::from)
+            .unwrap_or_else(|_| Utc::now());
+        (old, written)
+    });
+
+    // Keep the previous version when a prompt in a store is overwritten with new content
+    if let Some(backup_dir) = store_backup_dir(dir)
+        && previous.as_ref().is_some_and(|(old, _)| *old != file_content)
+    {
+        backup_file(&path, &backup_dir, prompt.id, options.backup_retention)?;
+    }
+
+    write_atomic(&path, &file_content)
         .map_err(|e| anyhow::anyhow!(format_io_error(&e, &path, "write")))?;
+    record_own_write(&path);
+
+    if let Some(history_dir) = store_history_dir(dir) {
+        let previous = previous.as_ref().map(|(old, written)| (old.as_str(), *written));
+        let retention = options.history_retention;
+        record_revision(&history_dir, prompt.id, previous, &file_content, retention)?;
+    }
 
     Ok(path)
 }
@@ -147,7 +255,7 @@ pub fn rename_prompt(old_name: &str, new_name: &str, dir: &Path) -> Result<PathB
 }
 
 /// Parse YAML frontmatter from a markdown file
-fn parse_frontmatter(content: &str) -> Result<(PromptFrontmatter, String)> {
+pub(crate) fn parse_frontmatter(content: &str) -> Result<(PromptFrontmatter, String)> {
     let content = content.trim();
     
     if !content.starts_with("---") {
@@ -187,16 +295,9 @@ pub fn load_all_prompts_everywhere() -> Result<Vec<crate::models::Prompt>> {
     // Main prompts directory
     prompts.extend(load_all_prompts(&prompts_dir()?)?);
 
-    // Folders
-    let folders_path = folders_dir()?;
-    if folders_path.exists() {
-        for entry in std::fs::read_dir(&folders_path)? {
-            let entry = entry?;
-            let path = entry.path();
-            if path.is_dir() {
-                prompts.extend(load_all_prompts(&path)?);
-            }
-        }
+    // Folders, at any depth
+    for folder in list_folders()? {
+        prompts.extend(load_all_prompts(&folders_dir()?.join(folder))?);
     }
 
     // Sort by name
@@ -205,37 +306,6 @@ pub fn load_all_prompts_everywhere() -> Result<Vec<crate::models::Prompt>> {
     Ok(prompts)
 }
 
-/// Get all prompt names across all directories (for uniqueness checking)
-pub fn get_all_prompt_names() -> Result<Vec<String>> {
-    let mut names = Vec::new();
-
-    // Main prompts directory
-    for prompt in load_all_prompts(&prompts_dir()?)? {
-        names.push(prompt.name);
-    }
-
-    // Archive directory
-    for prompt in load_all_prompts(&archive_dir()?)? {
-        names.push(prompt.name);
-    }
-
-    // Folders
-    let folders_path = folders_dir()?;
-    if folders_path.exists() {
-        for entry in std::fs::read_dir(&folders_path)? {
-            let entry = entry?;
-            let path = entry.path();
-            if path.is_dir() {
-                for prompt in load_all_prompts(&path)? {
-                    names.push(prompt.name);
-                }
-            }
-        }
-    }
-
-    Ok(names)
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -269,7 +339,7 @@ Multiple lines here.
         prompt.name = "test_prompt".to_string();
         prompt.add_tag("testing");
 
-        save_prompt(&prompt, dir_path).unwrap();
+        save_prompt(&prompt, dir_path, SaveOptions::default()).unwrap();
 
         let loaded = load_prompt(&dir_path.join("test_prompt.md")).unwrap();
         assert_eq!(loaded.name, "test_prompt");
@@ -277,6 +347,67 @@ Multiple lines here.
         assert!(loaded.tags.contains(&"testing".to_string()));
     }
 
+    #[test]
+    fn test_load_unmanaged_and_adopt() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("plain.md");
+        std::fs::write(&path, "Just some text\n").unwrap();
+
+        let loaded = load_prompt(&path).unwrap();
+        assert!(loaded.is_unmanaged());
+        assert_eq!(loaded.content, "Just some text\n");
+        assert_eq!(loaded.id, load_prompt(&path).unwrap().id);
+
+        // Saving an unmanaged prompt writes frontmatter, adopting it
+        let mut adopted = loaded.clone();
+        adopted.status = PromptStatus::Managed;
+        save_prompt(&adopted, dir.path(), SaveOptions::default()).unwrap();
+        let reloaded = load_prompt(&path).unwrap();
+        assert_eq!(reloaded.status, PromptStatus::Managed);
+        assert_eq!(reloaded.id, loaded.id);
+        assert_eq!(reloaded.content.trim_end(), "Just some text");
+    }
+
+    #[test]
+    fn test_only_closed_yaml_mappings_are_frontmatter() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("note.md");
+        for raw in [
+            "---\n\nA note opening with a horizontal rule\n",
+            "---\nSome text\n---\nMore text\n",
+            "---\n- a list\n---\nBody\n",
+            "---\ntags: [not closed\n---\nBody\n",
+            "----\nid: x\n---\nBody\n",
+        ] {
+            std::fs::write(&path, raw).unwrap();
+            let loaded = load_prompt(&path).unwrap();
+            assert!(loaded.is_unmanaged(), "{:?}", raw);
+            assert_eq!(loaded.content, raw);
+        }
+    }
+
+    #[test]
+    fn test_load_broken_and_repair() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("broken.md");
+        let raw = "---\nid: not-a-uuid\n---\nBody\n";
+        std::fs::write(&path, raw).unwrap();
+
+        let mut loaded = load_prompt(&path).unwrap();
+        assert!(loaded.broken_error().is_some());
+        assert_eq!(loaded.content, raw);
+
+        // Broken prompts are saved verbatim so the frontmatter can be fixed by hand
+        loaded.content = format!(
+            "---\nid: \"{}\"\ntags: []\ncreated: \"2026-01-15T10:30:00Z\"\nmodified: \"2026-01-15T10:30:00Z\"\n---\nBody\n",
+            loaded.id
+        );
+        save_prompt(&loaded, dir.path(), SaveOptions::default()).unwrap();
+        let repaired = load_prompt(&path).unwrap();
+        assert_eq!(repaired.status, PromptStatus::Managed);
+        assert_eq!(repaired.content.trim(), "Body");
+    }
+
     #[test]
     fn test_create_new_prompt() {
         let existing = vec!["test_content_here"];
@@ -0,0 +1,64 @@
+//! Settings kept between sessions
+//!
+//! `.piemme/.session.yaml` holds what the user last chose in the TUI and expects to find
+//! again at the next start, like the tag filter.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use super::write_atomic;
+use crate::models::TagFilter;
+
+/// Settings restored at startup
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    /// Active tag filter
+    #[serde(default)]
+    pub tag_filter: TagFilter,
+}
+
+impl Session {
+    /// Load the session file at `path`, or the defaults if there is none
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read session file: {}", path.display()))?;
+        let session: Option<Self> = serde_yaml::from_str(&contents)
+            .with_context(|| format!("Failed to parse session file: {}", path.display()))?;
+        Ok(session.unwrap_or_default())
+    }
+
+    /// Save the session to `path`
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let contents = serde_yaml::to_string(self).context("Failed to serialize session")?;
+        write_atomic(path, contents)
+            .with_context(|| format!("Failed to write session file: {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_session_roundtrip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(".session.yaml");
+        assert_eq!(Session::load(&path).unwrap(), Session::default());
+
+        let mut session = Session::default();
+        session.tag_filter.cycle("coding");
+        session.tag_filter.cycle("draft");
+        session.tag_filter.cycle("draft");
+        session.tag_filter.match_any = true;
+        session.save(&path).unwrap();
+        assert_eq!(Session::load(&path).unwrap(), session);
+
+        std::fs::write(&path, "").unwrap();
+        assert_eq!(Session::load(&path).unwrap(), Session::default());
+    }
+}
@@ -0,0 +1,78 @@
+//! Tag operations across every prompt file
+
+use anyhow::Result;
+use std::path::Path;
+
+use super::directory::list_subfolders;
+use super::prompt_io::{load_all_prompts, save_prompt, SaveOptions};
+use crate::models::{PromptStatus, TagEdit};
+
+/// Rewrite the frontmatter of every prompt (main list, folders and archive) whose tags
+/// `edit` changes, below `piemme` (a `.piemme` directory). Returns the changed prompts' names.
+///
+/// Files without valid frontmatter are left alone.
+pub fn edit_tags(piemme: &Path, edit: &TagEdit, options: SaveOptions) -> Result<Vec<String>> {
+    let folders = piemme.join("folders");
+    let mut dirs = vec![piemme.join("prompts"), piemme.join("archive")];
+    dirs.extend(list_subfolders(&folders)?.into_iter().map(|folder| folders.join(folder)));
+
+    let mut changed = Vec::new();
+    for dir in dirs.iter().filter(|dir| dir.exists()) {
+        for mut prompt in load_all_prompts(dir)? {
+            if prompt.status != PromptStatus::Managed {
+                continue;
+            }
+            let Some(tags) = edit.apply(&prompt.tags) else {
+                continue;
+            };
+            prompt.tags = tags;
+            prompt.modified = chrono::Utc::now();
+            save_prompt(&prompt, dir, options)?;
+            changed.push(prompt.name);
+        }
+    }
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::load_prompt;
+    use crate::models::Prompt;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_edit_tags_everywhere() {
+        let dir = tempdir().unwrap();
+        let piemme = dir.path();
+        let save = |name: &str, tags: &[&str], location: &str| {
+            let mut prompt = Prompt::with_content(name);
+            prompt.name = name.to_string();
+            prompt.tags = tags.iter().map(|t| t.to_string()).collect();
+            save_prompt(&prompt, &piemme.join(location), SaveOptions::default()).unwrap();
+        };
+        save("main", &["lang/rust", "coding"], "prompts");
+        save("nested", &["lang"], "folders/work/backend");
+        save("old", &["lang/go"], "archive");
+        save("other", &["writing"], "prompts");
+        std::fs::write(piemme.join("prompts/plain.md"), "No frontmatter #lang").unwrap();
+
+        let edit = TagEdit::Rename { from: "lang".into(), to: "language".into() };
+        let mut changed = edit_tags(piemme, &edit, SaveOptions::default()).unwrap();
+        changed.sort();
+        assert_eq!(changed, ["main", "nested", "old"]);
+
+        let tags = |path: &str| load_prompt(&piemme.join(path)).unwrap().tags;
+        assert_eq!(tags("prompts/main.md"), ["language/rust", "coding"]);
+        assert_eq!(tags("folders/work/backend/nested.md"), ["language"]);
+        assert_eq!(tags("archive/old.md"), ["language/go"]);
+        assert_eq!(tags("prompts/other.md"), ["writing"]);
+        let plain = std::fs::read_to_string(piemme.join("prompts/plain.md")).unwrap();
+        assert_eq!(plain, "No frontmatter #lang");
+
+        let delete = TagEdit::Delete("language".into());
+        let changed = edit_tags(piemme, &delete, SaveOptions::default()).unwrap();
+        assert_eq!(changed.len(), 3);
+        assert_eq!(tags("prompts/main.md"), ["coding"]);
+    }
+}
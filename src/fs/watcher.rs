@@ -0,0 +1,124 @@
+//! Watching the prompt directories for changes made outside piemme
+
+use anyhow::{Context, Result};
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::{LazyLock, Mutex};
+use std::time::SystemTime;
+
+/// Modification time and size of a file
+type FileStamp = (SystemTime, u64);
+
+/// Files piemme wrote itself, as it left them
+static OWN_WRITES: LazyLock<Mutex<HashMap<PathBuf, FileStamp>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn file_stamp(path: &Path) -> Option<(PathBuf, FileStamp)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    Some((path, (metadata.modified().ok()?, metadata.len())))
+}
+
+/// Remember that piemme just wrote `path`, so the event it causes is not taken for a
+/// change made outside
+pub(crate) fn record_own_write(path: &Path) {
+    if let Some((path, stamp)) = file_stamp(path) {
+        OWN_WRITES.lock().unwrap().insert(path, stamp);
+    }
+}
+
+/// Whether `path` is as piemme last wrote it
+pub fn is_own_write(path: &Path) -> bool {
+    file_stamp(path)
+        .is_some_and(|(path, stamp)| OWN_WRITES.lock().unwrap().get(&path) == Some(&stamp))
+}
+
+/// Reports prompt files created, modified or removed on disk
+pub struct PromptWatcher {
+    // Kept alive for as long as events are wanted
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+}
+
+impl PromptWatcher {
+    /// Start watching the given directories (recursively); missing ones are skipped
+    pub fn new(dirs: &[PathBuf]) -> Result<Self> {
+        let (sender, events) = channel();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            // The receiver is gone once the app shuts down
+            let _ = sender.send(event);
+        })
+        .with_context(|| "Failed to start file watcher")?;
+
+        for dir in dirs.iter().filter(|d| d.is_dir()) {
+            watcher
+                .watch(dir, RecursiveMode::Recursive)
+                .with_context(|| format!("Failed to watch {}", dir.display()))?;
+        }
+
+        Ok(Self {
+            _watcher: watcher,
+            events,
+        })
+    }
+
+    /// Drain pending events, returning each changed Markdown file once
+    pub fn changed_paths(&self) -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = self
+            .events
+            .try_iter()
+            .filter_map(|event| event.ok())
+            .filter(|event| !event.kind.is_access())
+            .flat_map(|event| event.paths)
+            .filter(|path| is_markdown(path))
+            .collect();
+        paths.sort();
+        paths.dedup();
+        paths
+    }
+}
+
+fn is_markdown(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "md")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_reports_changed_markdown_files() {
+        let dir = tempdir().unwrap();
+        let dir_path = dir.path().canonicalize().unwrap();
+        let watcher = PromptWatcher::new(std::slice::from_ref(&dir_path)).unwrap();
+
+        std::fs::write(dir_path.join("note.txt"), "ignored").unwrap();
+        std::fs::write(dir_path.join("prompt.md"), "hello").unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut changed = Vec::new();
+        while changed.is_empty() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(50));
+            changed = watcher.changed_paths();
+        }
+        assert_eq!(changed, vec![dir_path.join("prompt.md")]);
+    }
+
+    #[test]
+    fn test_own_writes() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("prompt.md");
+        assert!(!is_own_write(&path));
+
+        std::fs::write(&path, "mine").unwrap();
+        record_own_write(&path);
+        assert!(is_own_write(&path));
+
+        std::fs::write(&path, "theirs, longer").unwrap();
+        assert!(!is_own_write(&path));
+    }
+}
@@ -0,0 +1,294 @@
+//! Git integration through the local `git` command
+//!
+//! Everything here is optional: outside a repository, or without `git`
+//! installed, [`Repo::discover`] returns `None` and git features stay hidden.
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Git state of a prompt file with changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GitStatus {
+    /// Not tracked by git
+    Untracked,
+    /// Changed and not (fully) staged
+    Modified,
+    /// All changes staged for the next commit
+    Staged,
+}
+
+impl GitStatus {
+    /// Mark shown next to the prompt name
+    pub fn mark(self) -> &'static str {
+        match self {
+            GitStatus::Untracked => "?",
+            GitStatus::Modified => "M",
+            GitStatus::Staged => "S",
+        }
+    }
+}
+
+/// One line of `git blame` output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlameLine {
+    /// Abbreviated hash of the commit that last changed the line
+    pub commit: String,
+    /// Author of that commit
+    pub author: String,
+    /// When that commit was authored
+    pub time: DateTime<Utc>,
+    /// The line itself
+    pub text: String,
+}
+
+impl BlameLine {
+    /// Whether the line is not committed yet
+    pub fn is_uncommitted(&self) -> bool {
+        self.commit.chars().all(|c| c == '0')
+    }
+}
+
+/// A git working tree
+#[derive(Debug, Clone)]
+pub struct Repo {
+    root: PathBuf,
+}
+
+impl Repo {
+    /// Find the repository containing `dir`, if any (and if `git` is available)
+    pub fn discover(dir: &Path) -> Option<Self> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["rev-parse", "--show-toplevel"])
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        let root = String::from_utf8(output.stdout).ok()?;
+        Some(Self {
+            root: PathBuf::from(root.trim()),
+        })
+    }
+
+    /// Root directory of the working tree
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Run git in the repository, returning its standard output
+    fn run<I, S>(&self, args: I) -> Result<String>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&self.root)
+            .args(args)
+            .output()
+            .with_context(|| "Failed to run git")?;
+        if !output.status.success() {
+            bail!("git: {}", String::from_utf8_lossy(&output.stderr).trim());
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    /// Status of the changed files under `dir`, keyed by absolute path
+    pub fn statuses(&self, dir: &Path) -> Result<HashMap<PathBuf, GitStatus>> {
+        let output = self.run([
+            OsStr::new("status"),
+            OsStr::new("--porcelain=v1"),
+            OsStr::new("-z"),
+            OsStr::new("--untracked-files=all"),
+            OsStr::new("--"),
+            dir.as_os_str(),
+        ])?;
+        Ok(parse_status(&output)
+            .into_iter()
+            .map(|(path, status)| (self.root.join(path), status))
+            .collect())
+    }
+
+    /// Stage `paths` and commit only them, returning the new commit's hash and subject
+    pub fn commit(&self, paths: &[PathBuf], message: &str) -> Result<String> {
+        if message.trim().is_empty() {
+            bail!("Commit message is empty");
+        }
+
+        let paths = paths.iter().map(|p| p.as_os_str());
+        self.run([OsStr::new("add"), OsStr::new("--")].into_iter().chain(paths.clone()))?;
+        self.run(
+            [OsStr::new("commit"), OsStr::new("-m"), OsStr::new(message), OsStr::new("--")]
+                .into_iter()
+                .chain(paths),
+        )?;
+
+        let summary = self.run(["log", "-1", "--format=%h %s"])?;
+        Ok(summary.trim().to_string())
+    }
+
+    /// Full hash of the commit revision `rev` names
+    pub fn resolve(&self, rev: &str) -> Result<String> {
+        check_revision(rev)?;
+        let output = self.run(["rev-parse", "--verify", "--quiet", &format!("{}^{{commit}}", rev)])?;
+        Ok(output.trim().to_string())
+    }
+
+    /// Paths (relative to the root) of every file at revision `rev`
+    pub fn files_at(&self, rev: &str) -> Result<Vec<String>> {
+        check_revision(rev)?;
+        let output = self.run(["ls-tree", "-r", "-z", "--name-only", rev])?;
+        Ok(output.split('\0').filter(|p| !p.is_empty()).map(String::from).collect())
+    }
+
+    /// Content of the file at `path` (relative to the root) as of revision `rev`
+    pub fn show(&self, rev: &str, path: &str) -> Result<String> {
+        check_revision(rev)?;
+        self.run(["show", &format!("{}:{}", rev, path)])
+    }
+
+    /// Who last changed each line of the file at `path`
+    pub fn blame(&self, path: &Path) -> Result<Vec<BlameLine>> {
+        let output = self.run([
+            OsStr::new("blame"),
+            OsStr::new("--line-porcelain"),
+            OsStr::new("--"),
+            path.as_os_str(),
+        ])?;
+        Ok(parse_blame(&output))
+    }
+}
+
+/// Reject revisions git would take for options
+fn check_revision(rev: &str) -> Result<()> {
+    if rev.is_empty() || rev.starts_with('-') {
+        bail!("Invalid revision: '{}'", rev);
+    }
+    Ok(())
+}
+
+/// Parse `git status --porcelain=v1 -z` output into (path, status) pairs
+fn parse_status(output: &str) -> Vec<(String, GitStatus)> {
+    let mut statuses = Vec::new();
+    let mut entries = output.split('\0');
+
+    while let Some(entry) = entries.next() {
+        if entry.len() < 4 {
+            continue;
+        }
+        let (code, path) = entry.split_at(3);
+        let code = code.as_bytes();
+
+        // Renames and copies are followed by their original path
+        if matches!(code[0], b'R' | b'C') {
+            entries.next();
+        }
+
+        let status = if code.starts_with(b"??") {
+            GitStatus::Untracked
+        } else if code[1] != b' ' {
+            GitStatus::Modified
+        } else {
+            GitStatus::Staged
+        };
+        statuses.push((path.to_string(), status));
+    }
+    statuses
+}
+
+/// Parse `git blame --line-porcelain` output
+fn parse_blame(output: &str) -> Vec<BlameLine> {
+    let mut lines = Vec::new();
+    let mut commit = String::new();
+    let mut author = String::new();
+    let mut time = DateTime::<Utc>::UNIX_EPOCH;
+
+    for line in output.lines() {
+        if let Some(text) = line.strip_prefix('\t') {
+            lines.push(BlameLine {
+                commit: commit.clone(),
+                author: author.clone(),
+                time,
+                text: text.to_string(),
+            });
+        } else if let Some(name) = line.strip_prefix("author ") {
+            author = name.to_string();
+        } else if let Some(seconds) = line.strip_prefix("author-time ") {
+            time = seconds
+                .parse()
+                .ok()
+                .and_then(|s| DateTime::from_timestamp(s, 0))
+                .unwrap_or_default();
+        } else if let Some(hash) = line.split(' ').next()
+            && hash.len() == 40
+            && hash.chars().all(|c| c.is_ascii_hexdigit())
+        {
+            commit = hash[..8].to_string();
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_status() {
+        let output = "?? new.md\0 M changed.md\0M  staged.md\0MM both.md\0R  moved.md\0old.md\0";
+        assert_eq!(
+            parse_status(output),
+            vec![
+                ("new.md".to_string(), GitStatus::Untracked),
+                ("changed.md".to_string(), GitStatus::Modified),
+                ("staged.md".to_string(), GitStatus::Staged),
+                ("both.md".to_string(), GitStatus::Modified),
+                ("moved.md".to_string(), GitStatus::Staged),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_status_commit_and_blame() {
+        let dir = tempdir().unwrap();
+        let git = |args: &[&str]| {
+            let status = Command::new("git").arg("-C").arg(dir.path()).args(args).status();
+            status.is_ok_and(|s| s.success())
+        };
+        // Without git installed there is nothing to test
+        if !git(&["init", "-q"]) {
+            return;
+        }
+        git(&["config", "user.name", "Ada"]);
+        git(&["config", "user.email", "ada@example.com"]);
+
+        let repo = Repo::discover(dir.path()).unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let prompt = root.join("p.md");
+        std::fs::write(&prompt, "one\ntwo\n").unwrap();
+        assert_eq!(repo.statuses(&root).unwrap().get(&prompt), Some(&GitStatus::Untracked));
+
+        let summary = repo.commit(std::slice::from_ref(&prompt), "Add p").unwrap();
+        assert!(summary.ends_with("Add p"));
+        assert!(repo.statuses(&root).unwrap().is_empty());
+
+        std::fs::write(&prompt, "one\n2\n").unwrap();
+        assert_eq!(repo.statuses(&root).unwrap().get(&prompt), Some(&GitStatus::Modified));
+
+        let blame = repo.blame(&prompt).unwrap();
+        assert_eq!(blame.len(), 2);
+        assert_eq!(blame[0].author, "Ada");
+        assert!(!blame[0].is_uncommitted());
+        assert!(blame[1].is_uncommitted());
+        assert_eq!(blame[1].text, "2");
+
+        assert!(Repo::discover(&std::env::temp_dir().join("piemme-no-such-dir")).is_none());
+    }
+}
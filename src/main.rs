@@ -4,11 +4,16 @@
 //! and composing reusable prompts with vim-like keybindings.
 
 mod app;
+mod cli;
 mod config;
 mod engine;
 mod error;
 mod fs;
+mod git;
+mod mcp;
 mod models;
+mod search;
+mod server;
 mod tui;
 mod ui;
 
@@ -16,6 +21,12 @@ use anyhow::Result;
 use app::App;
 
 fn main() -> Result<()> {
+    // Subcommands run without the TUI
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        return cli::run(&args);
+    }
+
     // Set up panic handler to restore terminal on crash
     let original_hook = std::panic::take_hook();
     std::panic::set_hook(Box::new(move |panic_info| {
@@ -0,0 +1,392 @@
+//! Model Context Protocol server
+//!
+//! `piemme mcp` speaks JSON-RPC 2.0 over stdin/stdout, one message per line,
+//! exposing the prompt library through `prompts/list` and `prompts/get`.
+
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::io::{BufRead, Write};
+
+use crate::engine::{fill_placeholders, find_placeholders, has_commands, resolve_prompt};
+use crate::models::Prompt;
+
+/// Protocol revision implemented by this server
+const PROTOCOL_VERSION: &str = "2024-11-05";
+
+// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+/// Server-defined error: the prompt would run commands and the policy forbids it
+const COMMANDS_NOT_ALLOWED: i64 = -32001;
+
+/// An error to report back to the client
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// Serves prompts to an MCP client
+pub struct McpServer {
+    /// Prompts available to the client
+    pub prompts: Vec<Prompt>,
+    /// Whether prompts containing {{commands}} may be rendered (and their commands run)
+    pub allow_commands: bool,
+}
+
+impl McpServer {
+    /// Create a server for the given prompts
+    pub fn new(prompts: Vec<Prompt>, allow_commands: bool) -> Self {
+        Self {
+            prompts,
+            allow_commands,
+        }
+    }
+
+    /// Handle one incoming line, returning the response line (if the message expects one)
+    pub fn handle_message(&self, line: &str) -> Option<String> {
+        let message: Value = match serde_json::from_str(line) {
+            Ok(message) => message,
+            Err(e) => {
+                let error = RpcError::new(PARSE_ERROR, e.to_string());
+                return Some(error_response(Value::Null, error));
+            }
+        };
+
+        // Notifications carry no id and get no response
+        let id = message.get("id").cloned()?;
+
+        let Some(method) = message.get("method").and_then(Value::as_str) else {
+            return Some(error_response(id, RpcError::new(INVALID_REQUEST, "Missing method")));
+        };
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+        let response = match self.dispatch(method, &params) {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }).to_string(),
+            Err(error) => error_response(id, error),
+        };
+        Some(response)
+    }
+
+    fn dispatch(&self, method: &str, params: &Value) -> Result<Value, RpcError> {
+        match method {
+            "initialize" => Ok(json!({
+                "protocolVersion": PROTOCOL_VERSION,
+                "capabilities": { "prompts": { "listChanged": false } },
+                "serverInfo": { "name": "piemme", "version": env!("CARGO_PKG_VERSION") },
+            })),
+            "ping" => Ok(json!({})),
+            "prompts/list" => Ok(self.list_prompts()),
+            "prompts/get" => self.get_prompt(params),
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("Method not found: {}", method),
+            )),
+        }
+    }
+
+    /// Describe every loadable prompt, with its placeholders as arguments
+    fn list_prompts(&self) -> Value {
+        let prompts: Vec<Value> = self
+            .prompts
+            .iter()
+            .filter(|p| p.broken_error().is_none())
+            .map(|prompt| {
+                let arguments: Vec<Value> = find_placeholders(&self.resolve(prompt, false))
+                    .into_iter()
+                    .map(|name| json!({ "name": name, "required": true }))
+                    .collect();
+                json!({
+                    "name": prompt.name,
+                    "description": description(prompt),
+                    "arguments": arguments,
+                    "_meta": { "tags": prompt.tags },
+                })
+            })
+            .collect();
+
+        json!({ "prompts": prompts })
+    }
+
+    /// Render a prompt, filling placeholders from the request's arguments
+    fn get_prompt(&self, params: &Value) -> Result<Value, RpcError> {
+        let name = params
+            .get("name")
+            .and_then(Value::as_str)
+            .ok_or_else(|| RpcError::new(INVALID_PARAMS, "Missing prompt name"))?;
+        let prompt = self
+            .prompts
+            .iter()
+            .find(|p| p.name == name && p.broken_error().is_none())
+            .ok_or_else(|| RpcError::new(INVALID_PARAMS, format!("Prompt not found: {}", name)))?;
+        let arguments = params.get("arguments").cloned().unwrap_or(Value::Null);
+
+        // Check the policy before anything runs
+        let unexecuted = self.resolve(prompt, false);
+        if has_commands(&unexecuted) && !self.allow_commands {
+            return Err(RpcError::new(
+                COMMANDS_NOT_ALLOWED,
+                format!(
+                    "Prompt '{}' runs {{{{commands}}}}; set mcp_allow_commands: true to serve it",
+                    name
+                ),
+            ));
+        }
+
+        let missing: Vec<String> = find_placeholders(&unexecuted)
+            .into_iter()
+            .filter(|arg| arguments.get(arg).and_then(Value::as_str).is_none())
+            .collect();
+        if !missing.is_empty() {
+            return Err(RpcError::new(
+                INVALID_PARAMS,
+                format!("Missing argument(s): {}", missing.join(", ")),
+            ));
+        }
+
+        // Placeholders are filled last so argument values are never run as commands
+        let rendered = if self.allow_commands {
+            self.resolve(prompt, true)
+        } else {
+            unexecuted
+        };
+        let text = fill_placeholders(&rendered, |arg| {
+            arguments.get(arg).and_then(Value::as_str).map(str::to_string)
+        });
+
+        Ok(json!({
+            "description": description(prompt),
+            "messages": [{
+                "role": "user",
+                "content": { "type": "text", "text": text },
+            }],
+        }))
+    }
+
+    /// Resolve references (and optionally commands) in a prompt
+    fn resolve(&self, prompt: &Prompt, execute_commands: bool) -> String {
+        let get_content = |name: &str| -> Option<String> {
+            self.prompts.iter().find(|p| p.name == name).map(|p| p.content.clone())
+        };
+        resolve_prompt(&prompt.content, get_content, execute_commands).content
+    }
+}
+
+/// First non-empty line of the prompt, used as its description
+fn description(prompt: &Prompt) -> String {
+    prompt
+        .content
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or_default()
+        .trim_start_matches('#')
+        .trim()
+        .to_string()
+}
+
+fn error_response(id: Value, error: RpcError) -> String {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": error.code, "message": error.message },
+    })
+    .to_string()
+}
+
+/// Serve requests from `input` until it closes, reloading prompts for each request that reads them
+pub fn serve<R, W, L>(input: R, mut output: W, allow_commands: bool, load: L) -> Result<()>
+where
+    R: BufRead,
+    W: Write,
+    L: Fn() -> Result<Vec<Prompt>>,
+{
+    let mut server = McpServer::new(Vec::new(), allow_commands);
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match reload_prompts(&mut server, &line, &load) {
+            Ok(()) => server.handle_message(&line),
+            Err(response) => response,
+        };
+        if let Some(response) = response {
+            writeln!(output, "{}", response)?;
+            output.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// Reload the prompts if the message reads them, so edits show up without a restart; a load
+/// failure becomes the error response to send back (if the message expects one)
+fn reload_prompts<L>(server: &mut McpServer, line: &str, load: L) -> Result<(), Option<String>>
+where
+    L: Fn() -> Result<Vec<Prompt>>,
+{
+    let Ok(message) = serde_json::from_str::<Value>(line) else {
+        return Ok(());
+    };
+    let method = message.get("method").and_then(Value::as_str);
+    if !matches!(method, Some("prompts/list" | "prompts/get")) {
+        return Ok(());
+    }
+
+    match load() {
+        Ok(prompts) => {
+            server.prompts = prompts;
+            Ok(())
+        }
+        Err(e) => {
+            let error = RpcError::new(INTERNAL_ERROR, format!("Failed to load prompts: {:#}", e));
+            Err(message.get("id").cloned().map(|id| error_response(id, error)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(allow_commands: bool) -> McpServer {
+        let mut review = Prompt::with_content("# Review code\nCheck {{arg:file}} using [[style]]");
+        review.name = "review".to_string();
+        review.tags = vec!["coding".to_string()];
+        let mut style = Prompt::with_content("the {{arg:style}} guide");
+        style.name = "style".to_string();
+        let mut status = Prompt::with_content("Status:\n{{echo clean}}");
+        status.name = "status".to_string();
+        McpServer::new(vec![review, style, status], allow_commands)
+    }
+
+    fn call(server: &McpServer, request: Value) -> Value {
+        let response = server.handle_message(&request.to_string()).unwrap();
+        serde_json::from_str(&response).unwrap()
+    }
+
+    #[test]
+    fn test_initialize_and_list() {
+        let server = server(false);
+        let init = call(&server, json!({"jsonrpc": "2.0", "id": 1, "method": "initialize"}));
+        assert_eq!(init["result"]["serverInfo"]["name"], "piemme");
+
+        let notification = json!({"jsonrpc": "2.0", "method": "notifications/initialized"});
+        assert!(server.handle_message(&notification.to_string()).is_none());
+
+        let list = call(&server, json!({"jsonrpc": "2.0", "id": 2, "method": "prompts/list"}));
+        let review = &list["result"]["prompts"][0];
+        assert_eq!(review["name"], "review");
+        assert_eq!(review["description"], "Review code");
+        assert_eq!(review["_meta"]["tags"][0], "coding");
+        // Placeholders of referenced prompts become arguments too
+        let arguments: Vec<&str> = review["arguments"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|a| a["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(arguments, vec!["file", "style"]);
+    }
+
+    #[test]
+    fn test_get_fills_arguments() {
+        let server = server(false);
+        let response = call(
+            &server,
+            json!({"jsonrpc": "2.0", "id": 3, "method": "prompts/get", "params": {
+                "name": "review",
+                "arguments": {"file": "main.rs", "style": "{{echo no}}"},
+            }}),
+        );
+        let text = &response["result"]["messages"][0]["content"]["text"];
+        assert_eq!(text, "# Review code\nCheck main.rs using the {{echo no}} guide");
+
+        let missing = call(
+            &server,
+            json!({
+                "jsonrpc": "2.0", "id": 4, "method": "prompts/get", "params": {"name": "review"},
+            }),
+        );
+        assert_eq!(missing["error"]["code"], INVALID_PARAMS);
+    }
+
+    #[test]
+    fn test_commands_need_policy() {
+        let request = json!({
+            "jsonrpc": "2.0", "id": 5, "method": "prompts/get", "params": {"name": "status"},
+        });
+
+        let refused = call(&server(false), request.clone());
+        assert_eq!(refused["error"]["code"], COMMANDS_NOT_ALLOWED);
+
+        let allowed = call(&server(true), request);
+        assert_eq!(allowed["result"]["messages"][0]["content"]["text"], "Status:\nclean");
+    }
+
+    #[test]
+    fn test_errors() {
+        let server = server(false);
+        let unknown = call(&server, json!({"jsonrpc": "2.0", "id": 6, "method": "nope"}));
+        assert_eq!(unknown["error"]["code"], METHOD_NOT_FOUND);
+        let parse: Value = serde_json::from_str(&server.handle_message("{oops").unwrap()).unwrap();
+        assert_eq!(parse["error"]["code"], PARSE_ERROR);
+    }
+
+    #[test]
+    fn test_serve_stdio() {
+        let input = concat!(
+            r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}"#, "\n",
+            r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#, "\n",
+            r#"{"jsonrpc":"2.0","id":2,"method":"ping"}"#, "\n",
+        );
+        let mut output = Vec::new();
+        serve(input.as_bytes(), &mut output, false, || Ok(server(false).prompts)).unwrap();
+
+        let lines: Vec<&str> = std::str::from_utf8(&output).unwrap().lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[1].contains(r#""id":2"#));
+    }
+
+    #[test]
+    fn test_serve_reloads_only_for_prompt_requests() {
+        let input = concat!(
+            r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}"#, "\n",
+            r#"{"jsonrpc":"2.0","id":2,"method":"ping"}"#, "\n",
+            r#"{"jsonrpc":"2.0","id":3,"method":"prompts/list"}"#, "\n",
+            r#"{"jsonrpc":"2.0","id":4,"method":"ping"}"#, "\n",
+        );
+        let loads = std::cell::Cell::new(0);
+        let mut output = Vec::new();
+        let load = || {
+            loads.set(loads.get() + 1);
+            anyhow::bail!("library is unreadable")
+        };
+        serve(input.as_bytes(), &mut output, false, load).unwrap();
+        assert_eq!(loads.get(), 1);
+
+        // The failed load is reported and the session carries on
+        let responses: Vec<Value> = std::str::from_utf8(&output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(responses.len(), 4);
+        assert_eq!(responses[2]["id"], 3);
+        assert_eq!(responses[2]["error"]["code"], INTERNAL_ERROR);
+        assert!(responses[2]["error"]["message"].as_str().unwrap().contains("unreadable"));
+        assert_eq!(responses[3]["result"], json!({}));
+    }
+}
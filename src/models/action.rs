@@ -40,6 +40,8 @@ pub enum Action {
     UnarchivePrompt,
     /// Move prompt to a folder
     MoveToFolder,
+    /// Write frontmatter to a Markdown file that has none
+    AdoptPrompt,
 
     // Clipboard
     /// Copy rendered prompt to clipboard
@@ -54,6 +56,8 @@ pub enum Action {
     PreviousTagFilter,
     /// Filter by next tag
     NextTagFilter,
+    /// Open the tag filter popup
+    OpenTagFilter,
 
     // Search
     /// Open fuzzy search
@@ -70,6 +74,12 @@ pub enum Action {
     SearchDown,
     /// Confirm search selection (jump to prompt)
     ConfirmSearch,
+    /// Start naming the search query to save it as a view
+    SaveSearchAsView,
+    /// Save the search query under the typed view name
+    ConfirmSaveView,
+    /// Stop naming the view
+    CancelSaveView,
 
     // Editing
     /// Save current changes
@@ -88,6 +98,30 @@ pub enum Action {
     // Export
     /// Open export dialog
     Export,
+    /// Confirm export with the current options
+    ConfirmExport,
+    /// Cancel export popup
+    CancelExport,
+    /// Move focus to the next export option
+    ExportNextField,
+    /// Move focus to the previous export option
+    ExportPreviousField,
+    /// Change the focused export option forward
+    ExportOptionNext,
+    /// Change the focused export option backward
+    ExportOptionPrevious,
+
+    // External changes
+    /// Keep the editor's version of a prompt changed on disk
+    ConflictKeepMine,
+    /// Replace the editor's version with the one on disk
+    ConflictTakeTheirs,
+    /// Show or hide the diff between both versions
+    ConflictToggleDiff,
+    /// Scroll the conflict diff down
+    ConflictScrollDown,
+    /// Scroll the conflict diff up
+    ConflictScrollUp,
 
     // Settings
     /// Toggle safe mode
@@ -164,6 +198,36 @@ pub enum Action {
     CreateNewTag,
     /// Confirm new tag creation
     ConfirmNewTag,
+    /// Start renaming the selected tag on every prompt
+    RenameTag,
+    /// Confirm the new name of the tag being renamed
+    ConfirmRenameTag,
+    /// Start deleting the selected tag from every prompt
+    DeleteTag,
+    /// Delete the tag from every prompt
+    ConfirmDeleteTag,
+    /// Start merging the selected tag into another one
+    MergeTag,
+    /// Merge the tag being merged into the selected one
+    ConfirmMergeTag,
+    /// Cancel the tag rename, delete or merge in progress
+    CancelTagEdit,
+
+    // Tag filter popup actions
+    /// Apply the edited tag filter
+    ConfirmTagFilter,
+    /// Close the tag filter popup without applying it
+    CancelTagFilter,
+    /// Move up in tag filter popup
+    TagFilterUp,
+    /// Move down in tag filter popup
+    TagFilterDown,
+    /// Include, exclude or drop the selected tag
+    CycleTagFilterTag,
+    /// Switch between matching all and any included tag
+    ToggleTagFilterMatch,
+    /// Drop every tag from the edited filter
+    ClearTagFilter,
 
     // Folder selector actions
     /// Confirm folder selection
@@ -174,11 +238,79 @@ pub enum Action {
     FolderSelectorUp,
     /// Move down in folder selector
     FolderSelectorDown,
+    /// Expand the selected folder in the folder tree
+    FolderSelectorExpand,
+    /// Collapse the selected folder, or go to its parent
+    FolderSelectorCollapse,
+    /// Start renaming the selected folder
+    RenameFolder,
+    /// Confirm the new path of the folder being renamed
+    ConfirmRenameFolder,
+    /// Start deleting the selected folder
+    DeleteFolder,
+    /// Delete the folder, moving its prompts to the main list
+    DeleteFolderToRoot,
+    /// Delete the folder, moving its prompts to the archive
+    DeleteFolderToArchive,
+    /// Start merging the selected folder into another one
+    MergeFolder,
+    /// Merge the folder being merged into the selected one
+    ConfirmMergeFolder,
+    /// Cancel the rename, delete or merge in progress
+    CancelFolderEdit,
     /// Create new folder in selector
     CreateNewFolder,
     /// Confirm new folder creation
     ConfirmNewFolder,
 
+    // Backup picker actions
+    /// Open the backups of the selected prompt
+    OpenBackups,
+    /// Restore the selected backup
+    ConfirmBackupRestore,
+    /// Close the backup picker
+    CancelBackupPicker,
+    /// Move up in the backup picker
+    BackupPickerUp,
+    /// Move down in the backup picker
+    BackupPickerDown,
+
+    // Version history actions
+    /// Open the version history of the selected prompt
+    OpenHistory,
+    /// Restore the selected revision
+    RestoreRevision,
+    /// Close the version history
+    CloseHistory,
+    /// Move up in the revision list
+    HistoryUp,
+    /// Move down in the revision list
+    HistoryDown,
+    /// Compare other revisions with the selected one (or stop comparing)
+    HistoryToggleCompare,
+    /// Scroll the revision diff down
+    HistoryScrollDown,
+    /// Scroll the revision diff up
+    HistoryScrollUp,
+
+    // Git actions
+    /// Open the commit popup for the selected prompt
+    OpenCommitPopup,
+    /// Stage and commit with the entered message
+    ConfirmCommit,
+    /// Close the commit popup
+    CancelCommit,
+    /// Switch between committing the selected prompt and every changed prompt
+    ToggleCommitScope,
+    /// Show who last changed each line of the selected prompt
+    OpenBlame,
+    /// Close the blame view
+    CloseBlame,
+    /// Scroll the blame view down
+    BlameScrollDown,
+    /// Scroll the blame view up
+    BlameScrollUp,
+
     // Vim-style editor actions
     /// Enter Vim Insert mode (from Vim Normal)
     VimEnterInsert,
@@ -249,11 +381,138 @@ pub enum Action {
     /// Extend selection with Shift+Arrow (hybrid mode)
     ExtendSelection,
 
+    // Command palette actions
+    /// Open the command palette (fuzzy finder for actions)
+    OpenCommandPalette,
+    /// Run the selected action
+    ConfirmCommandPalette,
+    /// Close the command palette
+    CancelCommandPalette,
+    /// Move up in the command palette
+    CommandPaletteUp,
+    /// Move down in the command palette
+    CommandPaletteDown,
+
     // No action (used for unhandled keys)
     None,
 }
 
+/// Actions that can be bound to keys, with what they do
+const COMMANDS: &[(Action, &str)] = &[
+    (Action::MoveDown, "Move down"),
+    (Action::MoveUp, "Move up"),
+    (Action::GoToFirst, "Go to first prompt"),
+    (Action::GoToLast, "Go to last prompt"),
+    (Action::EnterInsertMode, "Enter editor (Vim Normal)"),
+    (Action::ExitMode, "Leave the current mode"),
+    (Action::TogglePreview, "Toggle preview mode"),
+    (Action::OpenArchive, "Open archive view"),
+    (Action::OpenFolder, "Open folder or saved view"),
+    (Action::NewPrompt, "Create new prompt"),
+    (Action::OpenRenamePopup, "Rename prompt"),
+    (Action::DeletePrompt, "Delete prompt"),
+    (Action::DuplicatePrompt, "Duplicate prompt"),
+    (Action::ArchivePrompt, "Archive prompt"),
+    (Action::UnarchivePrompt, "Unarchive prompt"),
+    (Action::MoveToFolder, "Move to folder"),
+    (Action::AdoptPrompt, "Adopt unmanaged file (add frontmatter)"),
+    (Action::CopyRendered, "Copy rendered to clipboard"),
+    (Action::CopyRaw, "Copy raw to clipboard"),
+    (Action::OpenTagSelector, "Open tag selector"),
+    (Action::PreviousTagFilter, "Filter by previous tag"),
+    (Action::NextTagFilter, "Filter by next tag"),
+    (Action::OpenTagFilter, "Filter by several tags"),
+    (Action::OpenSearch, "Open search"),
+    (Action::QuickOpen, "Quick open"),
+    (Action::QuickInsertReference, "Quick insert reference"),
+    (Action::Save, "Save"),
+    (Action::Undo, "Undo"),
+    (Action::Redo, "Redo"),
+    (Action::SelectAll, "Select all"),
+    (Action::CopySelection, "Copy selection to clipboard"),
+    (Action::Paste, "Paste from clipboard"),
+    (Action::Export, "Export prompt"),
+    (Action::ToggleSafeMode, "Toggle safe mode"),
+    (Action::ToggleFocus, "Toggle list/editor focus"),
+    (Action::OpenHelp, "Open/close this help"),
+    (Action::OpenCommandPalette, "Command palette"),
+    (Action::IncreaseLeftColumnWidth, "Widen the prompt list"),
+    (Action::DecreaseLeftColumnWidth, "Narrow the prompt list"),
+    (Action::Quit, "Quit application"),
+    (Action::OpenReferencePopup, "Insert reference"),
+    (Action::OpenFilePicker, "Insert file reference"),
+    (Action::OpenBackups, "Restore from backup"),
+    (Action::OpenHistory, "Version history (diff, restore)"),
+    (Action::OpenCommitPopup, "Git: stage & commit prompts"),
+    (Action::OpenBlame, "Git: blame prompt"),
+    (Action::VimEnterInsert, "Enter Insert mode"),
+    (Action::VimEnterInsertStart, "Insert at line start"),
+    (Action::VimEnterInsertEnd, "Append at line end"),
+    (Action::VimOpenBelow, "Open line below"),
+    (Action::VimOpenAbove, "Open line above"),
+    (Action::VimExitToNormal, "Return to Vim Normal"),
+    (Action::VimEnterVisual, "Visual mode (char)"),
+    (Action::VimEnterVisualLine, "Visual line mode"),
+    (Action::VimLeft, "Left"),
+    (Action::VimDown, "Down"),
+    (Action::VimUp, "Up"),
+    (Action::VimRight, "Right"),
+    (Action::VimLineStart, "Line start"),
+    (Action::VimFirstNonBlank, "First non-blank char"),
+    (Action::VimLineEnd, "Line end"),
+    (Action::VimWordForward, "Word forward"),
+    (Action::VimWordBackward, "Word backward"),
+    (Action::VimWordEnd, "End of word"),
+    (Action::VimGoToTop, "File start"),
+    (Action::VimGoToBottom, "File end"),
+    (Action::VimParagraphBackward, "Paragraph backward"),
+    (Action::VimParagraphForward, "Paragraph forward"),
+    (Action::VimDeleteChar, "Delete char or selection"),
+    (Action::VimDeleteToEnd, "Delete to end of line"),
+    (Action::VimDeleteLine, "Delete line"),
+    (Action::VimChangeToEnd, "Change to end of line"),
+    (Action::VimChangeLine, "Change line or selection"),
+    (Action::VimYank, "Yank (copy) line or selection"),
+    (Action::VimPut, "Put after"),
+    (Action::VimPutBefore, "Put before"),
+    (Action::VimStartDelete, "Delete with a motion (dd: line)"),
+    (Action::VimStartChange, "Change with a motion (cc: line)"),
+    (Action::VimStartYank, "Yank with a motion (yy: line)"),
+    (Action::ExtendSelection, "Extend selection"),
+];
+
 impl Action {
+    /// Actions that can be bound to keys, in a stable order
+    pub fn commands() -> impl Iterator<Item = &'static Action> {
+        COMMANDS.iter().map(|(action, _)| action)
+    }
+
+    /// Find a bindable action by its snake_case name (e.g. `toggle_safe_mode`)
+    pub fn from_name(name: &str) -> Option<Action> {
+        Self::commands().find(|action| action.name() == name).cloned()
+    }
+
+    /// The snake_case name of this action, as written in the config
+    pub fn name(&self) -> String {
+        let mut name = String::new();
+        for c in format!("{:?}", self).chars() {
+            if c.is_ascii_uppercase() && !name.is_empty() {
+                name.push('_');
+            }
+            name.push(c.to_ascii_lowercase());
+        }
+        name
+    }
+
+    /// What this action does, for the help overlay
+    pub fn description(&self) -> &'static str {
+        COMMANDS
+            .iter()
+            .find(|(action, _)| action == self)
+            .map(|(_, description)| *description)
+            .unwrap_or("")
+    }
+
     /// Check if this action requires confirmation
     pub fn requires_confirmation(&self) -> bool {
         matches!(
@@ -270,3 +529,19 @@ impl Action {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names_round_trip() {
+        assert_eq!(Action::ToggleSafeMode.name(), "toggle_safe_mode");
+        assert_eq!(Action::from_name("vim_go_to_top"), Some(Action::VimGoToTop));
+        assert_eq!(Action::from_name("confirm_search"), None);
+        for action in Action::commands() {
+            assert_eq!(Action::from_name(&action.name()).as_ref(), Some(action));
+            assert!(!action.description().is_empty());
+        }
+    }
+}
@@ -4,12 +4,16 @@ mod action;
 mod mode;
 pub mod prompt;
 mod state;
+mod tag_filter;
+pub mod tags;
 
 pub use action::Action;
 pub use mode::{EditorMode, Mode, VimOperator};
-pub use prompt::Prompt;
+pub use prompt::{Prompt, PromptStatus};
 pub use state::{
-    AppState, ConfirmDialog, FilePickerPopupState, FolderSelectorMode, FolderSelectorState, Notification,
-    NotificationLevel, PendingAction, PopupType, ReferencePopupState, RenamePopupState,
-    SearchPopupState, SearchResult, TagSelectorState,
+    AppState, BackupPickerState, BlameState, CommandPaletteState, CommitPopupState, ConfirmDialog, ConflictState, ExportField, ExportPopupState, FilePickerPopupState, FolderEdit, FolderSelectorMode, FolderSelectorState, HistoryState, Notification,
+    NotificationLevel, PaletteCommand, PendingAction, PopupType, ReferencePopupState, RenamePopupState,
+    SearchPopupState, SearchResult, TagFilterPopupState, TagSelectorEdit, TagSelectorState,
 };
+pub use tag_filter::{TagFilter, TagFilterState};
+pub use tags::{is_tag_or_child, normalize_tag, tag_tree, TagEdit};
@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 /// A prompt with its metadata
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Prompt {
     /// Unique identifier for the prompt
     pub id: Uuid,
@@ -22,6 +22,22 @@ pub struct Prompt {
     pub created: DateTime<Utc>,
     /// When the prompt was last modified
     pub modified: DateTime<Utc>,
+    /// Whether the file on disk has valid piemme frontmatter
+    #[serde(skip)]
+    pub status: PromptStatus,
+}
+
+/// How a prompt's file relates to the piemme file format
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PromptStatus {
+    /// Valid frontmatter
+    #[default]
+    Managed,
+    /// Plain Markdown without frontmatter; metadata is generated until adopted
+    Unmanaged,
+    /// Frontmatter present but unreadable; content holds the raw file for repair
+    Broken { error: String },
 }
 
 impl Prompt {
@@ -35,6 +51,7 @@ impl Prompt {
             tags: Vec::new(),
             created: now,
             modified: now,
+            status: PromptStatus::Managed,
         }
     }
 
@@ -72,9 +89,22 @@ impl Prompt {
         }
     }
 
-    /// Check if the prompt has a specific tag
+    /// Check if the prompt has a specific tag, or a tag below it (`lang/rust` for `lang`)
     pub fn has_tag(&self, tag: &str) -> bool {
-        self.tags.iter().any(|t| t == tag)
+        self.tags.iter().any(|t| super::is_tag_or_child(t, tag))
+    }
+
+    /// Check if the file lacks frontmatter and can be adopted
+    pub fn is_unmanaged(&self) -> bool {
+        self.status == PromptStatus::Unmanaged
+    }
+
+    /// Get the frontmatter error if the file is broken
+    pub fn broken_error(&self) -> Option<&str> {
+        match &self.status {
+            PromptStatus::Broken { error } => Some(error),
+            _ => None,
+        }
     }
 
     /// Get the first line of content (for preview)
@@ -152,11 +182,17 @@ pub fn generate_name_from_content(content: &str) -> String {
         return String::new();
     }
 
-    // Take first ~20 chars, convert to lowercase
-    let name: String = first_line
-        .chars()
-        .take(20)
-        .collect::<String>()
+    // Take first ~20 chars and normalise them
+    let start: String = first_line.chars().take(20).collect();
+    normalize_name(&start)
+}
+
+/// Normalise arbitrary text (e.g. a file name) into a valid prompt name
+///
+/// Lowercases, turns spaces and dashes into underscores, drops other special
+/// characters and collapses repeated underscores. May return an empty string.
+pub fn normalize_name(text: &str) -> String {
+    let name: String = text
         .to_lowercase()
         // Replace spaces and common separators with underscores
         .chars()
@@ -250,6 +286,14 @@ mod tests {
         assert_eq!(generate_name_from_content("Hi"), "hi");
     }
 
+    #[test]
+    fn test_normalize_name() {
+        assert_eq!(normalize_name("Code Review"), "code_review");
+        assert_eq!(normalize_name("  API -- Design (v2) "), "api_design_v2");
+        assert_eq!(normalize_name("Ünïcode"), "ncode");
+        assert_eq!(normalize_name("!!!"), "");
+    }
+
     #[test]
     fn test_make_unique_name() {
         let existing = vec!["test", "test_1", "test_2"];
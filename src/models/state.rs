@@ -1,6 +1,11 @@
 //! Application state management
 
-use super::{Action, EditorMode, Mode, Prompt};
+use super::{normalize_tag, tag_tree, Action, EditorMode, Mode, Prompt, TagEdit, TagFilter, TagFilterState};
+use crate::fs::{normalize_folder_path, parent_folder, Backup, ExportFormat, ExportOptions, Revision};
+use crate::git::{BlameLine, GitStatus};
+use nucleo::pattern::{CaseMatching, Normalization, Pattern};
+use nucleo::{Config as NucleoConfig, Matcher, Utf32Str};
+use std::collections::{HashMap, HashSet};
 
 /// The complete application state
 #[derive(Debug)]
@@ -13,8 +18,10 @@ pub struct AppState {
     pub selected_index: usize,
     /// Current folder path (None = root)
     pub current_folder: Option<String>,
-    /// Currently active tag filter (None = show all)
-    pub tag_filter: Option<String>,
+    /// Saved search listed instead of a folder, if any
+    pub current_view: Option<String>,
+    /// Tags the listed prompts must (or must not) have
+    pub tag_filter: TagFilter,
     /// All available tags
     pub all_tags: Vec<String>,
     /// Whether safe mode is enabled
@@ -49,12 +56,36 @@ pub struct AppState {
     pub reference_popup: Option<ReferencePopupState>,
     /// Tag selector popup state
     pub tag_selector: Option<TagSelectorState>,
+    /// Tag filter popup state
+    pub tag_filter_popup: Option<TagFilterPopupState>,
     /// Folder selector popup state
     pub folder_selector: Option<FolderSelectorState>,
     /// Search popup state
     pub search_popup: Option<SearchPopupState>,
     /// File picker popup state (for [[file:...]] insertion)
     pub file_picker: Option<FilePickerPopupState>,
+    /// Export popup state
+    pub export_popup: Option<ExportPopupState>,
+    /// Conflict prompt for a file changed on disk while being edited
+    pub conflict_popup: Option<ConflictState>,
+    /// Backup picker for restoring an earlier version of a prompt
+    pub backup_picker: Option<BackupPickerState>,
+    /// Version history of a prompt
+    pub history: Option<HistoryState>,
+    /// Git status of the listed prompts with changes, by name (empty outside a repository)
+    pub git_status: HashMap<String, GitStatus>,
+    /// Whether each pinned reference (`target@rev`) of the selected prompt exists at its
+    /// revision, checked when the content changes
+    pub pinned_refs: HashMap<String, bool>,
+    /// Content of the selected prompt with its references resolved, kept while in Preview
+    /// mode so drawing does not resolve it again on every frame
+    pub resolved_preview: Option<String>,
+    /// Commit popup state
+    pub commit_popup: Option<CommitPopupState>,
+    /// Blame view of a prompt
+    pub blame: Option<BlameState>,
+    /// Command palette state
+    pub command_palette: Option<CommandPaletteState>,
     /// Editor sub-mode (Vim Normal/Insert/Visual)
     pub editor_mode: EditorMode,
     /// Visual mode anchor position (row, col) for selection start
@@ -73,7 +104,8 @@ impl AppState {
             prompts: Vec::new(),
             selected_index: 0,
             current_folder: None,
-            tag_filter: None,
+            current_view: None,
+            tag_filter: TagFilter::default(),
             all_tags: Vec::new(),
             safe_mode: true,
             should_quit: false,
@@ -91,9 +123,20 @@ impl AppState {
             rename_popup: None,
             reference_popup: None,
             tag_selector: None,
+            tag_filter_popup: None,
             folder_selector: None,
             search_popup: None,
             file_picker: None,
+            export_popup: None,
+            conflict_popup: None,
+            backup_picker: None,
+            history: None,
+            git_status: HashMap::new(),
+            pinned_refs: HashMap::new(),
+            resolved_preview: None,
+            commit_popup: None,
+            blame: None,
+            command_palette: None,
             editor_mode: EditorMode::VimNormal,
             visual_anchor: None,
             yank_buffer: String::new(),
@@ -287,6 +330,12 @@ pub enum PendingAction {
     DeletePrompt { name: String },
     /// Permanently delete from archive
     PermanentDelete { name: String },
+    /// Export prompts with command output (safe mode confirmation)
+    ExportPrompts {
+        names: Vec<String>,
+        options: ExportOptions,
+        dest: String,
+    },
     /// Execute commands and copy to clipboard (safe mode confirmation)
     /// Contains: commands to execute, content with refs resolved (commands not yet executed)
     ExecuteCommandsAndCopy {
@@ -386,12 +435,16 @@ impl ReferencePopupState {
 pub struct SearchResult {
     /// The prompt name
     pub name: String,
-    /// Preview of the content (first line)
+    /// Preview of the content: the best matching line, or the first line
     pub preview: String,
-    /// Fuzzy match score (higher is better)
-    pub score: u32,
+    /// Line number of the preview when it is a content match (counting from 0)
+    pub line: Option<usize>,
+    /// Character indices that matched in the preview
+    pub preview_match_indices: Vec<usize>,
     /// Character indices that matched in the name
     pub name_match_indices: Vec<usize>,
+    /// Where the prompt lives: "prompts", "archive" or "folders/<path>"
+    pub location: String,
 }
 
 /// State for the fuzzy search popup
@@ -401,10 +454,20 @@ pub struct SearchPopupState {
     pub query: String,
     /// Selected index in search results
     pub selected_index: usize,
-    /// Search results (sorted by relevance)
+    /// Search results (sorted by relevance), the best ones when there are many
     pub results: Vec<SearchResult>,
+    /// Number of prompts matching, including those not in `results`
+    pub total: usize,
+    /// Whether matching is still in progress
+    pub searching: bool,
     /// Scroll offset for results list
     pub scroll_offset: usize,
+    /// First query term that could not be understood
+    pub error: Option<String>,
+    /// Content of the selected result, for the preview pane
+    pub selected_content: String,
+    /// Name being typed to save the query as a view, if saving
+    pub view_name: Option<String>,
 }
 
 impl SearchPopupState {
@@ -413,7 +476,12 @@ impl SearchPopupState {
             query: String::new(),
             selected_index: 0,
             results: Vec::new(),
+            total: 0,
+            searching: false,
             scroll_offset: 0,
+            error: None,
+            selected_content: String::new(),
+            view_name: None,
         }
     }
 
@@ -473,7 +541,8 @@ pub struct TagSelectorState {
     pub selected_index: usize,
     /// All available tags
     pub all_tags: Vec<String>,
-    /// Filtered tags (cached)
+    /// Filtered tags (cached), as a tree: every tag followed by the tags below it, including
+    /// parents only used through their children
     pub filtered_tags: Vec<String>,
     /// Tags currently assigned to the prompt
     pub prompt_tags: Vec<String>,
@@ -481,34 +550,46 @@ pub struct TagSelectorState {
     pub creating_new: bool,
     /// Input for new tag name
     pub new_tag_input: String,
+    /// Change to a tag on every prompt in progress, if any
+    pub edit: Option<TagSelectorEdit>,
+}
+
+/// A change to a tag on every prompt, started from the tag selector
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TagSelectorEdit {
+    /// Typing a new name for the tag
+    Rename { tag: String },
+    /// Confirming the tag is removed from every prompt
+    Delete { tag: String },
+    /// Choosing the tag to merge this one into
+    Merge { tag: String },
 }
 
 impl TagSelectorState {
     pub fn new(all_tags: Vec<String>, prompt_tags: Vec<String>) -> Self {
-        let filtered_tags = all_tags.clone();
-        Self {
+        let mut state = Self {
             filter: String::new(),
             selected_index: 0,
             all_tags,
-            filtered_tags,
+            filtered_tags: Vec::new(),
             prompt_tags,
             creating_new: false,
             new_tag_input: String::new(),
-        }
+            edit: None,
+        };
+        state.update_filter();
+        state
     }
 
     /// Update the filter and refresh filtered results
     pub fn update_filter(&mut self) {
-        if self.filter.is_empty() {
-            self.filtered_tags = self.all_tags.clone();
-        } else {
-            let filter_lower = self.filter.to_lowercase();
-            self.filtered_tags = self.all_tags
-                .iter()
-                .filter(|tag| tag.to_lowercase().contains(&filter_lower))
-                .cloned()
-                .collect();
-        }
+        let filter_lower = self.filter.to_lowercase();
+        let matching: Vec<String> = self.all_tags
+            .iter()
+            .filter(|tag| tag.to_lowercase().contains(&filter_lower))
+            .cloned()
+            .collect();
+        self.filtered_tags = tag_tree(&matching).into_iter().map(|node| node.tag).collect();
         // Reset selection if out of bounds
         if self.selected_index >= self.filtered_tags.len() {
             self.selected_index = 0;
@@ -571,9 +652,56 @@ impl TagSelectorState {
         self.new_tag_input.clear();
     }
     
+    /// Whether a tag name is being typed (creating or renaming)
+    pub fn is_typing_name(&self) -> bool {
+        self.creating_new || matches!(self.edit, Some(TagSelectorEdit::Rename { .. }))
+    }
+
+    /// Start an operation on the selected tag; false if none is selected
+    pub fn start_edit(&mut self, edit: fn(String) -> TagSelectorEdit) -> bool {
+        let Some(tag) = self.selected_tag().map(str::to_string) else {
+            return false;
+        };
+        let edit = edit(tag);
+        if let TagSelectorEdit::Rename { tag } = &edit {
+            self.new_tag_input = tag.clone();
+        }
+        self.edit = Some(edit);
+        true
+    }
+
+    /// Cancel the tag operation in progress
+    pub fn cancel_edit(&mut self) {
+        if matches!(self.edit.take(), Some(TagSelectorEdit::Rename { .. })) {
+            self.new_tag_input.clear();
+        }
+    }
+
+    /// Follow a tag change made on every prompt, selecting the tag it produced (if any)
+    pub fn apply_edit(&mut self, edit: &TagEdit) {
+        if let Some(tags) = edit.apply(&self.all_tags) {
+            self.all_tags = tags;
+            self.all_tags.sort();
+        }
+        if let Some(tags) = edit.apply(&self.prompt_tags) {
+            self.prompt_tags = tags;
+        }
+        self.edit = None;
+        self.new_tag_input.clear();
+        self.update_filter();
+
+        let selected = match edit {
+            TagEdit::Rename { to: tag, .. } | TagEdit::Merge { into: tag, .. } => Some(tag),
+            TagEdit::Delete(_) => None,
+        };
+        if let Some(pos) = selected.and_then(|tag| self.filtered_tags.iter().position(|t| t == tag)) {
+            self.selected_index = pos;
+        }
+    }
+
     /// Confirm creating a new tag, returns the new tag name if valid
     pub fn confirm_new_tag(&mut self) -> Option<String> {
-        let tag = self.new_tag_input.trim().to_lowercase().replace(' ', "_");
+        let tag = normalize_tag(&self.new_tag_input);
         if tag.is_empty() {
             return None;
         }
@@ -593,6 +721,91 @@ impl TagSelectorState {
     }
 }
 
+/// State for the tag filter popup
+#[derive(Debug, Clone)]
+pub struct TagFilterPopupState {
+    /// Search/filter input
+    pub filter: String,
+    /// Selected index in filtered results
+    pub selected_index: usize,
+    /// All available tags
+    pub all_tags: Vec<String>,
+    /// Filtered tags (cached), as a tree like the tag selector's
+    pub filtered_tags: Vec<String>,
+    /// Filter being edited, applied on confirm
+    pub tag_filter: TagFilter,
+}
+
+impl TagFilterPopupState {
+    pub fn new(all_tags: Vec<String>, tag_filter: TagFilter) -> Self {
+        let mut state = Self {
+            filter: String::new(),
+            selected_index: 0,
+            all_tags,
+            filtered_tags: Vec::new(),
+            tag_filter,
+        };
+        state.update_filter();
+        state
+    }
+
+    /// Update the filter and refresh filtered results
+    pub fn update_filter(&mut self) {
+        let filter_lower = self.filter.to_lowercase();
+        let matching: Vec<String> = self.all_tags
+            .iter()
+            .filter(|tag| tag.to_lowercase().contains(&filter_lower))
+            .cloned()
+            .collect();
+        self.filtered_tags = tag_tree(&matching).into_iter().map(|node| node.tag).collect();
+        if self.selected_index >= self.filtered_tags.len() {
+            self.selected_index = 0;
+        }
+    }
+
+    /// Get the currently selected tag
+    pub fn selected_tag(&self) -> Option<&str> {
+        self.filtered_tags.get(self.selected_index).map(|s| s.as_str())
+    }
+
+    /// How a tag takes part in the edited filter
+    pub fn tag_state(&self, tag: &str) -> TagFilterState {
+        self.tag_filter.state(tag)
+    }
+
+    /// Include, exclude or drop the selected tag
+    pub fn cycle_selected_tag(&mut self) {
+        if let Some(tag) = self.selected_tag().map(str::to_string) {
+            self.tag_filter.cycle(&tag);
+        }
+    }
+
+    /// Switch between matching all and any of the included tags
+    pub fn toggle_match_any(&mut self) {
+        self.tag_filter.match_any = !self.tag_filter.match_any;
+    }
+
+    /// Drop every tag from the filter
+    pub fn clear(&mut self) {
+        self.tag_filter = TagFilter { match_any: self.tag_filter.match_any, ..TagFilter::default() };
+    }
+
+    /// Move selection down
+    pub fn select_next(&mut self) {
+        if !self.filtered_tags.is_empty() {
+            self.selected_index = (self.selected_index + 1) % self.filtered_tags.len();
+        }
+    }
+
+    /// Move selection up
+    pub fn select_previous(&mut self) {
+        if !self.filtered_tags.is_empty() {
+            self.selected_index =
+                (self.selected_index + self.filtered_tags.len() - 1) % self.filtered_tags.len();
+        }
+    }
+}
+
 /// State for the file picker popup (for [[file:...]] insertion)
 #[derive(Debug, Clone)]
 pub struct FilePickerPopupState {
@@ -721,6 +934,344 @@ impl FilePickerPopupState {
     }
 }
 
+/// Field focused in the export popup
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportField {
+    Format,
+    Scope,
+    Commands,
+    Destination,
+}
+
+impl ExportField {
+    const ORDER: [ExportField; 4] = [
+        ExportField::Format,
+        ExportField::Scope,
+        ExportField::Commands,
+        ExportField::Destination,
+    ];
+}
+
+/// State for the export popup
+#[derive(Debug, Clone)]
+pub struct ExportPopupState {
+    /// Selected output format
+    pub format: ExportFormat,
+    /// Export all prompts in the current (filtered) view instead of just the selected one
+    pub all_filtered: bool,
+    /// Whether to execute {{commands}} and include their output
+    pub include_command_output: bool,
+    /// Destination file or directory
+    pub destination: String,
+    /// Currently focused field
+    pub focused: ExportField,
+}
+
+impl ExportPopupState {
+    pub fn new(format: ExportFormat) -> Self {
+        Self {
+            format,
+            all_filtered: false,
+            include_command_output: false,
+            destination: "exports".to_string(),
+            focused: ExportField::Format,
+        }
+    }
+
+    /// Get the export options for the current selection
+    pub fn options(&self) -> ExportOptions {
+        ExportOptions {
+            format: self.format,
+            include_command_output: self.include_command_output && self.format.is_rendered(),
+        }
+    }
+
+    /// Move focus to the next field
+    pub fn focus_next(&mut self) {
+        let pos = ExportField::ORDER.iter().position(|f| *f == self.focused).unwrap_or(0);
+        self.focused = ExportField::ORDER[(pos + 1) % ExportField::ORDER.len()];
+    }
+
+    /// Move focus to the previous field
+    pub fn focus_previous(&mut self) {
+        let pos = ExportField::ORDER.iter().position(|f| *f == self.focused).unwrap_or(0);
+        let len = ExportField::ORDER.len();
+        self.focused = ExportField::ORDER[(pos + len - 1) % len];
+    }
+
+    /// Change the value of the focused option (format cycles, toggles flip)
+    pub fn change_option(&mut self, forward: bool) {
+        match self.focused {
+            ExportField::Format => {
+                let formats = ExportFormat::ALL;
+                let pos = formats.iter().position(|f| *f == self.format).unwrap_or(0);
+                let next = if forward {
+                    (pos + 1) % formats.len()
+                } else {
+                    (pos + formats.len() - 1) % formats.len()
+                };
+                self.format = formats[next];
+            }
+            ExportField::Scope => self.all_filtered = !self.all_filtered,
+            ExportField::Commands => self.include_command_output = !self.include_command_output,
+            ExportField::Destination => {}
+        }
+    }
+}
+
+/// State for the conflict prompt shown when the edited prompt changes on disk
+#[derive(Debug, Clone)]
+pub struct ConflictState {
+    /// The editor's unsaved content
+    pub mine: String,
+    /// The prompt as it is now on disk
+    pub theirs: Prompt,
+    /// Whether the diff between both versions is shown
+    pub show_diff: bool,
+    /// Scroll offset of the diff
+    pub scroll: u16,
+}
+
+impl ConflictState {
+    pub fn new(mine: String, theirs: Prompt) -> Self {
+        Self {
+            mine,
+            theirs,
+            show_diff: false,
+            scroll: 0,
+        }
+    }
+}
+
+/// State for the backup picker popup
+#[derive(Debug, Clone)]
+pub struct BackupPickerState {
+    /// Name of the prompt the backups belong to
+    pub prompt_name: String,
+    /// Backups, newest first, with the first line of each
+    pub backups: Vec<(Backup, String)>,
+    /// Selected index
+    pub selected_index: usize,
+}
+
+impl BackupPickerState {
+    pub fn new(prompt_name: String, backups: Vec<(Backup, String)>) -> Self {
+        Self {
+            prompt_name,
+            backups,
+            selected_index: 0,
+        }
+    }
+
+    /// Get the selected backup
+    pub fn selected(&self) -> Option<&Backup> {
+        self.backups.get(self.selected_index).map(|(backup, _)| backup)
+    }
+
+    pub fn select_next(&mut self) {
+        if self.selected_index + 1 < self.backups.len() {
+            self.selected_index += 1;
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected_index = self.selected_index.saturating_sub(1);
+    }
+}
+
+/// State for the version history popup
+#[derive(Debug, Clone)]
+pub struct HistoryState {
+    /// Name of the prompt the revisions belong to
+    pub prompt_name: String,
+    /// Revisions, newest first
+    pub revisions: Vec<Revision>,
+    /// Selected index
+    pub selected_index: usize,
+    /// Revision the selected one is compared with; by default the one before it
+    pub compare_index: Option<usize>,
+    /// Scroll offset of the diff
+    pub diff_scroll: u16,
+}
+
+impl HistoryState {
+    pub fn new(prompt_name: String, revisions: Vec<Revision>) -> Self {
+        Self {
+            prompt_name,
+            revisions,
+            selected_index: 0,
+            compare_index: None,
+            diff_scroll: 0,
+        }
+    }
+
+    /// Get the selected revision
+    pub fn selected(&self) -> Option<&Revision> {
+        self.revisions.get(self.selected_index)
+    }
+
+    /// Get the revision the selected one is compared with, if any
+    pub fn base(&self) -> Option<&Revision> {
+        let index = self.compare_index.unwrap_or(self.selected_index + 1);
+        self.revisions.get(index)
+    }
+
+    pub fn select_next(&mut self) {
+        if self.selected_index + 1 < self.revisions.len() {
+            self.selected_index += 1;
+            self.diff_scroll = 0;
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        if self.selected_index > 0 {
+            self.selected_index -= 1;
+            self.diff_scroll = 0;
+        }
+    }
+
+    /// Pin the selected revision as the comparison base, or unpin it
+    pub fn toggle_compare(&mut self) {
+        self.compare_index = match self.compare_index {
+            Some(index) if index == self.selected_index => None,
+            _ => Some(self.selected_index),
+        };
+        self.diff_scroll = 0;
+    }
+}
+
+/// State for the commit popup
+#[derive(Debug, Clone)]
+pub struct CommitPopupState {
+    /// Commit message being typed
+    pub message: String,
+    /// Name of the selected prompt
+    pub selected_name: String,
+    /// Names of the listed prompts with changes
+    pub changed: Vec<String>,
+    /// Whether every changed prompt is committed rather than only the selected one
+    pub all_changed: bool,
+}
+
+impl CommitPopupState {
+    pub fn new(selected_name: String, changed: Vec<String>) -> Self {
+        // Nothing to commit for the selected prompt: offer the others
+        let all_changed = !changed.contains(&selected_name);
+        Self {
+            message: String::new(),
+            selected_name,
+            changed,
+            all_changed,
+        }
+    }
+
+    /// Names of the prompts that will be committed
+    pub fn names(&self) -> Vec<&str> {
+        if self.all_changed {
+            self.changed.iter().map(String::as_str).collect()
+        } else {
+            vec![self.selected_name.as_str()]
+        }
+    }
+}
+
+/// An action listed in the command palette
+#[derive(Debug, Clone)]
+pub struct PaletteCommand {
+    pub action: Action,
+    /// Keys currently bound to the action, e.g. `Ctrl+h` (empty when it has none)
+    pub keys: String,
+}
+
+impl PaletteCommand {
+    /// Text the filter matches: the description and the config name
+    fn haystack(&self) -> String {
+        format!("{} {}", self.action.description(), self.action.name())
+    }
+}
+
+/// State for the command palette (fuzzy finder for actions)
+#[derive(Debug, Clone)]
+pub struct CommandPaletteState {
+    /// Search/filter input
+    pub filter: String,
+    /// Selected index in filtered results
+    pub selected_index: usize,
+    /// Actions available in the mode the palette was opened in
+    pub commands: Vec<PaletteCommand>,
+    /// Indices into `commands` matching the filter, best first
+    pub filtered: Vec<usize>,
+}
+
+impl CommandPaletteState {
+    pub fn new(commands: Vec<PaletteCommand>) -> Self {
+        let filtered = (0..commands.len()).collect();
+        Self {
+            filter: String::new(),
+            selected_index: 0,
+            commands,
+            filtered,
+        }
+    }
+
+    /// Update the filter and refresh filtered results, best match first
+    pub fn update_filter(&mut self) {
+        let pattern = Pattern::parse(&self.filter, CaseMatching::Ignore, Normalization::Smart);
+        let mut matcher = Matcher::new(NucleoConfig::DEFAULT);
+        let mut buf = Vec::new();
+        let mut scored: Vec<(usize, u32)> = self
+            .commands
+            .iter()
+            .enumerate()
+            .filter_map(|(i, command)| {
+                let haystack = command.haystack();
+                let score = pattern.score(Utf32Str::new(&haystack, &mut buf), &mut matcher)?;
+                Some((i, score))
+            })
+            .collect();
+        // Stable: equal scores keep the palette order
+        scored.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+        self.filtered = scored.into_iter().map(|(i, _)| i).collect();
+        self.selected_index = 0;
+    }
+
+    /// The command under the cursor
+    pub fn selected(&self) -> Option<&PaletteCommand> {
+        self.filtered
+            .get(self.selected_index)
+            .map(|&i| &self.commands[i])
+    }
+
+    /// Move selection down
+    pub fn select_next(&mut self) {
+        if !self.filtered.is_empty() {
+            self.selected_index = (self.selected_index + 1) % self.filtered.len();
+        }
+    }
+
+    /// Move selection up
+    pub fn select_previous(&mut self) {
+        if !self.filtered.is_empty() {
+            if self.selected_index == 0 {
+                self.selected_index = self.filtered.len() - 1;
+            } else {
+                self.selected_index -= 1;
+            }
+        }
+    }
+}
+
+/// State for the blame view
+#[derive(Debug, Clone)]
+pub struct BlameState {
+    /// Name of the blamed prompt
+    pub prompt_name: String,
+    /// Lines of the prompt file with their last change
+    pub lines: Vec<BlameLine>,
+    /// Scroll offset
+    pub scroll: u16,
+}
+
 /// State for the folder selector popup
 #[derive(Debug, Clone)]
 pub struct FolderSelectorState {
@@ -728,18 +1279,37 @@ pub struct FolderSelectorState {
     pub filter: String,
     /// Selected index in filtered results
     pub selected_index: usize,
-    /// All available folders
+    /// All available folders, nested ones as paths like `work/backend`
     pub all_folders: Vec<String>,
-    /// Filtered folders (cached)
+    /// Listed folders (cached): the visible part of the tree, or the matches of the filter
     pub filtered_folders: Vec<String>,
+    /// Folders whose subfolders are shown in the tree
+    pub expanded: HashSet<String>,
+    /// Saved searches (name, query), listed after the folders
+    pub views: Vec<(String, String)>,
+    /// Listed views (cached): all of them, or the matches of the filter
+    pub filtered_views: Vec<(String, String)>,
     /// Whether we're in "new folder" input mode
     pub creating_new: bool,
-    /// Input for new folder name
+    /// Input for new folder name (also the new path while renaming)
     pub new_folder_input: String,
+    /// Folder operation in progress, if any
+    pub edit: Option<FolderEdit>,
     /// Mode: 'open' to navigate into folder, 'move' to move a prompt
     pub mode: FolderSelectorMode,
 }
 
+/// Operation on an existing folder, started from the folder selector
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FolderEdit {
+    /// Typing a new path for the folder
+    Rename { folder: String },
+    /// Choosing where the folder's prompts go before it is deleted
+    Delete { folder: String },
+    /// Choosing the folder to merge this one into
+    Merge { folder: String },
+}
+
 /// Mode for the folder selector
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FolderSelectorMode {
@@ -751,24 +1321,102 @@ pub enum FolderSelectorMode {
 
 impl FolderSelectorState {
     pub fn new(all_folders: Vec<String>, mode: FolderSelectorMode) -> Self {
-        let mut filtered_folders = vec!["(root)".to_string()];
-        filtered_folders.extend(all_folders.clone());
-        Self {
+        let mut state = Self {
             filter: String::new(),
             selected_index: 0,
             all_folders,
-            filtered_folders,
+            filtered_folders: Vec::new(),
+            expanded: HashSet::new(),
+            views: Vec::new(),
+            filtered_views: Vec::new(),
             creating_new: false,
             new_folder_input: String::new(),
+            edit: None,
             mode,
+        };
+        state.update_filter();
+        state
+    }
+
+    /// List saved searches after the folders
+    pub fn set_views(&mut self, views: Vec<(String, String)>) {
+        self.views = views;
+        self.update_filter();
+    }
+
+    /// Number of listed rows: the folders, then the views
+    pub fn row_count(&self) -> usize {
+        self.filtered_folders.len() + self.filtered_views.len()
+    }
+
+    /// Whether a folder name is being typed (creating or renaming)
+    pub fn is_typing_name(&self) -> bool {
+        self.creating_new || matches!(self.edit, Some(FolderEdit::Rename { .. }))
+    }
+
+    /// Start an operation on the selected folder (not the root); false if none is selected
+    pub fn start_edit(&mut self, edit: fn(String) -> FolderEdit) -> bool {
+        let Some(folder) = self.selected_folder().map(str::to_string) else {
+            return false;
+        };
+        let edit = edit(folder);
+        if let FolderEdit::Rename { folder } = &edit {
+            self.new_folder_input = folder.clone();
+        }
+        self.edit = Some(edit);
+        true
+    }
+
+    /// Cancel the folder operation in progress
+    pub fn cancel_edit(&mut self) {
+        if matches!(self.edit.take(), Some(FolderEdit::Rename { .. })) {
+            self.new_folder_input.clear();
+        }
+    }
+
+    /// Replace the folder list after folders changed on disk, selecting `folder` if given
+    pub fn set_folders(&mut self, all_folders: Vec<String>, folder: Option<&str>) {
+        self.all_folders = all_folders;
+        self.expanded.retain(|f| self.all_folders.contains(f));
+        self.edit = None;
+        self.new_folder_input.clear();
+        match folder {
+            Some(folder) => self.reveal(folder),
+            None => self.update_filter(),
+        }
+        if self.selected_index >= self.row_count() {
+            self.selected_index = 0;
         }
     }
 
+    /// Whether the folder list is shown as a tree (no filter typed)
+    pub fn is_tree(&self) -> bool {
+        self.filter.is_empty()
+    }
+
+    /// Whether `folder` has subfolders
+    pub fn has_children(&self, folder: &str) -> bool {
+        let prefix = format!("{}/", folder);
+        self.all_folders.iter().any(|f| f.starts_with(&prefix))
+    }
+
+    /// Whether every folder containing `folder` is expanded
+    fn is_visible(&self, folder: &str) -> bool {
+        folder
+            .match_indices('/')
+            .all(|(end, _)| self.expanded.contains(&folder[..end]))
+    }
+
     /// Update the filter and refresh filtered results
     pub fn update_filter(&mut self) {
         self.filtered_folders = vec!["(root)".to_string()];
         if self.filter.is_empty() {
-            self.filtered_folders.extend(self.all_folders.clone());
+            let visible: Vec<String> = self.all_folders
+                .iter()
+                .filter(|folder| self.is_visible(folder))
+                .cloned()
+                .collect();
+            self.filtered_folders.extend(visible);
         } else {
             let filter_lower = self.filter.to_lowercase();
             self.filtered_folders.extend(
@@ -778,13 +1426,25 @@ impl FolderSelectorState {
                     .cloned()
             );
         }
+        let filter_lower = self.filter.to_lowercase();
+        self.filtered_views = self.views
+            .iter()
+            .filter(|(name, _)| name.to_lowercase().contains(&filter_lower))
+            .cloned()
+            .collect();
         // Reset selection if out of bounds
-        if self.selected_index >= self.filtered_folders.len() {
+        if self.selected_index >= self.row_count() {
             self.selected_index = 0;
         }
     }
 
-    /// Get the currently selected folder (None means root)
+    /// Get the name of the selected view, if a view is selected
+    pub fn selected_view(&self) -> Option<&str> {
+        let index = self.selected_index.checked_sub(self.filtered_folders.len())?;
+        self.filtered_views.get(index).map(|(name, _)| name.as_str())
+    }
+
+    /// Get the currently selected folder (None means root, or a view: see `selected_view`)
     pub fn selected_folder(&self) -> Option<&str> {
         self.filtered_folders.get(self.selected_index).and_then(|s| {
             if s == "(root)" {
@@ -795,28 +1455,74 @@ impl FolderSelectorState {
         })
     }
 
+    /// Select `folder` if it is listed
+    fn select_folder(&mut self, folder: &str) {
+        if let Some(pos) = self.filtered_folders.iter().position(|f| f == folder) {
+            self.selected_index = pos;
+        }
+    }
+
+    /// Expand the selected folder in the tree
+    pub fn expand_selected(&mut self) {
+        if let Some(folder) = self.selected_folder().map(str::to_string)
+            && self.is_tree()
+            && self.has_children(&folder)
+        {
+            self.expanded.insert(folder);
+            self.update_filter();
+        }
+    }
+
+    /// Collapse the selected folder, or select its parent if it is already collapsed
+    pub fn collapse_selected(&mut self) {
+        let Some(folder) = self.selected_folder().map(str::to_string) else {
+            return;
+        };
+        if !self.is_tree() {
+            return;
+        }
+        if self.expanded.remove(&folder) {
+            self.update_filter();
+        } else if let Some(parent) = parent_folder(&folder) {
+            let parent = parent.to_string();
+            self.select_folder(&parent);
+        }
+    }
+
+    /// Expand the folders containing `folder` and select it
+    pub fn reveal(&mut self, folder: &str) {
+        for (end, _) in folder.match_indices('/') {
+            self.expanded.insert(folder[..end].to_string());
+        }
+        self.update_filter();
+        self.select_folder(folder);
+    }
+
     /// Move selection down
     pub fn select_next(&mut self) {
-        if !self.filtered_folders.is_empty() {
-            self.selected_index = (self.selected_index + 1) % self.filtered_folders.len();
+        if self.row_count() > 0 {
+            self.selected_index = (self.selected_index + 1) % self.row_count();
         }
     }
 
     /// Move selection up
     pub fn select_previous(&mut self) {
-        if !self.filtered_folders.is_empty() {
+        if self.row_count() > 0 {
             if self.selected_index == 0 {
-                self.selected_index = self.filtered_folders.len() - 1;
+                self.selected_index = self.row_count() - 1;
             } else {
                 self.selected_index -= 1;
             }
         }
     }
     
-    /// Start creating a new folder
+    /// Start creating a new folder, inside the selected one when browsing the tree
     pub fn start_creating_new(&mut self) {
         self.creating_new = true;
-        self.new_folder_input = self.filter.clone();
+        self.new_folder_input = match self.selected_folder() {
+            Some(folder) if self.is_tree() => format!("{}/", folder),
+            _ => self.filter.clone(),
+        };
     }
     
     /// Cancel creating a new folder
@@ -825,20 +1531,25 @@ impl FolderSelectorState {
         self.new_folder_input.clear();
     }
     
-    /// Confirm creating a new folder, returns the new folder name if valid
+    /// Confirm creating a new folder, returns the new folder path if valid
     pub fn confirm_new_folder(&mut self) -> Option<String> {
-        let folder = self.new_folder_input.trim().to_lowercase().replace(' ', "_");
-        if folder.is_empty() || folder == "(root)" {
+        let folder = normalize_folder_path(&self.new_folder_input);
+        if folder.is_empty() {
             return None;
         }
-        // Add to all_folders if not already present
-        if !self.all_folders.contains(&folder) {
-            self.all_folders.push(folder.clone());
-            self.all_folders.sort();
+        // Add it and any missing parents to all_folders
+        let mut path = Some(folder.as_str());
+        while let Some(current) = path {
+            if !self.all_folders.iter().any(|f| f == current) {
+                self.all_folders.push(current.to_string());
+            }
+            path = parent_folder(current);
         }
+        self.all_folders.sort_by(|a, b| a.split('/').cmp(b.split('/')));
         self.creating_new = false;
         self.new_folder_input.clear();
-        self.update_filter();
+        self.filter.clear();
+        self.reveal(&folder);
         Some(folder)
     }
 }
@@ -847,6 +1558,29 @@ impl FolderSelectorState {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_command_palette_filter() {
+        let commands = [Action::Quit, Action::ToggleSafeMode, Action::IncreaseLeftColumnWidth]
+            .into_iter()
+            .map(|action| PaletteCommand { action, keys: String::new() })
+            .collect();
+        let mut palette = CommandPaletteState::new(commands);
+        assert_eq!(palette.filtered, [0, 1, 2]);
+
+        palette.filter = "safe".to_string();
+        palette.update_filter();
+        assert_eq!(palette.selected().map(|c| &c.action), Some(&Action::ToggleSafeMode));
+
+        // Config names match as well as descriptions
+        palette.filter = "left_column".to_string();
+        palette.update_filter();
+        assert_eq!(palette.filtered, [2]);
+
+        palette.filter = "zzz".to_string();
+        palette.update_filter();
+        assert!(palette.selected().is_none());
+    }
+
     #[test]
     fn test_navigation() {
         let mut state = AppState::new();
@@ -877,6 +1611,31 @@ mod tests {
         assert_eq!(state.selected_index, 2);
     }
 
+    #[test]
+    fn test_export_popup_options() {
+        let mut popup = ExportPopupState::new(ExportFormat::Raw);
+        popup.change_option(true);
+        assert_eq!(popup.format, ExportFormat::Rendered);
+        popup.change_option(false);
+        popup.change_option(false);
+        assert_eq!(popup.format, ExportFormat::Combined);
+
+        popup.focus_next();
+        popup.change_option(true);
+        assert!(popup.all_filtered);
+
+        popup.focus_next();
+        popup.change_option(true);
+        assert!(popup.options().include_command_output);
+
+        popup.focus_next();
+        assert_eq!(popup.focused, ExportField::Destination);
+        popup.focus_next();
+        assert_eq!(popup.focused, ExportField::Format);
+        popup.focus_previous();
+        assert_eq!(popup.focused, ExportField::Destination);
+    }
+
     #[test]
     fn test_empty_navigation() {
         let mut state = AppState::new();
@@ -890,4 +1649,120 @@ mod tests {
         state.select_last();
         assert_eq!(state.selected_index, 0);
     }
+
+    #[test]
+    fn test_folder_tree() {
+        let folders = ["personal", "work", "work/backend", "work/backend/db"];
+        let folders = folders.iter().map(|f| f.to_string()).collect();
+        let mut selector = FolderSelectorState::new(folders, FolderSelectorMode::Open);
+        assert_eq!(selector.filtered_folders, vec!["(root)", "personal", "work"]);
+
+        selector.reveal("work/backend");
+        assert_eq!(selector.selected_folder(), Some("work/backend"));
+        // Revealing expands the parents only
+        assert_eq!(selector.filtered_folders, vec!["(root)", "personal", "work", "work/backend"]);
+
+        // Left collapses nothing here, so it goes up to the parent; a second Left collapses it
+        selector.collapse_selected();
+        assert_eq!(selector.selected_folder(), Some("work"));
+        selector.collapse_selected();
+        assert_eq!(selector.filtered_folders, vec!["(root)", "personal", "work"]);
+
+        selector.expand_selected();
+        assert!(selector.filtered_folders.contains(&"work/backend".to_string()));
+
+        // A filter lists matches at any depth
+        selector.filter = "db".to_string();
+        selector.update_filter();
+        assert_eq!(selector.filtered_folders, vec!["(root)", "work/backend/db"]);
+
+        // New folders are created inside the selected one
+        selector.filter.clear();
+        selector.update_filter();
+        selector.reveal("personal");
+        selector.start_creating_new();
+        assert_eq!(selector.new_folder_input, "personal/");
+        selector.new_folder_input.push_str("Side Projects/Ideas");
+        assert_eq!(selector.confirm_new_folder().as_deref(), Some("personal/side_projects/ideas"));
+        assert!(selector.all_folders.contains(&"personal/side_projects".to_string()));
+        assert_eq!(selector.selected_folder(), Some("personal/side_projects/ideas"));
+    }
+
+    #[test]
+    fn test_folder_edits() {
+        let folders = vec!["personal".to_string(), "work".to_string()];
+        let mut selector = FolderSelectorState::new(folders, FolderSelectorMode::Open);
+
+        // The root cannot be renamed, deleted or merged
+        assert!(!selector.start_edit(|folder| FolderEdit::Rename { folder }));
+
+        selector.select_next();
+        assert!(selector.start_edit(|folder| FolderEdit::Rename { folder }));
+        assert!(selector.is_typing_name());
+        assert_eq!(selector.new_folder_input, "personal");
+        selector.cancel_edit();
+        assert!(selector.edit.is_none() && selector.new_folder_input.is_empty());
+
+        selector.start_edit(|folder| FolderEdit::Merge { folder });
+        assert!(!selector.is_typing_name());
+        assert_eq!(selector.edit, Some(FolderEdit::Merge { folder: "personal".to_string() }));
+
+        // After the merge, the list is refreshed and the merged-into folder selected
+        selector.set_folders(vec!["work".to_string()], Some("work"));
+        assert!(selector.edit.is_none());
+        assert_eq!(selector.selected_folder(), Some("work"));
+    }
+
+    #[test]
+    fn test_tag_selector_tree_and_edits() {
+        let all_tags = vec!["lang/go".to_string(), "lang/rust".to_string(), "writing".to_string()];
+        let mut selector = TagSelectorState::new(all_tags, vec!["lang/rust".to_string()]);
+        assert_eq!(selector.filtered_tags, ["lang", "lang/go", "lang/rust", "writing"]);
+
+        selector.filter = "rust".to_string();
+        selector.update_filter();
+        assert_eq!(selector.filtered_tags, ["lang", "lang/rust"]);
+        selector.filter.clear();
+        selector.update_filter();
+
+        assert!(selector.start_edit(|tag| TagSelectorEdit::Rename { tag }));
+        assert!(selector.is_typing_name());
+        assert_eq!(selector.new_tag_input, "lang");
+        selector.apply_edit(&TagEdit::Rename { from: "lang".into(), to: "code".into() });
+        assert_eq!(selector.edit, None);
+        assert_eq!(selector.filtered_tags, ["code", "code/go", "code/rust", "writing"]);
+        assert_eq!(selector.prompt_tags, ["code/rust"]);
+        assert_eq!(selector.selected_tag(), Some("code"));
+    }
+
+    #[test]
+    fn test_folder_selector_views() {
+        let folders = vec!["work".to_string()];
+        let mut selector = FolderSelectorState::new(folders, FolderSelectorMode::Open);
+        selector.set_views(vec![
+            ("drafts".to_string(), "tag:draft".to_string()),
+            ("stale reviews".to_string(), "tag:review modified:<30d".to_string()),
+        ]);
+        assert_eq!(selector.row_count(), 4);
+
+        // Views follow the folders
+        selector.select_next();
+        assert_eq!(selector.selected_folder(), Some("work"));
+        assert_eq!(selector.selected_view(), None);
+        selector.select_next();
+        assert_eq!(selector.selected_view(), Some("drafts"));
+        assert!(!selector.start_edit(|folder| FolderEdit::Rename { folder }));
+        selector.select_next();
+        selector.select_next();
+        assert_eq!(selector.selected_index, 0);
+        selector.select_previous();
+        assert_eq!(selector.selected_view(), Some("stale reviews"));
+
+        // The filter applies to view names too
+        selector.filter = "stale".to_string();
+        selector.update_filter();
+        assert_eq!(selector.row_count(), 2);
+        selector.select_next();
+        assert_eq!(selector.selected_view(), Some("stale reviews"));
+    }
 }
@@ -0,0 +1,164 @@
+//! Tag filter combining included and excluded tags
+
+use serde::{Deserialize, Serialize};
+
+use super::{is_tag_or_child, TagEdit};
+
+/// Which prompts to list by their tags: prompts with all (or any) of the included tags and
+/// none of the excluded ones, where a tag also covers the tags below it
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagFilter {
+    /// Tags a prompt must have
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// Tags a prompt must not have
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// Whether a prompt needs any one of the included tags instead of all of them
+    #[serde(default)]
+    pub match_any: bool,
+}
+
+/// How a tag takes part in a filter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagFilterState {
+    Off,
+    Include,
+    Exclude,
+}
+
+impl TagFilter {
+    /// Filter listing only prompts tagged `tag`
+    pub fn single(tag: &str) -> Self {
+        Self { include: vec![tag.to_string()], ..Self::default() }
+    }
+
+    /// Whether the filter lets every prompt through
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// Whether a prompt with `tags` passes the filter
+    pub fn matches(&self, tags: &[String]) -> bool {
+        let has = |wanted: &String| tags.iter().any(|tag| is_tag_or_child(tag, wanted));
+        let included = if self.match_any && !self.include.is_empty() {
+            self.include.iter().any(has)
+        } else {
+            self.include.iter().all(has)
+        };
+        included && !self.exclude.iter().any(has)
+    }
+
+    /// How `tag` takes part in the filter
+    pub fn state(&self, tag: &str) -> TagFilterState {
+        if self.include.iter().any(|t| t == tag) {
+            TagFilterState::Include
+        } else if self.exclude.iter().any(|t| t == tag) {
+            TagFilterState::Exclude
+        } else {
+            TagFilterState::Off
+        }
+    }
+
+    /// Move `tag` to the next state: off, included, excluded, off again
+    pub fn cycle(&mut self, tag: &str) {
+        let next = match self.state(tag) {
+            TagFilterState::Off => TagFilterState::Include,
+            TagFilterState::Include => TagFilterState::Exclude,
+            TagFilterState::Exclude => TagFilterState::Off,
+        };
+        self.include.retain(|t| t != tag);
+        self.exclude.retain(|t| t != tag);
+        match next {
+            TagFilterState::Include => self.include.push(tag.to_string()),
+            TagFilterState::Exclude => self.exclude.push(tag.to_string()),
+            TagFilterState::Off => {}
+        }
+    }
+
+    /// Follow a tag renamed, merged or deleted on every prompt; returns whether the filter
+    /// changed
+    pub fn apply_edit(&mut self, edit: &TagEdit) -> bool {
+        let include = edit.apply(&self.include);
+        let exclude = edit.apply(&self.exclude);
+        let changed = include.is_some() || exclude.is_some();
+        if let Some(include) = include {
+            self.include = include;
+        }
+        if let Some(exclude) = exclude {
+            self.exclude = exclude;
+        }
+        changed
+    }
+
+    /// The filter as an expression like `(coding | work) & !draft`
+    pub fn describe(&self) -> String {
+        let joiner = if self.match_any { " | " } else { " & " };
+        let mut parts = Vec::new();
+        if !self.include.is_empty() {
+            let included = self.include.join(joiner);
+            if self.match_any && self.include.len() > 1 && !self.exclude.is_empty() {
+                parts.push(format!("({})", included));
+            } else {
+                parts.push(included);
+            }
+        }
+        parts.extend(self.exclude.iter().map(|tag| format!("!{}", tag)));
+        parts.join(" & ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(tags: &[&str]) -> Vec<String> {
+        tags.iter().map(|t| t.to_string()).collect()
+    }
+
+    #[test]
+    fn test_tag_filter_matching() {
+        let mut filter = TagFilter::default();
+        assert!(filter.is_empty());
+        assert!(filter.matches(&tags(&[])));
+
+        filter.cycle("coding");
+        filter.cycle("work");
+        filter.cycle("draft");
+        filter.cycle("draft");
+        assert_eq!(filter.state("draft"), TagFilterState::Exclude);
+        assert_eq!(filter.describe(), "coding & work & !draft");
+        assert!(filter.matches(&tags(&["coding", "work"])));
+        assert!(!filter.matches(&tags(&["coding"])));
+        assert!(!filter.matches(&tags(&["coding", "work", "draft"])));
+
+        filter.match_any = true;
+        assert_eq!(filter.describe(), "(coding | work) & !draft");
+        assert!(filter.matches(&tags(&["coding"])));
+        assert!(!filter.matches(&tags(&["work", "draft"])));
+        assert!(!filter.matches(&tags(&["writing"])));
+
+        // Only exclusions: everything else passes, in either mode
+        filter.cycle("coding");
+        filter.cycle("coding");
+        filter.cycle("work");
+        filter.cycle("work");
+        assert_eq!(filter.state("coding"), TagFilterState::Off);
+        assert_eq!(filter.describe(), "!draft");
+        assert!(filter.matches(&tags(&["writing"])));
+        assert!(!filter.matches(&tags(&["draft"])));
+
+        // Parents cover their children
+        let filter = TagFilter::single("lang");
+        assert!(filter.matches(&tags(&["lang/rust"])));
+        assert!(!filter.matches(&tags(&["language"])));
+
+        // Renamed tags are followed, deleted ones dropped
+        let mut filter =
+            TagFilter { include: tags(&["lang/rust"]), exclude: tags(&["draft"]), match_any: false };
+        assert!(filter.apply_edit(&TagEdit::Rename { from: "lang".into(), to: "language".into() }));
+        assert!(filter.apply_edit(&TagEdit::Delete("draft".into())));
+        assert!(!filter.apply_edit(&TagEdit::Delete("draft".into())));
+        assert_eq!(filter.describe(), "language/rust");
+    }
+}
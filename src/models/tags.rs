@@ -0,0 +1,216 @@
+//! Hierarchical tags
+//!
+//! Tags may be namespaced with `/`, like `lang/rust` or `team/platform`. A tag stands for
+//! itself and every tag below it: filtering by `lang` finds prompts tagged `lang/rust`, and
+//! renaming or deleting `lang` renames or deletes `lang/rust` as well.
+
+use anyhow::{bail, Result};
+
+/// Normalize a typed tag: lowercase, spaces as underscores, no empty path components
+pub fn normalize_tag(tag: &str) -> String {
+    tag.trim()
+        .to_lowercase()
+        .replace(' ', "_")
+        .split('/')
+        .filter(|component| !component.is_empty())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Whether `tag` is `parent` or a tag below it (`lang/rust` is under `lang`)
+pub fn is_tag_or_child(tag: &str, parent: &str) -> bool {
+    tag.strip_prefix(parent)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// A tag in the tag tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagNode {
+    /// Full tag path
+    pub tag: String,
+    /// Number of parents above the tag
+    pub depth: usize,
+    /// Whether only tags below this one are in use
+    pub implicit: bool,
+}
+
+impl TagNode {
+    /// Last component of the tag path
+    pub fn label(&self) -> &str {
+        self.tag.rsplit('/').next().unwrap_or(&self.tag)
+    }
+}
+
+/// Arrange `tags` as a tree: every tag directly followed by the tags below it, with parents
+/// nobody uses directly (like `lang` for `lang/rust`) added as implicit nodes
+pub fn tag_tree(tags: &[String]) -> Vec<TagNode> {
+    let mut paths: Vec<(String, bool)> = Vec::new();
+    for tag in tags {
+        let components: Vec<&str> = tag.split('/').collect();
+        for end in 1..components.len() {
+            paths.push((components[..end].join("/"), true));
+        }
+        paths.push((tag.clone(), false));
+    }
+    // Compare component by component so every tag is directly followed by its children
+    paths.sort_by(|(a, a_implicit), (b, b_implicit)| {
+        a.split('/').cmp(b.split('/')).then(a_implicit.cmp(b_implicit))
+    });
+    paths.dedup_by(|next, kept| next.0 == kept.0);
+
+    paths
+        .into_iter()
+        .map(|(tag, implicit)| TagNode { depth: tag.matches('/').count(), tag, implicit })
+        .collect()
+}
+
+/// A change to a tag on every prompt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TagEdit {
+    /// Give a tag (and the tags below it) a name no prompt uses yet
+    Rename { from: String, to: String },
+    /// Replace a tag (and the tags below it) by an existing one
+    Merge { from: String, into: String },
+    /// Remove a tag (and the tags below it)
+    Delete(String),
+}
+
+impl TagEdit {
+    /// Tag being renamed, merged or deleted
+    pub fn source(&self) -> &str {
+        match self {
+            Self::Rename { from, .. } | Self::Merge { from, .. } => from,
+            Self::Delete(tag) => tag,
+        }
+    }
+
+    /// Check the edit makes sense given the tags in use
+    pub fn validate(&self, tags_in_use: &[String]) -> Result<()> {
+        let in_use = |tag: &str| tags_in_use.iter().any(|t| is_tag_or_child(t, tag));
+        let source = self.source();
+        if source.is_empty() || !in_use(source) {
+            bail!("No prompt is tagged '{}'", source);
+        }
+        match self {
+            Self::Rename { from, to } => {
+                if to.is_empty() || to == from {
+                    bail!("Choose a different name for tag '{}'", from);
+                }
+                if is_tag_or_child(to, from) {
+                    bail!("Cannot move tag '{}' below itself", from);
+                }
+                if in_use(to) {
+                    bail!("Tag '{}' already exists (merge it instead)", to);
+                }
+            }
+            Self::Merge { from, into } => {
+                if is_tag_or_child(into, from) || is_tag_or_child(from, into) {
+                    bail!("Cannot merge '{}' into '{}': one contains the other", from, into);
+                }
+                if !in_use(into) {
+                    bail!("No prompt is tagged '{}' (rename instead)", into);
+                }
+            }
+            Self::Delete(_) => {}
+        }
+        Ok(())
+    }
+
+    /// What becomes of `tag`: None if the edit leaves it alone, Some(None) if it is deleted,
+    /// otherwise its new name
+    pub fn apply_to(&self, tag: &str) -> Option<Option<String>> {
+        let source = self.source();
+        if !is_tag_or_child(tag, source) {
+            return None;
+        }
+        let rest = &tag[source.len()..];
+        match self {
+            Self::Rename { to: target, .. } | Self::Merge { into: target, .. } => {
+                Some(Some(format!("{}{}", target, rest)))
+            }
+            Self::Delete(_) => Some(None),
+        }
+    }
+
+    /// Tags of a prompt after the edit, or None if the edit does not touch them
+    pub fn apply(&self, tags: &[String]) -> Option<Vec<String>> {
+        if !tags.iter().any(|tag| self.apply_to(tag).is_some()) {
+            return None;
+        }
+        let mut edited: Vec<String> = Vec::new();
+        for tag in tags {
+            let tag = match self.apply_to(tag) {
+                None => Some(tag.clone()),
+                Some(new) => new,
+            };
+            if let Some(tag) = tag.filter(|tag| !edited.contains(tag)) {
+                edited.push(tag);
+            }
+        }
+        Some(edited)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(tags: &[&str]) -> Vec<String> {
+        tags.iter().map(|t| t.to_string()).collect()
+    }
+
+    #[test]
+    fn test_tag_hierarchy() {
+        assert_eq!(normalize_tag(" Lang//Rust/ "), "lang/rust");
+        assert_eq!(normalize_tag("team platform"), "team_platform");
+
+        assert!(is_tag_or_child("lang/rust", "lang"));
+        assert!(is_tag_or_child("lang", "lang"));
+        assert!(!is_tag_or_child("language", "lang"));
+        assert!(!is_tag_or_child("lang", "lang/rust"));
+
+        let tree = tag_tree(&tags(&["writing", "lang/rust", "lang/go", "lang", "team/platform"]));
+        let rows: Vec<(&str, usize, bool)> =
+            tree.iter().map(|n| (n.tag.as_str(), n.depth, n.implicit)).collect();
+        assert_eq!(
+            rows,
+            vec![
+                ("lang", 0, false),
+                ("lang/go", 1, false),
+                ("lang/rust", 1, false),
+                ("team", 0, true),
+                ("team/platform", 1, false),
+                ("writing", 0, false),
+            ]
+        );
+        assert_eq!(tree[4].label(), "platform");
+    }
+
+    #[test]
+    fn test_tag_edits() {
+        let in_use = tags(&["lang/rust", "lang/go", "coding", "code"]);
+
+        let rename = TagEdit::Rename { from: "lang".into(), to: "language".into() };
+        rename.validate(&in_use).unwrap();
+        assert_eq!(
+            rename.apply(&tags(&["coding", "lang/rust"])),
+            Some(tags(&["coding", "language/rust"]))
+        );
+        assert_eq!(rename.apply(&tags(&["coding", "language"])), None);
+        assert!(TagEdit::Rename { from: "code".into(), to: "coding".into() }.validate(&in_use).is_err());
+        assert!(TagEdit::Rename { from: "lang".into(), to: "lang/old".into() }.validate(&in_use).is_err());
+        assert!(TagEdit::Rename { from: "nope".into(), to: "other".into() }.validate(&in_use).is_err());
+
+        let merge = TagEdit::Merge { from: "code".into(), into: "coding".into() };
+        merge.validate(&in_use).unwrap();
+        assert_eq!(merge.apply(&tags(&["code", "coding", "x"])), Some(tags(&["coding", "x"])));
+        assert!(TagEdit::Merge { from: "code".into(), into: "other".into() }.validate(&in_use).is_err());
+        assert!(TagEdit::Merge { from: "lang/go".into(), into: "lang".into() }.validate(&in_use).is_err());
+
+        let delete = TagEdit::Delete("lang".into());
+        delete.validate(&in_use).unwrap();
+        assert_eq!(delete.apply(&tags(&["lang/go", "coding"])), Some(tags(&["coding"])));
+        assert_eq!(delete.apply_to("lang/rust"), Some(None));
+        assert_eq!(delete.apply_to("language"), None);
+    }
+}
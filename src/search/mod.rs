@@ -0,0 +1,13 @@
+//! Prompt search
+//!
+//! The query language shared by the TUI search popup and the `search` command, and the
+//! background matcher the popup uses to stay responsive on large libraries, and searches
+//! saved as views.
+
+mod query;
+mod views;
+mod worker;
+
+pub use query::{Document, Query, search};
+pub use views::{load_views, save_view};
+pub use worker::BackgroundSearch;
@@ -0,0 +1,665 @@
+//! Search query language
+//!
+//! A query mixes fuzzy terms with operators; a prompt must match all of them:
+//!
+//! - `review code` – fuzzy match against name or content
+//! - `"exact phrase"` – case-insensitive substring of name or content
+//! - `name:review`, `body:"step by step"` – the same, limited to the name or the content
+//! - `tag:coding` – has the tag, or a tag below it like `coding/rust`
+//! - `folder:work` – in the folder or one of its subfolders
+//! - `in:archive` – in the main list (`in:main`), the archive, any folder (`in:folders`)
+//!   or anywhere (`in:all`); without `in:`, archived prompts are left out
+//! - `modified:>7d`, `modified:<2024-01-01`, `modified:2024-05-01` – changed within the
+//!   last 7 days, before a date, on a day (durations: `h`, `d`, `w`)
+//! - `has:command`, `has:ref`, `has:file`, `has:tag`, `has:placeholder`
+//! - `refs:code_review` – references the prompt `[[code_review]]`
+//!
+//! Any term can be negated with a leading `-`, as in `-tag:draft` or `-"todo"`.
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use nucleo::pattern::{Atom, CaseMatching, Normalization, Pattern};
+use nucleo::{Config as NucleoConfig, Matcher, Utf32Str};
+
+use crate::engine::{find_placeholders, find_references, has_commands, has_file_references};
+use crate::fs::IndexEntry;
+use crate::models::is_tag_or_child;
+
+/// Part of a prompt searched by text terms
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    /// Name or content
+    Any,
+    /// Name only
+    Name,
+    /// Content only
+    Body,
+}
+
+/// Locations searched by `in:`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    /// The main prompt list
+    Main,
+    /// The archive
+    Archive,
+    /// Any folder
+    Folders,
+    /// Everywhere
+    All,
+}
+
+/// Prompt features tested by `has:`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feature {
+    /// Runs a `{{command}}`
+    Command,
+    /// References another prompt
+    Reference,
+    /// References a file
+    FileReference,
+    /// Has at least one tag
+    Tag,
+    /// Has an `{{arg:NAME}}` placeholder
+    Placeholder,
+}
+
+/// A test a prompt must pass (or fail, when negated)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Filter {
+    /// Has this tag
+    Tag(String),
+    /// In this folder or below it
+    Folder(String),
+    /// In these locations
+    In(Scope),
+    /// Modified at or after this time
+    ModifiedAfter(DateTime<Utc>),
+    /// Modified before this time
+    ModifiedBefore(DateTime<Utc>),
+    /// Modified on the day starting at this time
+    ModifiedOn(DateTime<Utc>),
+    /// Has this feature
+    Has(Feature),
+    /// References this prompt
+    Refs(String),
+    /// Contains this text (case-insensitive)
+    Text(Field, String),
+}
+
+/// A filter of a query, possibly negated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Term {
+    pub filter: Filter,
+    pub negated: bool,
+}
+
+/// A parsed search query
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Query {
+    /// Filters every result passes
+    pub terms: Vec<Term>,
+    /// Fuzzy words matched against the name or content
+    pub fuzzy: String,
+    /// Fuzzy words matched against the name only
+    pub fuzzy_name: String,
+    /// Fuzzy words matched against the content only
+    pub fuzzy_body: String,
+    /// Terms that could not be understood (they are ignored)
+    pub errors: Vec<String>,
+}
+
+/// A prompt as seen by a query
+#[derive(Debug, Clone, Copy)]
+pub struct Document<'a> {
+    pub name: &'a str,
+    pub content: &'a str,
+    pub tags: &'a [String],
+    pub modified: DateTime<Utc>,
+    /// "prompts", "archive" or "folders/<path>"
+    pub location: &'a str,
+}
+
+impl<'a> From<&'a IndexEntry> for Document<'a> {
+    fn from(entry: &'a IndexEntry) -> Self {
+        Self {
+            name: &entry.name,
+            content: entry.body(),
+            tags: &entry.tags,
+            modified: entry.modified,
+            location: &entry.location,
+        }
+    }
+}
+
+/// A document matching a query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hit {
+    /// Position of the document in the searched slice
+    pub index: usize,
+    /// Fuzzy match score (0 without fuzzy terms; higher is better)
+    pub score: u32,
+    /// Character indices of the name matched by fuzzy terms
+    pub name_indices: Vec<usize>,
+    /// The content line matching best, when the content matched
+    pub snippet: Option<Snippet>,
+}
+
+/// The content line that matches a query best
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snippet {
+    /// Line number, counting from 0
+    pub line: usize,
+    /// Character indices of the line matched by fuzzy terms or phrases
+    pub indices: Vec<usize>,
+}
+
+impl Query {
+    /// Parse a query; terms that cannot be understood are listed in `errors`
+    pub fn parse(input: &str) -> Self {
+        parse_at(input, Utc::now())
+    }
+
+    /// Parse a query as of `now`, which relative times like `modified:7d` count back from
+    pub fn parse_at(input: &str, now: DateTime<Utc>) -> Self {
+        parse_at(input, now)
+    }
+
+    /// Whether `document` passes every filter (fuzzy terms are checked by [`search`])
+    pub fn matches(&self, document: &Document) -> bool {
+        let scoped = self.terms.iter().any(|t| matches!(t.filter, Filter::In(_)));
+        if !scoped && document.location == "archive" {
+            return false;
+        }
+        self.terms
+            .iter()
+            .all(|term| term.filter.matches(document) != term.negated)
+    }
+}
+
+/// Search `documents`, best matches first (ties in name order)
+pub fn search(query: &Query, documents: &[Document]) -> Vec<Hit> {
+    let patterns = Patterns::new(query);
+    let mut matcher = Matcher::new(NucleoConfig::DEFAULT);
+    let mut hits = Vec::new();
+    for (index, document) in documents.iter().enumerate() {
+        if !query.matches(document) {
+            continue;
+        }
+        let Some(score) = patterns.score(document, &mut matcher) else {
+            continue;
+        };
+        let (name_indices, snippet) = patterns.highlight(document, &mut matcher);
+        hits.push(Hit {
+            index,
+            score,
+            name_indices,
+            snippet,
+        });
+    }
+
+    hits.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then_with(|| documents[a.index].name.cmp(documents[b.index].name))
+    });
+    hits
+}
+
+/// The fuzzy patterns and phrases of a query, parsed once to match many documents
+pub struct Patterns {
+    any: Option<Pattern>,
+    name: Option<Pattern>,
+    body: Option<Pattern>,
+    phrases: Vec<String>,
+}
+
+impl Patterns {
+    pub fn new(query: &Query) -> Self {
+        let pattern = |text: &str| {
+            (!text.is_empty())
+                .then(|| Pattern::parse(text, CaseMatching::Smart, Normalization::Smart))
+        };
+        let phrases = query
+            .terms
+            .iter()
+            .filter_map(|term| match &term.filter {
+                Filter::Text(Field::Any | Field::Body, text) if !term.negated => Some(text.clone()),
+                _ => None,
+            })
+            .collect();
+        Self {
+            any: pattern(&query.fuzzy),
+            name: pattern(&query.fuzzy_name),
+            body: pattern(&query.fuzzy_body),
+            phrases,
+        }
+    }
+
+    /// Score `document`: unscoped words against its name and content together (as the
+    /// search popup's background matcher does), plus the scores of scoped words; None when
+    /// a pattern does not match
+    fn score(&self, document: &Document, matcher: &mut Matcher) -> Option<u32> {
+        let (mut name_buf, mut content_buf) = (Vec::new(), Vec::new());
+        let name = Utf32Str::new(document.name, &mut name_buf);
+        let content = Utf32Str::new(document.content, &mut content_buf);
+
+        let mut score = 0;
+        if let Some(pattern) = &self.any {
+            let text = format!("{}\n{}", document.name, document.content);
+            let mut text_buf = Vec::new();
+            score += pattern.score(Utf32Str::new(&text, &mut text_buf), matcher)?;
+        }
+        if let Some(pattern) = &self.name {
+            score += pattern.score(name, matcher)?;
+        }
+        if let Some(pattern) = &self.body {
+            score += pattern.score(content, matcher)?;
+        }
+        Some(score)
+    }
+
+    /// Where a matching `document` matched: character indices of its name, and its content
+    /// line that matched best
+    pub fn highlight(
+        &self,
+        document: &Document,
+        matcher: &mut Matcher,
+    ) -> (Vec<usize>, Option<Snippet>) {
+        let (mut name_buf, mut content_buf) = (Vec::new(), Vec::new());
+        let name = Utf32Str::new(document.name, &mut name_buf);
+        let content = Utf32Str::new(document.content, &mut content_buf);
+        let mut name_indices = Vec::new();
+        // Atoms of the fuzzy patterns that matched the content, to find its best line
+        let mut content_atoms = Vec::new();
+
+        if let Some(pattern) = &self.any {
+            let name_score = pattern.score(name, matcher);
+            let content_score = pattern.score(content, matcher);
+            // Words matching only across name and content are looked for in the content
+            if content_score.is_some() || name_score.is_none() {
+                content_atoms.extend(&pattern.atoms);
+            }
+            // Highlight the name when it is what matched best
+            if name_score.is_some() && name_score >= content_score {
+                pattern.indices(name, matcher, &mut name_indices);
+            }
+        }
+        if let Some(pattern) = &self.name {
+            pattern.indices(name, matcher, &mut name_indices);
+        }
+        if let Some(pattern) = &self.body {
+            content_atoms.extend(&pattern.atoms);
+        }
+
+        let mut name_indices: Vec<usize> = name_indices.into_iter().map(|i| i as usize).collect();
+        name_indices.sort_unstable();
+        name_indices.dedup();
+        let snippet = best_line(document.content, &content_atoms, &self.phrases, matcher);
+        (name_indices, snippet)
+    }
+}
+
+/// Find the content line matching the most atoms and phrases (the best scoring and then
+/// the first one on ties)
+fn best_line(
+    content: &str,
+    atoms: &[&Atom],
+    phrases: &[String],
+    matcher: &mut Matcher,
+) -> Option<Snippet> {
+    if atoms.is_empty() && phrases.is_empty() {
+        return None;
+    }
+
+    let mut best: Option<(usize, u32, Snippet)> = None;
+    let (mut buf, mut scratch) = (Vec::new(), Vec::new());
+    for (number, line) in content.lines().enumerate() {
+        let haystack = Utf32Str::new(line, &mut buf);
+        let (mut matched, mut score, mut indices) = (0, 0, Vec::new());
+        for atom in atoms {
+            scratch.clear();
+            if let Some(atom_score) = atom.indices(haystack, matcher, &mut scratch) {
+                matched += 1;
+                score += u32::from(atom_score);
+                indices.extend(scratch.iter().map(|&i| i as usize));
+            }
+        }
+        let lower = line.to_lowercase();
+        for phrase in phrases {
+            if let Some(start) = lower.find(phrase.as_str()) {
+                matched += 1;
+                let start = lower[..start].chars().count();
+                indices.extend(start..start + phrase.chars().count());
+            }
+        }
+
+        if matched > 0 && best.as_ref().is_none_or(|(m, s, _)| (matched, score) > (*m, *s)) {
+            indices.sort_unstable();
+            indices.dedup();
+            best = Some((matched, score, Snippet { line: number, indices }));
+        }
+    }
+    best.map(|(_, _, snippet)| snippet)
+}
+
+impl Filter {
+    fn matches(&self, document: &Document) -> bool {
+        match self {
+            Filter::Tag(tag) => {
+                let tag = tag.to_lowercase();
+                document.tags.iter().any(|t| is_tag_or_child(&t.to_lowercase(), &tag))
+            }
+            Filter::Folder(folder) => document
+                .location
+                .strip_prefix("folders/")
+                .and_then(|path| path.strip_prefix(folder.as_str()))
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/')),
+            Filter::In(scope) => match scope {
+                Scope::Main => document.location == "prompts",
+                Scope::Archive => document.location == "archive",
+                Scope::Folders => document.location.starts_with("folders/"),
+                Scope::All => true,
+            },
+            Filter::ModifiedAfter(time) => document.modified >= *time,
+            Filter::ModifiedBefore(time) => document.modified < *time,
+            Filter::ModifiedOn(start) => {
+                document.modified >= *start && document.modified < *start + Duration::days(1)
+            }
+            Filter::Has(feature) => match feature {
+                Feature::Command => has_commands(document.content),
+                Feature::Reference => !find_references(document.content).is_empty(),
+                Feature::FileReference => has_file_references(document.content),
+                Feature::Tag => !document.tags.is_empty(),
+                Feature::Placeholder => !find_placeholders(document.content).is_empty(),
+            },
+            Filter::Refs(name) => find_references(document.content)
+                .iter()
+                .any(|reference| reference.name == *name),
+            Filter::Text(field, text) => {
+                let contains = |haystack: &str| haystack.to_lowercase().contains(text.as_str());
+                match field {
+                    Field::Any => contains(document.name) || contains(document.content),
+                    Field::Name => contains(document.name),
+                    Field::Body => contains(document.content),
+                }
+            }
+        }
+    }
+}
+
+/// Parse a query, resolving relative times like `7d` against `now`
+fn parse_at(input: &str, now: DateTime<Utc>) -> Query {
+    let mut query = Query::default();
+    let add_words = |words: &mut String, word: &str| {
+        if !words.is_empty() {
+            words.push(' ');
+        }
+        words.push_str(word);
+    };
+
+    for token in tokenize(input) {
+        let (negated, token) = match token.strip_prefix('-') {
+            Some(rest) if !rest.is_empty() => (true, rest),
+            _ => (false, token.as_str()),
+        };
+        let (key, value) = match token.split_once(':') {
+            Some((key, value)) if !key.contains('"') => (Some(key), value),
+            _ => (None, token),
+        };
+        let quoted = value.len() >= 2 && value.starts_with('"') && value.ends_with('"');
+        let text = value.trim_matches('"');
+        if text.is_empty() {
+            continue;
+        }
+
+        let field = match key {
+            None => Some(Field::Any),
+            Some("name") => Some(Field::Name),
+            Some("body") => Some(Field::Body),
+            _ => None,
+        };
+        let filter = match (key, field) {
+            // Plain words are fuzzy; phrases and excluded words must appear as written
+            (_, Some(field)) if !quoted && !negated => {
+                let words = match field {
+                    Field::Any => &mut query.fuzzy,
+                    Field::Name => &mut query.fuzzy_name,
+                    Field::Body => &mut query.fuzzy_body,
+                };
+                add_words(words, text);
+                continue;
+            }
+            (_, Some(field)) => Ok(Filter::Text(field, text.to_lowercase())),
+            (Some("tag"), _) => Ok(Filter::Tag(text.to_string())),
+            (Some("folder"), _) => Ok(Filter::Folder(text.trim_matches('/').to_lowercase())),
+            (Some("in"), _) => parse_scope(text).map(Filter::In),
+            (Some("modified"), _) => parse_modified(text, now),
+            (Some("has"), _) => parse_feature(text).map(Filter::Has),
+            (Some("refs"), _) => Ok(Filter::Refs(text.to_string())),
+            // Not an operator (a URL, a time): search for the token as typed
+            _ => {
+                if negated {
+                    Ok(Filter::Text(Field::Any, token.to_lowercase()))
+                } else {
+                    add_words(&mut query.fuzzy, token);
+                    continue;
+                }
+            }
+        };
+
+        match filter {
+            Ok(filter) => query.terms.push(Term { filter, negated }),
+            Err(error) => query.errors.push(error),
+        }
+    }
+    query
+}
+
+/// Split a query at whitespace outside double quotes
+fn tokenize(input: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    let mut quoted = false;
+
+    for c in input.chars() {
+        if c == '"' {
+            quoted = !quoted;
+        }
+        if c.is_whitespace() && !quoted {
+            if !token.is_empty() {
+                tokens.push(std::mem::take(&mut token));
+            }
+        } else {
+            token.push(c);
+        }
+    }
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+    tokens
+}
+
+fn parse_scope(value: &str) -> Result<Scope, String> {
+    match value.to_lowercase().as_str() {
+        "main" | "prompts" | "root" => Ok(Scope::Main),
+        "archive" | "archived" => Ok(Scope::Archive),
+        "folders" | "folder" => Ok(Scope::Folders),
+        "all" | "any" => Ok(Scope::All),
+        _ => Err(format!("Unknown location 'in:{}' (main, archive, folders, all)", value)),
+    }
+}
+
+fn parse_feature(value: &str) -> Result<Feature, String> {
+    match value.to_lowercase().trim_end_matches('s') {
+        "command" | "cmd" => Ok(Feature::Command),
+        "ref" | "reference" => Ok(Feature::Reference),
+        "file" => Ok(Feature::FileReference),
+        "tag" => Ok(Feature::Tag),
+        "placeholder" | "arg" => Ok(Feature::Placeholder),
+        _ => Err(format!(
+            "Unknown feature 'has:{}' (command, ref, file, tag, placeholder)",
+            value
+        )),
+    }
+}
+
+/// Parse `>7d`, `<2024-01-01`, `2024-05-01` and the like into a time filter
+fn parse_modified(value: &str, now: DateTime<Utc>) -> Result<Filter, String> {
+    let (op, time) = ["<=", ">=", "<", ">", "="]
+        .iter()
+        .find_map(|op| value.strip_prefix(op).map(|rest| (*op, rest)))
+        .unwrap_or(("", value));
+    let invalid = || format!("Invalid time 'modified:{}' (like >7d, <2w, 2024-05-01)", value);
+
+    if let Ok(date) = NaiveDate::parse_from_str(time, "%Y-%m-%d") {
+        let start = date.and_hms_opt(0, 0, 0).ok_or_else(invalid)?.and_utc();
+        let end = start + Duration::days(1);
+        return Ok(match op {
+            ">" => Filter::ModifiedAfter(end),
+            ">=" => Filter::ModifiedAfter(start),
+            "<" => Filter::ModifiedBefore(start),
+            "<=" => Filter::ModifiedBefore(end),
+            _ => Filter::ModifiedOn(start),
+        });
+    }
+
+    let split = time.char_indices().last().map_or(0, |(i, _)| i);
+    let amount: i64 = time[..split].parse().map_err(|_| invalid())?;
+    let duration = match &time[split..] {
+        "h" => Duration::hours(amount),
+        "d" => Duration::days(amount),
+        "w" => Duration::weeks(amount),
+        _ => return Err(invalid()),
+    };
+    match op {
+        "" | ">" | ">=" => Ok(Filter::ModifiedAfter(now - duration)),
+        "<" | "<=" => Ok(Filter::ModifiedBefore(now - duration)),
+        _ => Err(invalid()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc<'a>(name: &'a str, content: &'a str, tags: &'a [String], location: &'a str) -> Document<'a> {
+        Document {
+            name,
+            content,
+            tags,
+            modified: "2024-05-01T12:00:00Z".parse().unwrap(),
+            location,
+        }
+    }
+
+    fn names<'a>(query: &str, documents: &[Document<'a>]) -> Vec<&'a str> {
+        let now = "2024-05-03T12:00:00Z".parse().unwrap();
+        search(&parse_at(query, now), documents)
+            .iter()
+            .map(|hit| documents[hit.index].name)
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_terms() {
+        let now: DateTime<Utc> = "2024-05-03T12:00:00Z".parse().unwrap();
+        let query = parse_at(
+            r#"review -tag:draft name:code body:"step by step" modified:>7d in:wat has:nope"#,
+            now,
+        );
+        assert_eq!(query.fuzzy, "review");
+        assert_eq!(query.fuzzy_name, "code");
+        assert_eq!(
+            query.terms,
+            vec![
+                Term { filter: Filter::Tag("draft".into()), negated: true },
+                Term { filter: Filter::Text(Field::Body, "step by step".into()), negated: false },
+                Term { filter: Filter::ModifiedAfter(now - Duration::days(7)), negated: false },
+            ]
+        );
+        assert_eq!(query.errors.len(), 2);
+
+        // Unknown keys are plain text
+        let query = parse_at("http://example", now);
+        assert!(query.terms.is_empty());
+        assert_eq!(query.fuzzy, "http://example");
+        assert_eq!(parse_at("  ", now), Query::default());
+    }
+
+    #[test]
+    fn test_search_filters() {
+        let coding = vec!["coding".to_string()];
+        let draft = vec!["coding".to_string(), "draft".to_string()];
+        let documents = vec![
+            doc("code_review", "Review this: {{git diff}}", &coding, "prompts"),
+            doc("summary", "Summarize [[code_review]] step by step", &draft, "folders/work"),
+            doc("notes", "Step by step notes", &[], "folders/work/deep"),
+            doc("old", "Old code", &coding, "archive"),
+        ];
+
+        assert_eq!(names("tag:coding", &documents), ["code_review", "summary"]);
+        assert_eq!(names("tag:coding -tag:draft", &documents), ["code_review"]);
+        assert_eq!(names("tag:coding in:all", &documents), ["code_review", "old", "summary"]);
+        assert_eq!(names("in:archive", &documents), ["old"]);
+        assert_eq!(names("folder:work", &documents), ["notes", "summary"]);
+        assert_eq!(names("folder:work/deep", &documents), ["notes"]);
+        assert_eq!(names("folder:wor", &documents), Vec::<&str>::new());
+        assert_eq!(names("has:command", &documents), ["code_review"]);
+        assert_eq!(names("-has:tag", &documents), ["notes"]);
+        assert_eq!(names("refs:code_review", &documents), ["summary"]);
+        assert_eq!(names(r#""step by step""#, &documents), ["notes", "summary"]);
+        assert_eq!(names(r#"name:"review""#, &documents), ["code_review"]);
+        assert_eq!(names(r#"-"step by step""#, &documents), ["code_review"]);
+        assert_eq!(names("modified:>7d", &documents), ["code_review", "notes", "summary"]);
+        assert_eq!(names("modified:<1d", &documents), ["code_review", "notes", "summary"]);
+        assert_eq!(names("modified:2024-05-01", &documents), ["code_review", "notes", "summary"]);
+        assert_eq!(names("modified:>2024-05-01", &documents), Vec::<&str>::new());
+        assert_eq!(names("modified:<=2024-05-01", &documents).len(), 3);
+    }
+
+    #[test]
+    fn test_search_fuzzy() {
+        let documents = vec![
+            doc("code_review", "Review this diff", &[], "prompts"),
+            doc("summary", "Summarize the code", &[], "prompts"),
+        ];
+
+        // Name matches are highlighted
+        let hits = search(&parse_at("cdrev", Utc::now()), &documents);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].name_indices, vec![0, 2, 5, 6, 7]);
+
+        assert_eq!(names("code", &documents), ["code_review", "summary"]);
+        assert_eq!(names("name:code", &documents), ["code_review"]);
+        assert_eq!(names("body:code", &documents), ["summary"]);
+        assert_eq!(names("name:summ body:code", &documents), ["summary"]);
+        assert_eq!(names("zzz", &documents), Vec::<&str>::new());
+        // Unscoped words may match across the name and the content
+        assert_eq!(names("review summarize", &documents), Vec::<&str>::new());
+        assert_eq!(names("summary code", &documents), ["summary"]);
+    }
+
+    #[test]
+    fn test_search_snippets() {
+        let content = "# Title\nIntro\n\nThen review the diff\nFinally, Step by step";
+        let documents = vec![doc("guide", content, &[], "prompts")];
+        let snippet = |query: &str| {
+            search(&parse_at(query, Utc::now()), &documents)
+                .pop()
+                .and_then(|hit| hit.snippet)
+        };
+
+        assert_eq!(
+            snippet("review"),
+            Some(Snippet { line: 3, indices: vec![5, 6, 7, 8, 9, 10] })
+        );
+        // The line matching the most terms wins
+        assert_eq!(snippet("then diff").map(|s| s.line), Some(3));
+        assert_eq!(
+            snippet(r#"body:"step by""#),
+            Some(Snippet { line: 4, indices: (9..16).collect() })
+        );
+        // Name matches have no snippet
+        assert_eq!(snippet("name:guide"), None);
+    }
+}
@@ -0,0 +1,70 @@
+//! Saved searches ("views")
+//!
+//! A view is a search query saved under a name, listed as a virtual folder whose prompts
+//! are the query's current matches. Views come from the `views` config key and from
+//! `.piemme/views.yaml`, a mapping of names to queries that piemme writes when a search
+//! is saved; a name in both uses the one in `views.yaml`.
+
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::config::Config;
+use crate::fs::write_atomic;
+
+/// Load the views of `config` and of the views file at `path` (which may not exist)
+pub fn load_views(config: &Config, path: &Path) -> Result<BTreeMap<String, String>> {
+    let mut views = config.views.clone();
+    views.extend(read_views_file(path)?);
+    Ok(views)
+}
+
+/// Save `query` as the view `name` in the views file at `path`, replacing any view of
+/// that name
+pub fn save_view(path: &Path, name: &str, query: &str) -> Result<()> {
+    let mut views = read_views_file(path)?;
+    views.insert(name.to_string(), query.to_string());
+    let contents = serde_yaml::to_string(&views).context("Failed to serialize views")?;
+    write_atomic(path, contents)
+        .with_context(|| format!("Failed to write views file: {}", path.display()))
+}
+
+fn read_views_file(path: &Path) -> Result<BTreeMap<String, String>> {
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read views file: {}", path.display()))?;
+    let views: Option<BTreeMap<String, String>> = serde_yaml::from_str(&contents)
+        .with_context(|| format!("Failed to parse views file: {}", path.display()))?;
+    Ok(views.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_views_file_overrides_config() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("views.yaml");
+        let mut config = Config::new();
+        config.views.insert("drafts".to_string(), "tag:draft".to_string());
+        config.views.insert("stale".to_string(), "modified:<30d".to_string());
+
+        assert_eq!(load_views(&config, &path).unwrap(), config.views);
+
+        save_view(&path, "stale", "tag:review modified:<30d").unwrap();
+        save_view(&path, "recent", "modified:>7d").unwrap();
+        let views = load_views(&config, &path).unwrap();
+        assert_eq!(views.len(), 3);
+        assert_eq!(views["drafts"], "tag:draft");
+        assert_eq!(views["stale"], "tag:review modified:<30d");
+        assert_eq!(views["recent"], "modified:>7d");
+
+        // Empty files are fine
+        std::fs::write(&path, "").unwrap();
+        assert_eq!(load_views(&config, &path).unwrap(), config.views);
+    }
+}
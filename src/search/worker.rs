@@ -0,0 +1,326 @@
+//! Background matching for the search popup
+//!
+//! Prompts passing the query's filters are injected into nucleo's worker pool from a
+//! separate thread, and the fuzzy words become nucleo column patterns: the name, the
+//! content, and both together for unscoped words. When typing only extends the fuzzy
+//! words, nucleo narrows its previous matches instead of starting over. The UI thread
+//! only ticks the matcher and details the few results it shows.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use chrono::{DateTime, Utc};
+use nucleo::pattern::{CaseMatching, Normalization};
+use nucleo::{Config as NucleoConfig, Matcher, Nucleo, Status};
+
+use super::query::{Document, Patterns, Query, Snippet};
+use crate::fs::IndexEntry;
+
+/// Nucleo column of the name
+const NAME: usize = 0;
+/// Nucleo column of the content
+const BODY: usize = 1;
+/// Nucleo column of the name and content, for unscoped words
+const TEXT: usize = 2;
+
+/// A prompt found by a [`BackgroundSearch`]
+#[derive(Debug, Clone)]
+pub struct Found<'a> {
+    pub entry: &'a IndexEntry,
+    /// Character indices of the name matched by fuzzy terms
+    pub name_indices: Vec<usize>,
+    /// The content line matching best, when the content matched
+    pub snippet: Option<Snippet>,
+}
+
+/// A query matched against a fixed set of prompts on background threads
+pub struct BackgroundSearch {
+    nucleo: Nucleo<u32>,
+    /// The searched prompts, in name order so that equal scores list by name
+    entries: Arc<Vec<IndexEntry>>,
+    query: Query,
+    /// When the search started; relative times in queries count back from it, so that
+    /// typing does not change a `modified:` filter and restart the injection
+    started: DateTime<Utc>,
+    /// Set to stop the running injection when the filters change
+    canceled: Arc<AtomicBool>,
+    /// Set once the running injection pushed every prompt
+    injected: Arc<AtomicBool>,
+    running: bool,
+    matcher: Matcher,
+}
+
+impl BackgroundSearch {
+    /// Start matching every prompt in `entries` against an empty query
+    pub fn new(mut entries: Vec<IndexEntry>) -> Self {
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        let mut search = Self {
+            nucleo: Nucleo::new(NucleoConfig::DEFAULT, Arc::new(|| {}), None, 3),
+            entries: Arc::new(entries),
+            query: Query::default(),
+            started: Utc::now(),
+            canceled: Arc::new(AtomicBool::new(false)),
+            injected: Arc::new(AtomicBool::new(false)),
+            running: true,
+            matcher: Matcher::new(NucleoConfig::DEFAULT),
+        };
+        search.inject();
+        search
+    }
+
+    /// Parse `input` as of the start of the search
+    pub fn parse(&self, input: &str) -> Query {
+        Query::parse_at(input, self.started)
+    }
+
+    /// Match `query` from now on; changed filters restart the injection, while fuzzy words
+    /// are matched again only in their columns
+    pub fn set_query(&mut self, query: Query) {
+        let columns = [
+            (NAME, &self.query.fuzzy_name, &query.fuzzy_name),
+            (BODY, &self.query.fuzzy_body, &query.fuzzy_body),
+            (TEXT, &self.query.fuzzy, &query.fuzzy),
+        ];
+        for (column, old, new) in columns {
+            if old != new {
+                let append = new.starts_with(old.as_str());
+                self.nucleo.pattern.reparse(
+                    column,
+                    new,
+                    CaseMatching::Smart,
+                    Normalization::Smart,
+                    append,
+                );
+            }
+        }
+
+        let filters_changed = query.terms != self.query.terms;
+        self.query = query;
+        if filters_changed {
+            self.nucleo.restart(false);
+            self.inject();
+        }
+        self.running = true;
+    }
+
+    /// Take in the matcher's progress, waiting at most `timeout_ms`; true when the
+    /// results changed
+    pub fn tick(&mut self, timeout_ms: u64) -> bool {
+        let Status { changed, running } = self.nucleo.tick(timeout_ms);
+        self.running = running || !self.injected.load(Ordering::Relaxed);
+        changed
+    }
+
+    /// Whether matching is still in progress
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// Number of prompts matching so far
+    pub fn count(&self) -> usize {
+        self.nucleo.snapshot().matched_item_count() as usize
+    }
+
+    /// The best `limit` matches so far, with their highlights
+    pub fn results(&mut self, limit: usize) -> Vec<Found<'_>> {
+        let patterns = Patterns::new(&self.query);
+        let snapshot = self.nucleo.snapshot();
+        let end = snapshot.matched_item_count().min(limit as u32);
+
+        snapshot
+            .matched_items(..end)
+            .map(|item| {
+                let entry = &self.entries[*item.data as usize];
+                let (name_indices, snippet) =
+                    patterns.highlight(&Document::from(entry), &mut self.matcher);
+                Found {
+                    entry,
+                    name_indices,
+                    snippet,
+                }
+            })
+            .collect()
+    }
+
+    /// Push the prompts passing the filters from a separate thread
+    fn inject(&mut self) {
+        self.canceled.store(true, Ordering::Relaxed);
+        self.canceled = Arc::new(AtomicBool::new(false));
+        self.injected = Arc::new(AtomicBool::new(false));
+
+        let injector = self.nucleo.injector();
+        let entries = Arc::clone(&self.entries);
+        let query = self.query.clone();
+        let (canceled, injected) = (Arc::clone(&self.canceled), Arc::clone(&self.injected));
+        thread::spawn(move || {
+            for (index, entry) in entries.iter().enumerate() {
+                if canceled.load(Ordering::Relaxed) {
+                    return;
+                }
+                if !query.matches(&Document::from(entry)) {
+                    continue;
+                }
+                injector.push(index as u32, |_, columns| {
+                    columns[NAME] = entry.name.as_str().into();
+                    columns[BODY] = entry.body().into();
+                    columns[TEXT] = format!("{}\n{}", entry.name, entry.body()).into();
+                });
+            }
+            injected.store(true, Ordering::Relaxed);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::search;
+    use std::time::{Duration, Instant};
+
+    fn entry(name: &str, content: &str, tags: &[&str], location: &str) -> IndexEntry {
+        IndexEntry {
+            id: uuid::Uuid::new_v4(),
+            name: name.to_string(),
+            preview: content.lines().next().unwrap_or("").to_string(),
+            content: Some(content.to_string()),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            location: location.to_string(),
+            modified: chrono::Utc::now(),
+            file: None,
+        }
+    }
+
+    /// Tick until matching finishes
+    fn settle(search: &mut BackgroundSearch) {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        search.tick(10);
+        while search.is_running() && Instant::now() < deadline {
+            search.tick(10);
+        }
+    }
+
+    fn names(search: &mut BackgroundSearch, query: &str) -> Vec<String> {
+        search.set_query(Query::parse(query));
+        settle(search);
+        search.results(100).iter().map(|found| found.entry.name.clone()).collect()
+    }
+
+    #[test]
+    fn test_background_search() {
+        let mut search = BackgroundSearch::new(vec![
+            entry("summary", "Summarize the code", &["draft"], "folders/work"),
+            entry("code_review", "Review this diff\nthen the tests", &["coding"], "prompts"),
+            entry("old_review", "Old review", &[], "archive"),
+        ]);
+        settle(&mut search);
+        assert_eq!(search.count(), 2);
+
+        // Typing narrows the matches
+        assert_eq!(names(&mut search, "re"), ["code_review", "summary"]);
+        assert_eq!(names(&mut search, "review"), ["code_review"]);
+        let mut everywhere = names(&mut search, "review in:all");
+        everywhere.sort();
+        assert_eq!(everywhere, ["code_review", "old_review"]);
+        assert_eq!(names(&mut search, "review -tag:coding in:all"), ["old_review"]);
+        assert_eq!(names(&mut search, "body:code"), ["summary"]);
+        assert_eq!(names(&mut search, "folder:work"), ["summary"]);
+
+        // Highlights are worked out for the listed results
+        search.set_query(Query::parse("tests"));
+        settle(&mut search);
+        let results = search.results(10);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].snippet.as_ref().map(|s| s.line), Some(1));
+    }
+
+    #[test]
+    fn test_relative_dates_fixed_for_the_search() {
+        let search = BackgroundSearch::new(Vec::new());
+        let query = search.parse("modified:7d review");
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(search.parse("modified:7d review").terms, query.terms);
+    }
+
+    #[test]
+    fn test_background_search_agrees_with_search() {
+        let entries = vec![
+            entry("code_review", "Summarize the code", &["coding"], "prompts"),
+            entry("summary", "Summarize the review\nstep by step", &["draft"], "folders/work"),
+            entry("review_notes", "Notes on the code", &[], "prompts"),
+            entry("old_review", "Old review", &[], "archive"),
+        ];
+        let mut background = BackgroundSearch::new(entries.clone());
+        let documents: Vec<Document> = entries.iter().map(Document::from).collect();
+
+        for query in [
+            "review summarize",
+            "code",
+            "re",
+            "summary code",
+            "name:review body:code",
+            "review -tag:draft",
+            "review in:all",
+            r#"step "by step""#,
+            "zzz",
+        ] {
+            let expected: Vec<String> = search(&Query::parse(query), &documents)
+                .iter()
+                .map(|hit| documents[hit.index].name.to_string())
+                .collect();
+            assert_eq!(names(&mut background, query), expected, "query: {}", query);
+        }
+    }
+
+    /// Synthetic benchmark: typing a query against 20k prompts.
+    /// Run with `cargo test --release bench_background_search -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn bench_background_search() {
+        let words = ["review", "summarize", "refactor", "explain", "translate", "debug", "plan"];
+        let entries: Vec<IndexEntry> = (0..20_000)
+            .map(|i| {
+                let content: String = (0..20)
+                    .map(|line| {
+                        let word = words[(i + line) % words.len()];
+                        format!("Line {} asks to {} item {} of the codebase.\n", line, word, i)
+                    })
+                    .collect();
+                let location = if i % 3 == 0 { "prompts" } else { "folders/work" };
+                entry(&format!("prompt_{:05}", i), &content, &[words[i % words.len()]], location)
+            })
+            .collect();
+
+        let started = Instant::now();
+        let mut search = BackgroundSearch::new(entries);
+        println!("start: {:?} on the UI thread", started.elapsed());
+        settle(&mut search);
+        println!("all {} prompts matched after {:?}", search.count(), started.elapsed());
+
+        let mut typed = String::new();
+        let mut slowest = Duration::ZERO;
+        for c in "tag:review refactr item 123".chars() {
+            typed.push(c);
+            // What the UI thread does for a keystroke and the frame after it
+            let keystroke = Instant::now();
+            search.set_query(Query::parse(&typed));
+            search.tick(0);
+            let ticked = keystroke.elapsed();
+            search.results(100);
+            let ui = keystroke.elapsed();
+            slowest = slowest.max(ui);
+
+            let settled = Instant::now();
+            settle(&mut search);
+            println!(
+                "{:<28} {:>6} matches  ui {:>9.2?} (tick {:>9.2?})  settled {:>9.2?}",
+                typed,
+                search.count(),
+                ui,
+                ticked,
+                settled.elapsed()
+            );
+        }
+        println!("slowest keystroke on the UI thread: {:?}", slowest);
+    }
+}
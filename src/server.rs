@@ -0,0 +1,880 @@
+//! Local HTTP/JSON API
+//!
+//! `piemme serve` answers plain HTTP/1.1 on 127.0.0.1, one request per
+//! connection. Reads and writes go through `fs::prompt_io` like the TUI does,
+//! and every write must quote the prompt's current `modified` timestamp.
+//!
+//! Web pages can reach a local port too, so every request must carry the bearer
+//! token and name this server in `Host`, and writes must be JSON (which browsers
+//! cannot send cross-origin without a preflight). Cross-origin requests are refused
+//! unless their origin (e.g. a browser extension's) is in `api_allowed_origins`; CORS
+//! preflights from those origins are answered.
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, TcpListener};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::engine::resolve_prompt_with_base;
+use crate::fs::{
+    create_new_prompt, list_subfolders, load_all_prompts, move_prompt, normalize_folder_path,
+    save_prompt, Index, IndexEntry, SaveOptions,
+};
+use crate::models::prompt::normalize_name;
+use crate::models::{Prompt, PromptStatus};
+
+/// Largest request body accepted
+const MAX_BODY_SIZE: usize = 4 * 1024 * 1024;
+
+/// A parsed HTTP request
+#[derive(Debug, Clone, Default)]
+pub struct Request {
+    pub method: String,
+    /// Percent-decoded path segments (e.g. ["prompts", "review", "render"])
+    pub segments: Vec<String>,
+    /// Percent-decoded query parameters
+    pub query: Vec<(String, String)>,
+    /// Headers with lowercased names
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// Get a header by (lowercase) name
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+
+    /// Get a query parameter by name
+    fn query_param(&self, name: &str) -> Option<&str> {
+        self.query.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+
+    /// Parse the body as a JSON object
+    fn json(&self) -> Result<Value, Response> {
+        let value: Value = serde_json::from_slice(&self.body)
+            .map_err(|e| Response::error(400, format!("Invalid JSON body: {}", e)))?;
+        if !value.is_object() {
+            return Err(Response::error(400, "Request body must be a JSON object"));
+        }
+        Ok(value)
+    }
+}
+
+/// Methods the routes answer, announced to CORS preflights
+const ALLOWED_METHODS: &str = "GET, POST, PUT";
+
+/// Request headers cross-origin clients may send, announced to CORS preflights
+const ALLOWED_HEADERS: &str = "Authorization, Content-Type";
+
+/// A JSON response
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub body: Value,
+    /// Headers besides Content-Type, Content-Length and Connection
+    pub headers: Vec<(String, String)>,
+}
+
+impl Response {
+    fn ok(body: Value) -> Self {
+        Self::with_status(200, body)
+    }
+
+    fn with_status(status: u16, body: Value) -> Self {
+        Self {
+            status,
+            body,
+            headers: Vec::new(),
+        }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Self::with_status(status, json!({ "error": message.into() }))
+    }
+
+    fn header(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.headers.push((name.to_string(), value.into()));
+        self
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            201 => "Created",
+            204 => "No Content",
+            400 => "Bad Request",
+            401 => "Unauthorized",
+            403 => "Forbidden",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            409 => "Conflict",
+            415 => "Unsupported Media Type",
+            428 => "Precondition Required",
+            _ => "Internal Server Error",
+        }
+    }
+}
+
+impl From<anyhow::Error> for Response {
+    fn from(error: anyhow::Error) -> Self {
+        Response::error(500, format!("{:#}", error))
+    }
+}
+
+/// A prompt together with the store location it was found in
+struct Located {
+    prompt: Prompt,
+    /// "prompts", "archive" or "folders/<path>" (folders nest, as in "folders/work/backend")
+    location: String,
+}
+
+/// Serves the prompt store rooted at a `.piemme` directory
+pub struct ApiServer {
+    root: PathBuf,
+    /// Port the server listens on, which requests must name in `Host`
+    port: u16,
+    token: Option<String>,
+    /// Origins besides this server's that may call it (e.g. `chrome-extension://<id>`)
+    allowed_origins: Vec<String>,
+    save_options: SaveOptions,
+}
+
+impl ApiServer {
+    /// Create a server for the store at `root` listening on `port`, requiring `token` if given
+    pub fn new(root: impl Into<PathBuf>, port: u16, token: Option<String>) -> Self {
+        Self {
+            root: root.into(),
+            port,
+            token,
+            allowed_origins: Vec::new(),
+            save_options: SaveOptions::default(),
+        }
+    }
+
+    /// Let pages and extensions served from `origins` call the API (with the token)
+    pub fn allow_origins(mut self, origins: Vec<String>) -> Self {
+        self.allowed_origins = origins;
+        self
+    }
+
+    /// Save prompts with `options` (e.g. the configured backup retention)
+    pub fn save_options(mut self, options: SaveOptions) -> Self {
+        self.save_options = options;
+        self
+    }
+
+    /// Handle a request and build its response
+    pub fn handle(&self, request: &Request) -> Response {
+        let cross_origin = match self.check_origin(request) {
+            Ok(origin) => origin,
+            Err(response) => return response,
+        };
+
+        let response = if request.method == "OPTIONS" {
+            // Preflights carry no token; the request they announce must have it
+            Response::with_status(204, Value::Null)
+                .header("Access-Control-Allow-Methods", ALLOWED_METHODS)
+                .header("Access-Control-Allow-Headers", ALLOWED_HEADERS)
+                .header("Access-Control-Max-Age", "600")
+        } else {
+            match self.check_access(request) {
+                Ok(()) => self.route(request),
+                Err(response) => response,
+            }
+        };
+
+        match cross_origin {
+            Some(origin) => response
+                .header("Access-Control-Allow-Origin", origin)
+                .header("Vary", "Origin"),
+            None => response,
+        }
+    }
+
+    /// Answer a request that passed the access checks
+    fn route(&self, request: &Request) -> Response {
+        let segments: Vec<&str> = request.segments.iter().map(String::as_str).collect();
+        let result = match (request.method.as_str(), segments.as_slice()) {
+            ("GET", ["prompts"]) => self.list(request),
+            ("POST", ["prompts"]) => self.create(request),
+            ("GET", ["prompts", name]) => self.get(name),
+            ("PUT", ["prompts", name]) => self.update(name, request),
+            ("GET", ["prompts", name, "render"]) => self.render(name),
+            ("PUT", ["prompts", name, "tags"]) => self.set_tags(name, request),
+            ("POST", ["prompts", name, "move"]) => self.move_to(name, request),
+            ("POST", ["prompts", name, "archive"]) => self.archive(name, request),
+            (_, ["prompts"] | ["prompts", _] | ["prompts", _, _]) => {
+                Err(Response::error(405, "Method not allowed"))
+            }
+            _ => Err(Response::error(404, "Not found")),
+        };
+
+        result.unwrap_or_else(|response| response)
+    }
+
+    /// Refuse requests not addressed to this server (DNS rebinding) or sent from an origin
+    /// that is neither this server's nor allowed; returns the allowed foreign origin, if any
+    fn check_origin(&self, request: &Request) -> Result<Option<String>, Response> {
+        let local = [
+            format!("127.0.0.1:{}", self.port),
+            format!("localhost:{}", self.port),
+        ];
+        if !request.header("host").is_some_and(|host| local.iter().any(|l| l == host)) {
+            return Err(Response::error(403, "Host must be 127.0.0.1 or localhost"));
+        }
+        let Some(origin) = request.header("origin") else {
+            return Ok(None);
+        };
+        if local.iter().any(|l| origin == format!("http://{}", l)) {
+            return Ok(None);
+        }
+        if self.allowed_origins.iter().any(|allowed| allowed == origin) {
+            return Ok(Some(origin.to_string()));
+        }
+        Err(Response::error(403, format!("Origin not allowed: {}", origin)))
+    }
+
+    /// Refuse requests without the token, or writing anything but JSON
+    fn check_access(&self, request: &Request) -> Result<(), Response> {
+        if let Some(token) = &self.token {
+            let expected = format!("Bearer {}", token);
+            let given = request.header("authorization").unwrap_or_default();
+            if !constant_time_eq(given.as_bytes(), expected.as_bytes()) {
+                return Err(Response::error(401, "Missing or invalid token"));
+            }
+        }
+
+        if request.method != "GET" {
+            let json = request.header("content-type").is_some_and(|content_type| {
+                let mime = content_type.split(';').next().unwrap_or_default();
+                mime.trim().eq_ignore_ascii_case("application/json")
+            });
+            if !json {
+                return Err(Response::error(415, "Content-Type must be application/json"));
+            }
+        }
+        Ok(())
+    }
+
+    /// GET /prompts[?tag=TAG]
+    fn list(&self, request: &Request) -> Result<Response, Response> {
+        let tag = request.query_param("tag");
+        let prompts: Vec<Value> = self
+            .all()?
+            .iter()
+            .filter(|l| tag.is_none_or(|t| l.prompt.has_tag(t)))
+            .map(|l| summary(&l.prompt, &l.location))
+            .collect();
+        Ok(Response::ok(json!({ "prompts": prompts })))
+    }
+
+    /// GET /prompts/NAME
+    fn get(&self, name: &str) -> Result<Response, Response> {
+        let located = self.find(name)?;
+        Ok(Response::ok(detail(&located.prompt, &located.location)))
+    }
+
+    /// GET /prompts/NAME/render (commands are listed, never run)
+    fn render(&self, name: &str) -> Result<Response, Response> {
+        let located = self.find(name)?;
+        let all = self.all()?;
+        let get_content = |name: &str| -> Option<String> {
+            all.iter()
+                .find(|l| l.prompt.name == name && l.location != "archive")
+                .map(|l| l.prompt.content.clone())
+        };
+        let base_dir = self.root.parent().unwrap_or(&self.root);
+        let result =
+            resolve_prompt_with_base(&located.prompt.content, get_content, false, base_dir);
+
+        Ok(Response::ok(json!({
+            "name": located.prompt.name,
+            "content": result.content,
+            "references": result.references,
+            "commands": result.commands,
+        })))
+    }
+
+    /// POST /prompts {content, name?, tags?, folder?}
+    fn create(&self, request: &Request) -> Result<Response, Response> {
+        let body = request.json()?;
+        let content = string_field(&body, "content")?.unwrap_or_default();
+        let location = folder_location(&body)?;
+
+        let all = self.all()?;
+        let names: Vec<&str> = all.iter().map(|l| l.prompt.name.as_str()).collect();
+        let mut prompt = create_new_prompt(&content, &names);
+        if let Some(name) = string_field(&body, "name")? {
+            let name = normalize_name(&name);
+            if name.is_empty() {
+                return Err(Response::error(400, "Invalid prompt name"));
+            }
+            if names.contains(&name.as_str()) {
+                return Err(Response::error(409, format!("Prompt already exists: {}", name)));
+            }
+            prompt.name = name;
+        }
+        if prompt.name.is_empty() {
+            return Err(Response::error(400, "Give a name or some content"));
+        }
+        if let Some(tags) = tags_field(&body)? {
+            prompt.tags = tags;
+        }
+
+        self.write(&prompt, &location)?;
+        Ok(Response::with_status(201, detail(&prompt, &location)))
+    }
+
+    /// PUT /prompts/NAME {content, modified}
+    fn update(&self, name: &str, request: &Request) -> Result<Response, Response> {
+        let body = request.json()?;
+        let mut located = self.find_unchanged(name, &body)?;
+        let content = string_field(&body, "content")?
+            .ok_or_else(|| Response::error(400, "Missing field: content"))?;
+
+        located.prompt.content = content;
+        located.prompt.modified = Utc::now();
+        if located.prompt.is_unmanaged() {
+            located.prompt.status = PromptStatus::Managed;
+        }
+        self.write(&located.prompt, &located.location)?;
+        self.reloaded(&located)
+    }
+
+    /// PUT /prompts/NAME/tags {tags, modified}
+    fn set_tags(&self, name: &str, request: &Request) -> Result<Response, Response> {
+        let body = request.json()?;
+        let mut located = self.find_unchanged(name, &body)?;
+        if located.prompt.broken_error().is_some() {
+            return Err(Response::error(409, "Prompt frontmatter is broken; repair it first"));
+        }
+
+        located.prompt.tags =
+            tags_field(&body)?.ok_or_else(|| Response::error(400, "Missing field: tags"))?;
+        located.prompt.modified = Utc::now();
+        located.prompt.status = PromptStatus::Managed;
+        self.write(&located.prompt, &located.location)?;
+        self.reloaded(&located)
+    }
+
+    /// POST /prompts/NAME/move {folder, modified}; a null folder moves to the main list
+    fn move_to(&self, name: &str, request: &Request) -> Result<Response, Response> {
+        let body = request.json()?;
+        let located = self.find_unchanged(name, &body)?;
+        let location = folder_location(&body)?;
+        self.relocate(located, location)
+    }
+
+    /// POST /prompts/NAME/archive {modified}
+    fn archive(&self, name: &str, request: &Request) -> Result<Response, Response> {
+        let body = request.json()?;
+        let located = self.find_unchanged(name, &body)?;
+        self.relocate(located, "archive".to_string())
+    }
+
+    fn relocate(&self, mut located: Located, location: String) -> Result<Response, Response> {
+        if located.location != location {
+            move_prompt(
+                &located.prompt.name,
+                &self.root.join(&located.location),
+                &self.root.join(&location),
+            )?;
+            let from = std::mem::replace(&mut located.location, location);
+            self.update_index(&located.prompt, &located.location, Some(&from))?;
+        }
+        Ok(Response::ok(detail(&located.prompt, &located.location)))
+    }
+
+    /// Load every prompt in the store with its location
+    fn all(&self) -> Result<Vec<Located>, Response> {
+        let mut locations = vec!["prompts".to_string(), "archive".to_string()];
+        let folders = list_subfolders(&self.root.join("folders"))?;
+        locations.extend(folders.into_iter().map(|folder| format!("folders/{}", folder)));
+
+        let mut all = Vec::new();
+        for location in locations {
+            for prompt in load_all_prompts(&self.root.join(&location))? {
+                all.push(Located {
+                    prompt,
+                    location: location.clone(),
+                });
+            }
+        }
+        Ok(all)
+    }
+
+    fn find(&self, name: &str) -> Result<Located, Response> {
+        self.all()?
+            .into_iter()
+            .find(|l| l.prompt.name == name)
+            .ok_or_else(|| Response::error(404, format!("Prompt not found: {}", name)))
+    }
+
+    /// Find a prompt for writing, checking the request quotes its current `modified`
+    fn find_unchanged(&self, name: &str, body: &Value) -> Result<Located, Response> {
+        let located = self.find(name)?;
+        let quoted = body
+            .get("modified")
+            .and_then(Value::as_str)
+            .ok_or_else(|| Response::error(428, "Missing field: modified"))?;
+        let quoted: DateTime<Utc> = quoted
+            .parse()
+            .map_err(|_| Response::error(400, format!("Invalid timestamp: {}", quoted)))?;
+
+        if quoted != located.prompt.modified {
+            return Err(Response::with_status(
+                409,
+                json!({
+                    "error": "Prompt was modified since it was read",
+                    "current": detail(&located.prompt, &located.location),
+                }),
+            ));
+        }
+        Ok(located)
+    }
+
+    fn write(&self, prompt: &Prompt, location: &str) -> Result<(), Response> {
+        save_prompt(prompt, &self.root.join(location), self.save_options)?;
+        self.update_index(prompt, location, None)
+    }
+
+    /// Respond with the prompt as now stored on disk
+    fn reloaded(&self, located: &Located) -> Result<Response, Response> {
+        let current = self.find(&located.prompt.name)?;
+        Ok(Response::ok(detail(&current.prompt, &current.location)))
+    }
+
+    /// Record `prompt` in the index at `location`, dropping its entry at `moved_from`
+    fn update_index(
+        &self,
+        prompt: &Prompt,
+        location: &str,
+        moved_from: Option<&str>,
+    ) -> Result<(), Response> {
+        let index_path = self.root.join(".index.json");
+        let mut index = Index::load_or_new(&index_path)?;
+        if let Some(from) = moved_from {
+            index.remove(from, &prompt.name);
+        }
+        index.upsert(IndexEntry::from_prompt(prompt, location));
+        index.save(&index_path)?;
+        Ok(())
+    }
+}
+
+/// Compare secrets without returning at the first differing byte, so response times do
+/// not reveal how much of a guessed token was right
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Metadata returned in listings
+fn summary(prompt: &Prompt, location: &str) -> Value {
+    let status = match &prompt.status {
+        PromptStatus::Managed => "managed",
+        PromptStatus::Unmanaged => "unmanaged",
+        PromptStatus::Broken { .. } => "broken",
+    };
+    json!({
+        "id": prompt.id,
+        "name": prompt.name,
+        "tags": prompt.tags,
+        "created": prompt.created,
+        "modified": prompt.modified,
+        "location": location,
+        "status": status,
+    })
+}
+
+/// Metadata plus raw content
+fn detail(prompt: &Prompt, location: &str) -> Value {
+    let mut value = summary(prompt, location);
+    value["content"] = json!(prompt.content);
+    if let Some(error) = prompt.broken_error() {
+        value["error"] = json!(error);
+    }
+    value
+}
+
+fn string_field(body: &Value, field: &str) -> Result<Option<String>, Response> {
+    match body.get(field) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(s)) => Ok(Some(s.clone())),
+        Some(_) => Err(Response::error(400, format!("Field '{}' must be a string", field))),
+    }
+}
+
+fn tags_field(body: &Value) -> Result<Option<Vec<String>>, Response> {
+    match body.get("tags") {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|item| item.as_str().map(str::to_string))
+            .collect::<Option<Vec<_>>>()
+            .map(Some)
+            .ok_or_else(|| Response::error(400, "Field 'tags' must be an array of strings")),
+        Some(_) => Err(Response::error(400, "Field 'tags' must be an array of strings")),
+    }
+}
+
+/// Location named by the body's `folder` field ("prompts" when absent or null)
+fn folder_location(body: &Value) -> Result<String, Response> {
+    match string_field(body, "folder")? {
+        None => Ok("prompts".to_string()),
+        Some(folder) if !folder.is_empty() && normalize_folder_path(&folder) == folder => {
+            Ok(format!("folders/{}", folder))
+        }
+        Some(folder) => Err(Response::error(400, format!("Invalid folder name: {}", folder))),
+    }
+}
+
+/// Read one HTTP request from a connection
+pub fn read_request(reader: &mut impl BufRead) -> Result<Request> {
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        bail!("Malformed request line");
+    };
+
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let mut request = Request {
+        method: method.to_string(),
+        segments: path
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .map(percent_decode)
+            .collect(),
+        query: query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+                (percent_decode(name), percent_decode(value))
+            })
+            .collect(),
+        ..Request::default()
+    };
+
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            request.headers.push((name.trim().to_lowercase(), value.trim().to_string()));
+        }
+    }
+
+    let length: usize = match request.header("content-length") {
+        Some(value) => value.parse().context("Invalid Content-Length")?,
+        None => 0,
+    };
+    if length > MAX_BODY_SIZE {
+        bail!("Request body too large");
+    }
+    request.body = vec![0; length];
+    reader.read_exact(&mut request.body)?;
+
+    Ok(request)
+}
+
+/// Write a response and close the exchange
+pub fn write_response(writer: &mut impl Write, response: &Response) -> Result<()> {
+    let body = match response.status {
+        204 => String::new(),
+        _ => serde_json::to_string_pretty(&response.body)?,
+    };
+    let headers: String = response
+        .headers
+        .iter()
+        .map(|(name, value)| format!("{}: {}\r\n", name, value))
+        .collect();
+    write!(
+        writer,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n{}\
+         Connection: close\r\n\r\n{}",
+        response.status,
+        response.reason(),
+        body.len(),
+        headers,
+        body
+    )?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Decode %XX escapes (and '+' as space) in a URL component
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+                match hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                    Some(byte) => {
+                        out.push(byte);
+                        i += 3;
+                        continue;
+                    }
+                    None => out.push(b'%'),
+                }
+            }
+            b'+' => out.push(b' '),
+            byte => out.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Accept connections on 127.0.0.1:`port` until the process is stopped, requiring `token`
+/// (a random one, printed, when not given) and answering the origins `config` allows
+pub fn serve(root: &Path, port: u16, token: Option<String>, config: &Config) -> Result<()> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))
+        .with_context(|| format!("Failed to bind 127.0.0.1:{}", port))?;
+    let port = listener.local_addr()?.port();
+    eprintln!("Serving {} on http://127.0.0.1:{}", root.display(), port);
+    let token = token.unwrap_or_else(|| {
+        let token = Uuid::new_v4().simple().to_string();
+        eprintln!("Token: {}", token);
+        token
+    });
+    let server = ApiServer::new(root, port, Some(token))
+        .allow_origins(config.api_allowed_origins.clone())
+        .save_options(config.save_options());
+
+    for stream in listener.incoming() {
+        let mut stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("error: {}", e);
+                continue;
+            }
+        };
+        stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+
+        let response = match read_request(&mut BufReader::new(&stream)) {
+            Ok(request) => server.handle(&request),
+            Err(e) => Response::error(400, format!("{:#}", e)),
+        };
+        if let Err(e) = write_response(&mut stream, &response) {
+            eprintln!("error: {:#}", e);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const PORT: u16 = 7411;
+
+    fn request(method: &str, path: &str, body: Value) -> Request {
+        let raw = format!(
+            "{} {} HTTP/1.1\r\nHost: 127.0.0.1:{}\r\nContent-Type: application/json\r\n\
+             Content-Length: {}\r\n\r\n{}",
+            method,
+            path,
+            PORT,
+            body.to_string().len(),
+            body
+        );
+        read_request(&mut raw.as_bytes()).unwrap()
+    }
+
+    /// `request` with header `name` replaced by `value`, or removed when None
+    fn with_header(mut request: Request, name: &str, value: Option<&str>) -> Request {
+        request.headers.retain(|(n, _)| n != name);
+        if let Some(value) = value {
+            request.headers.push((name.to_string(), value.to_string()));
+        }
+        request
+    }
+
+    #[test]
+    fn test_read_request() {
+        let raw = "GET /prompts/code%20review?tag=a+b HTTP/1.1\r\n\
+                   Authorization: Bearer x\r\n\r\n";
+        let request = read_request(&mut raw.as_bytes()).unwrap();
+        assert_eq!(request.method, "GET");
+        assert_eq!(request.segments, vec!["prompts", "code review"]);
+        assert_eq!(request.query_param("tag"), Some("a b"));
+        assert_eq!(request.header("authorization"), Some("Bearer x"));
+    }
+
+    #[test]
+    fn test_create_get_and_render() {
+        let dir = tempdir().unwrap();
+        let server = ApiServer::new(dir.path(), PORT, None);
+
+        let body = json!({"content": "World", "name": "name", "folder": "words"});
+        let created = server.handle(&request("POST", "/prompts", body));
+        assert_eq!(created.status, 201);
+        assert_eq!(created.body["location"], "folders/words");
+
+        let body = json!({"content": "Hello [[name]] {{date}}", "tags": ["demo"]});
+        let created = server.handle(&request("POST", "/prompts", body));
+        let name = created.body["name"].as_str().unwrap();
+
+        let path = format!("/prompts/{}/render", name);
+        let rendered = server.handle(&request("GET", &path, json!({})));
+        assert_eq!(rendered.body["content"], "Hello World {{date}}");
+        assert_eq!(rendered.body["commands"][0], "date");
+
+        let list = server.handle(&request("GET", "/prompts?tag=demo", json!({})));
+        assert_eq!(list.body["prompts"].as_array().unwrap().len(), 1);
+
+        let missing = server.handle(&request("GET", "/prompts/nope", json!({})));
+        assert_eq!(missing.status, 404);
+    }
+
+    #[test]
+    fn test_writes_need_current_modified() {
+        let dir = tempdir().unwrap();
+        let server = ApiServer::new(dir.path(), PORT, None);
+        let body = json!({"name": "p", "content": "v1"});
+        let created = server.handle(&request("POST", "/prompts", body));
+        let modified = created.body["modified"].clone();
+
+        let unquoted = server.handle(&request("PUT", "/prompts/p", json!({"content": "v2"})));
+        assert_eq!(unquoted.status, 428);
+
+        let body = json!({"content": "v2", "modified": modified});
+        let updated = server.handle(&request("PUT", "/prompts/p", body.clone()));
+        assert_eq!(updated.status, 200);
+        assert_eq!(updated.body["content"], "v2");
+
+        // The same stale timestamp now conflicts and returns the current prompt
+        let stale = server.handle(&request("PUT", "/prompts/p", body));
+        assert_eq!(stale.status, 409);
+        assert_eq!(stale.body["current"]["content"], "v2");
+
+        let modified = updated.body["modified"].clone();
+        let body = json!({"tags": ["x"], "modified": modified});
+        let tagged = server.handle(&request("PUT", "/prompts/p/tags", body));
+        assert_eq!(tagged.body["tags"][0], "x");
+
+        let body = json!({"modified": tagged.body["modified"]});
+        let archived = server.handle(&request("POST", "/prompts/p/archive", body));
+        assert_eq!(archived.body["location"], "archive");
+        assert!(dir.path().join("archive/p.md").exists());
+
+        let body = json!({"folder": "kept", "modified": archived.body["modified"]});
+        let moved = server.handle(&request("POST", "/prompts/p/move", body));
+        assert_eq!(moved.body["location"], "folders/kept");
+
+        let index = Index::load(&dir.path().join(".index.json")).unwrap();
+        assert_eq!(index.find("p").unwrap().location, "folders/kept");
+        assert_eq!(index.entries.len(), 1);
+    }
+
+    #[test]
+    fn test_token_required() {
+        let dir = tempdir().unwrap();
+        let server = ApiServer::new(dir.path(), PORT, Some("secret".to_string()));
+        assert_eq!(server.handle(&request("GET", "/prompts", json!({}))).status, 401);
+
+        let mut authorized = request("GET", "/prompts", json!({}));
+        authorized.headers.push(("authorization".to_string(), "Bearer secret".to_string()));
+        assert_eq!(server.handle(&authorized).status, 200);
+    }
+
+    #[test]
+    fn test_rejects_foreign_host() {
+        let dir = tempdir().unwrap();
+        let server = ApiServer::new(dir.path(), PORT, None);
+        let list = || request("GET", "/prompts", json!({}));
+        assert_eq!(server.handle(&list()).status, 200);
+        let local = with_header(list(), "host", Some("localhost:7411"));
+        assert_eq!(server.handle(&local).status, 200);
+
+        for host in [None, Some("evil.example:7411"), Some("127.0.0.1:8080"), Some("127.0.0.1")] {
+            let rebound = with_header(list(), "host", host);
+            assert_eq!(server.handle(&rebound).status, 403, "host: {:?}", host);
+        }
+    }
+
+    #[test]
+    fn test_rejects_foreign_origin() {
+        let dir = tempdir().unwrap();
+        let server = ApiServer::new(dir.path(), PORT, None);
+        let create = || request("POST", "/prompts", json!({"content": "x"}));
+
+        let same = with_header(create(), "origin", Some("http://localhost:7411"));
+        assert_eq!(server.handle(&same).status, 201);
+        let foreign = with_header(create(), "origin", Some("https://evil.example"));
+        assert_eq!(server.handle(&foreign).status, 403);
+        let other_port = with_header(create(), "origin", Some("http://127.0.0.1:3000"));
+        assert_eq!(server.handle(&other_port).status, 403);
+        let extension = with_header(create(), "origin", Some("chrome-extension://abc"));
+        assert_eq!(server.handle(&extension).status, 403);
+    }
+
+    #[test]
+    fn test_allowed_origins_and_preflight() {
+        let dir = tempdir().unwrap();
+        let extension = "moz-extension://abc";
+        let server = ApiServer::new(dir.path(), PORT, Some("secret".to_string()))
+            .allow_origins(vec![extension.to_string()]);
+        let allow_origin = |response: &Response| {
+            let header = response.headers.iter().find(|(n, _)| n == "Access-Control-Allow-Origin");
+            header.map(|(_, value)| value.clone())
+        };
+
+        // Preflights carry no token and are answered for allowed origins only
+        let preflight = request("OPTIONS", "/prompts", json!({}));
+        let preflight = with_header(preflight, "origin", Some(extension));
+        let answered = server.handle(&preflight);
+        assert_eq!(answered.status, 204);
+        assert_eq!(allow_origin(&answered).as_deref(), Some(extension));
+        let foreign = with_header(preflight.clone(), "origin", Some("https://evil.example"));
+        assert_eq!(server.handle(&foreign).status, 403);
+
+        // The request itself still needs the token
+        let create = request("POST", "/prompts", json!({"content": "x"}));
+        let create = with_header(create, "origin", Some(extension));
+        let refused = server.handle(&create);
+        assert_eq!(refused.status, 401);
+        assert_eq!(allow_origin(&refused).as_deref(), Some(extension));
+        let create = with_header(create, "authorization", Some("Bearer secret"));
+        assert_eq!(server.handle(&create).status, 201);
+        let wrong = with_header(create, "authorization", Some("Bearer secreT"));
+        assert_eq!(server.handle(&wrong).status, 401);
+
+        let mut written = Vec::new();
+        write_response(&mut written, &answered).unwrap();
+        let written = String::from_utf8(written).unwrap();
+        assert!(written.contains("Access-Control-Allow-Headers: Authorization, Content-Type\r\n"));
+        assert!(written.ends_with("\r\n\r\n"));
+    }
+
+    #[test]
+    fn test_writes_require_json() {
+        let dir = tempdir().unwrap();
+        let server = ApiServer::new(dir.path(), PORT, None);
+        let create = || request("POST", "/prompts", json!({"content": "x"}));
+
+        let charset = Some("application/json; charset=utf-8");
+        let charset = with_header(create(), "content-type", charset);
+        assert_eq!(server.handle(&charset).status, 201);
+        for content_type in [None, Some("text/plain"), Some("application/x-www-form-urlencoded")] {
+            let form = with_header(create(), "content-type", content_type);
+            assert_eq!(server.handle(&form).status, 415, "content type: {:?}", content_type);
+        }
+        // Reads carry no body and need no content type
+        let list = with_header(request("GET", "/prompts", json!({})), "content-type", None);
+        assert_eq!(server.handle(&list).status, 200);
+    }
+}
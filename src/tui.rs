@@ -2,21 +2,39 @@
 
 use anyhow::Result;
 use crossterm::{
-    event::{DisableMouseCapture, EnableMouseCapture},
+    event::{
+        DisableMouseCapture, EnableMouseCapture, KeyboardEnhancementFlags,
+        PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+    },
     execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    terminal::{
+        disable_raw_mode, enable_raw_mode, supports_keyboard_enhancement, EnterAlternateScreen,
+        LeaveAlternateScreen,
+    },
 };
 use ratatui::{backend::CrosstermBackend, Terminal};
 use std::io::{self, Stdout};
+use std::sync::atomic::{AtomicBool, Ordering};
 
 /// Type alias for our terminal
 pub type Tui = Terminal<CrosstermBackend<Stdout>>;
 
+/// Whether keyboard enhancement flags were pushed and must be popped on restore
+static ENHANCED_KEYS: AtomicBool = AtomicBool::new(false);
+
 /// Initialize the terminal for TUI rendering
 pub fn init_terminal() -> Result<Tui> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    // Lets terminals that support it tell Ctrl+Shift+p from Ctrl+p
+    if supports_keyboard_enhancement().unwrap_or(false) {
+        execute!(
+            stdout,
+            PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES)
+        )?;
+        ENHANCED_KEYS.store(true, Ordering::Relaxed);
+    }
     let backend = CrosstermBackend::new(stdout);
     let terminal = Terminal::new(backend)?;
     Ok(terminal)
@@ -24,6 +42,9 @@ pub fn init_terminal() -> Result<Tui> {
 
 /// Restore the terminal to its original state
 pub fn restore_terminal() -> Result<()> {
+    if ENHANCED_KEYS.swap(false, Ordering::Relaxed) {
+        execute!(io::stdout(), PopKeyboardEnhancementFlags)?;
+    }
     disable_raw_mode()?;
     execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture)?;
     Ok(())
@@ -31,6 +52,9 @@ pub fn restore_terminal() -> Result<()> {
 
 /// Restore terminal and return a result (for use in panic handlers)
 pub fn restore_terminal_on_panic() {
+    if ENHANCED_KEYS.swap(false, Ordering::Relaxed) {
+        let _ = execute!(io::stdout(), PopKeyboardEnhancementFlags);
+    }
     let _ = disable_raw_mode();
     let _ = execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture);
 }
@@ -0,0 +1,155 @@
+//! Colors fitted to what the terminal can show
+//!
+//! Tag and theme colors may be `#rrggbb` or 256-color indices, which not every terminal
+//! supports. Every tag is colored through [`tag_color`], which looks the color up in the config
+//! and falls back to the nearest color the terminal has; themes are fitted the same way. When
+//! `NO_COLOR` is set, no colors are used at all.
+
+use ratatui::style::Color;
+use std::sync::OnceLock;
+
+use crate::config::{parse_color, Config};
+
+/// Colors a terminal can show
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorSupport {
+    /// No colors (`NO_COLOR` is set)
+    NoColor,
+    /// The 16 ANSI colors
+    Basic,
+    /// The 256-color palette
+    Ansi256,
+    /// Any RGB color
+    TrueColor,
+}
+
+impl ColorSupport {
+    /// Tell what the terminal supports from `NO_COLOR`, `COLORTERM` and `TERM`
+    pub fn from_env(no_color: Option<&str>, colorterm: Option<&str>, term: Option<&str>) -> Self {
+        let term = term.unwrap_or_default();
+        if no_color.is_some_and(|value| !value.is_empty()) {
+            Self::NoColor
+        } else if matches!(colorterm, Some("truecolor" | "24bit")) || term.ends_with("-direct") {
+            Self::TrueColor
+        } else if term.contains("256color") {
+            Self::Ansi256
+        } else {
+            Self::Basic
+        }
+    }
+}
+
+/// What the current terminal supports, read from the environment once
+pub(super) fn color_support() -> ColorSupport {
+    static SUPPORT: OnceLock<ColorSupport> = OnceLock::new();
+    *SUPPORT.get_or_init(|| {
+        let no_color = std::env::var("NO_COLOR").ok();
+        let colorterm = std::env::var("COLORTERM").ok();
+        let term = std::env::var("TERM").ok();
+        ColorSupport::from_env(no_color.as_deref(), colorterm.as_deref(), term.as_deref())
+    })
+}
+
+/// Color of `tag` (or of its closest parent with one) from the config, fitted to the terminal
+pub fn tag_color(config: &Config, tag: &str) -> Color {
+    let color = parse_color(config.get_tag_color(tag)).unwrap_or(Color::White);
+    fit_color(color, color_support())
+}
+
+/// The 16 ANSI colors with their usual (xterm) RGB values
+const BASIC_COLORS: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// Replace `color` by the closest one the terminal supports
+pub fn fit_color(color: Color, support: ColorSupport) -> Color {
+    match (support, color) {
+        (ColorSupport::NoColor, _) => Color::Reset,
+        (ColorSupport::TrueColor, _) => color,
+        (ColorSupport::Ansi256, Color::Rgb(r, g, b)) => {
+            let index = (16..=255).min_by_key(|&i| distance(palette_rgb(i), (r, g, b)));
+            Color::Indexed(index.unwrap_or(15))
+        }
+        (ColorSupport::Basic, Color::Rgb(r, g, b)) => nearest_basic((r, g, b)),
+        (ColorSupport::Basic, Color::Indexed(index)) => match BASIC_COLORS.get(index as usize) {
+            Some((basic, _)) => *basic,
+            None => nearest_basic(palette_rgb(index)),
+        },
+        _ => color,
+    }
+}
+
+fn nearest_basic(rgb: (u8, u8, u8)) -> Color {
+    BASIC_COLORS
+        .iter()
+        .min_by_key(|(_, basic)| distance(*basic, rgb))
+        .map_or(Color::White, |(color, _)| *color)
+}
+
+/// RGB value of a 256-color palette entry
+fn palette_rgb(index: u8) -> (u8, u8, u8) {
+    const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+    match index {
+        0..=15 => BASIC_COLORS[index as usize].1,
+        16..=231 => {
+            let i = index - 16;
+            (LEVELS[(i / 36) as usize], LEVELS[(i / 6 % 6) as usize], LEVELS[(i % 6) as usize])
+        }
+        _ => {
+            let level = 8 + 10 * (index - 232);
+            (level, level, level)
+        }
+    }
+}
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let d = |x: u8, y: u8| (x as i32 - y as i32).pow(2) as u32;
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_color_support_from_env() {
+        let from_env = ColorSupport::from_env;
+        assert_eq!(from_env(None, Some("truecolor"), Some("xterm")), ColorSupport::TrueColor);
+        assert_eq!(from_env(None, None, Some("xterm-direct")), ColorSupport::TrueColor);
+        assert_eq!(from_env(None, None, Some("xterm-256color")), ColorSupport::Ansi256);
+        assert_eq!(from_env(None, None, Some("linux")), ColorSupport::Basic);
+        assert_eq!(from_env(None, None, None), ColorSupport::Basic);
+        assert_eq!(from_env(Some("1"), Some("truecolor"), None), ColorSupport::NoColor);
+        assert_eq!(from_env(Some(""), None, Some("linux")), ColorSupport::Basic);
+    }
+
+    #[test]
+    fn test_fit_color() {
+        let orange = Color::Rgb(255, 135, 0);
+        assert_eq!(fit_color(orange, ColorSupport::TrueColor), orange);
+        assert_eq!(fit_color(orange, ColorSupport::Ansi256), Color::Indexed(208));
+        assert_eq!(fit_color(orange, ColorSupport::Basic), Color::Yellow);
+        assert_eq!(fit_color(Color::Rgb(30, 30, 30), ColorSupport::Ansi256), Color::Indexed(234));
+
+        assert_eq!(fit_color(Color::Indexed(208), ColorSupport::Ansi256), Color::Indexed(208));
+        assert_eq!(fit_color(Color::Indexed(9), ColorSupport::Basic), Color::LightRed);
+        assert_eq!(fit_color(Color::Indexed(21), ColorSupport::Basic), Color::Blue);
+        assert_eq!(fit_color(Color::Cyan, ColorSupport::Basic), Color::Cyan);
+        assert_eq!(fit_color(orange, ColorSupport::NoColor), Color::Reset);
+    }
+}
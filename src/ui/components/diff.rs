@@ -0,0 +1,25 @@
+//! Line diff rendering
+
+use ratatui::{
+    style::Style,
+    text::{Line, Span},
+};
+use similar::{ChangeTag, TextDiff};
+
+use crate::ui::Theme;
+
+/// Render a line diff from `old` to `new`, coloring removed and added lines
+pub fn diff_lines(old: &str, new: &str, theme: &Theme) -> Vec<Line<'static>> {
+    TextDiff::from_lines(old, new)
+        .iter_all_changes()
+        .map(|change| {
+            let (sign, style) = match change.tag() {
+                ChangeTag::Delete => ("-", Style::default().fg(theme.diff_removed)),
+                ChangeTag::Insert => ("+", Style::default().fg(theme.diff_added)),
+                ChangeTag::Equal => (" ", Style::default().fg(theme.muted)),
+            };
+            let text = change.value().trim_end_matches('\n');
+            Line::from(Span::styled(format!("{} {}", sign, text), style))
+        })
+        .collect()
+}
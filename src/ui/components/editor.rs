@@ -1,14 +1,17 @@
 //! Editor component wrapping tui-textarea
 
+use std::collections::HashMap;
+
 use ratatui::{
     layout::Rect,
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
     Frame,
 };
 use tui_textarea::{CursorMove, Input, TextArea};
 
-use crate::config::Config;
+use crate::ui::Theme;
+use crate::engine::split_revision;
 
 /// Editor component wrapping tui-textarea with syntax highlighting support
 pub struct Editor<'a> {
@@ -20,6 +23,8 @@ pub struct Editor<'a> {
     title: String,
     /// Mode indicator (e.g., "[EDITING]", "[PREVIEW]")
     mode_indicator: Option<String>,
+    /// Colors of the borders and syntax highlighting
+    theme: Theme,
 }
 
 impl<'a> Editor<'a> {
@@ -34,13 +39,13 @@ impl<'a> Editor<'a> {
 
         // Configure default style
         textarea.set_cursor_line_style(Style::default());
-        textarea.set_line_number_style(Style::default().fg(Color::DarkGray));
 
         Self {
             textarea,
             focused: false,
             title: String::new(),
             mode_indicator: None,
+            theme: Theme::default(),
         }
     }
 
@@ -88,10 +93,11 @@ impl<'a> Editor<'a> {
     /// Apply the current styling based on focus state
     fn apply_styling(&mut self) {
         let border_color = if self.focused {
-            Color::Cyan
+            self.theme.accent
         } else {
-            Color::DarkGray
+            self.theme.muted
         };
+        self.textarea.set_line_number_style(Style::default().fg(self.theme.muted));
 
         let block = ratatui::widgets::Block::default()
             .title(self.full_title())
@@ -183,22 +189,34 @@ impl<'a> Editor<'a> {
     }
 
     /// Render the editor in the given area
-    pub fn render(&mut self, frame: &mut Frame, area: Rect, existing_prompts: &[&str], _config: &Config) {
+    pub fn render(
+        &mut self,
+        frame: &mut Frame,
+        area: Rect,
+        existing_prompts: &[&str],
+        pinned_refs: &HashMap<String, bool>,
+        theme: &Theme,
+    ) {
+        self.theme = theme.clone();
         self.apply_styling();
         
         // Apply syntax highlighting to the content
-        self.apply_syntax_highlighting(existing_prompts);
+        self.apply_syntax_highlighting(existing_prompts, pinned_refs);
         
         frame.render_widget(&self.textarea, area);
     }
 
     /// Apply syntax highlighting for references and commands
-    fn apply_syntax_highlighting(&mut self, existing_prompts: &[&str]) {
+    fn apply_syntax_highlighting(
+        &mut self,
+        existing_prompts: &[&str],
+        pinned_refs: &HashMap<String, bool>,
+    ) {
         let _lines: Vec<Line> = self
             .textarea
             .lines()
             .iter()
-            .map(|line| highlight_line(line, existing_prompts))
+            .map(|line| highlight_line(line, existing_prompts, pinned_refs, &self.theme))
             .collect();
 
         self.textarea.set_style(Style::default());
@@ -210,7 +228,12 @@ impl<'a> Editor<'a> {
 }
 
 /// Highlight a single line of content with references and commands
-fn highlight_line<'a>(line: &'a str, existing_prompts: &[&str]) -> Line<'a> {
+fn highlight_line<'a>(
+    line: &'a str,
+    existing_prompts: &[&str],
+    pinned_refs: &HashMap<String, bool>,
+    theme: &Theme,
+) -> Line<'a> {
     use ratatui::text::Span;
 
     let mut spans = Vec::new();
@@ -228,15 +251,21 @@ fn highlight_line<'a>(line: &'a str, existing_prompts: &[&str]) -> Line<'a> {
                 let full_ref = &line[current_pos..end + 2];
 
                 // Determine if reference is valid
-                let is_valid = if ref_name.starts_with("file:") {
+                let is_valid = if let Some(&exists) = pinned_refs.get(ref_name) {
+                    // Pinned references were checked against git when the content changed
+                    exists
+                } else if ref_name.starts_with("file:") {
                     // For file references, check if the file exists
                     let file_path = &ref_name[5..]; // Strip "file:" prefix
                     std::path::Path::new(file_path).exists()
+                } else if split_revision(ref_name).1.is_some() {
+                    // A pinned reference not checked yet
+                    false
                 } else {
                     // For prompt references, check against existing prompts
                     existing_prompts.contains(&ref_name)
                 };
-                let color = if is_valid { Color::Green } else { Color::Red };
+                let color = if is_valid { theme.reference } else { theme.broken_reference };
 
                 spans.push(Span::styled(
                     full_ref.to_string(),
@@ -264,12 +293,12 @@ fn highlight_line<'a>(line: &'a str, existing_prompts: &[&str]) -> Line<'a> {
                 // Add warning indicator before commands
                 spans.push(Span::styled(
                     "⚠ ",
-                    Style::default().fg(Color::LightRed),
+                    Style::default().fg(theme.command_marker),
                 ));
                 spans.push(Span::styled(
                     full_cmd.to_string(),
                     Style::default()
-                        .fg(Color::Yellow)
+                        .fg(theme.command)
                         .add_modifier(Modifier::BOLD),
                 ));
 
@@ -1,5 +1,6 @@
 //! UI Components
 
+mod diff;
 mod editor;
 mod help;
 mod popup;
@@ -7,12 +8,13 @@ mod prompt_list;
 mod status_bar;
 mod title_bar;
 
+pub use diff::diff_lines;
 pub use editor::Editor;
 pub use help::{render_help_overlay, get_help_max_scroll};
 pub use popup::{
-    centered_rect, render_confirm_dialog, render_file_picker_popup, render_folder_selector, render_popup_frame,
-    render_reference_popup, render_rename_popup, render_search_popup, render_tag_selector,
-    PopupConfig,
+    centered_rect, render_backup_picker, render_blame_popup, render_command_palette, render_commit_popup, render_confirm_dialog, render_conflict_popup, render_export_popup, render_file_picker_popup, render_folder_selector, render_history_popup, render_popup_frame,
+    render_reference_popup, render_rename_popup, render_search_popup, render_tag_filter_popup,
+    render_tag_selector, PopupConfig,
 };
 pub use prompt_list::render_prompt_list;
 pub use status_bar::render_status_bar;
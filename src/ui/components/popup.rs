@@ -4,11 +4,15 @@ use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
     Frame,
 };
 
-use crate::models::{FilePickerPopupState, FolderSelectorState, ReferencePopupState, RenamePopupState, SearchPopupState, TagSelectorState};
+use crate::config::Config;
+use crate::fs::ExportFormat;
+use crate::ui::{tag_color, Theme};
+use super::diff_lines;
+use crate::models::{BackupPickerState, BlameState, CommandPaletteState, CommitPopupState, ConflictState, ExportField, ExportPopupState, FilePickerPopupState, FolderEdit, FolderSelectorState, HistoryState, ReferencePopupState, RenamePopupState, SearchPopupState, TagFilterPopupState, TagFilterState, TagSelectorEdit, TagSelectorState};
 
 /// Configuration for a popup
 pub struct PopupConfig {
@@ -80,10 +84,11 @@ pub fn render_confirm_dialog(
     title: &str,
     message: &str,
     confirm_selected: bool,
+    theme: &Theme,
 ) {
     let config = PopupConfig::new(title)
         .with_size(50, 25)
-        .with_border_color(Color::Yellow);
+        .with_border_color(theme.highlight);
     
     let popup_area = centered_rect(config.width_percent, config.height_percent, area);
     
@@ -117,30 +122,26 @@ pub fn render_confirm_dialog(
     frame.render_widget(message_paragraph, chunks[0]);
     
     // Render buttons
-    let buttons = render_dialog_buttons(confirm_selected);
+    let buttons = render_dialog_buttons(confirm_selected, theme);
     let buttons_paragraph = Paragraph::new(buttons)
         .alignment(Alignment::Center);
     frame.render_widget(buttons_paragraph, chunks[1]);
 }
 
 /// Render the Yes/No buttons for confirmation dialogs
-fn render_dialog_buttons(confirm_selected: bool) -> Vec<Line<'static>> {
+fn render_dialog_buttons(confirm_selected: bool, theme: &Theme) -> Vec<Line<'static>> {
     let yes_style = if confirm_selected {
-        Style::default()
-            .fg(Color::Black)
-            .bg(Color::Green)
+        theme.badge(theme.success)
             .add_modifier(Modifier::BOLD)
     } else {
-        Style::default().fg(Color::Green)
+        Style::default().fg(theme.success)
     };
     
     let no_style = if !confirm_selected {
-        Style::default()
-            .fg(Color::Black)
-            .bg(Color::Red)
+        theme.badge(theme.error)
             .add_modifier(Modifier::BOLD)
     } else {
-        Style::default().fg(Color::Red)
+        Style::default().fg(theme.error)
     };
     
     vec![
@@ -175,10 +176,10 @@ pub fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
 }
 
 /// Render the rename popup with validation
-pub fn render_rename_popup(frame: &mut Frame, area: Rect, state: &RenamePopupState) {
+pub fn render_rename_popup(frame: &mut Frame, area: Rect, state: &RenamePopupState, theme: &Theme) {
     let config = PopupConfig::new("Rename Prompt")
         .with_size(50, 50)
-        .with_border_color(if state.is_valid { Color::Cyan } else { Color::Red });
+        .with_border_color(if state.is_valid { theme.accent } else { theme.error });
 
     let popup_area = centered_rect(config.width_percent, config.height_percent, area);
 
@@ -214,21 +215,21 @@ pub fn render_rename_popup(frame: &mut Frame, area: Rect, state: &RenamePopupSta
     let input_block = Block::default()
         .borders(Borders::ALL)
         .border_style(if state.is_valid {
-            Style::default().fg(Color::Green)
+            Style::default().fg(theme.success)
         } else {
-            Style::default().fg(Color::Red)
+            Style::default().fg(theme.error)
         });
 
     let input_text = Paragraph::new(format!("{}_", state.input))
-        .style(Style::default().fg(Color::White))
+        .style(Style::default().fg(theme.text))
         .block(input_block);
     frame.render_widget(input_text, chunks[1]);
 
     // Error message or validation status
     let status = if let Some(ref err) = state.error_message {
-        Paragraph::new(err.as_str()).style(Style::default().fg(Color::Red))
+        Paragraph::new(err.as_str()).style(Style::default().fg(theme.error))
     } else if state.is_valid {
-        Paragraph::new("✓ Valid name").style(Style::default().fg(Color::Green))
+        Paragraph::new("✓ Valid name").style(Style::default().fg(theme.success))
     } else {
         Paragraph::new("").style(Style::default())
     };
@@ -237,16 +238,21 @@ pub fn render_rename_popup(frame: &mut Frame, area: Rect, state: &RenamePopupSta
     // Hints
     let hints = Paragraph::new(Span::styled(
         "Enter: confirm | Esc: cancel | Valid: a-z, 0-9, _",
-        Style::default().fg(Color::DarkGray),
+        Style::default().fg(theme.muted),
     ));
     frame.render_widget(hints, chunks[3]);
 }
 
 /// Render the reference insertion popup (fuzzy finder for prompts)
-pub fn render_reference_popup(frame: &mut Frame, area: Rect, state: &ReferencePopupState) {
+pub fn render_reference_popup(
+    frame: &mut Frame,
+    area: Rect,
+    state: &ReferencePopupState,
+    theme: &Theme,
+) {
     let config = PopupConfig::new("Insert Reference [[prompt]]")
         .with_size(60, 50)
-        .with_border_color(Color::Cyan);
+        .with_border_color(theme.accent);
 
     let popup_area = centered_rect(config.width_percent, config.height_percent, area);
 
@@ -277,15 +283,15 @@ pub fn render_reference_popup(frame: &mut Frame, area: Rect, state: &ReferencePo
     let filter_block = Block::default()
         .title(" Filter ")
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::Yellow));
+        .border_style(Style::default().fg(theme.highlight));
 
     let filter_text = if state.filter.is_empty() {
         Paragraph::new("Type to filter...")
-            .style(Style::default().fg(Color::DarkGray))
+            .style(Style::default().fg(theme.muted))
             .block(filter_block)
     } else {
         Paragraph::new(format!("{}_", state.filter))
-            .style(Style::default().fg(Color::White))
+            .style(Style::default().fg(theme.text))
             .block(filter_block)
     };
     frame.render_widget(filter_text, chunks[0]);
@@ -297,12 +303,10 @@ pub fn render_reference_popup(frame: &mut Frame, area: Rect, state: &ReferencePo
         .enumerate()
         .map(|(i, name)| {
             let style = if i == state.selected_index {
-                Style::default()
-                    .fg(Color::Black)
-                    .bg(Color::Cyan)
+                theme.selected()
                     .add_modifier(Modifier::BOLD)
             } else {
-                Style::default().fg(Color::White)
+                Style::default().fg(theme.text)
             };
             ListItem::new(Line::from(Span::styled(name.clone(), style)))
         })
@@ -319,16 +323,129 @@ pub fn render_reference_popup(frame: &mut Frame, area: Rect, state: &ReferencePo
     // Hints
     let hints = Paragraph::new(Span::styled(
         "↑↓: navigate | Enter: insert | Esc: cancel",
-        Style::default().fg(Color::DarkGray),
+        Style::default().fg(theme.muted),
+    ));
+    frame.render_widget(hints, chunks[2]);
+}
+
+/// Render the command palette (fuzzy finder for actions)
+pub fn render_command_palette(
+    frame: &mut Frame,
+    area: Rect,
+    state: &CommandPaletteState,
+    theme: &Theme,
+) {
+    let config = PopupConfig::new("Command Palette")
+        .with_size(60, 60)
+        .with_border_color(theme.accent);
+
+    let popup_area = centered_rect(config.width_percent, config.height_percent, area);
+
+    // Clear the background
+    frame.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .title(format!(" {} ", config.title))
+        .title_alignment(Alignment::Center)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(config.border_color));
+
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),  // Filter input
+            Constraint::Min(3),     // Results list
+            Constraint::Length(1),  // Hints
+        ])
+        .margin(1)
+        .split(inner);
+
+    // Filter input
+    let filter_block = Block::default()
+        .title(" Filter ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.highlight));
+
+    let filter_text = if state.filter.is_empty() {
+        Paragraph::new("Type to filter...")
+            .style(Style::default().fg(theme.muted))
+            .block(filter_block)
+    } else {
+        Paragraph::new(format!("{}_", state.filter))
+            .style(Style::default().fg(theme.text))
+            .block(filter_block)
+    };
+    frame.render_widget(filter_text, chunks[0]);
+
+    // Results: description, current keys and the name to bind it under `keybindings:`
+    let width = state
+        .commands
+        .iter()
+        .map(|c| c.action.description().chars().count())
+        .max()
+        .unwrap_or(0);
+    let keys_width = state.commands.iter().map(|c| c.keys.chars().count()).max().unwrap_or(0);
+    let items: Vec<ListItem> = state
+        .filtered
+        .iter()
+        .enumerate()
+        .map(|(i, &index)| {
+            let command = &state.commands[index];
+            let line = if i == state.selected_index {
+                let style = theme.selected().add_modifier(Modifier::BOLD);
+                Line::from(vec![
+                    Span::styled(format!("{:width$}  ", command.action.description()), style),
+                    Span::styled(format!("{:keys_width$}  ", command.keys), style),
+                    Span::styled(command.action.name(), style),
+                ])
+            } else {
+                Line::from(vec![
+                    Span::styled(
+                        format!("{:width$}  ", command.action.description()),
+                        Style::default().fg(theme.text),
+                    ),
+                    Span::styled(
+                        format!("{:keys_width$}  ", command.keys),
+                        Style::default().fg(theme.accent).add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(command.action.name(), Style::default().fg(theme.muted)),
+                ])
+            };
+            ListItem::new(line)
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .title(format!(" Commands ({}) ", state.filtered.len()))
+                .borders(Borders::ALL),
+        );
+    // Scrolls to keep the selection visible
+    let mut list_state = ListState::default().with_selected(Some(state.selected_index));
+    frame.render_stateful_widget(list, chunks[1], &mut list_state);
+
+    // Hints
+    let hints = Paragraph::new(Span::styled(
+        "↑↓: navigate | Enter: run | Esc: cancel",
+        Style::default().fg(theme.muted),
     ));
     frame.render_widget(hints, chunks[2]);
 }
 
 /// Render the file picker popup (fuzzy finder for files)
-pub fn render_file_picker_popup(frame: &mut Frame, area: Rect, state: &FilePickerPopupState) {
+pub fn render_file_picker_popup(
+    frame: &mut Frame,
+    area: Rect,
+    state: &FilePickerPopupState,
+    theme: &Theme,
+) {
     let config = PopupConfig::new("Insert File Reference [[file:path]]")
         .with_size(70, 60)
-        .with_border_color(Color::Green);
+        .with_border_color(theme.success);
 
     let popup_area = centered_rect(config.width_percent, config.height_percent, area);
 
@@ -359,15 +476,15 @@ pub fn render_file_picker_popup(frame: &mut Frame, area: Rect, state: &FilePicke
     let filter_block = Block::default()
         .title(" Filter ")
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::Yellow));
+        .border_style(Style::default().fg(theme.highlight));
 
     let filter_text = if state.filter.is_empty() {
         Paragraph::new("Type to filter files...")
-            .style(Style::default().fg(Color::DarkGray))
+            .style(Style::default().fg(theme.muted))
             .block(filter_block)
     } else {
         Paragraph::new(format!("{}_", state.filter))
-            .style(Style::default().fg(Color::White))
+            .style(Style::default().fg(theme.text))
             .block(filter_block)
     };
     frame.render_widget(filter_text, chunks[0]);
@@ -382,12 +499,10 @@ pub fn render_file_picker_popup(frame: &mut Frame, area: Rect, state: &FilePicke
         .take(visible_height)
         .map(|(i, path)| {
             let style = if i == state.selected_index {
-                Style::default()
-                    .fg(Color::Black)
-                    .bg(Color::Green)
+                theme.badge(theme.success)
                     .add_modifier(Modifier::BOLD)
             } else {
-                Style::default().fg(Color::White)
+                Style::default().fg(theme.text)
             };
             ListItem::new(Line::from(Span::styled(path.clone(), style)))
         })
@@ -404,16 +519,22 @@ pub fn render_file_picker_popup(frame: &mut Frame, area: Rect, state: &FilePicke
     // Hints
     let hints = Paragraph::new(Span::styled(
         "↑↓: navigate | Enter: insert | Esc: cancel",
-        Style::default().fg(Color::DarkGray),
+        Style::default().fg(theme.muted),
     ));
     frame.render_widget(hints, chunks[2]);
 }
 
 /// Render the tag selector popup
-pub fn render_tag_selector(frame: &mut Frame, area: Rect, state: &TagSelectorState) {
+pub fn render_tag_selector(
+    frame: &mut Frame,
+    area: Rect,
+    state: &TagSelectorState,
+    app_config: &Config,
+    theme: &Theme,
+) {
     let config = PopupConfig::new("Manage Tags")
         .with_size(50, 50)
-        .with_border_color(Color::Yellow);
+        .with_border_color(theme.highlight);
 
     let popup_area = centered_rect(config.width_percent, config.height_percent, area);
 
@@ -430,7 +551,7 @@ pub fn render_tag_selector(frame: &mut Frame, area: Rect, state: &TagSelectorSta
     let inner = block.inner(popup_area);
     frame.render_widget(block, popup_area);
 
-    if state.creating_new {
+    if state.is_typing_name() {
         // Show new tag input
         let chunks = Layout::default()
             .direction(Direction::Vertical)
@@ -443,22 +564,26 @@ pub fn render_tag_selector(frame: &mut Frame, area: Rect, state: &TagSelectorSta
             .margin(1)
             .split(inner);
 
-        let label = Paragraph::new("Enter new tag name:");
-        frame.render_widget(label, chunks[0]);
+        let label = match &state.edit {
+            Some(TagSelectorEdit::Rename { tag }) => format!("Rename tag '{}' on every prompt to:", tag),
+            _ => "Enter new tag name (use / to nest):".to_string(),
+        };
+        frame.render_widget(Paragraph::new(label), chunks[0]);
 
         let input_block = Block::default()
             .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::Yellow));
+            .border_style(Style::default().fg(theme.highlight));
 
         let input_text = Paragraph::new(format!("{}_", state.new_tag_input))
-            .style(Style::default().fg(Color::White))
+            .style(Style::default().fg(theme.text))
             .block(input_block);
         frame.render_widget(input_text, chunks[1]);
 
-        let hints = Paragraph::new(Span::styled(
-            "Enter: create | Esc: cancel",
-            Style::default().fg(Color::DarkGray),
-        ));
+        let hint = match state.edit {
+            Some(_) => "Enter: rename | Esc: cancel",
+            None => "Enter: create | Esc: cancel",
+        };
+        let hints = Paragraph::new(Span::styled(hint, Style::default().fg(theme.muted)));
         frame.render_widget(hints, chunks[3]);
     } else {
         // Show tag list
@@ -476,15 +601,15 @@ pub fn render_tag_selector(frame: &mut Frame, area: Rect, state: &TagSelectorSta
         let filter_block = Block::default()
             .title(" Filter ")
             .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::Yellow));
+            .border_style(Style::default().fg(theme.highlight));
 
         let filter_text = if state.filter.is_empty() {
             Paragraph::new("Type to filter...")
-                .style(Style::default().fg(Color::DarkGray))
+                .style(Style::default().fg(theme.muted))
                 .block(filter_block)
         } else {
             Paragraph::new(format!("{}_", state.filter))
-                .style(Style::default().fg(Color::White))
+                .style(Style::default().fg(theme.text))
                 .block(filter_block)
         };
         frame.render_widget(filter_text, chunks[0]);
@@ -497,18 +622,27 @@ pub fn render_tag_selector(frame: &mut Frame, area: Rect, state: &TagSelectorSta
             .map(|(i, tag)| {
                 let is_assigned = state.is_tag_assigned(tag);
                 let checkbox = if is_assigned { "[✓] " } else { "[ ] " };
-                
+                // Nested tags show their last component below their parent
+                let depth = tag.matches('/').count();
+                let label = tag.rsplit('/').next().unwrap_or(tag);
+                let in_use = state.all_tags.contains(tag);
+
                 let style = if i == state.selected_index {
-                    Style::default()
-                        .fg(Color::Black)
-                        .bg(Color::Yellow)
+                    theme.badge(theme.highlight)
                         .add_modifier(Modifier::BOLD)
                 } else if is_assigned {
-                    Style::default().fg(Color::Green)
+                    Style::default().fg(theme.success)
+                } else if !in_use {
+                    Style::default().fg(theme.muted)
                 } else {
-                    Style::default().fg(Color::White)
+                    Style::default().fg(theme.text)
                 };
-                ListItem::new(Line::from(Span::styled(format!("{}{}", checkbox, tag), style)))
+                let bullet = style.fg(tag_color(app_config, tag));
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!("{}{}", "  ".repeat(depth), checkbox), style),
+                    Span::styled("● ", bullet),
+                    Span::styled(label, style),
+                ]))
             })
             .collect();
 
@@ -520,17 +654,534 @@ pub fn render_tag_selector(frame: &mut Frame, area: Rect, state: &TagSelectorSta
             );
         frame.render_widget(list, chunks[1]);
 
-        // Hints
-        let hints = Paragraph::new(Span::styled(
-            "↑↓: navigate | Enter/Space: toggle | Ctrl+n: new | Esc: done",
-            Style::default().fg(Color::DarkGray),
-        ));
-        frame.render_widget(hints, chunks[2]);
+        // Hints, or the question of the tag operation in progress
+        let hints = match &state.edit {
+            Some(TagSelectorEdit::Delete { tag }) => Span::styled(
+                format!("Delete '{}' (and tags below it) from every prompt? y: delete | n: cancel", tag),
+                Style::default().fg(theme.highlight),
+            ),
+            Some(TagSelectorEdit::Merge { tag }) => Span::styled(
+                format!("Merge '{}' into the selected tag? Enter: merge | Esc: cancel", tag),
+                Style::default().fg(theme.highlight),
+            ),
+            _ => Span::styled(
+                concat!(
+                    "↑↓: navigate | Enter/Space: toggle | ",
+                    "Ctrl+n/r/d/g: new/rename/delete/merge | Esc: done",
+                ),
+                Style::default().fg(theme.muted),
+            ),
+        };
+        frame.render_widget(Paragraph::new(hints), chunks[2]);
+    }
+}
+
+/// Render the tag filter popup
+pub fn render_tag_filter_popup(
+    frame: &mut Frame,
+    area: Rect,
+    state: &TagFilterPopupState,
+    app_config: &Config,
+    theme: &Theme,
+) {
+    let config = PopupConfig::new("Filter by Tags")
+        .with_size(50, 50)
+        .with_border_color(theme.highlight);
+
+    let popup_area = centered_rect(config.width_percent, config.height_percent, area);
+
+    // Clear the background
+    frame.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .title(format!(" {} ", config.title))
+        .title_alignment(Alignment::Center)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(config.border_color));
+
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),  // Filter input
+            Constraint::Length(1),  // Expression
+            Constraint::Min(3),     // Tag list
+            Constraint::Length(1),  // Hints
+        ])
+        .margin(1)
+        .split(inner);
+
+    // Filter input
+    let filter_block = Block::default()
+        .title(" Filter ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.highlight));
+
+    let filter_text = if state.filter.is_empty() {
+        Paragraph::new("Type to filter...")
+            .style(Style::default().fg(theme.muted))
+            .block(filter_block)
+    } else {
+        Paragraph::new(format!("{}_", state.filter))
+            .style(Style::default().fg(theme.text))
+            .block(filter_block)
+    };
+    frame.render_widget(filter_text, chunks[0]);
+
+    // Match mode and the resulting expression
+    let mode = if state.tag_filter.match_any { "Match any" } else { "Match all" };
+    let expression = if state.tag_filter.is_empty() {
+        "all prompts".to_string()
+    } else {
+        state.tag_filter.describe()
+    };
+    let summary = Paragraph::new(Line::from(vec![
+        Span::styled(format!("{}: ", mode), Style::default().fg(theme.accent)),
+        Span::styled(expression, Style::default().fg(theme.highlight)),
+    ]));
+    frame.render_widget(summary, chunks[1]);
+
+    // Tag list
+    let items: Vec<ListItem> = state
+        .filtered_tags
+        .iter()
+        .enumerate()
+        .map(|(i, tag)| {
+            let tag_state = state.tag_state(tag);
+            let (checkbox, color) = match tag_state {
+                TagFilterState::Include => ("[+] ", theme.success),
+                TagFilterState::Exclude => ("[-] ", theme.error),
+                TagFilterState::Off => ("[ ] ", theme.text),
+            };
+
+            let depth = tag.matches('/').count();
+            let label = tag.rsplit('/').next().unwrap_or(tag);
+
+            let style = if i == state.selected_index {
+                theme.badge(theme.highlight)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(color)
+            };
+            let bullet = style.fg(tag_color(app_config, tag));
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{}{}", "  ".repeat(depth), checkbox), style),
+                Span::styled("● ", bullet),
+                Span::styled(label, style),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .title(format!(" Tags ({}) ", state.filtered_tags.len()))
+                .borders(Borders::ALL),
+        );
+    frame.render_widget(list, chunks[2]);
+
+    // Hints
+    let hints = Paragraph::new(Span::styled(
+        "↑↓: navigate | Space: include/exclude/off | Tab: all/any | Ctrl+x: clear | Enter: apply | Esc: cancel",
+        Style::default().fg(theme.muted),
+    ));
+    frame.render_widget(hints, chunks[3]);
+}
+
+/// Render the export popup
+pub fn render_export_popup(
+    frame: &mut Frame,
+    area: Rect,
+    state: &ExportPopupState,
+    selected_name: Option<&str>,
+    view_count: usize,
+    theme: &Theme,
+) {
+    let config = PopupConfig::new("Export")
+        .with_size(50, 50)
+        .with_border_color(theme.accent);
+
+    let inner = render_popup_frame(frame, area, &config);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(4),  // Options
+            Constraint::Length(3),  // Destination input
+            Constraint::Min(1),     // Spacer
+            Constraint::Length(1),  // Hints
+        ])
+        .margin(1)
+        .split(inner);
+
+    let label_style = |field: ExportField| {
+        if state.focused == field {
+            theme.selected()
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(theme.accent)
+        }
+    };
+
+    // Format choices, with the active one highlighted
+    let mut format_spans = vec![Span::styled(" Format   ", label_style(ExportField::Format))];
+    for format in ExportFormat::ALL {
+        let style = if format == state.format {
+            Style::default().fg(theme.success).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(theme.muted)
+        };
+        format_spans.push(Span::raw(" "));
+        format_spans.push(Span::styled(format.name(), style));
+    }
+
+    let scope = if state.all_filtered {
+        format!("all in view ({})", view_count)
+    } else {
+        format!("selected ({})", selected_name.unwrap_or("-"))
+    };
+
+    let commands = if !state.format.is_rendered() {
+        Span::styled("n/a for raw", Style::default().fg(theme.muted))
+    } else if state.include_command_output {
+        Span::styled("[✓] run and include output", Style::default().fg(theme.highlight))
+    } else {
+        Span::styled("[ ] leave {{commands}} as-is", Style::default().fg(theme.text))
+    };
+
+    let options = Paragraph::new(vec![
+        Line::from(format_spans),
+        Line::from(vec![
+            Span::styled(" Scope    ", label_style(ExportField::Scope)),
+            Span::raw(" "),
+            Span::raw(scope),
+        ]),
+        Line::from(vec![
+            Span::styled(" Commands ", label_style(ExportField::Commands)),
+            Span::raw(" "),
+            commands,
+        ]),
+    ]);
+    frame.render_widget(options, chunks[0]);
+
+    // Destination input
+    let input_block = Block::default()
+        .title(" Destination (file or directory) ")
+        .borders(Borders::ALL)
+        .border_style(if state.focused == ExportField::Destination {
+            Style::default().fg(theme.accent)
+        } else {
+            Style::default().fg(theme.muted)
+        });
+    let cursor = if state.focused == ExportField::Destination { "_" } else { "" };
+    let input_text = Paragraph::new(format!("{}{}", state.destination, cursor))
+        .style(Style::default().fg(theme.text))
+        .block(input_block);
+    frame.render_widget(input_text, chunks[1]);
+
+    // Hints
+    let hints = Paragraph::new(Span::styled(
+        "Tab/↑↓: field | ←→/Space: change | Enter: export | Esc: cancel",
+        Style::default().fg(theme.muted),
+    ));
+    frame.render_widget(hints, chunks[3]);
+}
+
+/// Render the prompt shown when the prompt being edited changed on disk
+pub fn render_conflict_popup(frame: &mut Frame, area: Rect, state: &ConflictState, theme: &Theme) {
+    let height = if state.show_diff { 70 } else { 30 };
+    let config = PopupConfig::new("Changed on Disk")
+        .with_size(60, height)
+        .with_border_color(theme.highlight);
+
+    let inner = render_popup_frame(frame, area, &config);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(2),  // Message
+            Constraint::Min(1),     // Diff
+            Constraint::Length(1),  // Hints
+        ])
+        .margin(1)
+        .split(inner);
+
+    let message = Paragraph::new(format!(
+        "'{}' was changed outside piemme while you were editing it.",
+        state.theirs.name
+    ))
+    .wrap(Wrap { trim: false });
+    frame.render_widget(message, chunks[0]);
+
+    if state.show_diff {
+        let diff = Paragraph::new(diff_lines(&state.mine, &state.theirs.content, theme))
+            .block(
+                Block::default()
+                    .title(" - mine / + theirs ")
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(theme.muted)),
+            )
+            .scroll((state.scroll, 0));
+        frame.render_widget(diff, chunks[1]);
     }
+
+    let hints = Paragraph::new(Span::styled(
+        "m/Esc: keep mine | t: take theirs | d: diff | j/k: scroll",
+        Style::default().fg(theme.muted),
+    ));
+    frame.render_widget(hints, chunks[2]);
+}
+
+/// Render the backup picker popup
+pub fn render_backup_picker(
+    frame: &mut Frame,
+    area: Rect,
+    state: &BackupPickerState,
+    theme: &Theme,
+) {
+    let config = PopupConfig::new(format!("Backups of '{}'", state.prompt_name))
+        .with_size(60, 50)
+        .with_border_color(theme.accent);
+
+    let inner = render_popup_frame(frame, area, &config);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(1),     // Backup list
+            Constraint::Length(1),  // Hints
+        ])
+        .margin(1)
+        .split(inner);
+
+    let visible_height = chunks[0].height as usize;
+    let scroll_offset = state.selected_index.saturating_sub(visible_height.saturating_sub(1));
+    let items: Vec<ListItem> = state
+        .backups
+        .iter()
+        .enumerate()
+        .skip(scroll_offset)
+        .take(visible_height)
+        .map(|(i, (backup, preview))| {
+            let taken = backup.taken.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S");
+            let (time_style, preview_style) = if i == state.selected_index {
+                let style = theme.selected();
+                (style.add_modifier(Modifier::BOLD), style)
+            } else {
+                (Style::default().fg(theme.text), Style::default().fg(theme.muted))
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{}  ", taken), time_style),
+                Span::styled(preview.clone(), preview_style),
+            ]))
+        })
+        .collect();
+    frame.render_widget(List::new(items), chunks[0]);
+
+    let hints = Paragraph::new(Span::styled(
+        "↑↓/jk: select | Enter: restore | Esc: close",
+        Style::default().fg(theme.muted),
+    ));
+    frame.render_widget(hints, chunks[1]);
+}
+
+/// Render the version history popup: revisions on the left, a diff on the right
+pub fn render_history_popup(frame: &mut Frame, area: Rect, state: &HistoryState, theme: &Theme) {
+    let config = PopupConfig::new(format!("History of '{}'", state.prompt_name))
+        .with_size(80, 70)
+        .with_border_color(theme.accent);
+
+    let inner = render_popup_frame(frame, area, &config);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(3),     // Revisions and diff
+            Constraint::Length(1),  // Hints
+        ])
+        .margin(1)
+        .split(inner);
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(35), Constraint::Percentage(65)])
+        .split(rows[0]);
+
+    // Revision list
+    let visible_height = columns[0].height.saturating_sub(2) as usize;
+    let scroll_offset = state.selected_index.saturating_sub(visible_height.saturating_sub(1));
+    let items: Vec<ListItem> = state
+        .revisions
+        .iter()
+        .enumerate()
+        .skip(scroll_offset)
+        .take(visible_height)
+        .map(|(i, revision)| {
+            let saved = revision.saved.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S");
+            let marker = if state.compare_index == Some(i) { "◆" } else { " " };
+            let content = revision.content();
+            let preview = content.lines().find(|l| !l.trim().is_empty()).unwrap_or_default();
+            let (style, preview_style) = if i == state.selected_index {
+                let style = theme.selected();
+                (style.add_modifier(Modifier::BOLD), style)
+            } else {
+                (Style::default().fg(theme.text), Style::default().fg(theme.muted))
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{}#{:<4} {}  ", marker, revision.number, saved), style),
+                Span::styled(preview.to_string(), preview_style),
+            ]))
+        })
+        .collect();
+    let list = List::new(items).block(
+        Block::default()
+            .title(format!(" Revisions ({}) ", state.revisions.len()))
+            .borders(Borders::ALL),
+    );
+    frame.render_widget(list, columns[0]);
+
+    // Diff from the base revision to the selected one
+    let (title, lines) = match (state.base(), state.selected()) {
+        (Some(base), Some(selected)) => (
+            format!(" #{} → #{} ", base.number, selected.number),
+            diff_lines(&base.text, &selected.text, theme),
+        ),
+        (None, Some(selected)) => (
+            format!(" #{} (first revision) ", selected.number),
+            diff_lines("", &selected.text, theme),
+        ),
+        _ => (String::new(), Vec::new()),
+    };
+    let diff = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme.muted)),
+        )
+        .scroll((state.diff_scroll, 0));
+    frame.render_widget(diff, columns[1]);
+
+    let hints = Paragraph::new(Span::styled(
+        "↑↓/jk: select | c: compare with selected | J/K: scroll diff | Enter: restore | Esc: close",
+        Style::default().fg(theme.muted),
+    ));
+    frame.render_widget(hints, rows[1]);
+}
+
+/// Render the commit popup
+pub fn render_commit_popup(frame: &mut Frame, area: Rect, state: &CommitPopupState, theme: &Theme) {
+    let config = PopupConfig::new("Commit Prompts")
+        .with_size(60, 40)
+        .with_border_color(theme.success);
+
+    let inner = render_popup_frame(frame, area, &config);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),  // Scope
+            Constraint::Min(1),     // Prompts to commit
+            Constraint::Length(3),  // Message input
+            Constraint::Length(1),  // Hints
+        ])
+        .margin(1)
+        .split(inner);
+
+    let scope = if state.all_changed {
+        format!("All changed prompts ({})", state.changed.len())
+    } else {
+        "Selected prompt".to_string()
+    };
+    frame.render_widget(Paragraph::new(scope), chunks[0]);
+
+    let names: Vec<Line> = state
+        .names()
+        .into_iter()
+        .map(|name| {
+            Line::from(Span::styled(format!("  {}", name), Style::default().fg(theme.text)))
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(names), chunks[1]);
+
+    let input_block = Block::default()
+        .title(" Message ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.success));
+    let input_text = Paragraph::new(format!("{}_", state.message))
+        .style(Style::default().fg(theme.text))
+        .block(input_block);
+    frame.render_widget(input_text, chunks[2]);
+
+    let hints = Paragraph::new(Span::styled(
+        "Enter: stage & commit | Tab: selected/all changed | Esc: cancel",
+        Style::default().fg(theme.muted),
+    ));
+    frame.render_widget(hints, chunks[3]);
+}
+
+/// Render the blame view: the last commit to change each line of a prompt
+pub fn render_blame_popup(frame: &mut Frame, area: Rect, state: &BlameState, theme: &Theme) {
+    let config = PopupConfig::new(format!("Blame '{}'", state.prompt_name))
+        .with_size(85, 80)
+        .with_border_color(theme.accent);
+
+    let inner = render_popup_frame(frame, area, &config);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(1),     // Blamed lines
+            Constraint::Length(1),  // Hints
+        ])
+        .margin(1)
+        .split(inner);
+
+    let author_width = state
+        .lines
+        .iter()
+        .map(|line| line.author.chars().count())
+        .max()
+        .unwrap_or(0)
+        .min(20);
+    let lines: Vec<Line> = state
+        .lines
+        .iter()
+        .map(|line| {
+            let (commit, author, date) = if line.is_uncommitted() {
+                ("--------".to_string(), "Not committed".to_string(), String::new())
+            } else {
+                let date = line.time.with_timezone(&chrono::Local).format("%Y-%m-%d").to_string();
+                (line.commit.clone(), line.author.chars().take(author_width).collect(), date)
+            };
+            Line::from(vec![
+                Span::styled(format!("{} ", commit), Style::default().fg(theme.highlight)),
+                Span::styled(
+                    format!("{:<width$} ", author, width = author_width),
+                    Style::default().fg(theme.accent),
+                ),
+                Span::styled(format!("{:<10} │ ", date), Style::default().fg(theme.muted)),
+                Span::raw(line.text.clone()),
+            ])
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines).scroll((state.scroll, 0)), chunks[0]);
+
+    let hints = Paragraph::new(Span::styled(
+        "j/k: scroll | Esc: close",
+        Style::default().fg(theme.muted),
+    ));
+    frame.render_widget(hints, chunks[1]);
 }
 
 /// Render the folder selector popup
-pub fn render_folder_selector(frame: &mut Frame, area: Rect, state: &FolderSelectorState) {
+pub fn render_folder_selector(
+    frame: &mut Frame,
+    area: Rect,
+    state: &FolderSelectorState,
+    theme: &Theme,
+) {
     let title = match state.mode {
         crate::models::FolderSelectorMode::Open => "Open Folder",
         crate::models::FolderSelectorMode::Move => "Move to Folder",
@@ -538,7 +1189,7 @@ pub fn render_folder_selector(frame: &mut Frame, area: Rect, state: &FolderSelec
     
     let config = PopupConfig::new(title)
         .with_size(50, 50)
-        .with_border_color(Color::Magenta);
+        .with_border_color(theme.mode_folder);
 
     let popup_area = centered_rect(config.width_percent, config.height_percent, area);
 
@@ -555,8 +1206,8 @@ pub fn render_folder_selector(frame: &mut Frame, area: Rect, state: &FolderSelec
     let inner = block.inner(popup_area);
     frame.render_widget(block, popup_area);
 
-    if state.creating_new {
-        // Show new folder input
+    if state.is_typing_name() {
+        // Show new folder input (or the new path of a renamed folder)
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
@@ -568,22 +1219,26 @@ pub fn render_folder_selector(frame: &mut Frame, area: Rect, state: &FolderSelec
             .margin(1)
             .split(inner);
 
-        let label = Paragraph::new("Enter new folder name:");
-        frame.render_widget(label, chunks[0]);
+        let label = match &state.edit {
+            Some(FolderEdit::Rename { folder }) => format!("Rename folder '{}' to:", folder),
+            _ => "Enter new folder name (use / to nest):".to_string(),
+        };
+        frame.render_widget(Paragraph::new(label), chunks[0]);
 
         let input_block = Block::default()
             .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::Magenta));
+            .border_style(Style::default().fg(theme.mode_folder));
 
         let input_text = Paragraph::new(format!("{}_", state.new_folder_input))
-            .style(Style::default().fg(Color::White))
+            .style(Style::default().fg(theme.text))
             .block(input_block);
         frame.render_widget(input_text, chunks[1]);
 
-        let hints = Paragraph::new(Span::styled(
-            "Enter: create | Esc: cancel",
-            Style::default().fg(Color::DarkGray),
-        ));
+        let hint = match state.edit {
+            Some(_) => "Enter: rename | Esc: cancel",
+            None => "Enter: create | Esc: cancel",
+        };
+        let hints = Paragraph::new(Span::styled(hint, Style::default().fg(theme.muted)));
         frame.render_widget(hints, chunks[3]);
     } else {
         // Show folder list
@@ -601,61 +1256,107 @@ pub fn render_folder_selector(frame: &mut Frame, area: Rect, state: &FolderSelec
         let filter_block = Block::default()
             .title(" Filter ")
             .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::Magenta));
+            .border_style(Style::default().fg(theme.mode_folder));
 
         let filter_text = if state.filter.is_empty() {
             Paragraph::new("Type to filter...")
-                .style(Style::default().fg(Color::DarkGray))
+                .style(Style::default().fg(theme.muted))
                 .block(filter_block)
         } else {
             Paragraph::new(format!("{}_", state.filter))
-                .style(Style::default().fg(Color::White))
+                .style(Style::default().fg(theme.text))
                 .block(filter_block)
         };
         frame.render_widget(filter_text, chunks[0]);
 
         // Folder list
-        let items: Vec<ListItem> = state
+        let mut items: Vec<ListItem> = state
             .filtered_folders
             .iter()
             .enumerate()
             .map(|(i, folder)| {
                 let icon = if folder == "(root)" { "📁 " } else { "📂 " };
+                // The tree shows each folder under its parent; filter matches show full paths
+                let label = match folder.rsplit_once('/') {
+                    Some((_, last)) if state.is_tree() => last,
+                    _ => folder.as_str(),
+                };
+                let marker = if folder == "(root)" || !state.is_tree() {
+                    ""
+                } else if !state.has_children(folder) {
+                    "  "
+                } else if state.expanded.contains(folder) {
+                    "▾ "
+                } else {
+                    "▸ "
+                };
+                let depth = if state.is_tree() { folder.matches('/').count() } else { 0 };
                 
                 let style = if i == state.selected_index {
-                    Style::default()
-                        .fg(Color::Black)
-                        .bg(Color::Magenta)
+                    theme.badge(theme.mode_folder)
                         .add_modifier(Modifier::BOLD)
                 } else {
-                    Style::default().fg(Color::White)
+                    Style::default().fg(theme.text)
                 };
-                ListItem::new(Line::from(Span::styled(format!("{}{}", icon, folder), style)))
+                let text = format!("{}{}{}{}", "  ".repeat(depth), marker, icon, label);
+                ListItem::new(Line::from(Span::styled(text, style)))
             })
             .collect();
 
-        let list = List::new(items)
-            .block(
-                Block::default()
-                    .title(format!(" Folders ({}) ", state.filtered_folders.len()))
-                    .borders(Borders::ALL),
-            );
+        // Saved searches, as virtual folders after the real ones
+        for (i, (name, query)) in state.filtered_views.iter().enumerate() {
+            let selected = state.filtered_folders.len() + i == state.selected_index;
+            let (style, query_style) = if selected {
+                let style = theme.badge(theme.mode_folder);
+                (style.add_modifier(Modifier::BOLD), style)
+            } else {
+                (Style::default().fg(theme.accent), Style::default().fg(theme.muted))
+            };
+            items.push(ListItem::new(Line::from(vec![
+                Span::styled(format!("🔍 {}", name), style),
+                Span::styled(format!("  {}", query), query_style),
+            ])));
+        }
+
+        let title = if state.filtered_views.is_empty() {
+            format!(" Folders ({}) ", state.filtered_folders.len())
+        } else {
+            format!(
+                " Folders ({}) · Views ({}) ",
+                state.filtered_folders.len(),
+                state.filtered_views.len()
+            )
+        };
+        let list = List::new(items).block(Block::default().title(title).borders(Borders::ALL));
         frame.render_widget(list, chunks[1]);
 
-        // Hints
-        let hints = Paragraph::new(Span::styled(
-            "↑↓: navigate | Enter: select | Ctrl+n: new folder | Esc: cancel",
-            Style::default().fg(Color::DarkGray),
-        ));
-        frame.render_widget(hints, chunks[2]);
+        // Hints, or the question of the folder operation in progress
+        let hints = match &state.edit {
+            Some(FolderEdit::Delete { folder }) => Span::styled(
+                format!("Delete '{}'? Move its prompts to r: root | a: archive | Esc: cancel", folder),
+                Style::default().fg(theme.highlight),
+            ),
+            Some(FolderEdit::Merge { folder }) => Span::styled(
+                format!("Merge '{}' into the selected folder? Enter: merge | Esc: cancel", folder),
+                Style::default().fg(theme.highlight),
+            ),
+            _ => Span::styled(
+                concat!(
+                    "↑↓: navigate | ←→: collapse/expand | Enter: select | ",
+                    "Ctrl+n/r/d/g: new/rename/delete/merge | Esc: cancel",
+                ),
+                Style::default().fg(theme.muted),
+            ),
+        };
+        frame.render_widget(Paragraph::new(hints), chunks[2]);
     }
 }
 
 /// Render the search popup (fuzzy finder for prompts)
-pub fn render_search_popup(frame: &mut Frame, area: Rect, state: &SearchPopupState) {
+pub fn render_search_popup(frame: &mut Frame, area: Rect, state: &SearchPopupState, theme: &Theme) {
     let config = PopupConfig::new("Search Prompts")
         .with_size(70, 60)
-        .with_border_color(Color::Green);
+        .with_border_color(theme.success);
 
     let popup_area = centered_rect(config.width_percent, config.height_percent, area);
 
@@ -686,21 +1387,36 @@ pub fn render_search_popup(frame: &mut Frame, area: Rect, state: &SearchPopupSta
     let search_block = Block::default()
         .title(" Search ")
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::Yellow));
+        .border_style(Style::default().fg(theme.highlight));
 
-    let search_text = if state.query.is_empty() {
-        Paragraph::new("Type to search by name or content...")
-            .style(Style::default().fg(Color::DarkGray))
+    let search_text = if let Some(name) = &state.view_name {
+        let block = Block::default()
+            .title(format!(" Save '{}' as view named ", state.query.trim()))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.accent));
+        Paragraph::new(format!("{}_", name))
+            .style(Style::default().fg(theme.text))
+            .block(block)
+    } else if state.query.is_empty() {
+        Paragraph::new("Type to search, or filter with tag: folder: in: modified: has: refs: ...")
+            .style(Style::default().fg(theme.muted))
             .block(search_block)
     } else {
         Paragraph::new(format!("{}_", state.query))
-            .style(Style::default().fg(Color::White))
+            .style(Style::default().fg(theme.text))
             .block(search_block)
     };
     frame.render_widget(search_text, chunks[0]);
 
+    // Results on the left, the selected prompt on the right
+    let panes = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+        .split(chunks[1]);
+
     // Results list - calculate visible height
-    let results_inner_height = chunks[1].height.saturating_sub(2) as usize; // Account for borders
+    let results_inner_height = panes[0].height.saturating_sub(2) as usize; // Account for borders
+    let preview_width = (panes[0].width as usize).saturating_sub(4);
 
     // Create items with scroll offset
     let items: Vec<ListItem> = state
@@ -713,104 +1429,214 @@ pub fn render_search_popup(frame: &mut Frame, area: Rect, state: &SearchPopupSta
             let is_selected = i == state.selected_index;
             
             // Build the name with match highlights
-            let name_spans = build_highlighted_spans(&result.name, &result.name_match_indices, is_selected);
-            
-            // Build preview line
-            let preview_style = if is_selected {
-                Style::default().fg(Color::Black).bg(Color::Green)
-            } else {
-                Style::default().fg(Color::DarkGray)
+            let mut name_spans = build_highlighted_spans(&result.name, &result.name_match_indices, is_selected, theme);
+            let place = match result.location.as_str() {
+                "prompts" => None,
+                location => Some(location.strip_prefix("folders/").unwrap_or(location)),
             };
+            if let Some(place) = place {
+                name_spans.push(Span::styled(format!("  ({})", place), preview_style(is_selected, theme)));
+            }
             
-            // Truncate preview to fit
-            let preview = if result.preview.len() > 60 {
-                format!("  {}...", &result.preview[..57])
-            } else {
-                format!("  {}", result.preview)
-            };
+            // Build preview line: the matching line with its number, cut to fit around the match
+            let number = result.line.map(|line| format!("{}: ", line + 1)).unwrap_or_default();
+            let width = preview_width.saturating_sub(number.chars().count());
+            let (preview, indices) = fit_around_match(&result.preview, &result.preview_match_indices, width);
+            let mut preview_spans = vec![Span::styled(format!("  {}", number), preview_style(is_selected, theme))];
+            preview_spans.extend(styled_matches(
+                &preview,
+                &indices,
+                preview_style(is_selected, theme),
+                match_style(is_selected, theme),
+            ));
             
             let content = vec![
                 Line::from(name_spans),
-                Line::from(Span::styled(preview, preview_style)),
+                Line::from(preview_spans),
             ];
             
             ListItem::new(content)
         })
         .collect();
 
-    let results_title = if state.results.is_empty() {
-        if state.query.is_empty() {
+    let results_title = if let Some(error) = &state.error {
+        format!(" {} ", error)
+    } else if state.results.is_empty() {
+        if state.searching {
+            " Searching... ".to_string()
+        } else if state.query.is_empty() {
             " Results ".to_string()
         } else {
             " No matches ".to_string()
         }
     } else {
-        format!(" Results ({}) ", state.results.len())
+        let more = if state.searching { "+" } else { "" };
+        if state.total > state.results.len() {
+            format!(" Results ({} of {}{}) ", state.results.len(), state.total, more)
+        } else {
+            format!(" Results ({}{}) ", state.results.len(), more)
+        }
     };
 
     let list = List::new(items)
         .block(
             Block::default()
                 .title(results_title)
-                .borders(Borders::ALL),
+                .borders(Borders::ALL)
+                .border_style(if state.error.is_some() {
+                    Style::default().fg(theme.error)
+                } else {
+                    Style::default()
+                }),
         );
-    frame.render_widget(list, chunks[1]);
+    frame.render_widget(list, panes[0]);
+
+    render_search_preview(frame, panes[1], state, theme);
 
     // Hints
     let hints = Paragraph::new(Span::styled(
-        "↑↓: navigate | Enter: jump to prompt | Esc: cancel",
-        Style::default().fg(Color::DarkGray),
+        if state.view_name.is_some() {
+            "Enter: save view | Esc: back to search"
+        } else {
+            "↑↓: navigate | Enter: jump (content matches open at the line) | Ctrl+s: save as view | Esc: cancel"
+        },
+        Style::default().fg(theme.muted),
     ));
     frame.render_widget(hints, chunks[2]);
 }
 
-/// Build spans with highlighted matching characters
-fn build_highlighted_spans(text: &str, match_indices: &[usize], is_selected: bool) -> Vec<Span<'static>> {
-    let base_style = if is_selected {
-        Style::default().fg(Color::Black).bg(Color::Green)
-    } else {
-        Style::default().fg(Color::White)
+/// Render the lines around the selected result's match, numbered, with the match highlighted
+fn render_search_preview(frame: &mut Frame, area: Rect, state: &SearchPopupState, theme: &Theme) {
+    let block = Block::default()
+        .title(" Preview ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.muted));
+    let height = area.height.saturating_sub(2) as usize;
+
+    let Some(result) = state.selected_result() else {
+        frame.render_widget(block, area);
+        return;
     };
-    
-    let highlight_style = if is_selected {
-        Style::default()
-            .fg(Color::Yellow)
-            .bg(Color::Green)
+
+    // Center the matching line
+    let line_count = state.selected_content.lines().count();
+    let first = result
+        .line
+        .map(|line| line.saturating_sub(height / 2).min(line_count.saturating_sub(height)))
+        .unwrap_or(0);
+    let number_width = (first + height).min(line_count).max(1).to_string().len();
+
+    let lines: Vec<Line> = state
+        .selected_content
+        .lines()
+        .enumerate()
+        .skip(first)
+        .take(height)
+        .map(|(number, text)| {
+            let gutter = Span::styled(
+                format!("{:>width$} ", number + 1, width = number_width),
+                Style::default().fg(theme.muted),
+            );
+            let mut spans = vec![gutter];
+            if result.line == Some(number) {
+                spans.extend(styled_matches(
+                    text,
+                    &result.preview_match_indices,
+                    Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
+                    match_style(false, theme),
+                ));
+            } else {
+                spans.push(Span::styled(text.to_string(), Style::default().fg(theme.text)));
+            }
+            Line::from(spans)
+        })
+        .collect();
+
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+/// Style of the secondary text of a search result
+fn preview_style(is_selected: bool, theme: &Theme) -> Style {
+    if is_selected {
+        theme.badge(theme.success)
+    } else {
+        Style::default().fg(theme.muted)
+    }
+}
+
+/// Style of matched characters in a search result
+fn match_style(is_selected: bool, theme: &Theme) -> Style {
+    if is_selected {
+        theme
+            .badge(theme.success)
+            .fg(theme.highlight)
             .add_modifier(Modifier::BOLD)
     } else {
         Style::default()
-            .fg(Color::Green)
+            .fg(theme.success)
             .add_modifier(Modifier::BOLD)
+    }
+}
+
+/// Cut `text` to `width` characters, keeping the first match in view, and shift the match
+/// indices to the cut text
+fn fit_around_match(text: &str, indices: &[usize], width: usize) -> (String, Vec<usize>) {
+    let chars: Vec<char> = text.chars().collect();
+    if chars.len() <= width {
+        return (text.to_string(), indices.to_vec());
+    }
+
+    let room = width.saturating_sub(6);
+    let first_match = indices.first().copied().unwrap_or(0);
+    let start = if first_match < room { 0 } else { first_match.saturating_sub(room / 3) };
+    let end = (start + room).min(chars.len());
+    let prefix = if start > 0 { "..." } else { "" };
+    let suffix = if end < chars.len() { "..." } else { "" };
+
+    let cut: String = chars[start..end].iter().collect();
+    let shifted = indices
+        .iter()
+        .filter(|&&i| i >= start && i < end)
+        .map(|i| i - start + prefix.len())
+        .collect();
+    (format!("{}{}{}", prefix, cut, suffix), shifted)
+}
+
+/// Build spans with highlighted matching characters
+fn build_highlighted_spans(
+    text: &str,
+    match_indices: &[usize],
+    is_selected: bool,
+    theme: &Theme,
+) -> Vec<Span<'static>> {
+    let base_style = if is_selected {
+        theme.badge(theme.success)
+    } else {
+        Style::default().fg(theme.text)
     };
-    
-    let mut spans = Vec::new();
-    let mut last_idx = 0;
-    
-    for &idx in match_indices {
-        if idx >= text.len() {
-            continue;
-        }
-        
-        // Add non-matching text before this match
-        if idx > last_idx {
-            spans.push(Span::styled(text[last_idx..idx].to_string(), base_style));
+    styled_matches(text, match_indices, base_style, match_style(is_selected, theme))
+}
+
+/// Split `text` into spans, styling the characters at `match_indices` with `highlight`
+fn styled_matches(text: &str, match_indices: &[usize], base: Style, highlight: Style) -> Vec<Span<'static>> {
+    let mut spans: Vec<Span<'static>> = Vec::new();
+    let mut run = String::new();
+    let mut run_matched = false;
+
+    for (i, c) in text.chars().enumerate() {
+        let matched = match_indices.contains(&i);
+        if matched != run_matched && !run.is_empty() {
+            let style = if run_matched { highlight } else { base };
+            spans.push(Span::styled(std::mem::take(&mut run), style));
         }
-        
-        // Add the matching character
-        let end_idx = (idx + 1).min(text.len());
-        spans.push(Span::styled(text[idx..end_idx].to_string(), highlight_style));
-        last_idx = end_idx;
-    }
-    
-    // Add any remaining text
-    if last_idx < text.len() {
-        spans.push(Span::styled(text[last_idx..].to_string(), base_style));
+        run_matched = matched;
+        run.push(c);
     }
-    
-    if spans.is_empty() {
-        spans.push(Span::styled(text.to_string(), base_style));
+    if !run.is_empty() || spans.is_empty() {
+        let style = if run_matched { highlight } else { base };
+        spans.push(Span::styled(run, style));
     }
-    
+
     spans
 }
 
@@ -837,4 +1663,22 @@ mod tests {
         assert_eq!(config.height_percent, 40);
         assert_eq!(config.border_color, Color::Red);
     }
+
+    #[test]
+    fn test_search_match_highlighting() {
+        let spans = styled_matches("héllo", &[1, 2], Style::default(), Style::default().fg(Color::Green));
+        let texts: Vec<&str> = spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(texts, ["h", "él", "lo"]);
+
+        // Short lines are kept whole
+        assert_eq!(fit_around_match("short", &[0], 20), ("short".to_string(), vec![0]));
+
+        // Long lines are cut around the first match
+        let line = format!("{}needle{}", "x".repeat(50), "y".repeat(50));
+        let (cut, indices) = fit_around_match(&line, &[50, 51], 30);
+        assert!(cut.starts_with("...") && cut.ends_with("..."));
+        assert_eq!(cut.chars().count(), 30);
+        let matched: String = indices.iter().map(|&i| cut.chars().nth(i).unwrap()).collect();
+        assert_eq!(matched, "ne");
+    }
 }
@@ -2,29 +2,37 @@
 
 use ratatui::{
     layout::Rect,
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
     Frame,
 };
 
 use crate::config::Config;
+use crate::git::GitStatus;
 use crate::models::AppState;
+use crate::ui::{tag_color, Theme};
 
 /// Render the prompt list
-pub fn render_prompt_list(frame: &mut Frame, area: Rect, state: &AppState, config: &Config) {
+pub fn render_prompt_list(
+    frame: &mut Frame,
+    area: Rect,
+    state: &AppState,
+    config: &Config,
+    theme: &Theme,
+) {
     // Different title and styling for archive mode
     let (title, title_style, border_color) = if state.mode == crate::models::Mode::Archive {
         (
             format!(" 📦 Archived ({}) ", state.prompts.len()),
-            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
-            Color::Yellow,
+            Style::default().fg(theme.mode_archive).add_modifier(Modifier::BOLD),
+            theme.mode_archive,
         )
     } else {
         (
             format!(" Prompts ({}) ", state.prompts.len()),
             Style::default(),
-            if state.editor_focused { Color::DarkGray } else { Color::Cyan },
+            if state.editor_focused { theme.muted } else { theme.accent },
         )
     };
     
@@ -42,7 +50,7 @@ pub fn render_prompt_list(frame: &mut Frame, area: Rect, state: &AppState, confi
             Line::from(""),
             Line::from(empty_text),
             Line::from(""),
-            Line::from(Span::styled(hint_text, Style::default().fg(Color::DarkGray))),
+            Line::from(Span::styled(hint_text, Style::default().fg(theme.muted))),
         ])
         .block(
             Block::default()
@@ -63,9 +71,13 @@ pub fn render_prompt_list(frame: &mut Frame, area: Rect, state: &AppState, confi
         .map(|(idx, prompt)| {
             let mut spans = Vec::new();
             
-            // Tag color indicator
-            if let Some(first_tag) = prompt.tags.first() {
-                let color = tag_color(config.get_tag_color(first_tag));
+            // Status badge, then tag color indicator
+            if prompt.broken_error().is_some() {
+                spans.push(Span::styled("✗ ", Style::default().fg(theme.error)));
+            } else if prompt.is_unmanaged() {
+                spans.push(Span::styled("○ ", Style::default().fg(theme.muted)));
+            } else if let Some(first_tag) = prompt.tags.first() {
+                let color = tag_color(config, first_tag);
                 spans.push(Span::styled("● ", Style::default().fg(color)));
             } else {
                 spans.push(Span::raw("  "));
@@ -73,15 +85,25 @@ pub fn render_prompt_list(frame: &mut Frame, area: Rect, state: &AppState, confi
             
             // Prompt name
             let name_style = if idx == state.selected_index && !state.editor_focused {
-                Style::default()
-                    .fg(Color::Black)
-                    .bg(Color::Cyan)
-                    .add_modifier(Modifier::BOLD)
+                theme.selected().add_modifier(Modifier::BOLD)
+            } else if prompt.is_unmanaged() {
+                Style::default().fg(theme.muted).add_modifier(Modifier::ITALIC)
             } else {
                 Style::default()
             };
             
             spans.push(Span::styled(&prompt.name, name_style));
+            if prompt.is_unmanaged() {
+                spans.push(Span::styled(" unmanaged", Style::default().fg(theme.muted)));
+            }
+            if let Some(status) = state.git_status.get(&prompt.name) {
+                let color = match status {
+                    GitStatus::Untracked => theme.git_untracked,
+                    GitStatus::Modified => theme.git_modified,
+                    GitStatus::Staged => theme.git_staged,
+                };
+                spans.push(Span::styled(format!(" {}", status.mark()), Style::default().fg(color)));
+            }
             
             ListItem::new(Line::from(spans))
         })
@@ -107,18 +129,3 @@ pub fn render_prompt_list(frame: &mut Frame, area: Rect, state: &AppState, confi
 
     frame.render_stateful_widget(list, area, &mut list_state);
 }
-
-/// Convert a color name to a ratatui Color
-fn tag_color(name: &str) -> Color {
-    match name.to_lowercase().as_str() {
-        "blue" => Color::Blue,
-        "green" => Color::Green,
-        "yellow" => Color::Yellow,
-        "magenta" | "purple" => Color::Magenta,
-        "cyan" => Color::Cyan,
-        "red" => Color::Red,
-        "white" => Color::White,
-        "gray" | "grey" => Color::Gray,
-        _ => Color::White,
-    }
-}
@@ -2,67 +2,67 @@
 
 use ratatui::{
     layout::Rect,
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Paragraph},
     Frame,
 };
 
+use crate::config::Config;
 use crate::models::{AppState, EditorMode, NotificationLevel, VimOperator};
+use crate::ui::{tag_color, Theme};
 
 /// Render the status bar
-pub fn render_status_bar(frame: &mut Frame, area: Rect, state: &AppState, archived_count: usize) {
+pub fn render_status_bar(
+    frame: &mut Frame,
+    area: Rect,
+    state: &AppState,
+    config: &Config,
+    theme: &Theme,
+    archived_count: usize,
+) {
     let mut spans = Vec::new();
 
     // Mode indicator with vim editor sub-mode
     if state.mode == crate::models::Mode::Insert {
         // Show vim-style mode indicator when in editor
         let (mode_text, mode_color) = match state.editor_mode {
-            EditorMode::VimNormal => ("NORMAL", Color::Blue),
-            EditorMode::VimInsert => ("INSERT", Color::Green),
-            EditorMode::VimVisual => ("VISUAL", Color::Magenta),
-            EditorMode::VimVisualLine => ("V-LINE", Color::Magenta),
+            EditorMode::VimNormal => ("NORMAL", theme.mode_normal),
+            EditorMode::VimInsert => ("INSERT", theme.mode_insert),
+            EditorMode::VimVisual => ("VISUAL", theme.mode_visual),
+            EditorMode::VimVisualLine => ("V-LINE", theme.mode_visual),
             EditorMode::VimOperatorPending(op) => {
                 use crate::models::VimOperator;
                 match op {
-                    VimOperator::Delete => ("d...", Color::Red),
-                    VimOperator::Change => ("c...", Color::Yellow),
-                    VimOperator::Yank => ("y...", Color::Cyan),
+                    VimOperator::Delete => ("d...", theme.error),
+                    VimOperator::Change => ("c...", theme.mode_operator),
+                    VimOperator::Yank => ("y...", theme.accent),
                 }
             }
         };
         
         spans.push(Span::styled(
             format!(" [{}] ", mode_text),
-            Style::default()
-                .fg(Color::Black)
-                .bg(mode_color)
-                .add_modifier(Modifier::BOLD),
+            theme.badge(mode_color).add_modifier(Modifier::BOLD),
         ));
         
         // Add "EDITING" indicator
         spans.push(Span::styled(
             " EDITING ",
-            Style::default()
-                .fg(Color::Black)
-                .bg(Color::DarkGray)
-                .add_modifier(Modifier::BOLD),
+            theme.badge(theme.muted).add_modifier(Modifier::BOLD),
         ));
     } else {
         let mode_color = match state.mode {
-            crate::models::Mode::Normal => Color::Blue,
-            crate::models::Mode::Insert => Color::Green, // Won't reach here
-            crate::models::Mode::Archive => Color::Yellow,
-            crate::models::Mode::Folder => Color::Magenta,
-            crate::models::Mode::Preview => Color::Cyan,
+            crate::models::Mode::Normal => theme.mode_normal,
+            crate::models::Mode::Insert => theme.mode_insert, // Won't reach here
+            crate::models::Mode::Archive => theme.mode_archive,
+            crate::models::Mode::Folder => theme.mode_folder,
+            crate::models::Mode::Preview => theme.mode_preview,
         };
         
         spans.push(Span::styled(
             format!(" [{}] ", state.mode.as_str()),
-            Style::default()
-                .fg(Color::Black)
-                .bg(mode_color)
-                .add_modifier(Modifier::BOLD),
+            theme.badge(mode_color).add_modifier(Modifier::BOLD),
         ));
     }
     spans.push(Span::raw(" "));
@@ -71,8 +71,12 @@ pub fn render_status_bar(frame: &mut Frame, area: Rect, state: &AppState, archiv
     if let Some(prompt) = state.selected_prompt() {
         if !prompt.tags.is_empty() {
             spans.push(Span::raw("Tags: "));
-            let tags_str = prompt.tags.join(", ");
-            spans.push(Span::styled(tags_str, Style::default().fg(Color::Yellow)));
+            for (i, tag) in prompt.tags.iter().enumerate() {
+                if i > 0 {
+                    spans.push(Span::raw(", "));
+                }
+                spans.push(Span::styled(tag.as_str(), Style::default().fg(tag_color(config, tag))));
+            }
             spans.push(Span::raw(" │ "));
         }
     }
@@ -80,38 +84,29 @@ pub fn render_status_bar(frame: &mut Frame, area: Rect, state: &AppState, archiv
     // Statistics
     spans.push(Span::styled(
         format!("{} prompts", state.prompts.len()),
-        Style::default().fg(Color::Cyan),
+        Style::default().fg(theme.accent),
     ));
     spans.push(Span::raw(" │ "));
     
     spans.push(Span::styled(
         format!("{} archived", archived_count),
-        Style::default().fg(Color::DarkGray),
+        Style::default().fg(theme.muted),
     ));
     spans.push(Span::raw(" │ "));
     
     spans.push(Span::styled(
         format!("{} tags", state.all_tags.len()),
-        Style::default().fg(Color::Magenta),
+        Style::default().fg(theme.highlight),
     ));
 
-    // Tag filter indicator
-    if let Some(tag) = &state.tag_filter {
-        spans.push(Span::raw(" │ "));
-        spans.push(Span::styled(
-            format!("Filter: {}", tag),
-            Style::default().fg(Color::Yellow).add_modifier(Modifier::ITALIC),
-        ));
-    }
-
     // Notification (if any)
     if let Some(notification) = &state.notification {
         spans.push(Span::raw(" │ "));
         let notif_color = match notification.level {
-            NotificationLevel::Info => Color::White,
-            NotificationLevel::Success => Color::Green,
-            NotificationLevel::Warning => Color::Yellow,
-            NotificationLevel::Error => Color::Red,
+            NotificationLevel::Info => theme.info,
+            NotificationLevel::Success => theme.success,
+            NotificationLevel::Warning => theme.warning,
+            NotificationLevel::Error => theme.error,
         };
         spans.push(Span::styled(&notification.message, Style::default().fg(notif_color)));
     }
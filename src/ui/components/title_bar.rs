@@ -2,29 +2,30 @@
 
 use ratatui::{
     layout::Rect,
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Paragraph},
     Frame,
 };
 
 use crate::models::AppState;
+use crate::ui::Theme;
 
 /// Render the title bar
-pub fn render_title_bar(frame: &mut Frame, area: Rect, state: &AppState) {
+pub fn render_title_bar(frame: &mut Frame, area: Rect, state: &AppState, theme: &Theme) {
     // Different title styling for archive mode
     let (title_text, title_style) = if state.mode == crate::models::Mode::Archive {
         (
             " piemme - archive ",
             Style::default()
-                .fg(Color::Yellow)
+                .fg(theme.mode_archive)
                 .add_modifier(Modifier::BOLD),
         )
     } else {
         (
             " piemme ",
             Style::default()
-                .fg(Color::Cyan)
+                .fg(theme.accent)
                 .add_modifier(Modifier::BOLD),
         )
     };
@@ -35,16 +36,33 @@ pub fn render_title_bar(frame: &mut Frame, area: Rect, state: &AppState) {
     if state.mode == crate::models::Mode::Archive {
         spans.push(Span::styled(
             "📦 ",
-            Style::default().fg(Color::Yellow),
+            Style::default().fg(theme.mode_archive),
         ));
     }
 
-    // Current folder
+    // Current folder, as a breadcrumb for nested folders
     if let Some(folder) = &state.current_folder {
         spans.push(Span::raw(" 📁 /"));
+        for (i, part) in folder.split('/').enumerate() {
+            if i > 0 {
+                spans.push(Span::styled(" › ", Style::default().fg(theme.muted)));
+            }
+            spans.push(Span::styled(part, Style::default().fg(theme.highlight)));
+        }
+    }
+
+    // Open saved search
+    if let Some(view) = &state.current_view {
+        spans.push(Span::raw(" 🔍 "));
+        spans.push(Span::styled(view.as_str(), Style::default().fg(theme.accent)));
+    }
+
+    // Active tag filter
+    if !state.tag_filter.is_empty() {
+        spans.push(Span::raw(" 🏷 "));
         spans.push(Span::styled(
-            folder,
-            Style::default().fg(Color::Yellow),
+            state.tag_filter.describe(),
+            Style::default().fg(theme.highlight).add_modifier(Modifier::ITALIC),
         ));
     }
 
@@ -59,9 +77,9 @@ pub fn render_title_bar(frame: &mut Frame, area: Rect, state: &AppState) {
     };
     
     let safe_mode_style = if state.safe_mode {
-        Style::default().fg(Color::Green)
+        Style::default().fg(theme.success)
     } else {
-        Style::default().fg(Color::Red)
+        Style::default().fg(theme.error)
     };
 
     // For simplicity, just append (proper right-alignment would need width calculation)
@@ -2,81 +2,131 @@
 
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
-use crate::models::{Action, AppState, EditorMode, Mode};
+use crate::models::{Action, AppState, EditorMode, ExportField, FolderEdit, SearchPopupState, TagSelectorEdit};
+use crate::ui::{KeyChord, KeyPress, Keymap, KeymapMode};
 
 /// Handle a key event and return the corresponding action
-pub fn handle_key_event(key: KeyEvent, state: &AppState) -> Action {
+pub fn handle_key_event(key: KeyEvent, state: &AppState, keymap: &mut Keymap) -> Action {
+    // Popups and the help overlay have fixed keys and interrupt any key sequence
+    if let Some(action) = handle_overlay_keys(key, state, keymap) {
+        keymap.clear_pending();
+        return action;
+    }
+
+    // A Vim operator (d, c, y) takes its motion next
+    let Some(mode) = KeymapMode::of(state) else {
+        return handle_vim_operator_pending_mode(key, state);
+    };
+
+    match keymap.press(mode, KeyChord::from_event(key)) {
+        // v and V leave the visual mode they would enter
+        KeyPress::Bound(Action::VimEnterVisual) if state.editor_mode == EditorMode::VimVisual => {
+            Action::VimExitToNormal
+        }
+        KeyPress::Bound(Action::VimEnterVisualLine)
+            if state.editor_mode == EditorMode::VimVisualLine =>
+        {
+            Action::VimExitToNormal
+        }
+        KeyPress::Bound(action) => action,
+        // Unbound keys are typed in the editor's Insert mode
+        KeyPress::Pending | KeyPress::Unbound => Action::None,
+    }
+}
+
+/// Handle keys of an open popup or the help overlay (None when neither is open)
+pub fn handle_overlay_keys(key: KeyEvent, state: &AppState, keymap: &Keymap) -> Option<Action> {
     // If a confirmation dialog is active, handle it first
     if state.confirm_dialog.is_some() {
-        return handle_confirm_dialog(key);
+        return Some(handle_confirm_dialog(key));
+    }
+
+    // If the edited prompt changed on disk, resolve that first
+    if state.conflict_popup.is_some() {
+        return Some(handle_conflict_popup(key));
     }
 
     // If rename popup is active, handle it
     if state.rename_popup.is_some() {
-        return handle_rename_popup(key);
+        return Some(handle_rename_popup(key));
     }
 
     // If reference popup is active, handle it
     if state.reference_popup.is_some() {
-        return handle_reference_popup(key);
+        return Some(handle_reference_popup(key));
     }
 
     // If file picker popup is active, handle it
     if state.file_picker.is_some() {
-        return handle_file_picker_popup(key);
+        return Some(handle_file_picker_popup(key));
+    }
+
+    // If export popup is active, handle it
+    if let Some(export) = &state.export_popup {
+        return Some(handle_export_popup(key, export.focused));
+    }
+
+    // If backup picker is active, handle it
+    if state.backup_picker.is_some() {
+        return Some(handle_backup_picker(key));
+    }
+
+    // If commit popup is active, handle it
+    if state.commit_popup.is_some() {
+        return Some(handle_commit_popup(key));
+    }
+
+    // If blame view is open, handle it
+    if state.blame.is_some() {
+        return Some(handle_blame(key));
+    }
+
+    // If version history is open, handle it
+    if state.history.is_some() {
+        return Some(handle_history(key));
     }
 
     // If search popup is active, handle it
-    if state.search_popup.is_some() {
-        return handle_search_popup(key);
+    if let Some(popup) = &state.search_popup {
+        return Some(handle_search_popup(key, popup));
     }
 
     // If tag selector is active, handle it
     if state.tag_selector.is_some() {
-        return handle_tag_selector(key, state);
+        return Some(handle_tag_selector(key, state));
+    }
+
+    // If tag filter popup is active, handle it
+    if state.tag_filter_popup.is_some() {
+        return Some(handle_tag_filter_popup(key));
     }
 
     // If folder selector is active, handle it
     if state.folder_selector.is_some() {
-        return handle_folder_selector(key, state);
+        return Some(handle_folder_selector(key, state));
     }
 
-    // If help is open, handle help-specific keybindings
-    if state.show_help {
-        return handle_help_keys(key);
+    // If the command palette is open, handle it
+    if state.command_palette.is_some() {
+        return Some(handle_command_palette(key));
     }
 
-    // Global keybindings (work in all modes)
-    match key.code {
-        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-            return Action::Quit;
-        }
-        KeyCode::Char('y') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-            // Ctrl+y copies rendered prompt to clipboard in all modes
-            // (overrides vim-style y and Redo in Insert mode)
-            return Action::CopyRendered;
-        }
-        KeyCode::Char('?') => {
-            // Don't trigger help when in Insert mode (VimInsert) - allow typing '?'
-            if state.mode != Mode::Insert || state.editor_mode != EditorMode::VimInsert {
-                return Action::OpenHelp;
-            }
-        }
-        _ => {}
+    // If help is open, handle help-specific keybindings
+    if state.show_help {
+        return Some(handle_help_keys(key, state, keymap));
     }
 
-    // Mode-specific keybindings
-    match state.mode {
-        Mode::Normal => handle_normal_mode(key, state),
-        Mode::Insert => handle_insert_mode(key, state),
-        Mode::Archive => handle_archive_mode(key),
-        Mode::Folder => handle_folder_mode(key, state),
-        Mode::Preview => handle_preview_mode(key),
-    }
+    None
 }
 
 /// Handle keys when help overlay is open
-fn handle_help_keys(key: KeyEvent) -> Action {
+fn handle_help_keys(key: KeyEvent, state: &AppState, keymap: &Keymap) -> Action {
+    // The key that opened help also closes it
+    let chord = [KeyChord::from_event(key)];
+    let bound = KeymapMode::of(state).and_then(|mode| keymap.action_for(mode, &chord));
+    if bound == Some(&Action::OpenHelp) {
+        return Action::OpenHelp;
+    }
     match key.code {
         KeyCode::Esc | KeyCode::Char('?') | KeyCode::Char('q') => Action::OpenHelp, // Toggle off
         KeyCode::Char('j') | KeyCode::Down => Action::HelpScrollDown,
@@ -102,228 +152,32 @@ fn handle_confirm_dialog(key: KeyEvent) -> Action {
     }
 }
 
-/// Handle keys in Normal mode
-fn handle_normal_mode(key: KeyEvent, _state: &AppState) -> Action {
-    // Check for popups/overlays first
-    // (would be handled here)
-
-    match key.code {
-        // Navigation
-        KeyCode::Char('j') | KeyCode::Down => Action::MoveDown,
-        KeyCode::Char('k') | KeyCode::Up => Action::MoveUp,
-        KeyCode::Char('g') => Action::GoToFirst,
-        KeyCode::Char('G') => Action::GoToLast,
-
-        // Mode switching
-        KeyCode::Enter | KeyCode::Char('i') => Action::EnterInsertMode,
-        KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::QuickOpen,
-        KeyCode::Char('p') => Action::TogglePreview,
-        KeyCode::Char('A') => Action::OpenArchive,
-        KeyCode::Char('O') => Action::OpenFolder,
-
-        // Prompt management
-        KeyCode::Char('n') => Action::NewPrompt,
-        KeyCode::Char('r') => Action::OpenRenamePopup,
-        KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::DuplicatePrompt,
-        KeyCode::Char('d') => Action::DeletePrompt,
-        KeyCode::Char('a') => Action::ArchivePrompt,
-
-        // Clipboard
-        KeyCode::Char('y') => Action::CopyRendered,
-
-        // Tags
-        KeyCode::Char('t') => Action::OpenTagSelector,
-        KeyCode::Char('[') => Action::PreviousTagFilter,
-        KeyCode::Char(']') => Action::NextTagFilter,
-
-        // Search
-        KeyCode::Char('/') => Action::OpenSearch,
-
-        // Other
-        KeyCode::Tab => Action::ToggleFocus,
-        KeyCode::Char('!') => Action::ToggleSafeMode,
-        KeyCode::Char('e') => Action::Export,
-        KeyCode::Char('M') => Action::MoveToFolder,
-        KeyCode::Char('q') => Action::Quit,
-        
-        // Column resize
-        KeyCode::Char('l') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::IncreaseLeftColumnWidth,
-        KeyCode::Char('h') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::DecreaseLeftColumnWidth,
-
-        _ => Action::None,
-    }
-}
-
-/// Handle keys in Insert mode (dispatch to appropriate vim sub-mode handler)
-fn handle_insert_mode(key: KeyEvent, state: &AppState) -> Action {
-    match state.editor_mode {
-        EditorMode::VimNormal => handle_vim_normal_mode(key),
-        EditorMode::VimInsert => handle_vim_insert_mode(key),
-        EditorMode::VimVisual | EditorMode::VimVisualLine => handle_vim_visual_mode(key, state),
-        EditorMode::VimOperatorPending(_) => handle_vim_operator_pending_mode(key, state),
-    }
-}
-
-/// Handle keys in Vim Normal mode (within the editor)
-fn handle_vim_normal_mode(key: KeyEvent) -> Action {
-    match key.code {
-        // Exit editor entirely
-        KeyCode::Esc => Action::ExitMode,
-        // Quit application from editor normal mode
-        KeyCode::Char('q') => Action::Quit,
-        
-        // Save
-        KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::Save,
-        
-        // Enter Vim Insert mode
-        KeyCode::Char('i') => Action::VimEnterInsert,
-        KeyCode::Char('I') => Action::VimEnterInsertStart,
-        KeyCode::Char('a') => Action::VimEnterInsert, // Will move cursor right first in app.rs
-        KeyCode::Char('A') => Action::VimEnterInsertEnd,
-        KeyCode::Char('o') => Action::VimOpenBelow,
-        KeyCode::Char('O') => Action::VimOpenAbove,
-        
-        // Visual modes
-        KeyCode::Char('v') => Action::VimEnterVisual,
-        KeyCode::Char('V') => Action::VimEnterVisualLine,
-        
-        // Hybrid: Shift+Arrow for selection (check BEFORE plain arrows)
-        KeyCode::Left if key.modifiers.contains(KeyModifiers::SHIFT) => Action::ExtendSelection,
-        KeyCode::Right if key.modifiers.contains(KeyModifiers::SHIFT) => Action::ExtendSelection,
-        KeyCode::Up if key.modifiers.contains(KeyModifiers::SHIFT) => Action::ExtendSelection,
-        KeyCode::Down if key.modifiers.contains(KeyModifiers::SHIFT) => Action::ExtendSelection,
-        
-        // Movement (plain arrows)
-        KeyCode::Char('h') | KeyCode::Left => Action::VimLeft,
-        KeyCode::Char('j') | KeyCode::Down => Action::VimDown,
-        KeyCode::Char('k') | KeyCode::Up => Action::VimUp,
-        KeyCode::Char('l') | KeyCode::Right => Action::VimRight,
-        KeyCode::Char('0') | KeyCode::Home => Action::VimLineStart,
-        KeyCode::Char('^') => Action::VimFirstNonBlank,
-        KeyCode::Char('$') | KeyCode::End => Action::VimLineEnd,
-        KeyCode::Char('w') => Action::VimWordForward,
-        KeyCode::Char('b') => Action::VimWordBackward,
-        KeyCode::Char('e') => Action::VimWordEnd,
-        KeyCode::Char('g') => Action::VimGoToTop,
-        KeyCode::Char('G') => Action::VimGoToBottom,
-        // Paragraph movements
-        KeyCode::Char('{') => Action::VimParagraphBackward,
-        KeyCode::Char('}') => Action::VimParagraphForward,
-        
-        // Editing - start operator-pending mode for d, c, y
-        KeyCode::Char('x') | KeyCode::Delete => Action::VimDeleteChar,
-        KeyCode::Char('D') => Action::VimDeleteToEnd,
-        KeyCode::Char('C') => Action::VimChangeToEnd,
-        KeyCode::Char('d') => Action::VimStartDelete,  // Enter operator-pending mode
-        KeyCode::Char('c') => Action::VimStartChange,  // Enter operator-pending mode
-        
-        // Yank (vim style) - enter operator-pending mode
-        KeyCode::Char('y') => Action::VimStartYank,
-        KeyCode::Char('p') => Action::VimPut,
-        KeyCode::Char('P') => Action::VimPutBefore,
-        
-        // Undo/Redo
-        KeyCode::Char('u') => Action::Undo,
-        
-        // Open reference popup (both r and Ctrl+r open reference popup)
-        KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::OpenReferencePopup,
-        KeyCode::Char('r') => Action::OpenReferencePopup,
-        
-        // Open file picker popup (Ctrl+f)
-        KeyCode::Char('f') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::OpenFilePicker,
-        
-        // Help
-        KeyCode::Char('?') => Action::OpenHelp,
-        
-        _ => Action::None,
-    }
-}
-
-/// Handle keys in Vim Insert mode (actual text editing)
-fn handle_vim_insert_mode(key: KeyEvent) -> Action {
+/// Handle keys when the export popup is active
+fn handle_export_popup(key: KeyEvent, focused: ExportField) -> Action {
+    let editing_text = focused == ExportField::Destination;
     match key.code {
-        // Exit to Vim Normal mode
-        KeyCode::Esc => Action::VimExitToNormal,
-        
-        // Save (also exits to normal)
-        KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::Save,
-        
-        // Standard editor shortcuts that work in insert mode
-        // Note: Ctrl+y is handled globally for CopyRendered
-        // Note: Ctrl+c is handled globally for Quit
-        KeyCode::Char('z') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::Undo,
-        KeyCode::Char('a') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::SelectAll,
-        KeyCode::Char('l') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::QuickInsertReference,
-        KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::OpenReferencePopup,
-        KeyCode::Char('f') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::OpenFilePicker,
-        
-        // Hybrid: Shift+Arrow for selection while in insert mode
-        KeyCode::Left if key.modifiers.contains(KeyModifiers::SHIFT) => Action::ExtendSelection,
-        KeyCode::Right if key.modifiers.contains(KeyModifiers::SHIFT) => Action::ExtendSelection,
-        KeyCode::Up if key.modifiers.contains(KeyModifiers::SHIFT) => Action::ExtendSelection,
-        KeyCode::Down if key.modifiers.contains(KeyModifiers::SHIFT) => Action::ExtendSelection,
-        
-        // Help
-        KeyCode::Char('?') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::OpenHelp,
-        
-        // All other keys handled by tui-textarea
-        _ => Action::None,
+        KeyCode::Enter => Action::ConfirmExport,
+        KeyCode::Esc => Action::CancelExport,
+        KeyCode::Tab | KeyCode::Down => Action::ExportNextField,
+        KeyCode::BackTab | KeyCode::Up => Action::ExportPreviousField,
+        KeyCode::Char('j') if !editing_text => Action::ExportNextField,
+        KeyCode::Char('k') if !editing_text => Action::ExportPreviousField,
+        KeyCode::Right | KeyCode::Char(' ') | KeyCode::Char('l') if !editing_text => {
+            Action::ExportOptionNext
+        }
+        KeyCode::Left | KeyCode::Char('h') if !editing_text => Action::ExportOptionPrevious,
+        _ => Action::None, // Text input for the destination is handled by the caller
     }
 }
 
-/// Handle keys in Vim Visual modes
-fn handle_vim_visual_mode(key: KeyEvent, state: &AppState) -> Action {
+/// Handle keys in the conflict prompt
+fn handle_conflict_popup(key: KeyEvent) -> Action {
     match key.code {
-        // Exit visual mode
-        KeyCode::Esc => Action::VimExitToNormal,
-        
-        // Ctrl+A to select all
-        KeyCode::Char('a') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::SelectAll,
-        
-        // Hybrid: Shift+Arrow continues selection (check BEFORE plain arrows)
-        KeyCode::Left if key.modifiers.contains(KeyModifiers::SHIFT) => Action::VimLeft,
-        KeyCode::Right if key.modifiers.contains(KeyModifiers::SHIFT) => Action::VimRight,
-        KeyCode::Up if key.modifiers.contains(KeyModifiers::SHIFT) => Action::VimUp,
-        KeyCode::Down if key.modifiers.contains(KeyModifiers::SHIFT) => Action::VimDown,
-        
-        // Movement (extends selection)
-        KeyCode::Char('h') | KeyCode::Left => Action::VimLeft,
-        KeyCode::Char('j') | KeyCode::Down => Action::VimDown,
-        KeyCode::Char('k') | KeyCode::Up => Action::VimUp,
-        KeyCode::Char('l') | KeyCode::Right => Action::VimRight,
-        KeyCode::Char('0') | KeyCode::Home => Action::VimLineStart,
-        KeyCode::Char('^') => Action::VimFirstNonBlank,
-        KeyCode::Char('$') | KeyCode::End => Action::VimLineEnd,
-        KeyCode::Char('w') => Action::VimWordForward,
-        KeyCode::Char('b') => Action::VimWordBackward,
-        KeyCode::Char('e') => Action::VimWordEnd,
-        KeyCode::Char('g') => Action::VimGoToTop,
-        KeyCode::Char('G') => Action::VimGoToBottom,
-        // Paragraph movements
-        KeyCode::Char('{') => Action::VimParagraphBackward,
-        KeyCode::Char('}') => Action::VimParagraphForward,
-        
-        // Actions on selection
-        KeyCode::Char('d') | KeyCode::Char('x') => Action::VimDeleteChar, // Delete selection
-        KeyCode::Char('c') => Action::VimChangeLine, // Change selection
-        KeyCode::Char('y') => Action::VimYank, // Yank selection
-        
-        // Switch visual modes
-        KeyCode::Char('v') => {
-            if state.editor_mode == EditorMode::VimVisual {
-                Action::VimExitToNormal // Toggle off
-            } else {
-                Action::VimEnterVisual // Switch to char-wise
-            }
-        }
-        KeyCode::Char('V') => {
-            if state.editor_mode == EditorMode::VimVisualLine {
-                Action::VimExitToNormal // Toggle off
-            } else {
-                Action::VimEnterVisualLine // Switch to line-wise
-            }
-        }
-        
+        KeyCode::Char('m') | KeyCode::Esc => Action::ConflictKeepMine,
+        KeyCode::Char('t') => Action::ConflictTakeTheirs,
+        KeyCode::Char('d') => Action::ConflictToggleDiff,
+        KeyCode::Char('j') | KeyCode::Down => Action::ConflictScrollDown,
+        KeyCode::Char('k') | KeyCode::Up => Action::ConflictScrollUp,
         _ => Action::None,
     }
 }
@@ -367,37 +221,6 @@ fn handle_vim_operator_pending_mode(key: KeyEvent, state: &AppState) -> Action {
     }
 }
 
-/// Handle keys in Archive mode
-fn handle_archive_mode(key: KeyEvent) -> Action {
-    match key.code {
-        KeyCode::Char('j') | KeyCode::Down => Action::MoveDown,
-        KeyCode::Char('k') | KeyCode::Up => Action::MoveUp,
-        KeyCode::Char('u') => Action::UnarchivePrompt,
-        KeyCode::Delete => Action::DeletePrompt,
-        KeyCode::Esc => Action::ExitMode,
-        _ => Action::None,
-    }
-}
-
-/// Handle keys in Folder mode
-fn handle_folder_mode(key: KeyEvent, state: &AppState) -> Action {
-    match key.code {
-        KeyCode::Esc => Action::ExitMode,
-        // All other keys work like normal mode
-        _ => handle_normal_mode(key, state),
-    }
-}
-
-/// Handle keys in Preview mode
-fn handle_preview_mode(key: KeyEvent) -> Action {
-    match key.code {
-        KeyCode::Esc | KeyCode::Char('p') => Action::ExitMode,
-        KeyCode::Char('j') | KeyCode::Down => Action::MoveDown,
-        KeyCode::Char('k') | KeyCode::Up => Action::MoveUp,
-        _ => Action::None,
-    }
-}
-
 /// Handle keys when rename popup is active
 fn handle_rename_popup(key: KeyEvent) -> Action {
     match key.code {
@@ -438,6 +261,30 @@ fn handle_tag_selector(key: KeyEvent, state: &AppState) -> Action {
                 _ => Action::None,
             };
         }
+        match selector.edit {
+            Some(TagSelectorEdit::Rename { .. }) => {
+                return match key.code {
+                    KeyCode::Enter => Action::ConfirmRenameTag,
+                    KeyCode::Esc => Action::CancelTagEdit,
+                    // Other keys handled in app.rs for text input
+                    _ => Action::None,
+                };
+            }
+            Some(TagSelectorEdit::Delete { .. }) => {
+                return match key.code {
+                    KeyCode::Char('y') => Action::ConfirmDeleteTag,
+                    KeyCode::Esc | KeyCode::Char('n') => Action::CancelTagEdit,
+                    _ => Action::None,
+                };
+            }
+            Some(TagSelectorEdit::Merge { .. }) => match key.code {
+                KeyCode::Enter => return Action::ConfirmMergeTag,
+                KeyCode::Esc => return Action::CancelTagEdit,
+                // Navigate to the tag to merge into
+                _ => {}
+            },
+            None => {}
+        }
     }
 
     match key.code {
@@ -446,6 +293,24 @@ fn handle_tag_selector(key: KeyEvent, state: &AppState) -> Action {
         KeyCode::Up | KeyCode::Char('k') => Action::TagSelectorUp,
         KeyCode::Down | KeyCode::Char('j') => Action::TagSelectorDown,
         KeyCode::Char('n') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::CreateNewTag,
+        KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::RenameTag,
+        KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::DeleteTag,
+        KeyCode::Char('g') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::MergeTag,
+        // Other keys are handled directly by the popup input handling in app.rs
+        _ => Action::None,
+    }
+}
+
+/// Handle keys when tag filter popup is active
+fn handle_tag_filter_popup(key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Enter => Action::ConfirmTagFilter,
+        KeyCode::Esc => Action::CancelTagFilter,
+        KeyCode::Up => Action::TagFilterUp,
+        KeyCode::Down => Action::TagFilterDown,
+        KeyCode::Char(' ') => Action::CycleTagFilterTag,
+        KeyCode::Tab => Action::ToggleTagFilterMatch,
+        KeyCode::Char('x') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::ClearTagFilter,
         // Other keys are handled directly by the popup input handling in app.rs
         _ => Action::None,
     }
@@ -463,6 +328,31 @@ fn handle_folder_selector(key: KeyEvent, state: &AppState) -> Action {
                 _ => Action::None,
             };
         }
+        match selector.edit {
+            Some(FolderEdit::Rename { .. }) => {
+                return match key.code {
+                    KeyCode::Enter => Action::ConfirmRenameFolder,
+                    KeyCode::Esc => Action::CancelFolderEdit,
+                    // Other keys handled in app.rs for text input
+                    _ => Action::None,
+                };
+            }
+            Some(FolderEdit::Delete { .. }) => {
+                return match key.code {
+                    KeyCode::Char('r') => Action::DeleteFolderToRoot,
+                    KeyCode::Char('a') => Action::DeleteFolderToArchive,
+                    KeyCode::Esc | KeyCode::Char('n') => Action::CancelFolderEdit,
+                    _ => Action::None,
+                };
+            }
+            Some(FolderEdit::Merge { .. }) => match key.code {
+                KeyCode::Enter => return Action::ConfirmMergeFolder,
+                KeyCode::Esc => return Action::CancelFolderEdit,
+                // Navigate to the folder to merge into
+                _ => {}
+            },
+            None => {}
+        }
     }
 
     match key.code {
@@ -470,15 +360,76 @@ fn handle_folder_selector(key: KeyEvent, state: &AppState) -> Action {
         KeyCode::Esc => Action::CancelFolderSelector,
         KeyCode::Up | KeyCode::Char('k') => Action::FolderSelectorUp,
         KeyCode::Down | KeyCode::Char('j') => Action::FolderSelectorDown,
+        KeyCode::Right => Action::FolderSelectorExpand,
+        KeyCode::Left => Action::FolderSelectorCollapse,
         KeyCode::Char('n') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::CreateNewFolder,
+        KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::RenameFolder,
+        KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::DeleteFolder,
+        KeyCode::Char('g') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::MergeFolder,
         // Other keys are handled directly by the popup input handling in app.rs
         _ => Action::None,
     }
 }
 
+/// Handle keys when backup picker is active
+fn handle_backup_picker(key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Enter => Action::ConfirmBackupRestore,
+        KeyCode::Esc | KeyCode::Char('q') => Action::CancelBackupPicker,
+        KeyCode::Up | KeyCode::Char('k') => Action::BackupPickerUp,
+        KeyCode::Down | KeyCode::Char('j') => Action::BackupPickerDown,
+        _ => Action::None,
+    }
+}
+
+/// Handle keys when the commit popup is active
+fn handle_commit_popup(key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Enter => Action::ConfirmCommit,
+        KeyCode::Esc => Action::CancelCommit,
+        KeyCode::Tab => Action::ToggleCommitScope,
+        // Other keys are handled directly by the popup input handling in app.rs
+        _ => Action::None,
+    }
+}
+
+/// Handle keys in the blame view
+fn handle_blame(key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('b') => Action::CloseBlame,
+        KeyCode::Down | KeyCode::Char('j') => Action::BlameScrollDown,
+        KeyCode::Up | KeyCode::Char('k') => Action::BlameScrollUp,
+        _ => Action::None,
+    }
+}
+
+/// Handle keys in the version history
+fn handle_history(key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Enter => Action::RestoreRevision,
+        KeyCode::Esc | KeyCode::Char('q') => Action::CloseHistory,
+        KeyCode::Up | KeyCode::Char('k') => Action::HistoryUp,
+        KeyCode::Down | KeyCode::Char('j') => Action::HistoryDown,
+        KeyCode::Char('c') => Action::HistoryToggleCompare,
+        KeyCode::PageDown | KeyCode::Char('J') => Action::HistoryScrollDown,
+        KeyCode::PageUp | KeyCode::Char('K') => Action::HistoryScrollUp,
+        _ => Action::None,
+    }
+}
+
 /// Handle keys when search popup is active
-fn handle_search_popup(key: KeyEvent) -> Action {
+fn handle_search_popup(key: KeyEvent, popup: &SearchPopupState) -> Action {
+    if popup.view_name.is_some() {
+        return match key.code {
+            KeyCode::Enter => Action::ConfirmSaveView,
+            KeyCode::Esc => Action::CancelSaveView,
+            _ => Action::None,
+        };
+    }
     match key.code {
+        KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            Action::SaveSearchAsView
+        }
         KeyCode::Enter => Action::ConfirmSearch,
         KeyCode::Esc => Action::CloseSearch,
         KeyCode::Up | KeyCode::Char('k') if key.modifiers.contains(KeyModifiers::CONTROL) => {
@@ -494,6 +445,24 @@ fn handle_search_popup(key: KeyEvent) -> Action {
     }
 }
 
+/// Handle keys in the command palette
+fn handle_command_palette(key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Enter => Action::ConfirmCommandPalette,
+        KeyCode::Esc => Action::CancelCommandPalette,
+        KeyCode::Up | KeyCode::Char('k') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            Action::CommandPaletteUp
+        }
+        KeyCode::Down | KeyCode::Char('j') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            Action::CommandPaletteDown
+        }
+        KeyCode::Up => Action::CommandPaletteUp,
+        KeyCode::Down => Action::CommandPaletteDown,
+        // Other keys are handled directly by the popup input handling in app.rs
+        _ => Action::None,
+    }
+}
+
 /// Handle keys when file picker popup is active
 fn handle_file_picker_popup(key: KeyEvent) -> Action {
     match key.code {
@@ -0,0 +1,871 @@
+//! Key chords and the per-mode keymap, with defaults overridable from the config
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use anyhow::{bail, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::models::{Action, AppState, EditorMode, Mode};
+
+/// A key with its modifiers, e.g. `ctrl+s` or `G`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    /// The chord of a key event, ignoring Shift where it is already part of the key
+    pub fn from_event(key: KeyEvent) -> Self {
+        let modifiers = key.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        Self::new(key.code, modifiers)
+    }
+
+    /// Fold Shift into characters (`shift+g` is `G`) and into BackTab
+    fn new(code: KeyCode, mut modifiers: KeyModifiers) -> Self {
+        let code = match code {
+            KeyCode::Char(c) if modifiers.contains(KeyModifiers::SHIFT) => {
+                modifiers.remove(KeyModifiers::SHIFT);
+                KeyCode::Char(c.to_ascii_uppercase())
+            }
+            KeyCode::Tab if modifiers.contains(KeyModifiers::SHIFT) => {
+                modifiers.remove(KeyModifiers::SHIFT);
+                KeyCode::BackTab
+            }
+            KeyCode::BackTab => {
+                modifiers.remove(KeyModifiers::SHIFT);
+                KeyCode::BackTab
+            }
+            code => code,
+        };
+        Self { code, modifiers }
+    }
+
+    /// Parse a chord such as `ctrl+s`, `G`, `shift+left`, `esc` or `space`
+    pub fn parse(text: &str) -> Result<Self> {
+        let text = text.trim();
+        let (modifiers, key) = if text == "+" {
+            ("", "+")
+        } else if let Some(modifiers) = text.strip_suffix("++") {
+            (modifiers, "+")
+        } else {
+            match text.rsplit_once('+') {
+                Some((modifiers, key)) => (modifiers, key),
+                None => ("", text),
+            }
+        };
+
+        let mut parsed = KeyModifiers::NONE;
+        for modifier in modifiers.split('+').filter(|m| !m.is_empty()) {
+            parsed |= match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" | "meta" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => bail!("unknown modifier '{}' (expected ctrl, alt or shift)", modifier),
+            };
+        }
+
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            (None, _) => bail!("missing key after '{}'", text),
+            _ => match key.to_lowercase().as_str() {
+                "esc" | "escape" => KeyCode::Esc,
+                "enter" | "return" => KeyCode::Enter,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "space" => KeyCode::Char(' '),
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                name => match name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                    Some(n) if (1..=12).contains(&n) => KeyCode::F(n),
+                    _ => bail!(
+                        "unknown key '{}' (separate the keys of a sequence with spaces)",
+                        key
+                    ),
+                },
+            },
+        };
+        Ok(Self::new(code, parsed))
+    }
+
+    /// Whether this chord types a character in the editor
+    fn is_typed(&self) -> bool {
+        matches!(self.code, KeyCode::Char(_))
+            && !self.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "Shift+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            // Shift is folded into the character; show it where a modifier hides the case
+            KeyCode::Char(c)
+                if c.is_ascii_uppercase()
+                    && self.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+            {
+                write!(f, "Shift+{}", c.to_ascii_lowercase())
+            }
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::Esc => write!(f, "Esc"),
+            KeyCode::BackTab => write!(f, "Shift+Tab"),
+            KeyCode::Up => write!(f, "↑"),
+            KeyCode::Down => write!(f, "↓"),
+            KeyCode::Left => write!(f, "←"),
+            KeyCode::Right => write!(f, "→"),
+            KeyCode::PageUp => write!(f, "PageUp"),
+            KeyCode::PageDown => write!(f, "PageDown"),
+            code => write!(f, "{}", code),
+        }
+    }
+}
+
+/// Parse a space-separated key sequence such as `g g` or `ctrl+x ctrl+s`
+fn parse_sequence(text: &str) -> Result<Vec<KeyChord>> {
+    if text.trim().is_empty() {
+        bail!("empty key");
+    }
+    // A lone space is the space key rather than an empty sequence
+    if text == " " {
+        return Ok(vec![KeyChord::parse("space")?]);
+    }
+    text.split_whitespace().map(KeyChord::parse).collect()
+}
+
+/// Render a key sequence for display
+fn display_sequence(keys: &[KeyChord]) -> String {
+    keys.iter().map(|k| k.to_string()).collect::<Vec<_>>().join(" ")
+}
+
+/// A group of bindings, as named under `keybindings:` in the config
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum KeymapMode {
+    /// Bindings that apply everywhere, before those of the mode
+    Global,
+    /// The prompt list
+    Normal,
+    /// Inside a folder or view (falls back to `Normal`)
+    Folder,
+    /// The archive view
+    Archive,
+    /// The preview
+    Preview,
+    /// The editor in Vim Normal mode
+    Editor,
+    /// The editor in Insert mode (other keys are typed)
+    Insert,
+    /// The editor in Visual mode
+    Visual,
+}
+
+/// Every keymap mode with its config name
+pub const KEYMAP_MODES: &[(KeymapMode, &str)] = &[
+    (KeymapMode::Global, "global"),
+    (KeymapMode::Normal, "normal"),
+    (KeymapMode::Folder, "folder"),
+    (KeymapMode::Archive, "archive"),
+    (KeymapMode::Preview, "preview"),
+    (KeymapMode::Editor, "editor"),
+    (KeymapMode::Insert, "insert"),
+    (KeymapMode::Visual, "visual"),
+];
+
+impl KeymapMode {
+    /// The config name of this mode
+    pub fn name(self) -> &'static str {
+        KEYMAP_MODES
+            .iter()
+            .find(|(mode, _)| *mode == self)
+            .map(|(_, name)| *name)
+            .unwrap_or("")
+    }
+
+    /// Find a mode by its config name
+    pub fn from_name(name: &str) -> Option<Self> {
+        KEYMAP_MODES.iter().find(|(_, n)| *n == name).map(|(mode, _)| *mode)
+    }
+
+    /// The keymap mode the app is in (None while a Vim operator waits for its motion)
+    pub fn of(state: &AppState) -> Option<Self> {
+        Some(match state.mode {
+            Mode::Normal => KeymapMode::Normal,
+            Mode::Folder => KeymapMode::Folder,
+            Mode::Archive => KeymapMode::Archive,
+            Mode::Preview => KeymapMode::Preview,
+            Mode::Insert => match state.editor_mode {
+                EditorMode::VimNormal => KeymapMode::Editor,
+                EditorMode::VimInsert => KeymapMode::Insert,
+                EditorMode::VimVisual | EditorMode::VimVisualLine => KeymapMode::Visual,
+                EditorMode::VimOperatorPending(_) => return None,
+            },
+        })
+    }
+
+    /// Modes whose bindings apply in this one, in lookup order
+    fn chain(self) -> &'static [KeymapMode] {
+        use KeymapMode::*;
+        match self {
+            Global => &[Global],
+            Normal => &[Global, Normal],
+            Folder => &[Global, Folder, Normal],
+            Archive => &[Global, Archive],
+            Preview => &[Global, Preview],
+            Editor => &[Global, Editor],
+            Insert => &[Global, Insert],
+            Visual => &[Global, Visual],
+        }
+    }
+}
+
+/// Built-in bindings, one or more keys per action
+const DEFAULT_BINDINGS: &[(KeymapMode, &[&str], Action)] = &[
+    (KeymapMode::Global, &["ctrl+c"], Action::Quit),
+    // Ctrl+y overrides vim-style y and Redo in the editor
+    (KeymapMode::Global, &["ctrl+y"], Action::CopyRendered),
+    (KeymapMode::Global, &["ctrl+shift+p"], Action::OpenCommandPalette),
+    // Navigation
+    (KeymapMode::Normal, &["j", "down"], Action::MoveDown),
+    (KeymapMode::Normal, &["k", "up"], Action::MoveUp),
+    (KeymapMode::Normal, &["g"], Action::GoToFirst),
+    (KeymapMode::Normal, &["G"], Action::GoToLast),
+    // Mode switching
+    (KeymapMode::Normal, &["enter", "i"], Action::EnterInsertMode),
+    (KeymapMode::Normal, &["ctrl+p"], Action::QuickOpen),
+    (KeymapMode::Normal, &["p"], Action::TogglePreview),
+    (KeymapMode::Normal, &["A"], Action::OpenArchive),
+    (KeymapMode::Normal, &["O"], Action::OpenFolder),
+    // Prompt management
+    (KeymapMode::Normal, &["n"], Action::NewPrompt),
+    (KeymapMode::Normal, &["r"], Action::OpenRenamePopup),
+    (KeymapMode::Normal, &["ctrl+d"], Action::DuplicatePrompt),
+    (KeymapMode::Normal, &["d"], Action::DeletePrompt),
+    (KeymapMode::Normal, &["a"], Action::ArchivePrompt),
+    (KeymapMode::Normal, &["F"], Action::AdoptPrompt),
+    (KeymapMode::Normal, &["B"], Action::OpenBackups),
+    (KeymapMode::Normal, &["H"], Action::OpenHistory),
+    // Git
+    (KeymapMode::Normal, &["C"], Action::OpenCommitPopup),
+    (KeymapMode::Normal, &["b"], Action::OpenBlame),
+    // Clipboard
+    (KeymapMode::Normal, &["y"], Action::CopyRendered),
+    // Tags
+    (KeymapMode::Normal, &["t"], Action::OpenTagSelector),
+    (KeymapMode::Normal, &["["], Action::PreviousTagFilter),
+    (KeymapMode::Normal, &["]"], Action::NextTagFilter),
+    (KeymapMode::Normal, &["T"], Action::OpenTagFilter),
+    // Search
+    (KeymapMode::Normal, &["/"], Action::OpenSearch),
+    // Other
+    (KeymapMode::Normal, &["tab"], Action::ToggleFocus),
+    (KeymapMode::Normal, &["!"], Action::ToggleSafeMode),
+    (KeymapMode::Normal, &["e"], Action::Export),
+    (KeymapMode::Normal, &["M"], Action::MoveToFolder),
+    (KeymapMode::Normal, &["q"], Action::Quit),
+    (KeymapMode::Normal, &["?"], Action::OpenHelp),
+    (KeymapMode::Normal, &[":"], Action::OpenCommandPalette),
+    // Column resize
+    (KeymapMode::Normal, &["ctrl+l"], Action::IncreaseLeftColumnWidth),
+    (KeymapMode::Normal, &["ctrl+h"], Action::DecreaseLeftColumnWidth),
+    // Folder mode works like normal mode, but Esc goes up a level
+    (KeymapMode::Folder, &["esc"], Action::ExitMode),
+    // Archive
+    (KeymapMode::Archive, &["j", "down"], Action::MoveDown),
+    (KeymapMode::Archive, &["k", "up"], Action::MoveUp),
+    (KeymapMode::Archive, &["u"], Action::UnarchivePrompt),
+    (KeymapMode::Archive, &["F"], Action::AdoptPrompt),
+    (KeymapMode::Archive, &["delete"], Action::DeletePrompt),
+    (KeymapMode::Archive, &["esc"], Action::ExitMode),
+    (KeymapMode::Archive, &["?"], Action::OpenHelp),
+    (KeymapMode::Archive, &[":"], Action::OpenCommandPalette),
+    // Preview
+    (KeymapMode::Preview, &["esc", "p"], Action::ExitMode),
+    (KeymapMode::Preview, &["j", "down"], Action::MoveDown),
+    (KeymapMode::Preview, &["k", "up"], Action::MoveUp),
+    (KeymapMode::Preview, &["?"], Action::OpenHelp),
+    (KeymapMode::Preview, &[":"], Action::OpenCommandPalette),
+    // Editor (Vim Normal)
+    (KeymapMode::Editor, &["esc"], Action::ExitMode),
+    (KeymapMode::Editor, &["q"], Action::Quit),
+    (KeymapMode::Editor, &["ctrl+s"], Action::Save),
+    (KeymapMode::Editor, &["i", "a"], Action::VimEnterInsert),
+    (KeymapMode::Editor, &["I"], Action::VimEnterInsertStart),
+    (KeymapMode::Editor, &["A"], Action::VimEnterInsertEnd),
+    (KeymapMode::Editor, &["o"], Action::VimOpenBelow),
+    (KeymapMode::Editor, &["O"], Action::VimOpenAbove),
+    (KeymapMode::Editor, &["v"], Action::VimEnterVisual),
+    (KeymapMode::Editor, &["V"], Action::VimEnterVisualLine),
+    (
+        KeymapMode::Editor,
+        &["shift+left", "shift+right", "shift+up", "shift+down"],
+        Action::ExtendSelection,
+    ),
+    (KeymapMode::Editor, &["h", "left"], Action::VimLeft),
+    (KeymapMode::Editor, &["j", "down"], Action::VimDown),
+    (KeymapMode::Editor, &["k", "up"], Action::VimUp),
+    (KeymapMode::Editor, &["l", "right"], Action::VimRight),
+    (KeymapMode::Editor, &["0", "home"], Action::VimLineStart),
+    (KeymapMode::Editor, &["^"], Action::VimFirstNonBlank),
+    (KeymapMode::Editor, &["$", "end"], Action::VimLineEnd),
+    (KeymapMode::Editor, &["w"], Action::VimWordForward),
+    (KeymapMode::Editor, &["b"], Action::VimWordBackward),
+    (KeymapMode::Editor, &["e"], Action::VimWordEnd),
+    (KeymapMode::Editor, &["g"], Action::VimGoToTop),
+    (KeymapMode::Editor, &["G"], Action::VimGoToBottom),
+    (KeymapMode::Editor, &["{"], Action::VimParagraphBackward),
+    (KeymapMode::Editor, &["}"], Action::VimParagraphForward),
+    (KeymapMode::Editor, &["x", "delete"], Action::VimDeleteChar),
+    (KeymapMode::Editor, &["D"], Action::VimDeleteToEnd),
+    (KeymapMode::Editor, &["C"], Action::VimChangeToEnd),
+    (KeymapMode::Editor, &["d"], Action::VimStartDelete),
+    (KeymapMode::Editor, &["c"], Action::VimStartChange),
+    (KeymapMode::Editor, &["y"], Action::VimStartYank),
+    (KeymapMode::Editor, &["p"], Action::VimPut),
+    (KeymapMode::Editor, &["P"], Action::VimPutBefore),
+    (KeymapMode::Editor, &["u"], Action::Undo),
+    (KeymapMode::Editor, &["r", "ctrl+r"], Action::OpenReferencePopup),
+    (KeymapMode::Editor, &["ctrl+f"], Action::OpenFilePicker),
+    (KeymapMode::Editor, &["?"], Action::OpenHelp),
+    (KeymapMode::Editor, &[":"], Action::OpenCommandPalette),
+    // Editor (Insert): every other key is typed
+    (KeymapMode::Insert, &["esc"], Action::VimExitToNormal),
+    (KeymapMode::Insert, &["ctrl+s"], Action::Save),
+    (KeymapMode::Insert, &["ctrl+z"], Action::Undo),
+    (KeymapMode::Insert, &["ctrl+a"], Action::SelectAll),
+    (
+        KeymapMode::Insert,
+        &["shift+left", "shift+right", "shift+up", "shift+down"],
+        Action::ExtendSelection,
+    ),
+    (KeymapMode::Insert, &["ctrl+l"], Action::QuickInsertReference),
+    (KeymapMode::Insert, &["ctrl+r"], Action::OpenReferencePopup),
+    (KeymapMode::Insert, &["ctrl+f"], Action::OpenFilePicker),
+    (KeymapMode::Insert, &["ctrl+?"], Action::OpenHelp),
+    // For terminals that cannot tell Ctrl+Shift+p from Ctrl+p
+    (KeymapMode::Insert, &["alt+p"], Action::OpenCommandPalette),
+    // Editor (Visual): motions extend the selection
+    (KeymapMode::Visual, &["esc"], Action::VimExitToNormal),
+    (KeymapMode::Visual, &["ctrl+a"], Action::SelectAll),
+    (KeymapMode::Visual, &["h", "left", "shift+left"], Action::VimLeft),
+    (KeymapMode::Visual, &["j", "down", "shift+down"], Action::VimDown),
+    (KeymapMode::Visual, &["k", "up", "shift+up"], Action::VimUp),
+    (KeymapMode::Visual, &["l", "right", "shift+right"], Action::VimRight),
+    (KeymapMode::Visual, &["0", "home"], Action::VimLineStart),
+    (KeymapMode::Visual, &["^"], Action::VimFirstNonBlank),
+    (KeymapMode::Visual, &["$", "end"], Action::VimLineEnd),
+    (KeymapMode::Visual, &["w"], Action::VimWordForward),
+    (KeymapMode::Visual, &["b"], Action::VimWordBackward),
+    (KeymapMode::Visual, &["e"], Action::VimWordEnd),
+    (KeymapMode::Visual, &["g"], Action::VimGoToTop),
+    (KeymapMode::Visual, &["G"], Action::VimGoToBottom),
+    (KeymapMode::Visual, &["{"], Action::VimParagraphBackward),
+    (KeymapMode::Visual, &["}"], Action::VimParagraphForward),
+    (KeymapMode::Visual, &["d", "x"], Action::VimDeleteChar),
+    (KeymapMode::Visual, &["c"], Action::VimChangeLine),
+    (KeymapMode::Visual, &["y"], Action::VimYank),
+    (KeymapMode::Visual, &["v"], Action::VimEnterVisual),
+    (KeymapMode::Visual, &["V"], Action::VimEnterVisualLine),
+    (KeymapMode::Visual, &["?"], Action::OpenHelp),
+    (KeymapMode::Visual, &[":"], Action::OpenCommandPalette),
+];
+
+/// Actions without a default key, and the modes they work in
+const UNBOUND_COMMANDS: &[(KeymapMode, Action)] = &[
+    (KeymapMode::Normal, Action::CopyRaw),
+    (KeymapMode::Preview, Action::CopyRaw),
+    (KeymapMode::Editor, Action::Redo),
+    (KeymapMode::Editor, Action::Paste),
+    (KeymapMode::Insert, Action::Redo),
+    (KeymapMode::Insert, Action::Paste),
+    (KeymapMode::Visual, Action::CopySelection),
+];
+
+/// A key sequence bound to an action
+#[derive(Debug, Clone)]
+pub struct Binding {
+    pub keys: Vec<KeyChord>,
+    pub action: Action,
+    /// The key as written in the config (None for built-in bindings)
+    source: Option<String>,
+}
+
+/// A problem with the `keybindings` config section
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeymapError {
+    /// Dotted config path, e.g. `keybindings.normal.ctrl+h`
+    pub field: String,
+    pub message: String,
+}
+
+/// Outcome of pressing a key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyPress {
+    /// The key (sequence) is bound to an action
+    Bound(Action),
+    /// The key starts a longer sequence; waiting for the next one
+    Pending,
+    /// Nothing is bound to the key
+    Unbound,
+}
+
+/// Effective key bindings per mode, plus the keys of a sequence typed so far
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: BTreeMap<KeymapMode, Vec<Binding>>,
+    pending: Vec<KeyChord>,
+    pending_mode: Option<KeymapMode>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let mut keymap = Self {
+            bindings: BTreeMap::new(),
+            pending: Vec::new(),
+            pending_mode: None,
+        };
+        for (mode, keys, action) in DEFAULT_BINDINGS {
+            for key in *keys {
+                let keys = parse_sequence(key).expect("default key bindings parse");
+                keymap.bind(*mode, keys, Some(action.clone()), None);
+            }
+        }
+        keymap
+    }
+}
+
+impl Keymap {
+    /// Build the keymap from the defaults and the `keybindings` config section
+    /// (mode -> key -> action name, or `none` to unbind), with every problem found
+    pub fn from_config(config: &BTreeMap<String, BTreeMap<String, String>>) -> (Self, Vec<KeymapError>) {
+        let mut keymap = Self::default();
+        let mut errors = Vec::new();
+
+        for (mode_name, entries) in config {
+            let Some(mode) = KeymapMode::from_name(mode_name) else {
+                let names: Vec<_> = KEYMAP_MODES.iter().map(|(_, name)| *name).collect();
+                errors.push(KeymapError {
+                    field: format!("keybindings.{}", mode_name),
+                    message: format!("Unknown mode. Must be one of: {}", names.join(", ")),
+                });
+                continue;
+            };
+
+            let mut seen: Vec<(Vec<KeyChord>, &str)> = Vec::new();
+            for (key, action_name) in entries {
+                let error = |message: String| KeymapError {
+                    field: format!("keybindings.{}.{}", mode_name, key),
+                    message,
+                };
+                let keys = match parse_sequence(key) {
+                    Ok(keys) => keys,
+                    Err(e) => {
+                        errors.push(error(format!("Invalid key: {}", e)));
+                        continue;
+                    }
+                };
+                let action = match action_name.as_str() {
+                    "none" => None,
+                    name => match Action::from_name(name) {
+                        Some(action) => Some(action),
+                        None => {
+                            errors.push(error(format!(
+                                "Unknown action '{}'. Use an action name such as \
+                                 toggle_safe_mode, or none to unbind the key",
+                                name
+                            )));
+                            continue;
+                        }
+                    },
+                };
+                if keys.len() > 1 && matches!(mode, KeymapMode::Global | KeymapMode::Insert) {
+                    errors.push(error(format!(
+                        "Key sequences are not available in {} bindings",
+                        mode.name()
+                    )));
+                    continue;
+                }
+                if let Some((_, other)) = seen.iter().find(|(other, _)| *other == keys) {
+                    errors.push(error(format!("Same key as '{}'", other)));
+                    continue;
+                }
+                seen.push((keys.clone(), key));
+                keymap.bind(mode, keys, action, Some(key.clone()));
+            }
+        }
+
+        errors.extend(keymap.conflicts());
+        (keymap, errors)
+    }
+
+    /// Bind `keys` to `action` in `mode`, replacing any binding of the same keys
+    /// (`None` only removes it)
+    fn bind(&mut self, mode: KeymapMode, keys: Vec<KeyChord>, action: Option<Action>, source: Option<String>) {
+        let bindings = self.bindings.entry(mode).or_default();
+        let position = bindings.iter().position(|b| b.keys == keys);
+        bindings.retain(|b| b.keys != keys);
+        if let Some(action) = action {
+            let binding = Binding { keys, action, source };
+            match position {
+                Some(position) => bindings.insert(position, binding),
+                None => bindings.push(binding),
+            }
+        }
+    }
+
+    /// Bindings defined in `mode` itself
+    pub fn bindings(&self, mode: KeymapMode) -> &[Binding] {
+        self.bindings.get(&mode).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Bindings that apply in `mode`, in lookup order
+    fn effective(&self, mode: KeymapMode) -> impl Iterator<Item = (KeymapMode, &Binding)> {
+        mode.chain()
+            .iter()
+            .flat_map(move |m| self.bindings(*m).iter().map(move |b| (*m, b)))
+    }
+
+    /// The action bound to exactly `keys` in `mode`
+    pub fn action_for(&self, mode: KeymapMode, keys: &[KeyChord]) -> Option<&Action> {
+        self.effective(mode).find(|(_, b)| b.keys == keys).map(|(_, b)| &b.action)
+    }
+
+    /// Keys bound to `action` in `mode` that reach it, for display
+    pub fn keys_for(&self, mode: KeymapMode, action: &Action) -> Vec<String> {
+        let mut keys: Vec<String> = Vec::new();
+        for (_, binding) in self.effective(mode) {
+            let text = display_sequence(&binding.keys);
+            if binding.action == *action
+                && self.action_for(mode, &binding.keys) == Some(action)
+                && !keys.contains(&text)
+            {
+                keys.push(text);
+            }
+        }
+        keys
+    }
+
+    /// Actions that work in `mode`, whether or not a key is bound to them, for the
+    /// command palette (in the order of `Action::commands`)
+    pub fn commands(&self, mode: KeymapMode) -> Vec<Action> {
+        let defaults = Keymap::default();
+        let available = |action: &Action| {
+            defaults.effective(mode).any(|(_, b)| b.action == *action)
+                || self.effective(mode).any(|(_, b)| b.action == *action)
+                || UNBOUND_COMMANDS
+                    .iter()
+                    .any(|(m, a)| a == action && mode.chain().contains(m))
+        };
+        Action::commands()
+            // Selection extends with the arrow key pressed, which the palette has none of
+            .filter(|action| !matches!(action, Action::OpenCommandPalette | Action::ExtendSelection))
+            .filter(|action| available(action))
+            .cloned()
+            .collect()
+    }
+
+    /// Actions bound in `mode` itself with their keys, in binding order
+    pub fn actions(&self, mode: KeymapMode) -> Vec<(Action, Vec<String>)> {
+        let mut actions: Vec<(Action, Vec<String>)> = Vec::new();
+        for binding in self.bindings(mode) {
+            let keys = display_sequence(&binding.keys);
+            match actions.iter_mut().find(|(action, _)| *action == binding.action) {
+                Some((_, all)) => all.push(keys),
+                None => actions.push((binding.action.clone(), vec![keys])),
+            }
+        }
+        actions
+    }
+
+    /// Whether a key sequence has been started but not finished
+    pub fn is_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Forget a partially typed key sequence
+    pub fn clear_pending(&mut self) {
+        self.pending.clear();
+        self.pending_mode = None;
+    }
+
+    /// Resolve a key pressed in `mode`, continuing a pending sequence
+    pub fn press(&mut self, mode: KeymapMode, chord: KeyChord) -> KeyPress {
+        if self.pending_mode != Some(mode) {
+            self.pending.clear();
+        }
+        let mut keys = std::mem::take(&mut self.pending);
+        keys.push(chord);
+        self.pending_mode = None;
+
+        loop {
+            if let Some(action) = self.action_for(mode, &keys) {
+                return KeyPress::Bound(action.clone());
+            }
+            if self.effective(mode).any(|(_, b)| b.keys.starts_with(&keys)) {
+                self.pending = keys;
+                self.pending_mode = Some(mode);
+                return KeyPress::Pending;
+            }
+            if keys.len() == 1 {
+                return KeyPress::Unbound;
+            }
+            // The sequence went nowhere: start over from the last key
+            keys = vec![chord];
+        }
+    }
+
+    /// Configured bindings that can never fire, or that stop keys from being typed
+    pub fn conflicts(&self) -> Vec<KeymapError> {
+        let mut errors = Vec::new();
+
+        for (mode, _) in KEYMAP_MODES {
+            let mode = *mode;
+            let effective: Vec<_> = self.effective(mode).collect();
+            for (i, (first_mode, first)) in effective.iter().enumerate() {
+                for (second_mode, second) in &effective[i + 1..] {
+                    // Pairs are checked in the mode of one of their bindings only once
+                    if *first_mode != mode && *second_mode != mode {
+                        continue;
+                    }
+                    if first.source.is_none() && second.source.is_none() {
+                        continue;
+                    }
+                    // Bindings earlier in the chain win; a shorter prefix fires at once
+                    let (hidden, hidden_mode, by, by_mode) = if first.keys == second.keys {
+                        // A folder binding deliberately overrides the normal one
+                        if *first_mode != KeymapMode::Global || first.action == second.action {
+                            continue;
+                        }
+                        (second, *second_mode, first, *first_mode)
+                    } else if second.keys.starts_with(&first.keys) {
+                        (second, *second_mode, first, *first_mode)
+                    } else if first.keys.starts_with(&second.keys) {
+                        (first, *first_mode, second, *second_mode)
+                    } else {
+                        continue;
+                    };
+                    let (blamed, blamed_mode, message) = if hidden.source.is_some() {
+                        let first = if hidden.keys == by.keys { "" } else { ", which fires first" };
+                        let message = format!(
+                            "Never fires: '{}' is bound to {} in {}{}",
+                            display_sequence(&by.keys),
+                            by.action.name(),
+                            by_mode.name(),
+                            first
+                        );
+                        (hidden, hidden_mode, message)
+                    } else {
+                        let message = format!(
+                            "Hides '{}' ({}) in {}",
+                            display_sequence(&hidden.keys),
+                            hidden.action.name(),
+                            hidden_mode.name()
+                        );
+                        (by, by_mode, message)
+                    };
+                    errors.push(KeymapError {
+                        field: format!(
+                            "keybindings.{}.{}",
+                            blamed_mode.name(),
+                            blamed.source.as_deref().unwrap_or_default()
+                        ),
+                        message,
+                    });
+                }
+            }
+        }
+
+        // Plain keys in the editor's Insert mode are for typing
+        for mode in [KeymapMode::Global, KeymapMode::Insert] {
+            for binding in self.bindings(mode) {
+                if let Some(source) = &binding.source
+                    && binding.keys.iter().any(KeyChord::is_typed)
+                {
+                    errors.push(KeymapError {
+                        field: format!("keybindings.{}.{}", mode.name(), source),
+                        message: "Would stop the key from being typed in the editor. Add \
+                                  ctrl or alt, or bind it in the other modes"
+                            .to_string(),
+                    });
+                }
+            }
+        }
+
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord(text: &str) -> KeyChord {
+        KeyChord::parse(text).unwrap()
+    }
+
+    fn config(entries: &[(&str, &str, &str)]) -> BTreeMap<String, BTreeMap<String, String>> {
+        let mut config: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
+        for (mode, key, action) in entries {
+            config
+                .entry(mode.to_string())
+                .or_default()
+                .insert(key.to_string(), action.to_string());
+        }
+        config
+    }
+
+    #[test]
+    fn test_parse_chords() {
+        assert_eq!(chord("ctrl+s"), KeyChord::new(KeyCode::Char('s'), KeyModifiers::CONTROL));
+        assert_eq!(chord("Ctrl+Alt+x").modifiers, KeyModifiers::CONTROL | KeyModifiers::ALT);
+        assert_eq!(chord("shift+g"), chord("G"));
+        assert_eq!(chord("shift+tab").code, KeyCode::BackTab);
+        assert_eq!(chord("esc").code, KeyCode::Esc);
+        assert_eq!(chord("space").code, KeyCode::Char(' '));
+        assert_eq!(chord("f5").code, KeyCode::F(5));
+        assert_eq!(chord("ctrl++").code, KeyCode::Char('+'));
+        assert_eq!(chord("shift+left").modifiers, KeyModifiers::SHIFT);
+        assert!(KeyChord::parse("hyper+x").is_err());
+        assert!(KeyChord::parse("gg").is_err());
+        assert_eq!(parse_sequence("g g").unwrap(), vec![chord("g"), chord("g")]);
+    }
+
+    #[test]
+    fn test_events_match_parsed_chords() {
+        let shifted = KeyEvent::new(KeyCode::Char('G'), KeyModifiers::SHIFT);
+        assert_eq!(KeyChord::from_event(shifted), chord("G"));
+        let palette = KeyEvent::new(KeyCode::Char('p'), KeyModifiers::CONTROL | KeyModifiers::SHIFT);
+        assert_eq!(KeyChord::from_event(palette), chord("ctrl+shift+p"));
+        assert_ne!(chord("ctrl+shift+p"), chord("ctrl+p"));
+        assert_eq!(chord("ctrl+h").to_string(), "Ctrl+h");
+        assert_eq!(chord("down").to_string(), "↓");
+        assert_eq!(chord("ctrl+shift+p").to_string(), "Ctrl+Shift+p");
+    }
+
+    #[test]
+    fn test_defaults_resolve_and_do_not_conflict() {
+        let mut keymap = Keymap::default();
+        assert!(keymap.conflicts().is_empty());
+        assert_eq!(keymap.press(KeymapMode::Normal, chord("j")), KeyPress::Bound(Action::MoveDown));
+        assert_eq!(keymap.press(KeymapMode::Folder, chord("esc")), KeyPress::Bound(Action::ExitMode));
+        assert_eq!(keymap.press(KeymapMode::Folder, chord("n")), KeyPress::Bound(Action::NewPrompt));
+        assert_eq!(keymap.press(KeymapMode::Editor, chord("ctrl+y")), KeyPress::Bound(Action::CopyRendered));
+        assert_eq!(keymap.press(KeymapMode::Insert, chord("x")), KeyPress::Unbound);
+        assert_eq!(
+            keymap.press(KeymapMode::Insert, chord("alt+p")),
+            KeyPress::Bound(Action::OpenCommandPalette)
+        );
+        let actions = keymap.actions(KeymapMode::Normal);
+        assert_eq!(actions[0], (Action::MoveDown, vec!["j".to_string(), "↓".to_string()]));
+    }
+
+    #[test]
+    fn test_config_rebinds_and_unbinds() {
+        let (mut keymap, errors) = Keymap::from_config(&config(&[
+            ("normal", "ctrl+h", "none"),
+            ("normal", "ctrl+l", "none"),
+            ("normal", "alt+h", "decrease_left_column_width"),
+            ("normal", "<", "decrease_left_column_width"),
+        ]));
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(keymap.press(KeymapMode::Normal, chord("ctrl+h")), KeyPress::Unbound);
+        assert_eq!(
+            keymap.press(KeymapMode::Normal, chord("alt+h")),
+            KeyPress::Bound(Action::DecreaseLeftColumnWidth)
+        );
+        let (_, keys) = keymap
+            .actions(KeymapMode::Normal)
+            .into_iter()
+            .find(|(action, _)| *action == Action::DecreaseLeftColumnWidth)
+            .unwrap();
+        assert_eq!(keys, ["<", "Alt+h"]);
+    }
+
+    #[test]
+    fn test_key_sequences() {
+        let (mut keymap, errors) = Keymap::from_config(&config(&[
+            ("editor", "g", "none"),
+            ("editor", "g g", "vim_go_to_top"),
+        ]));
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(keymap.press(KeymapMode::Editor, chord("g")), KeyPress::Pending);
+        assert_eq!(keymap.press(KeymapMode::Editor, chord("g")), KeyPress::Bound(Action::VimGoToTop));
+        // A key that does not continue the sequence counts on its own
+        assert_eq!(keymap.press(KeymapMode::Editor, chord("g")), KeyPress::Pending);
+        assert_eq!(keymap.press(KeymapMode::Editor, chord("w")), KeyPress::Bound(Action::VimWordForward));
+        // Changing mode drops the pending keys
+        assert_eq!(keymap.press(KeymapMode::Editor, chord("g")), KeyPress::Pending);
+        assert_eq!(keymap.press(KeymapMode::Visual, chord("g")), KeyPress::Bound(Action::VimGoToTop));
+    }
+
+    #[test]
+    fn test_conflicts_are_reported() {
+        let (_, errors) = Keymap::from_config(&config(&[
+            ("editor", "g g", "vim_go_to_top"),
+            ("global", "ctrl+s", "save"),
+            ("normal", "ctrl+s", "quit"),
+            ("insert", "j", "undo"),
+            ("insert", "ctrl+x ctrl+s", "save"),
+            ("normal", "frobnicate", "quit"),
+            ("normal", "x", "frobnicate"),
+            ("normal", "Ctrl+K", "quit"),
+            ("normal", "ctrl+K", "quit"),
+            ("modal", "x", "quit"),
+        ]));
+        let fields: Vec<_> = errors.iter().map(|e| e.field.as_str()).collect();
+        assert!(fields.contains(&"keybindings.editor.g g"), "{:?}", errors);
+        assert!(fields.contains(&"keybindings.normal.ctrl+s"), "{:?}", errors);
+        assert!(fields.contains(&"keybindings.insert.j"), "{:?}", errors);
+        assert!(fields.contains(&"keybindings.insert.ctrl+x ctrl+s"), "{:?}", errors);
+        assert!(fields.contains(&"keybindings.normal.frobnicate"), "{:?}", errors);
+        assert!(fields.contains(&"keybindings.normal.x"), "{:?}", errors);
+        assert!(fields.contains(&"keybindings.normal.ctrl+K"), "{:?}", errors);
+        assert!(fields.contains(&"keybindings.modal"), "{:?}", errors);
+        let prefix = errors.iter().find(|e| e.field == "keybindings.editor.g g").unwrap();
+        assert!(prefix.message.contains("vim_go_to_top"), "{}", prefix.message);
+    }
+
+    #[test]
+    fn test_commands_per_mode() {
+        let (keymap, _) = Keymap::from_config(&config(&[("normal", "!", "none")]));
+        let normal = keymap.commands(KeymapMode::Normal);
+        // Unbound actions stay in the palette, as do actions without a default key
+        assert!(normal.contains(&Action::ToggleSafeMode));
+        assert!(normal.contains(&Action::CopyRaw));
+        assert!(!normal.contains(&Action::VimWordForward));
+        assert!(!normal.contains(&Action::OpenCommandPalette));
+        assert!(keymap.keys_for(KeymapMode::Normal, &Action::ToggleSafeMode).is_empty());
+
+        let insert = keymap.commands(KeymapMode::Insert);
+        assert!(insert.contains(&Action::Redo));
+        assert!(!insert.contains(&Action::NewPrompt));
+        assert!(keymap.commands(KeymapMode::Folder).contains(&Action::ExitMode));
+        assert_eq!(keymap.keys_for(KeymapMode::Editor, &Action::CopyRendered), ["Ctrl+y"]);
+    }
+
+    #[test]
+    fn test_folder_overrides_normal_without_conflict() {
+        let (mut keymap, errors) = Keymap::from_config(&config(&[("folder", "q", "exit_mode")]));
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(keymap.press(KeymapMode::Folder, chord("q")), KeyPress::Bound(Action::ExitMode));
+        assert_eq!(keymap.press(KeymapMode::Normal, chord("q")), KeyPress::Bound(Action::Quit));
+    }
+}
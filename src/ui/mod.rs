@@ -1,9 +1,15 @@
 //! UI components and rendering
 
+mod colors;
 mod components;
 mod keybindings;
+mod keymap;
 mod render;
+mod theme;
 
+pub use colors::tag_color;
 pub use components::*;
-pub use keybindings::handle_key_event;
+pub use keybindings::{handle_key_event, handle_overlay_keys};
+pub use keymap::{KeyChord, KeyPress, Keymap, KeymapMode};
 pub use render::render;
+pub use theme::{theme_exists, Theme, BUILTIN_THEMES, THEME_ROLES};
@@ -2,29 +2,36 @@
 
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Paragraph, Wrap},
     Frame,
 };
+use std::collections::HashMap;
 use tui_textarea::TextArea;
 
 use crate::config::Config;
+use crate::engine::split_revision;
 use crate::models::AppState;
 
+use super::{Keymap, Theme};
+
 use super::components::{
-    render_confirm_dialog, render_file_picker_popup, render_folder_selector, render_help_overlay, render_prompt_list,
+    render_backup_picker, render_blame_popup, render_command_palette, render_commit_popup, render_confirm_dialog, render_conflict_popup, render_export_popup, render_file_picker_popup, render_folder_selector, render_help_overlay, render_history_popup, render_prompt_list,
     render_reference_popup, render_rename_popup, render_search_popup, render_status_bar,
-    render_tag_selector, render_title_bar,
+    render_tag_filter_popup, render_tag_selector, render_title_bar,
 };
 
 use crate::models::Prompt;
 
 /// Render the entire application
+#[allow(clippy::too_many_arguments)]
 pub fn render(
     frame: &mut Frame,
     state: &AppState,
     config: &Config,
+    keymap: &Keymap,
+    theme: &Theme,
     archived_count: usize,
     editor: Option<&TextArea>,
     all_prompts: &[Prompt],
@@ -42,7 +49,7 @@ pub fn render(
         .split(size);
 
     // Render title bar
-    render_title_bar(frame, main_chunks[0], state);
+    render_title_bar(frame, main_chunks[0], state, theme);
 
     // Content area: left panel (list) and right panel (editor)
     let content_chunks = Layout::default()
@@ -54,17 +61,17 @@ pub fn render(
         .split(main_chunks[1]);
 
     // Render prompt list
-    render_prompt_list(frame, content_chunks[0], state, config);
+    render_prompt_list(frame, content_chunks[0], state, config, theme);
 
     // Render editor/viewer
-    render_editor(frame, content_chunks[1], state, config, editor, all_prompts);
+    render_editor(frame, content_chunks[1], state, theme, editor, all_prompts);
 
     // Render status bar
-    render_status_bar(frame, main_chunks[2], state, archived_count);
+    render_status_bar(frame, main_chunks[2], state, config, theme, archived_count);
 
     // Render help overlay if active
     if state.show_help {
-        render_help_overlay(frame, size, state.mode, state.help_scroll_offset);
+        render_help_overlay(frame, size, state.mode, keymap, state.help_scroll_offset, theme);
     }
 
     // Render confirmation dialog if active
@@ -75,37 +82,79 @@ pub fn render(
             &dialog.title,
             &dialog.message,
             dialog.yes_selected,
+            theme,
         );
     }
 
     // Render rename popup if active
     if let Some(rename_state) = &state.rename_popup {
-        render_rename_popup(frame, size, rename_state);
+        render_rename_popup(frame, size, rename_state, theme);
     }
 
     // Render reference popup if active
     if let Some(ref_state) = &state.reference_popup {
-        render_reference_popup(frame, size, ref_state);
+        render_reference_popup(frame, size, ref_state, theme);
     }
 
     // Render tag selector if active
     if let Some(tag_state) = &state.tag_selector {
-        render_tag_selector(frame, size, tag_state);
+        render_tag_selector(frame, size, tag_state, config, theme);
+    }
+
+    // Render tag filter popup if active
+    if let Some(filter_state) = &state.tag_filter_popup {
+        render_tag_filter_popup(frame, size, filter_state, config, theme);
     }
 
     // Render folder selector if active
     if let Some(folder_state) = &state.folder_selector {
-        render_folder_selector(frame, size, folder_state);
+        render_folder_selector(frame, size, folder_state, theme);
     }
 
     // Render search popup if active
     if let Some(search_state) = &state.search_popup {
-        render_search_popup(frame, size, search_state);
+        render_search_popup(frame, size, search_state, theme);
+    }
+
+    // Render export popup if active
+    if let Some(export_state) = &state.export_popup {
+        let selected_name = state.selected_prompt().map(|p| p.name.as_str());
+        render_export_popup(frame, size, export_state, selected_name, state.prompts.len(), theme);
     }
 
     // Render file picker popup if active
     if let Some(file_picker_state) = &state.file_picker {
-        render_file_picker_popup(frame, size, file_picker_state);
+        render_file_picker_popup(frame, size, file_picker_state, theme);
+    }
+
+    // Render backup picker if active
+    if let Some(backup_state) = &state.backup_picker {
+        render_backup_picker(frame, size, backup_state, theme);
+    }
+
+    // Render version history if active
+    if let Some(history_state) = &state.history {
+        render_history_popup(frame, size, history_state, theme);
+    }
+
+    // Render blame view if active
+    if let Some(blame_state) = &state.blame {
+        render_blame_popup(frame, size, blame_state, theme);
+    }
+
+    // Render commit popup if active
+    if let Some(commit_state) = &state.commit_popup {
+        render_commit_popup(frame, size, commit_state, theme);
+    }
+
+    // Render command palette if active
+    if let Some(palette_state) = &state.command_palette {
+        render_command_palette(frame, size, palette_state, theme);
+    }
+
+    // Render the conflict prompt on top of everything else
+    if let Some(conflict_state) = &state.conflict_popup {
+        render_conflict_popup(frame, size, conflict_state, theme);
     }
 }
 
@@ -114,14 +163,14 @@ fn render_editor(
     frame: &mut Frame,
     area: Rect,
     state: &AppState,
-    config: &Config,
+    theme: &Theme,
     editor: Option<&TextArea>,
     all_prompts: &[Prompt],
 ) {
     let border_style = if state.editor_focused {
-        Style::default().fg(Color::Cyan)
+        Style::default().fg(theme.accent)
     } else {
-        Style::default().fg(Color::DarkGray)
+        Style::default().fg(theme.muted)
     };
 
     // Collect all prompt names for reference validation (from all_prompts for cross-folder references)
@@ -133,7 +182,8 @@ fn render_editor(
             // Build title with vim mode indicator
             let vim_mode_str = state.editor_mode.as_str();
             let title = if let Some(prompt) = state.selected_prompt() {
-                format!(" {} [{}] ", prompt.name, vim_mode_str)
+                let repair = if prompt.broken_error().is_some() { "[REPAIR] " } else { "" };
+                format!(" {} [{}] {}", prompt.name, vim_mode_str, repair)
             } else {
                 format!(" [{}] ", vim_mode_str)
             };
@@ -143,12 +193,12 @@ fn render_editor(
             
             // Choose border color based on vim mode
             let editor_border_style = match state.editor_mode {
-                crate::models::EditorMode::VimNormal => Style::default().fg(Color::Blue),
-                crate::models::EditorMode::VimInsert => Style::default().fg(Color::Green),
+                crate::models::EditorMode::VimNormal => Style::default().fg(theme.mode_normal),
+                crate::models::EditorMode::VimInsert => Style::default().fg(theme.mode_insert),
                 crate::models::EditorMode::VimVisual | crate::models::EditorMode::VimVisualLine => {
-                    Style::default().fg(Color::Magenta)
+                    Style::default().fg(theme.mode_visual)
                 }
-                crate::models::EditorMode::VimOperatorPending(_) => Style::default().fg(Color::Yellow),
+                crate::models::EditorMode::VimOperatorPending(_) => Style::default().fg(theme.mode_operator),
             };
             
             styled_textarea.set_block(
@@ -162,7 +212,7 @@ fn render_editor(
             let cursor_style = match state.editor_mode {
                 crate::models::EditorMode::VimNormal => {
                     // Block cursor for normal mode
-                    Style::default().bg(Color::White).fg(Color::Black)
+                    theme.badge(theme.text)
                 }
                 crate::models::EditorMode::VimInsert => {
                     // Line cursor for insert mode (reversed)
@@ -170,18 +220,18 @@ fn render_editor(
                 }
                 crate::models::EditorMode::VimVisual | crate::models::EditorMode::VimVisualLine => {
                     // Highlight cursor for visual mode
-                    Style::default().bg(Color::Magenta).fg(Color::White)
+                    theme.badge(theme.mode_visual)
                 }
                 crate::models::EditorMode::VimOperatorPending(_) => {
                     // Block cursor for operator-pending mode (like normal)
-                    Style::default().bg(Color::Yellow).fg(Color::Black)
+                    theme.badge(theme.mode_operator)
                 }
             };
             styled_textarea.set_cursor_style(cursor_style);
             
             // Highlight current line in normal mode
             if state.editor_mode == crate::models::EditorMode::VimNormal {
-                styled_textarea.set_cursor_line_style(Style::default().bg(Color::DarkGray));
+                styled_textarea.set_cursor_line_style(Style::default().bg(theme.cursor_line));
             } else {
                 styled_textarea.set_cursor_line_style(Style::default());
             }
@@ -195,27 +245,48 @@ fn render_editor(
     let (title, content, preview_border_style) = if let Some(prompt) = state.selected_prompt() {
         let title = format!(" {} ", prompt.name);
         
-        // In Preview mode, resolve references and commands
+        // In Preview mode, show the content resolved when it last changed
         if state.mode == crate::models::Mode::Preview {
-            // Create a closure to get prompt content by name (using ALL prompts for cross-folder references)
-            let get_content = |name: &str| -> Option<String> {
-                all_prompts.iter().find(|p| p.name == name).map(|p| p.content.clone())
-            };
-            
-            // Resolve the content (without executing commands in preview for safety)
-            let result = crate::engine::resolve_prompt(&prompt.content, get_content, false);
-            
+            let resolved = state.resolved_preview.as_deref().unwrap_or(&prompt.content);
+
             // Show resolved content without additional highlighting
             // (already resolved, so no [[]] or {{}} patterns)
-            let content: Vec<Line> = result.content
+            let content: Vec<Line> = resolved
                 .lines()
                 .map(|line| Line::from(line.to_string()))
                 .collect();
             
             // Use a different border color for preview mode
-            (title, content, Style::default().fg(Color::Magenta))
+            (title, content, Style::default().fg(theme.mode_preview))
+        } else if let Some(error) = prompt.broken_error() {
+            // Broken frontmatter: show the error above the raw file
+            let mut content = vec![
+                Line::from(Span::styled(
+                    format!("✗ {}", error),
+                    Style::default().fg(theme.error).add_modifier(Modifier::BOLD),
+                )),
+                Line::from(Span::styled(
+                    "Press Enter to repair the frontmatter, then save",
+                    Style::default().fg(theme.muted),
+                )),
+                Line::from(""),
+            ];
+            content.extend(prompt.content.lines().map(|line| Line::from(line.to_string())));
+            (title, content, Style::default().fg(theme.error))
+        } else if prompt.is_unmanaged() {
+            let mut content = vec![
+                Line::from(Span::styled(
+                    "Unmanaged file (no frontmatter): press 'F' to adopt",
+                    Style::default().fg(theme.muted),
+                )),
+                Line::from(""),
+            ];
+            let pinned = &state.pinned_refs;
+            content.extend(highlight_content(&prompt.content, &prompt_names, pinned, theme));
+            (title, content, border_style)
         } else {
-            let content = highlight_content(&prompt.content, &prompt_names, config);
+            let pinned = &state.pinned_refs;
+            let content = highlight_content(&prompt.content, &prompt_names, pinned, theme);
             (title, content, border_style)
         }
     } else {
@@ -247,15 +318,25 @@ fn render_editor(
 }
 
 /// Apply syntax highlighting to content
-fn highlight_content<'a>(content: &'a str, existing_prompts: &[&str], _config: &Config) -> Vec<Line<'a>> {
+fn highlight_content<'a>(
+    content: &'a str,
+    existing_prompts: &[&str],
+    pinned_refs: &HashMap<String, bool>,
+    theme: &Theme,
+) -> Vec<Line<'a>> {
     content
         .lines()
-        .map(|line| highlight_line(line, existing_prompts))
+        .map(|line| highlight_line(line, existing_prompts, pinned_refs, theme))
         .collect()
 }
 
 /// Highlight a single line of content
-fn highlight_line<'a>(line: &'a str, existing_prompts: &[&str]) -> Line<'a> {
+fn highlight_line<'a>(
+    line: &'a str,
+    existing_prompts: &[&str],
+    pinned_refs: &HashMap<String, bool>,
+    theme: &Theme,
+) -> Line<'a> {
     let mut spans = Vec::new();
     let mut current_pos = 0;
     let line_bytes = line.as_bytes();
@@ -271,15 +352,21 @@ fn highlight_line<'a>(line: &'a str, existing_prompts: &[&str]) -> Line<'a> {
                 let full_ref = &line[current_pos..end + 2];
                 
                 // Determine if reference is valid
-                let is_valid = if ref_name.starts_with("file:") {
+                let is_valid = if let Some(&exists) = pinned_refs.get(ref_name) {
+                    // Pinned references were checked against git when the content changed
+                    exists
+                } else if ref_name.starts_with("file:") {
                     // For file references, check if the file exists
                     let file_path = &ref_name[5..]; // Strip "file:" prefix
                     std::path::Path::new(file_path).exists()
+                } else if split_revision(ref_name).1.is_some() {
+                    // A pinned reference not checked yet
+                    false
                 } else {
                     // For prompt references, check against existing prompts
                     existing_prompts.contains(&ref_name)
                 };
-                let color = if is_valid { Color::Green } else { Color::Red };
+                let color = if is_valid { theme.reference } else { theme.broken_reference };
                 
                 spans.push(Span::styled(
                     full_ref.to_string(),
@@ -307,11 +394,11 @@ fn highlight_line<'a>(line: &'a str, existing_prompts: &[&str]) -> Line<'a> {
                 // Add warning indicator before commands
                 spans.push(Span::styled(
                     "⚠ ",
-                    Style::default().fg(Color::LightRed),
+                    Style::default().fg(theme.command_marker),
                 ));
                 spans.push(Span::styled(
                     full_cmd.to_string(),
-                    Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+                    Style::default().fg(theme.command).add_modifier(Modifier::BOLD),
                 ));
                 
                 current_pos = end + 2;
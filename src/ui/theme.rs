@@ -0,0 +1,431 @@
+//! Color themes
+//!
+//! Every color the UI draws comes from a [`Theme`]: one color per role (borders, hints,
+//! selections, editor modes, syntax tokens...). Themes are picked by name in the `theme`
+//! config section: a built-in one (`dark`, `light`, `high-contrast`) or a `<name>.yaml` file
+//! in a themes directory, with single roles overridden under `theme.colors`.
+
+use anyhow::{bail, Context, Result};
+use ratatui::style::{Color, Modifier, Style};
+use serde_yaml::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use super::colors::{color_support, fit_color, ColorSupport};
+use crate::config::{parse_color, ThemeConfig};
+
+/// Names of the built-in themes
+pub const BUILTIN_THEMES: &[&str] = &["dark", "light", "high-contrast"];
+
+/// Names of the colors a theme sets
+pub const THEME_ROLES: &[&str] = &[
+    "text",
+    "muted",
+    "accent",
+    "highlight",
+    "success",
+    "warning",
+    "error",
+    "info",
+    "selection_fg",
+    "selection_bg",
+    "cursor_line",
+    "mode_normal",
+    "mode_insert",
+    "mode_visual",
+    "mode_operator",
+    "mode_archive",
+    "mode_folder",
+    "mode_preview",
+    "reference",
+    "broken_reference",
+    "command",
+    "command_marker",
+    "diff_added",
+    "diff_removed",
+    "git_untracked",
+    "git_modified",
+    "git_staged",
+];
+
+/// Colors of every UI element and syntax token
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    /// Regular text
+    pub text: Color,
+    /// Hints, placeholders, unfocused borders and other secondary text
+    pub muted: Color,
+    /// Focused borders, popup frames and headings
+    pub accent: Color,
+    /// Input boxes, list titles and folder names
+    pub highlight: Color,
+    /// Valid input and confirmations
+    pub success: Color,
+    /// Warnings
+    pub warning: Color,
+    /// Errors and invalid input
+    pub error: Color,
+    /// Informational notifications
+    pub info: Color,
+    /// Text of selected rows and badges
+    pub selection_fg: Color,
+    /// Background of selected rows
+    pub selection_bg: Color,
+    /// Background of the line under the cursor in vim normal mode
+    pub cursor_line: Color,
+    /// Vim normal mode (editor border and status badge)
+    pub mode_normal: Color,
+    /// Vim insert mode
+    pub mode_insert: Color,
+    /// Vim visual modes
+    pub mode_visual: Color,
+    /// Vim operator-pending mode
+    pub mode_operator: Color,
+    /// Archive view
+    pub mode_archive: Color,
+    /// Folder view
+    pub mode_folder: Color,
+    /// Preview of the resolved prompt
+    pub mode_preview: Color,
+    /// `[[references]]` to existing prompts and files
+    pub reference: Color,
+    /// `[[references]]` to missing prompts and files
+    pub broken_reference: Color,
+    /// `{{commands}}`
+    pub command: Color,
+    /// Warning sign in front of `{{commands}}`
+    pub command_marker: Color,
+    /// Added lines in diffs
+    pub diff_added: Color,
+    /// Removed lines in diffs
+    pub diff_removed: Color,
+    /// Prompts git does not track yet
+    pub git_untracked: Color,
+    /// Prompts with uncommitted changes
+    pub git_modified: Color,
+    /// Prompts with staged changes
+    pub git_staged: Color,
+    /// Whether colors are off (`NO_COLOR`), so selections are shown reversed instead
+    pub plain: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl Theme {
+    /// Light text on a dark terminal
+    pub fn dark() -> Self {
+        Self {
+            text: Color::White,
+            muted: Color::DarkGray,
+            accent: Color::Cyan,
+            highlight: Color::Yellow,
+            success: Color::Green,
+            warning: Color::Yellow,
+            error: Color::Red,
+            info: Color::White,
+            selection_fg: Color::Black,
+            selection_bg: Color::Cyan,
+            cursor_line: Color::DarkGray,
+            mode_normal: Color::Blue,
+            mode_insert: Color::Green,
+            mode_visual: Color::Magenta,
+            mode_operator: Color::Yellow,
+            mode_archive: Color::Yellow,
+            mode_folder: Color::Magenta,
+            mode_preview: Color::Magenta,
+            reference: Color::Green,
+            broken_reference: Color::Red,
+            command: Color::Yellow,
+            command_marker: Color::LightRed,
+            diff_added: Color::Green,
+            diff_removed: Color::Red,
+            git_untracked: Color::Magenta,
+            git_modified: Color::Yellow,
+            git_staged: Color::Green,
+            plain: false,
+        }
+    }
+
+    /// Dark text on a light terminal
+    pub fn light() -> Self {
+        Self {
+            text: Color::Rgb(40, 40, 40),
+            muted: Color::Rgb(128, 128, 128),
+            accent: Color::Rgb(0, 95, 175),
+            highlight: Color::Rgb(135, 95, 0),
+            success: Color::Rgb(0, 135, 0),
+            warning: Color::Rgb(175, 95, 0),
+            error: Color::Rgb(175, 0, 0),
+            info: Color::Rgb(40, 40, 40),
+            selection_fg: Color::Rgb(255, 255, 255),
+            selection_bg: Color::Rgb(0, 95, 175),
+            cursor_line: Color::Rgb(228, 228, 228),
+            mode_normal: Color::Rgb(0, 95, 175),
+            mode_insert: Color::Rgb(0, 135, 0),
+            mode_visual: Color::Rgb(135, 0, 135),
+            mode_operator: Color::Rgb(175, 95, 0),
+            mode_archive: Color::Rgb(175, 95, 0),
+            mode_folder: Color::Rgb(135, 0, 135),
+            mode_preview: Color::Rgb(135, 0, 135),
+            reference: Color::Rgb(0, 135, 0),
+            broken_reference: Color::Rgb(175, 0, 0),
+            command: Color::Rgb(175, 95, 0),
+            command_marker: Color::Rgb(215, 0, 0),
+            diff_added: Color::Rgb(0, 135, 0),
+            diff_removed: Color::Rgb(175, 0, 0),
+            git_untracked: Color::Rgb(135, 0, 135),
+            git_modified: Color::Rgb(175, 95, 0),
+            git_staged: Color::Rgb(0, 135, 0),
+            plain: false,
+        }
+    }
+
+    /// Bright, saturated colors on a black terminal
+    pub fn high_contrast() -> Self {
+        Self {
+            text: Color::White,
+            muted: Color::Gray,
+            accent: Color::LightCyan,
+            highlight: Color::LightYellow,
+            success: Color::LightGreen,
+            warning: Color::LightYellow,
+            error: Color::LightRed,
+            info: Color::White,
+            selection_fg: Color::Black,
+            selection_bg: Color::LightYellow,
+            cursor_line: Color::Blue,
+            mode_normal: Color::LightBlue,
+            mode_insert: Color::LightGreen,
+            mode_visual: Color::LightMagenta,
+            mode_operator: Color::LightYellow,
+            mode_archive: Color::LightYellow,
+            mode_folder: Color::LightMagenta,
+            mode_preview: Color::LightMagenta,
+            reference: Color::LightGreen,
+            broken_reference: Color::LightRed,
+            command: Color::LightYellow,
+            command_marker: Color::LightRed,
+            diff_added: Color::LightGreen,
+            diff_removed: Color::LightRed,
+            git_untracked: Color::LightMagenta,
+            git_modified: Color::LightYellow,
+            git_staged: Color::LightGreen,
+            plain: false,
+        }
+    }
+
+    /// Built-in theme called `name`
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Self::dark()),
+            "light" => Some(Self::light()),
+            "high-contrast" => Some(Self::high_contrast()),
+            _ => None,
+        }
+    }
+
+    /// Theme chosen by `config`: a built-in theme, or `<name>.yaml` from the first of `dirs`
+    /// that has it, with the configured colors applied on top
+    pub fn load(config: &ThemeConfig, dirs: &[PathBuf]) -> Result<Self> {
+        let mut theme = match Self::builtin(&config.name) {
+            Some(theme) => theme,
+            None => match find_theme_file(&config.name, dirs) {
+                Some(path) => Self::from_file(&path)?,
+                None => bail!(
+                    "Unknown theme '{}': not one of {} and no {}.yaml in the themes directory",
+                    config.name,
+                    BUILTIN_THEMES.join(", "),
+                    config.name
+                ),
+            },
+        };
+        theme.set_colors(&config.colors).context("Invalid theme.colors")?;
+        Ok(theme)
+    }
+
+    /// Read a theme file: a mapping of roles to colors, starting from the built-in theme
+    /// named by an optional `base` key (`dark` by default)
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read theme file: {}", path.display()))?;
+        let mut colors: BTreeMap<String, Value> = serde_yaml::from_str(&contents)
+            .with_context(|| format!("Failed to parse theme file: {}", path.display()))?;
+
+        let base = match colors.remove("base") {
+            Some(Value::String(base)) => base,
+            Some(other) => bail!("Invalid base theme {:?} in {}", other, path.display()),
+            None => "dark".to_string(),
+        };
+        let mut theme = Self::builtin(&base).with_context(|| {
+            format!("Unknown base theme '{}' in {}", base, path.display())
+        })?;
+
+        let colors: BTreeMap<String, String> = colors
+            .into_iter()
+            .map(|(role, value)| match value {
+                Value::String(color) => (role, color),
+                Value::Number(index) => (role, index.to_string()),
+                _ => (role, String::new()),
+            })
+            .collect();
+        theme.set_colors(&colors).with_context(|| format!("In {}", path.display()))?;
+        Ok(theme)
+    }
+
+    /// Set roles from a mapping of role names to colors
+    pub fn set_colors(&mut self, colors: &BTreeMap<String, String>) -> Result<()> {
+        for (role, value) in colors {
+            let color = parse_color(value).with_context(|| {
+                format!("Invalid color '{}' for {} (quote hex colors: \"#rrggbb\")", value, role)
+            })?;
+            *self.role_mut(role).with_context(|| format!("Unknown theme color '{}'", role))? =
+                color;
+        }
+        Ok(())
+    }
+
+    fn role_mut(&mut self, role: &str) -> Option<&mut Color> {
+        Some(match role {
+            "text" => &mut self.text,
+            "muted" => &mut self.muted,
+            "accent" => &mut self.accent,
+            "highlight" => &mut self.highlight,
+            "success" => &mut self.success,
+            "warning" => &mut self.warning,
+            "error" => &mut self.error,
+            "info" => &mut self.info,
+            "selection_fg" => &mut self.selection_fg,
+            "selection_bg" => &mut self.selection_bg,
+            "cursor_line" => &mut self.cursor_line,
+            "mode_normal" => &mut self.mode_normal,
+            "mode_insert" => &mut self.mode_insert,
+            "mode_visual" => &mut self.mode_visual,
+            "mode_operator" => &mut self.mode_operator,
+            "mode_archive" => &mut self.mode_archive,
+            "mode_folder" => &mut self.mode_folder,
+            "mode_preview" => &mut self.mode_preview,
+            "reference" => &mut self.reference,
+            "broken_reference" => &mut self.broken_reference,
+            "command" => &mut self.command,
+            "command_marker" => &mut self.command_marker,
+            "diff_added" => &mut self.diff_added,
+            "diff_removed" => &mut self.diff_removed,
+            "git_untracked" => &mut self.git_untracked,
+            "git_modified" => &mut self.git_modified,
+            "git_staged" => &mut self.git_staged,
+            _ => return None,
+        })
+    }
+
+    /// The theme with every color replaced by the closest one the terminal supports; with
+    /// no colors at all, selections and badges are shown reversed
+    pub fn fitted(mut self, support: ColorSupport) -> Self {
+        for role in THEME_ROLES {
+            if let Some(color) = self.role_mut(role) {
+                *color = fit_color(*color, support);
+            }
+        }
+        self.plain = support == ColorSupport::NoColor;
+        self
+    }
+
+    /// The theme fitted to the current terminal
+    pub fn for_terminal(self) -> Self {
+        self.fitted(color_support())
+    }
+
+    /// Style of a badge or selected row with `color` as background
+    pub fn badge(&self, color: Color) -> Style {
+        if self.plain {
+            Style::default().add_modifier(Modifier::REVERSED)
+        } else {
+            Style::default().fg(self.selection_fg).bg(color)
+        }
+    }
+
+    /// Style of the selected row of a list
+    pub fn selected(&self) -> Style {
+        self.badge(self.selection_bg)
+    }
+}
+
+/// Path of the theme file called `name` in the first of `dirs` that has one
+fn find_theme_file(name: &str, dirs: &[PathBuf]) -> Option<PathBuf> {
+    if name.is_empty() || name.contains(['/', '\\']) {
+        return None;
+    }
+    dirs.iter()
+        .map(|dir| dir.join(format!("{}.yaml", name)))
+        .find(|path| path.exists())
+}
+
+/// Whether a theme called `name` can be found (built in or in one of `dirs`)
+pub fn theme_exists(name: &str, dirs: &[PathBuf]) -> bool {
+    Theme::builtin(name).is_some() || find_theme_file(name, dirs).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_theme_roles() {
+        let mut theme = Theme::dark();
+        for role in THEME_ROLES {
+            assert!(theme.role_mut(role).is_some(), "missing role {}", role);
+        }
+        assert_eq!(theme.role_mut("accent"), Some(&mut Color::Cyan));
+        assert_eq!(theme.role_mut("nope"), None);
+        for name in BUILTIN_THEMES {
+            assert!(Theme::builtin(name).is_some());
+        }
+    }
+
+    #[test]
+    fn test_theme_load() {
+        let dir = tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("solar.yaml"),
+            "base: light\naccent: \"#268bd2\"\nmuted: 244\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("broken.yaml"), "acent: red\n").unwrap();
+        let dirs = vec![dir.path().join("missing"), dir.path().to_path_buf()];
+
+        let config = |name: &str, colors: &[(&str, &str)]| ThemeConfig {
+            name: name.to_string(),
+            colors: colors.iter().map(|(r, c)| (r.to_string(), c.to_string())).collect(),
+        };
+
+        let theme = Theme::load(&config("solar", &[("error", "magenta")]), &dirs).unwrap();
+        assert_eq!(theme.accent, Color::Rgb(38, 139, 210));
+        assert_eq!(theme.muted, Color::Indexed(244));
+        assert_eq!(theme.text, Theme::light().text);
+        assert_eq!(theme.error, Color::Magenta);
+
+        let theme = Theme::load(&config("high-contrast", &[]), &dirs).unwrap();
+        assert_eq!(theme, Theme::high_contrast());
+        assert!(Theme::load(&config("broken", &[]), &dirs).is_err());
+        assert!(Theme::load(&config("nope", &[]), &dirs).is_err());
+        assert!(Theme::load(&config("dark", &[("accent", "")]), &dirs).is_err());
+        assert!(theme_exists("solar", &dirs));
+        assert!(!theme_exists("../solar", &dirs));
+    }
+
+    #[test]
+    fn test_theme_without_colors() {
+        let theme = Theme::dark().fitted(ColorSupport::NoColor);
+        assert!(theme.plain);
+        assert_eq!(theme.accent, Color::Reset);
+        assert_eq!(theme.selected(), Style::default().add_modifier(Modifier::REVERSED));
+
+        let theme = Theme::light().fitted(ColorSupport::Basic);
+        assert!(!theme.plain);
+        assert_eq!(theme.selected(), Style::default().fg(Color::White).bg(Color::Blue));
+    }
+}